@@ -0,0 +1,560 @@
+//! [BTOR2](https://github.com/Boolector/btor2tools) model generation, for formal verification with model checkers such as `btormc`, `pono`, or `avr`.
+
+mod compiler;
+mod model;
+
+use compiler::*;
+
+use crate::error::*;
+use crate::graph;
+use crate::module_context::*;
+use crate::validation::*;
+
+use typed_arena::Arena;
+
+use std::io::Write;
+
+#[derive(Default)]
+pub struct GenerationOptions {
+    /// If `true`, each [`Cover`](crate::PropertyKind::Cover) property is included as a `bad` state property, so that a model checker reports a trace reaching it. As this inverts the meaning of `bad` states, covers are omitted by default, and are best checked separately from assertions.
+    pub covers_as_bad_states: bool,
+    /// If `true`, logic isn't simplified (by folding constants, eliminating identity and absorbing elements, and merging common subexpressions) before code generation, so the generated model mirrors the module graph exactly. This is mostly useful for comparing against simplified output when debugging.
+    pub disable_simplification: bool,
+}
+
+/// Generates a BTOR2 model of `m` and its entire hierarchy (with all instances flattened), writing it to `w`.
+///
+/// The model is built as follows:
+///
+/// - `m`'s inputs become `input`s, and its outputs become `output`s, named after the inputs and outputs themselves.
+/// - Each [`Register`](crate::Register) becomes a bit vector `state`, and each [`Mem`](crate::Mem) becomes an array `state`. States are named after their hierarchical paths, e.g. `cpu.alu.r` for a register called `r` in an instance called `alu` within an instance called `cpu`.
+/// - Each [assertion](crate::Module::assert) becomes a `bad` state property, which is reached if its condition is low, and each [assumption](crate::Module::assume) becomes a `constraint`. Both are named after their hierarchical paths. Covers are only included if [`GenerationOptions::covers_as_bad_states`] is set.
+///
+/// The model's initial state is the state just after reset: registers with [default values](crate::Register::default_value) and memories with [initial contents](crate::Mem::initial_contents) are initialized accordingly, while all other state (including the values of synchronous read ports before their first read) is unconstrained.
+///
+/// Each of `m`'s [`ClockDomain`](crate::ClockDomain)s has an active-high reset input (`reset` for the default clock domain, and `<name>_reset` for a clock domain called `name`), which is only added if anything depends on it. Resets are modeled as synchronous: in a transition in which a clock domain's reset is high, its registers with default values take their default values. Properties aren't checked in transitions in which their clock domain's reset is high.
+///
+/// If `m` only has its [default clock domain](crate::Module::default_clock_domain), every transition of the model is a positive edge of its clock. Otherwise, each clock domain has a clock input (`clk` for the default clock domain, and `<name>_clk` for a clock domain called `name`), and its state only changes (and its properties are only checked) in the transitions in which its clock input is high, so the model checker is free to choose any interleaving of the clock domains' edges.
+///
+/// Reads from [`DontCare`](crate::ReadDuringWrite::DontCare) read ports that coincide with a write to the same location produce unconstrained values.
+///
+/// # Examples
+///
+/// ```
+/// use kaze::*;
+///
+/// let c = Context::new();
+///
+/// let m = c.module("Counter");
+/// let count = m.reg("count", 4);
+/// count.default_value(0u32);
+/// count.drive_next(count.value + m.lit(1u32, 4));
+/// m.output("count", count.value);
+/// m.assert("in_range", count.value.le(m.lit(9u32, 4)));
+///
+/// let mut model = Vec::new();
+/// btor2::generate(m, btor2::GenerationOptions::default(), &mut model)?;
+///
+/// # Ok::<(), kaze::Error>(())
+/// ```
+pub fn generate<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: GenerationOptions,
+    mut w: W,
+) -> Result<(), Error> {
+    validate_module_hierarchy(m)?;
+
+    let context_arena = Arena::new();
+    let root_context = context_arena.alloc(ModuleContext::new());
+
+    let mut c = Compiler::new(m, &context_arena, !options.disable_simplification);
+
+    c.model.comment(&format!(
+        "BTOR2 model of module {}, generated by kaze",
+        m.name
+    ));
+
+    for (_, &input) in m.inputs.borrow().iter() {
+        c.compile_signal(input, root_context);
+    }
+
+    for (name, &output) in m.outputs.borrow().iter() {
+        let node = c.compile_signal(output, root_context);
+        c.model.named_node("output", &[node], name);
+    }
+
+    compile_properties(&mut c, m, root_context, &context_arena, &options);
+
+    c.compile_pending_states();
+
+    c.model.write(&mut w)?;
+
+    Ok(())
+}
+
+fn compile_properties<'graph, 'arena>(
+    c: &mut Compiler<'graph, 'arena>,
+    m: &'graph graph::Module<'graph>,
+    context: &'arena ModuleContext<'graph, 'arena>,
+    context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+    options: &GenerationOptions,
+) {
+    for property in m.properties.borrow().iter() {
+        if property.kind == graph::PropertyKind::Cover && !options.covers_as_bad_states {
+            continue;
+        }
+        let path = context.relative_path(&property.name);
+        let condition = c.compile_signal(property.condition, context);
+        let enable = c.property_enable(context.root_clock_domain(property.resolved_clock_domain()));
+        let sort = c.model.bitvec_sort(1);
+        match property.kind {
+            graph::PropertyKind::Assert => {
+                let violated = c.model.node("not", &[sort, condition]);
+                let violated = c.model.node("and", &[sort, enable, violated]);
+                c.model.named_node("bad", &[violated], &path);
+            }
+            graph::PropertyKind::Assume => {
+                let disabled = c.model.node("not", &[sort, enable]);
+                let holds = c.model.node("or", &[sort, disabled, condition]);
+                c.model.named_node("constraint", &[holds], &path);
+            }
+            graph::PropertyKind::Cover => {
+                let covered = c.model.node("and", &[sort, enable, condition]);
+                c.model.named_node("bad", &[covered], &path);
+            }
+        }
+    }
+
+    for instance in m.instances.borrow().iter() {
+        compile_properties(
+            c,
+            instance.instantiated_module,
+            context.get_child(instance, context_arena),
+            context_arena,
+            options,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::*;
+
+    use std::collections::HashMap;
+
+    fn generate_string<'a>(m: &'a Module<'a>, options: GenerationOptions) -> String {
+        let mut model = Vec::new();
+        generate(m, options, &mut model).unwrap();
+        let model = String::from_utf8(model).unwrap();
+        check_model(&model);
+        model
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Sort {
+        BitVec(u32),
+        Array(u32, u32),
+    }
+
+    // Parses `model` and panics unless it's well-formed BTOR2: lines are numbered sequentially
+    //  and only refer to lines before them, and every operator's arguments have the right sorts.
+    fn check_model(model: &str) {
+        let mut sorts = HashMap::new();
+        let mut nodes = HashMap::new();
+        let mut states = HashMap::new();
+        let mut num_lines = 0;
+        for line in model.lines() {
+            if line.starts_with(';') {
+                continue;
+            }
+            let tokens = line.split(' ').collect::<Vec<_>>();
+            num_lines += 1;
+            let id = tokens[0].parse::<u32>().unwrap();
+            assert_eq!(id, num_lines, "line ids aren't sequential: {}", line);
+            let op = tokens[1];
+            let arg = |i: usize| -> u32 {
+                let arg = tokens[2 + i].parse::<u32>().unwrap();
+                arg
+            };
+            let sort = |i: usize| -> Sort {
+                let sort = arg(i);
+                assert!(sort < id, "forward reference: {}", line);
+                *sorts
+                    .get(&sort)
+                    .unwrap_or_else(|| panic!("not a sort: {}", line))
+            };
+            let node = |i: usize| -> Sort {
+                let node = arg(i);
+                assert!(node < id, "forward reference: {}", line);
+                *nodes
+                    .get(&node)
+                    .unwrap_or_else(|| panic!("not a node: {}", line))
+            };
+            let width = |sort: Sort| -> u32 {
+                match sort {
+                    Sort::BitVec(width) => width,
+                    _ => panic!("expected a bitvec: {}", line),
+                }
+            };
+            let bool_sort = Sort::BitVec(1);
+            let result = match op {
+                "sort" => {
+                    let sort = match tokens[2] {
+                        "bitvec" => Sort::BitVec(tokens[3].parse().unwrap()),
+                        "array" => {
+                            let address_sort = arg(1);
+                            let element_sort = arg(2);
+                            assert!(address_sort < id && element_sort < id);
+                            Sort::Array(width(sorts[&address_sort]), width(sorts[&element_sort]))
+                        }
+                        _ => panic!("unknown sort: {}", line),
+                    };
+                    sorts.insert(id, sort);
+                    continue;
+                }
+                "input" => Some(sort(0)),
+                "state" => {
+                    states.insert(id, (false, false));
+                    Some(sort(0))
+                }
+                "output" => {
+                    node(0);
+                    None
+                }
+                "bad" | "constraint" => {
+                    assert_eq!(node(0), bool_sort, "{}", line);
+                    None
+                }
+                "const" => {
+                    let bits = tokens[3];
+                    assert!(bits.chars().all(|c| c == '0' || c == '1'));
+                    assert_eq!(bits.len() as u32, width(sort(0)), "{}", line);
+                    Some(sort(0))
+                }
+                "init" | "next" => {
+                    let state = states
+                        .get_mut(&arg(1))
+                        .unwrap_or_else(|| panic!("not a state: {}", line));
+                    let defined = if op == "init" {
+                        &mut state.0
+                    } else {
+                        &mut state.1
+                    };
+                    assert!(!*defined, "state defined twice: {}", line);
+                    *defined = true;
+                    assert_eq!(node(1), sort(0), "{}", line);
+                    match (op, sort(0), node(2)) {
+                        ("init", Sort::Array(_, element_width), Sort::BitVec(width))
+                            if width == element_width => {}
+                        (_, sort, value) => assert_eq!(sort, value, "{}", line),
+                    }
+                    None
+                }
+                "not" => {
+                    assert_eq!(node(1), sort(0), "{}", line);
+                    Some(sort(0))
+                }
+                "and" | "or" | "xor" | "add" | "sub" | "mul" | "sll" | "srl" | "sra" => {
+                    width(sort(0));
+                    assert_eq!(node(1), sort(0), "{}", line);
+                    assert_eq!(node(2), sort(0), "{}", line);
+                    Some(sort(0))
+                }
+                "eq" | "neq" | "ult" | "ulte" | "ugt" | "ugte" | "slt" | "slte" | "sgt"
+                | "sgte" => {
+                    assert_eq!(sort(0), bool_sort, "{}", line);
+                    width(node(1));
+                    assert_eq!(node(1), node(2), "{}", line);
+                    Some(sort(0))
+                }
+                "slice" => {
+                    let (upper, lower) = (arg(2), arg(3));
+                    assert!(upper >= lower && upper < width(node(1)), "{}", line);
+                    assert_eq!(width(sort(0)), upper - lower + 1, "{}", line);
+                    Some(sort(0))
+                }
+                "uext" | "sext" => {
+                    assert_eq!(width(sort(0)), width(node(1)) + arg(2), "{}", line);
+                    Some(sort(0))
+                }
+                "concat" => {
+                    assert_eq!(width(sort(0)), width(node(1)) + width(node(2)), "{}", line);
+                    Some(sort(0))
+                }
+                "ite" => {
+                    assert_eq!(node(1), bool_sort, "{}", line);
+                    assert_eq!(node(2), sort(0), "{}", line);
+                    assert_eq!(node(3), sort(0), "{}", line);
+                    Some(sort(0))
+                }
+                "read" | "write" => {
+                    let (address_width, element_width) = match node(1) {
+                        Sort::Array(address_width, element_width) => (address_width, element_width),
+                        _ => panic!("expected an array: {}", line),
+                    };
+                    assert_eq!(width(node(2)), address_width, "{}", line);
+                    if op == "read" {
+                        assert_eq!(width(sort(0)), element_width, "{}", line);
+                    } else {
+                        assert_eq!(sort(0), node(1), "{}", line);
+                        assert_eq!(width(node(3)), element_width, "{}", line);
+                    }
+                    Some(sort(0))
+                }
+                _ => panic!("unknown operator: {}", line),
+            };
+            if let Some(sort) = result {
+                nodes.insert(id, sort);
+            }
+        }
+    }
+
+    #[test]
+    fn undriven_register_error() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        let _ = a.reg("r", 1);
+
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains a register called \"r\" which is not driven."
+        );
+    }
+
+    #[test]
+    fn counter() {
+        let c = Context::new();
+
+        let m = c.module("Counter");
+        let en = m.input("en", 1);
+        let count = m.reg("count", 4);
+        count.default_value(0u32);
+        count.drive_next(m.mux(en, count.value + m.lit(1u32, 4), count.value));
+        m.output("count", count.value);
+        m.assert("in_range", count.value.le(m.lit(9u32, 4)));
+        m.assume("en_first", en | count.value.ne(m.lit(0u32, 4)));
+
+        assert_eq!(
+            generate_string(m, GenerationOptions::default()),
+            "; BTOR2 model of module Counter, generated by kaze\n\
+             1 sort bitvec 1\n\
+             2 input 1 en\n\
+             3 sort bitvec 4\n\
+             4 state 3 count\n\
+             5 output 4 count\n\
+             6 const 3 1001\n\
+             7 ulte 1 4 6\n\
+             8 input 1 reset\n\
+             9 not 1 8\n\
+             10 not 1 7\n\
+             11 and 1 9 10\n\
+             12 bad 11 in_range\n\
+             13 const 3 0000\n\
+             14 neq 1 4 13\n\
+             15 or 1 2 14\n\
+             16 not 1 9\n\
+             17 or 1 16 15\n\
+             18 constraint 17 en_first\n\
+             19 const 3 0001\n\
+             20 add 3 4 19\n\
+             21 ite 3 2 20 4\n\
+             22 init 3 4 13\n\
+             23 ite 3 8 13 21\n\
+             24 next 3 4 23\n"
+        );
+    }
+
+    #[test]
+    fn hierarchy() {
+        let c = Context::new();
+
+        let inner = c.module("Inner");
+        let i = inner.input("i", 4);
+        let r = inner.reg("r", 4);
+        r.drive_next(r.value + i);
+        inner.output("o", r.value);
+        inner.assert("nonzero", r.value.ne(inner.lit(0u32, 4)));
+        inner.cover("max", r.value.eq(inner.lit(15u32, 4)));
+
+        let m = c.module("Top");
+        let x = m.instance("x", "Inner");
+        x.drive_input("i", m.input("i", 4));
+        let y = m.instance("y", "Inner");
+        y.drive_input("i", x.output("o"));
+        m.output("o", y.output("o"));
+
+        assert_eq!(
+            generate_string(m, GenerationOptions::default()),
+            "; BTOR2 model of module Top, generated by kaze\n\
+             1 sort bitvec 4\n\
+             2 input 1 i\n\
+             3 state 1 y.r\n\
+             4 output 3 o\n\
+             5 const 1 0000\n\
+             6 state 1 x.r\n\
+             7 sort bitvec 1\n\
+             8 neq 7 6 5\n\
+             9 input 7 reset\n\
+             10 not 7 9\n\
+             11 not 7 8\n\
+             12 and 7 10 11\n\
+             13 bad 12 x.nonzero\n\
+             14 neq 7 3 5\n\
+             15 not 7 14\n\
+             16 and 7 10 15\n\
+             17 bad 16 y.nonzero\n\
+             18 add 1 6 2\n\
+             19 next 1 6 18\n\
+             20 add 1 3 6\n\
+             21 next 1 3 20\n"
+        );
+
+        let model = generate_string(
+            m,
+            GenerationOptions {
+                covers_as_bad_states: true,
+                ..Default::default()
+            },
+        );
+        let lines = model.lines().collect::<Vec<_>>();
+        assert!(lines.contains(&"17 bad 16 x.max"));
+        assert!(lines.contains(&"24 bad 23 y.max"));
+    }
+
+    #[test]
+    fn shift_amounts() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let a = m.input("a", 4);
+        let b = m.input("b", 2);
+        let c = m.input("c", 8);
+        m.output("shl", a << b);
+        m.output("shr", a.shr_arithmetic(c));
+
+        assert_eq!(
+            generate_string(m, GenerationOptions::default()),
+            "; BTOR2 model of module A, generated by kaze\n\
+             1 sort bitvec 4\n\
+             2 input 1 a\n\
+             3 sort bitvec 2\n\
+             4 input 3 b\n\
+             5 sort bitvec 8\n\
+             6 input 5 c\n\
+             7 uext 1 4 2\n\
+             8 sll 1 2 7\n\
+             9 output 8 shl\n\
+             10 sort bitvec 1\n\
+             11 const 5 00000100\n\
+             12 ugte 10 6 11\n\
+             13 ite 5 12 11 6\n\
+             14 slice 1 13 3 0\n\
+             15 sra 1 2 14\n\
+             16 output 15 shr\n"
+        );
+    }
+
+    #[test]
+    fn mems() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let address = m.input("address", 2);
+        let value = m.input("value", 8);
+        let enable = m.input("enable", 1);
+
+        let rom = m.mem("rom", 2, 8);
+        rom.initial_contents(&[0u32; 4]);
+        m.output("rom_data", rom.async_read_port(address));
+
+        let ram = m.mem("ram", 2, 8);
+        ram.initial_contents(&[1u32, 2, 1, 1]);
+        ram.masked_write_port(address, value, enable, m.input("mask", 2), 4);
+        m.output(
+            "ram_data",
+            ram.read_port_with_read_during_write(address, enable, ReadDuringWrite::WriteFirst),
+        );
+
+        let model = generate_string(m, GenerationOptions::default());
+        let lines = model.lines().collect::<Vec<_>>();
+        // Sync read port value
+        assert!(lines.contains(&"8 state 6"));
+        // Async read port
+        assert!(lines.contains(&"10 sort array 1 6"));
+        assert!(lines.contains(&"11 state 10 rom"));
+        assert!(lines.contains(&"12 read 6 11 2"));
+        // Constant initial contents
+        assert!(lines.contains(&"14 const 6 00000000"));
+        assert!(lines.contains(&"15 init 10 11 14"));
+        assert!(lines.contains(&"16 next 10 11 11"));
+        // Masked write port, read (write-first) by the sync read port
+        assert!(lines.contains(&"17 state 10 ram"));
+        assert!(lines.contains(&"18 slice 3 5 1 1"));
+        assert!(lines.contains(&"20 sext 19 18 3"));
+        assert!(lines.contains(&"23 concat 6 20 22"));
+        assert!(lines.contains(&"28 or 6 26 27"));
+        assert!(lines.contains(&"29 write 10 17 2 28"));
+        assert!(lines.contains(&"30 ite 10 4 29 17"));
+        assert!(lines.contains(&"31 read 6 30 2"));
+        assert!(lines.contains(&"32 ite 6 4 31 8"));
+        assert!(lines.contains(&"33 next 6 8 32"));
+        // Non-constant initial contents are written to a constant array of the most common element
+        assert!(lines.contains(&"34 const 6 00000001"));
+        assert!(lines.contains(&"35 state 10"));
+        assert!(lines.contains(&"36 init 10 35 34"));
+        assert!(lines.contains(&"37 next 10 35 35"));
+        assert!(lines.contains(&"40 write 10 35 38 39"));
+        assert!(lines.contains(&"41 init 10 17 40"));
+        assert!(lines.contains(&"42 next 10 17 30"));
+        assert_eq!(lines.len(), 43);
+    }
+
+    #[test]
+    fn clock_domains() {
+        let c = Context::new();
+
+        let inner = c.module("Inner");
+        let r = inner.reg("r", 1);
+        r.default_value(false);
+        r.drive_next(!r.value);
+        inner.output("o", r.value);
+        inner.assert("toggles", r.value.ne(r.value.reg_next("prev")));
+
+        let m = c.module("Top");
+        let fast = m.clock_domain("fast");
+        let slow = m.clock_domain("slow");
+        let a = m.instance("a", "Inner");
+        a.drive_default_clock_domain(fast);
+        let b = m.instance("b", "Inner");
+        b.drive_default_clock_domain(slow);
+        m.output("a", a.output("o"));
+        m.output("b", b.output("o"));
+
+        let model = generate_string(m, GenerationOptions::default());
+        let lines = model.lines().collect::<Vec<_>>();
+        // Properties are only checked when their clock domain is clocked and not reset
+        assert!(lines.contains(&"8 input 1 fast_reset"));
+        assert!(lines.contains(&"9 not 1 8"));
+        assert!(lines.contains(&"10 input 1 fast_clk"));
+        assert!(lines.contains(&"11 and 1 10 9"));
+        assert!(lines.contains(&"13 and 1 11 12"));
+        assert!(lines.contains(&"14 bad 13 a.toggles"));
+        assert!(lines.contains(&"17 input 1 slow_reset"));
+        assert!(lines.contains(&"19 input 1 slow_clk"));
+        assert!(lines.contains(&"23 bad 22 b.toggles"));
+        // Registers without default values only change when clocked
+        assert!(lines.contains(&"24 ite 1 19 4 15"));
+        assert!(lines.contains(&"25 next 1 15 24"));
+        // Registers with default values take them on reset
+        assert!(lines.contains(&"29 const 1 0"));
+        assert!(lines.contains(&"30 init 1 4 29"));
+        assert!(lines.contains(&"31 ite 1 17 29 28"));
+        assert!(lines.contains(&"32 ite 1 19 31 4"));
+        assert!(lines.contains(&"33 next 1 4 32"));
+    }
+}
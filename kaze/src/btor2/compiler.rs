@@ -0,0 +1,763 @@
+use super::model::*;
+
+use crate::graph;
+use crate::module_context::*;
+use crate::reset::*;
+use crate::simplify::*;
+
+use typed_arena::Arena;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// A state element whose `init` and `next` lines have yet to be added. These are added after the
+//  logic that refers to the state element, as that logic may be needed to compute them.
+enum PendingState<'graph, 'arena> {
+    Reg {
+        data: &'graph graph::RegisterData<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        state: NodeId,
+    },
+    Mem {
+        mem: &'graph graph::Mem<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        state: NodeId,
+    },
+    ReadPort {
+        mem: &'graph graph::Mem<'graph>,
+        read_port: graph::ReadPort<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        state: NodeId,
+    },
+}
+
+type ContextKey<'graph, 'arena> = *const ModuleContext<'graph, 'arena>;
+type ClockDomainKey<'graph> = *const graph::ClockDomain<'graph>;
+
+pub(super) struct Compiler<'graph, 'arena> {
+    context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+    // Whether the root module has more than one clock domain, in which case each clock domain's
+    //  state only transitions when its clock input is high
+    has_clock_inputs: bool,
+
+    pub model: Model,
+
+    simplifier: Simplifier<'graph>,
+    signal_nodes: HashMap<(ContextKey<'graph, 'arena>, *const graph::Signal<'graph>), NodeId>,
+    mem_states: HashMap<(ContextKey<'graph, 'arena>, *const graph::Mem<'graph>), NodeId>,
+    mem_next_states: HashMap<(ContextKey<'graph, 'arena>, *const graph::Mem<'graph>), NodeId>,
+    clock_inputs: HashMap<ClockDomainKey<'graph>, NodeId>,
+    reset_inputs: HashMap<ClockDomainKey<'graph>, NodeId>,
+    property_enables: HashMap<ClockDomainKey<'graph>, NodeId>,
+    pending_states: Vec<PendingState<'graph, 'arena>>,
+}
+
+impl<'graph, 'arena> Compiler<'graph, 'arena> {
+    pub fn new(
+        m: &'graph graph::Module<'graph>,
+        context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+        simplify: bool,
+    ) -> Compiler<'graph, 'arena> {
+        Compiler {
+            context_arena,
+            has_clock_inputs: !m.clock_domains.borrow().is_empty(),
+
+            model: Model::new(),
+
+            simplifier: Simplifier::new(m.context(), simplify, false),
+            signal_nodes: HashMap::new(),
+            mem_states: HashMap::new(),
+            mem_next_states: HashMap::new(),
+            clock_inputs: HashMap::new(),
+            reset_inputs: HashMap::new(),
+            property_enables: HashMap::new(),
+            pending_states: Vec::new(),
+        }
+    }
+
+    // Returns the input that's high in the transitions in which `clock_domain` (which belongs to
+    //  the root module) is clocked, if the root module has more than one clock domain.
+    fn clock_input(&mut self, clock_domain: &'graph graph::ClockDomain<'graph>) -> Option<NodeId> {
+        if !self.has_clock_inputs {
+            return None;
+        }
+        let key = clock_domain as *const _;
+        if let Some(&input) = self.clock_inputs.get(&key) {
+            return Some(input);
+        }
+        let sort = self.model.bitvec_sort(1);
+        let input = self
+            .model
+            .named_node("input", &[sort], &clock_domain.clock_name());
+        self.clock_inputs.insert(key, input);
+        Some(input)
+    }
+
+    // Returns the (active-high) reset input of `clock_domain`, which belongs to the root module.
+    fn reset_input(&mut self, clock_domain: &'graph graph::ClockDomain<'graph>) -> NodeId {
+        let key = clock_domain as *const _;
+        if let Some(&input) = self.reset_inputs.get(&key) {
+            return input;
+        }
+        let sort = self.model.bitvec_sort(1);
+        let input = self.model.named_node(
+            "input",
+            &[sort],
+            &clock_domain.reset_name(ResetPolarity::ActiveHigh),
+        );
+        self.reset_inputs.insert(key, input);
+        input
+    }
+
+    // Returns a node that's high in the transitions in which properties in `clock_domain` (which
+    //  belongs to the root module) are checked, i.e. when it's clocked and not reset.
+    pub fn property_enable(&mut self, clock_domain: &'graph graph::ClockDomain<'graph>) -> NodeId {
+        let key = clock_domain as *const _;
+        if let Some(&enable) = self.property_enables.get(&key) {
+            return enable;
+        }
+        let sort = self.model.bitvec_sort(1);
+        let reset = self.reset_input(clock_domain);
+        let not_reset = self.model.node("not", &[sort, reset]);
+        let enable = match self.clock_input(clock_domain) {
+            Some(clock) => self.model.node("and", &[sort, clock, not_reset]),
+            _ => not_reset,
+        };
+        self.property_enables.insert(key, enable);
+        enable
+    }
+
+    // Adds the `next` line for `state`, which only takes the value `next` in the transitions in
+    //  which `clock_domain` (which belongs to the root module) is clocked.
+    fn gen_next(
+        &mut self,
+        sort: NodeId,
+        state: NodeId,
+        next: NodeId,
+        clock_domain: &'graph graph::ClockDomain<'graph>,
+    ) {
+        let next = match self.clock_input(clock_domain) {
+            Some(clock) => self.model.node("ite", &[sort, clock, next, state]),
+            _ => next,
+        };
+        self.model.node("next", &[sort, state, next]);
+    }
+
+    pub fn compile_signal(
+        &mut self,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+    ) -> NodeId {
+        enum Frame<'graph, 'arena> {
+            Enter {
+                signal: &'graph graph::Signal<'graph>,
+                context: &'arena ModuleContext<'graph, 'arena>,
+            },
+            Leave {
+                signal: &'graph graph::Signal<'graph>,
+                context: &'arena ModuleContext<'graph, 'arena>,
+            },
+        }
+
+        let mut frames = Vec::new();
+        frames.push(Frame::Enter { signal, context });
+
+        let mut results = Vec::new();
+
+        while let Some(frame) = frames.pop() {
+            if let Some((key, node)) = match frame {
+                Frame::Enter { signal, context } => {
+                    let signal = self.simplifier.simplify(signal);
+                    let key = (context as *const _, signal as *const _);
+                    if let Some(&node) = self.signal_nodes.get(&key) {
+                        results.push(node);
+                        continue;
+                    }
+
+                    match signal.data {
+                        graph::SignalData::Lit {
+                            ref value,
+                            bit_width,
+                        } => Some((key, self.model.constant(value, bit_width))),
+
+                        graph::SignalData::Input {
+                            ref name,
+                            bit_width,
+                        } => {
+                            if let Some((instance, parent)) = context.instance_and_parent {
+                                frames.push(Frame::Enter {
+                                    signal: instance.driven_inputs.borrow()[name],
+                                    context: parent,
+                                });
+                                None
+                            } else {
+                                let sort = self.model.bitvec_sort(bit_width);
+                                Some((key, self.model.named_node("input", &[sort], name)))
+                            }
+                        }
+
+                        graph::SignalData::Reg { data } => {
+                            let sort = self.model.bitvec_sort(data.bit_width);
+                            let state = self.model.named_node(
+                                "state",
+                                &[sort],
+                                &context.relative_path(&data.name),
+                            );
+                            self.pending_states.push(PendingState::Reg {
+                                data,
+                                context,
+                                state,
+                            });
+                            Some((key, state))
+                        }
+
+                        graph::SignalData::UnOp { source, .. }
+                        | graph::SignalData::Bits { source, .. }
+                        | graph::SignalData::Repeat { source, .. } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: source,
+                                context,
+                            });
+                            None
+                        }
+                        graph::SignalData::SimpleBinOp { lhs, rhs, .. }
+                        | graph::SignalData::AdditiveBinOp { lhs, rhs, .. }
+                        | graph::SignalData::ComparisonBinOp { lhs, rhs, .. }
+                        | graph::SignalData::ShiftBinOp { lhs, rhs, .. }
+                        | graph::SignalData::Mul { lhs, rhs, .. }
+                        | graph::SignalData::MulSigned { lhs, rhs, .. }
+                        | graph::SignalData::Concat { lhs, rhs, .. } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: lhs,
+                                context,
+                            });
+                            frames.push(Frame::Enter {
+                                signal: rhs,
+                                context,
+                            });
+                            None
+                        }
+
+                        graph::SignalData::Mux {
+                            cond,
+                            when_true,
+                            when_false,
+                            ..
+                        } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: cond,
+                                context,
+                            });
+                            frames.push(Frame::Enter {
+                                signal: when_true,
+                                context,
+                            });
+                            frames.push(Frame::Enter {
+                                signal: when_false,
+                                context,
+                            });
+                            None
+                        }
+
+                        graph::SignalData::InstanceOutput {
+                            instance, ref name, ..
+                        } => {
+                            let output = instance.instantiated_module.outputs.borrow()[name];
+                            frames.push(Frame::Enter {
+                                signal: output,
+                                context: context.get_child(instance, self.context_arena),
+                            });
+                            None
+                        }
+
+                        graph::SignalData::MemReadPortOutput {
+                            mem,
+                            address,
+                            enable,
+                            read_during_write,
+                        } => {
+                            // The value read is held in its own state, as it's only updated on the clock edge
+                            let sort = self.model.bitvec_sort(mem.element_bit_width);
+                            let state = self.model.node("state", &[sort]);
+                            self.pending_states.push(PendingState::ReadPort {
+                                mem,
+                                read_port: graph::ReadPort {
+                                    address,
+                                    enable,
+                                    read_during_write,
+                                },
+                                context,
+                                state,
+                            });
+                            Some((key, state))
+                        }
+
+                        graph::SignalData::MemAsyncReadPortOutput { address, .. } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: address,
+                                context,
+                            });
+                            None
+                        }
+                    }
+                }
+                Frame::Leave { signal, context } => {
+                    let key = (context as *const _, signal as *const _);
+
+                    match signal.data {
+                        graph::SignalData::Lit { .. } => unreachable!(),
+
+                        graph::SignalData::Input { .. } => unreachable!(),
+
+                        graph::SignalData::Reg { .. } => unreachable!(),
+
+                        graph::SignalData::UnOp { op, bit_width, .. } => {
+                            let source = results.pop().unwrap();
+                            let sort = self.model.bitvec_sort(bit_width);
+                            let op = match op {
+                                graph::UnOp::Not => "not",
+                            };
+                            Some((key, self.model.node(op, &[sort, source])))
+                        }
+                        graph::SignalData::SimpleBinOp { op, bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let sort = self.model.bitvec_sort(bit_width);
+                            let op = match op {
+                                graph::SimpleBinOp::BitAnd => "and",
+                                graph::SimpleBinOp::BitOr => "or",
+                                graph::SimpleBinOp::BitXor => "xor",
+                            };
+                            Some((key, self.model.node(op, &[sort, lhs, rhs])))
+                        }
+                        graph::SignalData::AdditiveBinOp { op, bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let sort = self.model.bitvec_sort(bit_width);
+                            let op = match op {
+                                graph::AdditiveBinOp::Add => "add",
+                                graph::AdditiveBinOp::Sub => "sub",
+                            };
+                            Some((key, self.model.node(op, &[sort, lhs, rhs])))
+                        }
+                        graph::SignalData::ComparisonBinOp { op, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let sort = self.model.bitvec_sort(1);
+                            let op = match op {
+                                graph::ComparisonBinOp::Equal => "eq",
+                                graph::ComparisonBinOp::NotEqual => "neq",
+                                graph::ComparisonBinOp::LessThan => "ult",
+                                graph::ComparisonBinOp::LessThanEqual => "ulte",
+                                graph::ComparisonBinOp::GreaterThan => "ugt",
+                                graph::ComparisonBinOp::GreaterThanEqual => "ugte",
+                                graph::ComparisonBinOp::LessThanSigned => "slt",
+                                graph::ComparisonBinOp::LessThanEqualSigned => "slte",
+                                graph::ComparisonBinOp::GreaterThanSigned => "sgt",
+                                graph::ComparisonBinOp::GreaterThanEqualSigned => "sgte",
+                            };
+                            Some((key, self.model.node(op, &[sort, lhs, rhs])))
+                        }
+                        graph::SignalData::ShiftBinOp {
+                            lhs,
+                            rhs,
+                            op,
+                            bit_width,
+                        } => {
+                            let lhs_bit_width = lhs.bit_width();
+                            let rhs_bit_width = rhs.bit_width();
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let rhs = self.gen_shift_amount(rhs, rhs_bit_width, lhs_bit_width);
+                            let sort = self.model.bitvec_sort(bit_width);
+                            let op = match op {
+                                graph::ShiftBinOp::Shl => "sll",
+                                graph::ShiftBinOp::Shr => "srl",
+                                graph::ShiftBinOp::ShrArithmetic => "sra",
+                            };
+                            Some((key, self.model.node(op, &[sort, lhs, rhs])))
+                        }
+
+                        graph::SignalData::Mul {
+                            lhs,
+                            rhs,
+                            bit_width,
+                        } => {
+                            let lhs_bit_width = lhs.bit_width();
+                            let rhs_bit_width = rhs.bit_width();
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let lhs = self.gen_extend("uext", lhs, lhs_bit_width, bit_width);
+                            let rhs = self.gen_extend("uext", rhs, rhs_bit_width, bit_width);
+                            let sort = self.model.bitvec_sort(bit_width);
+                            Some((key, self.model.node("mul", &[sort, lhs, rhs])))
+                        }
+                        graph::SignalData::MulSigned {
+                            lhs,
+                            rhs,
+                            bit_width,
+                        } => {
+                            let lhs_bit_width = lhs.bit_width();
+                            let rhs_bit_width = rhs.bit_width();
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let lhs = self.gen_extend("sext", lhs, lhs_bit_width, bit_width);
+                            let rhs = self.gen_extend("sext", rhs, rhs_bit_width, bit_width);
+                            let sort = self.model.bitvec_sort(bit_width);
+                            Some((key, self.model.node("mul", &[sort, lhs, rhs])))
+                        }
+
+                        graph::SignalData::Bits {
+                            range_high,
+                            range_low,
+                            ..
+                        } => {
+                            let source = results.pop().unwrap();
+                            let sort = self.model.bitvec_sort(range_high - range_low + 1);
+                            Some((
+                                key,
+                                self.model
+                                    .node("slice", &[sort, source, range_high, range_low]),
+                            ))
+                        }
+
+                        graph::SignalData::Repeat { source, count, .. } => {
+                            let source_bit_width = source.bit_width();
+                            let source = results.pop().unwrap();
+                            let mut node = source;
+                            for i in 1..count {
+                                let sort = self.model.bitvec_sort(source_bit_width * (i + 1));
+                                node = self.model.node("concat", &[sort, node, source]);
+                            }
+                            Some((key, node))
+                        }
+                        graph::SignalData::Concat { bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let sort = self.model.bitvec_sort(bit_width);
+                            Some((key, self.model.node("concat", &[sort, lhs, rhs])))
+                        }
+
+                        graph::SignalData::Mux { bit_width, .. } => {
+                            let cond = results.pop().unwrap();
+                            let when_true = results.pop().unwrap();
+                            let when_false = results.pop().unwrap();
+                            let sort = self.model.bitvec_sort(bit_width);
+                            Some((
+                                key,
+                                self.model.node("ite", &[sort, cond, when_true, when_false]),
+                            ))
+                        }
+
+                        graph::SignalData::InstanceOutput { .. } => unreachable!(),
+
+                        graph::SignalData::MemReadPortOutput { .. } => unreachable!(),
+
+                        graph::SignalData::MemAsyncReadPortOutput { mem, .. } => {
+                            let address = results.pop().unwrap();
+                            let mem_state = self.mem_state(mem, context);
+                            let sort = self.model.bitvec_sort(mem.element_bit_width);
+                            Some((key, self.model.node("read", &[sort, mem_state, address])))
+                        }
+                    }
+                }
+            } {
+                self.signal_nodes.insert(key, node);
+                results.push(node);
+            }
+        }
+
+        results.pop().unwrap()
+    }
+
+    // Adds the `init` and `next` lines for every state element referred to so far, compiling the
+    //  logic they depend on (which may refer to further state elements) along the way.
+    pub fn compile_pending_states(&mut self) {
+        while let Some(pending_state) = self.pending_states.pop() {
+            match pending_state {
+                PendingState::Reg {
+                    data,
+                    context,
+                    state,
+                } => {
+                    let sort = self.model.bitvec_sort(data.bit_width);
+                    let clock_domain = context.root_clock_domain(data.resolved_clock_domain());
+                    let mut next = self.compile_signal(data.next.borrow().unwrap(), context);
+                    if let Some(ref initial_value) = *data.initial_value.borrow() {
+                        let initial_value = self.model.constant(initial_value, data.bit_width);
+                        self.model.node("init", &[sort, state, initial_value]);
+                        let reset = self.reset_input(clock_domain);
+                        next = self.model.node("ite", &[sort, reset, initial_value, next]);
+                    }
+                    self.gen_next(sort, state, next, clock_domain);
+                }
+                PendingState::Mem {
+                    mem,
+                    context,
+                    state,
+                } => {
+                    let sort = self
+                        .model
+                        .array_sort(mem.address_bit_width, mem.element_bit_width);
+                    if let Some(ref initial_contents) = *mem.initial_contents.borrow() {
+                        let initial_contents = self.gen_mem_initial_contents(mem, initial_contents);
+                        self.model.node("init", &[sort, state, initial_contents]);
+                    }
+                    let next = self.mem_next_state(mem, context);
+                    let clock_domain = context.root_clock_domain(mem.resolved_clock_domain());
+                    self.gen_next(sort, state, next, clock_domain);
+                }
+                PendingState::ReadPort {
+                    mem,
+                    read_port,
+                    context,
+                    state,
+                } => {
+                    let sort = self.model.bitvec_sort(mem.element_bit_width);
+                    let address = self.compile_signal(read_port.address, context);
+                    let enable = self.compile_signal(read_port.enable, context);
+                    let value = match read_port.read_during_write {
+                        graph::ReadDuringWrite::ReadFirst => {
+                            let mem_state = self.mem_state(mem, context);
+                            self.model.node("read", &[sort, mem_state, address])
+                        }
+                        graph::ReadDuringWrite::WriteFirst => {
+                            let mem_next_state = self.mem_next_state(mem, context);
+                            self.model.node("read", &[sort, mem_next_state, address])
+                        }
+                        graph::ReadDuringWrite::DontCare => {
+                            let mem_state = self.mem_state(mem, context);
+                            let value = self.model.node("read", &[sort, mem_state, address]);
+                            // An unconstrained value is read if any write port writes the location
+                            match self.gen_write_collision(mem, address, context) {
+                                Some(collision) => {
+                                    let undefined = self.model.node("input", &[sort]);
+                                    self.model.node("ite", &[sort, collision, undefined, value])
+                                }
+                                _ => value,
+                            }
+                        }
+                    };
+                    let next = self.model.node("ite", &[sort, enable, value, state]);
+                    let clock_domain = context.root_clock_domain(mem.resolved_clock_domain());
+                    self.gen_next(sort, state, next, clock_domain);
+                }
+            }
+        }
+    }
+
+    fn mem_state(
+        &mut self,
+        mem: &'graph graph::Mem<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+    ) -> NodeId {
+        let key = (context as *const _, mem as *const _);
+        if let Some(&state) = self.mem_states.get(&key) {
+            return state;
+        }
+        let sort = self
+            .model
+            .array_sort(mem.address_bit_width, mem.element_bit_width);
+        let state = self
+            .model
+            .named_node("state", &[sort], &context.relative_path(&mem.name));
+        self.mem_states.insert(key, state);
+        self.pending_states.push(PendingState::Mem {
+            mem,
+            context,
+            state,
+        });
+        state
+    }
+
+    // Returns the contents of `mem` after its write ports have been applied, in priority order.
+    fn mem_next_state(
+        &mut self,
+        mem: &'graph graph::Mem<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+    ) -> NodeId {
+        let key = (context as *const _, mem as *const _);
+        if let Some(&next) = self.mem_next_states.get(&key) {
+            return next;
+        }
+        let sort = self
+            .model
+            .array_sort(mem.address_bit_width, mem.element_bit_width);
+        let element_sort = self.model.bitvec_sort(mem.element_bit_width);
+        let mut next = self.mem_state(mem, context);
+        let write_ports = mem.write_ports.borrow().clone();
+        for write_port in write_ports.iter() {
+            let address = self.compile_signal(write_port.address, context);
+            let value = self.compile_signal(write_port.value, context);
+            let enable = self.compile_signal(write_port.enable, context);
+            let value = match write_port.mask {
+                Some(mask) => {
+                    // Merge the written lanes with the element's previous value
+                    let mask = self.gen_lane_mask(mask, mem.element_bit_width, context);
+                    let inverse_mask = self.model.node("not", &[element_sort, mask]);
+                    let previous = self.model.node("read", &[element_sort, next, address]);
+                    let previous = self
+                        .model
+                        .node("and", &[element_sort, previous, inverse_mask]);
+                    let value = self.model.node("and", &[element_sort, value, mask]);
+                    self.model.node("or", &[element_sort, previous, value])
+                }
+                _ => value,
+            };
+            let written = self.model.node("write", &[sort, next, address, value]);
+            next = self.model.node("ite", &[sort, enable, written, next]);
+        }
+        self.mem_next_states.insert(key, next);
+        next
+    }
+
+    fn gen_mem_initial_contents(
+        &mut self,
+        mem: &'graph graph::Mem<'graph>,
+        initial_contents: &[graph::Constant],
+    ) -> NodeId {
+        let element_bit_width = mem.element_bit_width;
+
+        // Arrays can be initialized directly with a single element value, so the most common
+        //  element is used for the whole array, and only the others are written individually
+        let elements = initial_contents
+            .iter()
+            .map(|element| element.binary_string(element_bit_width))
+            .collect::<Vec<_>>();
+        let mut counts = HashMap::new();
+        for element in elements.iter() {
+            *counts.entry(element).or_insert(0) += 1;
+        }
+        let (common_index, _) = elements
+            .iter()
+            .enumerate()
+            .max_by_key(|&(index, element)| (counts[element], std::cmp::Reverse(index)))
+            .unwrap();
+        let common_element = self
+            .model
+            .constant(&initial_contents[common_index], element_bit_width);
+        if counts[&elements[common_index]] == elements.len() {
+            return common_element;
+        }
+
+        // A constant array can only be created as a state, which keeps its initial value
+        let sort = self
+            .model
+            .array_sort(mem.address_bit_width, element_bit_width);
+        let mut contents = self.model.node("state", &[sort]);
+        self.model.node("init", &[sort, contents, common_element]);
+        self.model.node("next", &[sort, contents, contents]);
+        for (address, element) in initial_contents.iter().enumerate() {
+            if elements[address] == elements[common_index] {
+                continue;
+            }
+            let address = self
+                .model
+                .constant(&graph::Constant::U64(address as _), mem.address_bit_width);
+            let element = self.model.constant(element, element_bit_width);
+            contents = self
+                .model
+                .node("write", &[sort, contents, address, element]);
+        }
+        contents
+    }
+
+    // Returns a node that's high if any of `mem`'s write ports writes the location at `address`, if it has any write ports.
+    fn gen_write_collision(
+        &mut self,
+        mem: &'graph graph::Mem<'graph>,
+        address: NodeId,
+        context: &'arena ModuleContext<'graph, 'arena>,
+    ) -> Option<NodeId> {
+        let bool_sort = self.model.bitvec_sort(1);
+        let mut ret = None;
+        let write_ports = mem.write_ports.borrow().clone();
+        for write_port in write_ports.iter() {
+            let write_address = self.compile_signal(write_port.address, context);
+            let enable = self.compile_signal(write_port.enable, context);
+            let same_address = self.model.node("eq", &[bool_sort, write_address, address]);
+            let collision = self.model.node("and", &[bool_sort, enable, same_address]);
+            ret = Some(match ret {
+                Some(ret) => self.model.node("or", &[bool_sort, ret, collision]),
+                _ => collision,
+            });
+        }
+        ret
+    }
+
+    // Expands each bit of a write mask to its lane, producing a bit mask as wide as an element.
+    fn gen_lane_mask(
+        &mut self,
+        mask: graph::WriteMask<'graph>,
+        element_bit_width: u32,
+        context: &'arena ModuleContext<'graph, 'arena>,
+    ) -> NodeId {
+        let bool_sort = self.model.bitvec_sort(1);
+        let num_lanes = element_bit_width / mask.granularity;
+        let mask_bits = self.compile_signal(mask.mask, context);
+        let mut ret = None;
+        for lane in (0..num_lanes).rev() {
+            let bit = self
+                .model
+                .node("slice", &[bool_sort, mask_bits, lane, lane]);
+            let bits = self.gen_extend("sext", bit, 1, mask.granularity);
+            ret = Some(match ret {
+                Some(ret) => {
+                    let sort = self
+                        .model
+                        .bitvec_sort((num_lanes - lane) * mask.granularity);
+                    self.model.node("concat", &[sort, ret, bits])
+                }
+                _ => bits,
+            });
+        }
+        ret.unwrap()
+    }
+
+    fn gen_extend(
+        &mut self,
+        op: &str,
+        node: NodeId,
+        bit_width: u32,
+        target_bit_width: u32,
+    ) -> NodeId {
+        if bit_width == target_bit_width {
+            return node;
+        }
+        let sort = self.model.bitvec_sort(target_bit_width);
+        self.model
+            .node(op, &[sort, node, target_bit_width - bit_width])
+    }
+
+    // BTOR2's shift operators require their operands to have the same bit width. Shift amounts
+    //  that are at least as large as the shifted value's bit width shift out all of its bits.
+    fn gen_shift_amount(
+        &mut self,
+        amount: NodeId,
+        bit_width: u32,
+        target_bit_width: u32,
+    ) -> NodeId {
+        match bit_width.cmp(&target_bit_width) {
+            Ordering::Less => self.gen_extend("uext", amount, bit_width, target_bit_width),
+            Ordering::Equal => amount,
+            Ordering::Greater => {
+                // Clamp the amount before truncating it, so that large amounts still shift out
+                //  all bits (`target_bit_width` always fits into `target_bit_width` bits)
+                let bool_sort = self.model.bitvec_sort(1);
+                let sort = self.model.bitvec_sort(bit_width);
+                let target_sort = self.model.bitvec_sort(target_bit_width);
+                let max = self
+                    .model
+                    .constant(&graph::Constant::U32(target_bit_width), bit_width);
+                let overflow = self.model.node("ugte", &[bool_sort, amount, max]);
+                let amount = self.model.node("ite", &[sort, overflow, max, amount]);
+                self.model
+                    .node("slice", &[target_sort, amount, target_bit_width - 1, 0])
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::graph;
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+pub(super) type NodeId = u32;
+
+// A BTOR2 model under construction. Every line in a BTOR2 model (sorts included) has a unique
+//  id, and may only refer to lines before it, so lines are numbered in the order they're added.
+pub(super) struct Model {
+    lines: Vec<String>,
+    num_nodes: NodeId,
+
+    bitvec_sorts: HashMap<u32, NodeId>,
+    array_sorts: HashMap<(u32, u32), NodeId>,
+    consts: HashMap<(u32, String), NodeId>,
+}
+
+impl Model {
+    pub fn new() -> Model {
+        Model {
+            lines: Vec::new(),
+            num_nodes: 0,
+
+            bitvec_sorts: HashMap::new(),
+            array_sorts: HashMap::new(),
+            consts: HashMap::new(),
+        }
+    }
+
+    pub fn bitvec_sort(&mut self, bit_width: u32) -> NodeId {
+        if let Some(&sort) = self.bitvec_sorts.get(&bit_width) {
+            return sort;
+        }
+        let sort = self.push(format!("sort bitvec {}", bit_width));
+        self.bitvec_sorts.insert(bit_width, sort);
+        sort
+    }
+
+    pub fn array_sort(&mut self, address_bit_width: u32, element_bit_width: u32) -> NodeId {
+        let key = (address_bit_width, element_bit_width);
+        if let Some(&sort) = self.array_sorts.get(&key) {
+            return sort;
+        }
+        let address_sort = self.bitvec_sort(address_bit_width);
+        let element_sort = self.bitvec_sort(element_bit_width);
+        let sort = self.push(format!("sort array {} {}", address_sort, element_sort));
+        self.array_sorts.insert(key, sort);
+        sort
+    }
+
+    pub fn constant(&mut self, value: &graph::Constant, bit_width: u32) -> NodeId {
+        let key = (bit_width, value.binary_string(bit_width));
+        if let Some(&node) = self.consts.get(&key) {
+            return node;
+        }
+        let sort = self.bitvec_sort(bit_width);
+        let node = self.push(format!("const {} {}", sort, key.1));
+        self.consts.insert(key, node);
+        node
+    }
+
+    // Adds a line with the specified operator and numeric arguments (sorts, nodes, and indices).
+    pub fn node(&mut self, op: &str, args: &[u32]) -> NodeId {
+        self.push(Self::format_node(op, args))
+    }
+
+    // Like `node`, but with a symbol naming the line.
+    pub fn named_node(&mut self, op: &str, args: &[u32], symbol: &str) -> NodeId {
+        self.push(format!("{} {}", Self::format_node(op, args), symbol))
+    }
+
+    pub fn comment(&mut self, comment: &str) {
+        self.lines.push(format!("; {}", comment));
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for line in self.lines.iter() {
+            writeln!(w, "{}", line)?;
+        }
+
+        Ok(())
+    }
+
+    fn format_node(op: &str, args: &[u32]) -> String {
+        let mut ret = op.to_string();
+        for arg in args.iter() {
+            ret.push_str(&format!(" {}", arg));
+        }
+        ret
+    }
+
+    fn push(&mut self, line: String) -> NodeId {
+        self.num_nodes += 1;
+        self.lines.push(format!("{} {}", self.num_nodes, line));
+        self.num_nodes
+    }
+}
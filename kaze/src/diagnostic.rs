@@ -0,0 +1,242 @@
+use crate::error::*;
+use crate::graph;
+use crate::lint::*;
+use crate::validation::*;
+
+use std::fmt;
+
+/// The severity of a [`Diagnostic`].
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// The problem doesn't prevent code generation, but likely indicates a bug in the design.
+    Warning,
+    /// The problem prevents code generation.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The problem reported by a [`Diagnostic`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A problem that prevents code generation. [`Diagnostic`]s of this kind have [`Severity::Error`].
+    Error(Error),
+    /// A likely mistake in the design, as reported by [`lint::check`](crate::lint::check). [`Diagnostic`]s of this kind have [`Severity::Warning`].
+    Lint(LintKind),
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticKind::Error(error) => write!(f, "{}", error),
+            DiagnosticKind::Lint(lint) => write!(f, "{}", lint),
+        }
+    }
+}
+
+/// A single problem found in a [`Module`](crate::Module) hierarchy by [`diagnose`] or [`lint::check`](crate::lint::check).
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// How severe this problem is.
+    pub severity: Severity,
+    /// The hierarchical path of the item this problem refers to, made up of the root module's name, followed by the names of the instances leading to the item and the name of the item itself, separated by `.` (e.g. `top.cpu.alu.r`).
+    ///
+    /// For problems with unnamed items (such as muxes), this is the path of the instance containing the item.
+    pub path: String,
+    /// The problem itself.
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.kind)
+    }
+}
+
+/// Validates `m` and every [`Module`](crate::Module) instantiated (directly or indirectly) within it, returning every problem found.
+///
+/// Unlike the code generators, which stop at the first problem they find, this walks the whole hierarchy, so all problems in a design can be fixed at once.
+/// Errors are returned in the same order the code generators would report them in. If there are none, the warnings reported by [`lint::check`](crate::lint::check) are returned instead; code can be generated for `m` if none of the returned [`Diagnostic`]s have [`Severity::Error`].
+///
+/// Note that if `m` contains a recursive definition, the hierarchy below the offending instance(s) can't be walked, so only problems with instances are reported in this case.
+///
+/// # Examples
+///
+/// ```
+/// use kaze::*;
+///
+/// let c = Context::new();
+///
+/// let inner = c.module("Inner");
+/// let r = inner.reg("r", 1);
+/// inner.output("o", r.value);
+///
+/// let top = c.module("Top");
+/// let i1 = top.instance("i1", "Inner");
+/// let i2 = top.instance("i2", "Inner");
+/// top.output("o", i1.output("o") & i2.output("o"));
+///
+/// let diagnostics = diagnose(top);
+/// assert_eq!(diagnostics.len(), 2);
+/// assert_eq!(diagnostics[0].severity, Severity::Error);
+/// assert_eq!(diagnostics[0].path, "Top.i1.r");
+/// assert_eq!(diagnostics[1].path, "Top.i2.r");
+/// ```
+pub fn diagnose<'a>(m: &'a graph::Module<'a>) -> Vec<Diagnostic> {
+    let diagnostics = collect_diagnostics(m);
+    if !diagnostics.is_empty() {
+        return diagnostics;
+    }
+    collect_lints(m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::*;
+
+    #[test]
+    fn no_diagnostics() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        let r = a.reg("r", 1);
+        r.drive_next(!r.value);
+        a.output("o", r.value);
+
+        assert!(diagnose(a).is_empty());
+    }
+
+    #[test]
+    fn collects_all_errors() {
+        let c = Context::new();
+
+        let alu = c.module("Alu");
+        let _ = alu.reg("r", 1);
+        let i = alu.input("i", 1);
+        alu.output("o", i);
+
+        let cpu = c.module("Cpu");
+        let alu_inst = cpu.instance("alu", "Alu");
+        let m = cpu.mem("m", 1, 1);
+        let _ = m.read_port(cpu.low(), cpu.low());
+        cpu.output("o", alu_inst.output("o"));
+
+        let top = c.module("Top");
+        let cpu_inst = top.instance("cpu", "Cpu");
+        top.output("o", cpu_inst.output("o"));
+
+        let diagnostics = diagnose(top);
+        let paths = diagnostics
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["Top.cpu.alu.i", "Top.cpu.alu.r", "Top.cpu.m"]);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+        assert!(matches!(
+            diagnostics[0].kind,
+            DiagnosticKind::Error(Error::UndrivenInstanceInput { .. })
+        ));
+        assert!(matches!(
+            diagnostics[1].kind,
+            DiagnosticKind::Error(Error::UndrivenRegister { .. })
+        ));
+        assert!(matches!(
+            diagnostics[2].kind,
+            DiagnosticKind::Error(Error::MemWithoutInitialContentsOrWritePort { .. })
+        ));
+        // The code generators stop at the first error
+        assert!(matches!(
+            validate_module_hierarchy(top),
+            Err(Error::UndrivenInstanceInput { .. })
+        ));
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "error: Top.cpu.alu.r: Cannot generate code for module \"Top\" because module \"Alu\" contains a register called \"r\" which is not driven."
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_stop_traversal() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        let b = c.module("B");
+        let _ = b.reg("r", 1);
+
+        let _ = a.instance("b1", "B");
+        let _ = a.instance("b2", "B");
+        let _ = b.instance("a", "A");
+
+        let diagnostics = diagnose(a);
+        let paths = diagnostics
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["A.b1.a", "A.b2.a"]);
+        assert!(diagnostics.iter().all(|d| matches!(
+            d.kind,
+            DiagnosticKind::Error(Error::RecursiveDefinition { .. })
+        )));
+    }
+
+    #[test]
+    fn combinational_loops() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        a.output("o", a.input("i", 1));
+
+        let b = c.module("B");
+        let a1 = b.instance("a1", "A");
+        let a2 = b.instance("a2", "A");
+        a1.drive_input("i", a1.output("o"));
+        a2.drive_input("i", a2.output("o"));
+
+        let diagnostics = diagnose(b);
+        let paths = diagnostics
+            .iter()
+            .map(|d| d.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["B.a1.o", "B.a2.o"]);
+        assert!(diagnostics.iter().all(|d| matches!(
+            d.kind,
+            DiagnosticKind::Error(Error::CombinationalLoop { .. })
+        )));
+    }
+
+    #[test]
+    fn warnings_without_errors() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        let _ = a.input("i", 1);
+        let r = a.reg("r", 1);
+        r.drive_next(!r.value);
+        a.output("o", r.value);
+
+        let diagnostics = diagnose(a);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].path, "A.i");
+        assert!(matches!(
+            diagnostics[0].kind,
+            DiagnosticKind::Lint(LintKind::UnusedInput { .. })
+        ));
+
+        // Warnings aren't reported alongside errors
+        let _ = a.reg("s", 1);
+        let diagnostics = diagnose(a);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+}
@@ -0,0 +1,515 @@
+use crate::graph::{MAX_SIGNAL_BIT_WIDTH, MIN_SIGNAL_BIT_WIDTH};
+
+use std::fmt;
+use std::io;
+
+/// An error describing misuse of the kaze API, or a failure to generate code.
+///
+/// `Error`s are returned by the fallible (`try_`) variants of graph methods, as well as by the code generators (e.g. [`sim::generate`](crate::sim::generate) and [`verilog::generate`](crate::verilog::generate)).
+/// Each variant carries the names of the items involved (modules, instances, signals, etc.), and its [`Display`](fmt::Display) implementation produces the same message that the corresponding panicking method would.
+///
+/// `Error`s can be converted into [`std::io::Error`]s, so the code generators can be used in functions returning [`std::io::Result`] with the `?` operator.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An I/O error occurred while writing generated code or a memory file.
+    Io(io::Error),
+
+    /// A module called `module` already exists in the [`Context`](crate::Context).
+    ModuleAlreadyExists { module: String },
+    /// `module` attempted to instantiate `instantiated_module`, which doesn't exist in the [`Context`](crate::Context).
+    ModuleNotFound {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+    },
+
+    /// A literal in `module` was created with an invalid bit width.
+    InvalidLitBitWidth { module: String, bit_width: u32 },
+    /// An input called `input` in `module` was created with an invalid bit width.
+    InvalidInputBitWidth {
+        module: String,
+        input: String,
+        bit_width: u32,
+    },
+    /// A register called `register` in `module` was created with an invalid bit width.
+    InvalidRegisterBitWidth {
+        module: String,
+        register: String,
+        bit_width: u32,
+    },
+    /// A memory called `mem` in `module` was created with an invalid address bit width.
+    InvalidMemAddressBitWidth {
+        module: String,
+        mem: String,
+        bit_width: u32,
+    },
+    /// A memory called `mem` in `module` was created with an invalid element bit width.
+    InvalidMemElementBitWidth {
+        module: String,
+        mem: String,
+        bit_width: u32,
+    },
+    /// A literal in `module` was created with a value that requires `required_bits` bits, which doesn't fit into `bit_width` bits.
+    LitValueTooWide {
+        module: String,
+        value: String,
+        bit_width: u32,
+        required_bits: u32,
+    },
+    /// An output called `output` in `module` was driven by a signal from another module.
+    OutputFromAnotherModule { module: String, output: String },
+    /// Signals from different modules were combined in `module`.
+    SignalsFromDifferentModules { module: String },
+    /// Bit `index` was taken from a signal in `module` that's only `bit_width` bits wide.
+    BitIndexOutOfRange {
+        module: String,
+        index: u32,
+        bit_width: u32,
+    },
+    /// A range of bits was taken from a signal in `module` with a lower bound (`range_low`) that's out of range for its `bit_width`.
+    BitsRangeLowOutOfRange {
+        module: String,
+        range_low: u32,
+        bit_width: u32,
+    },
+    /// A range of bits was taken from a signal in `module` with an upper bound (`range_high`) that's out of range for its `bit_width`.
+    BitsRangeHighOutOfRange {
+        module: String,
+        range_high: u32,
+        bit_width: u32,
+    },
+    /// A range of bits was taken from a signal in `module` with a lower bound that's greater than its upper bound.
+    BitsRangeInverted {
+        module: String,
+        range_high: u32,
+        range_low: u32,
+    },
+    /// A `source_bit_width`-bit signal in `module` was repeated `count` times, resulting in an invalid bit width.
+    InvalidRepeatBitWidth {
+        module: String,
+        source_bit_width: u32,
+        count: u32,
+        bit_width: u64,
+    },
+    /// Signals in `module` were concatenated, resulting in a bit width wider than [`MAX_SIGNAL_BIT_WIDTH`].
+    ConcatBitWidthTooWide {
+        module: String,
+        lhs_bit_width: u32,
+        rhs_bit_width: u32,
+        bit_width: u32,
+    },
+    /// Signals in `module` were multiplied, resulting in a bit width wider than [`MAX_SIGNAL_BIT_WIDTH`].
+    MulBitWidthTooWide {
+        module: String,
+        lhs_bit_width: u32,
+        rhs_bit_width: u32,
+        bit_width: u32,
+    },
+    /// Signals with different bit widths were combined in `module` by an operation that requires equal bit widths.
+    SignalBitWidthMismatch {
+        module: String,
+        lhs_bit_width: u32,
+        rhs_bit_width: u32,
+    },
+    /// A signed comparison was performed between 1-bit signals in `module`.
+    SignedComparisonOfSingleBitSignals { module: String },
+    /// A multiplexer in `module` was created with a condition that isn't 1 bit wide.
+    InvalidMuxConditionBitWidth { module: String, bit_width: u32 },
+    /// A multiplexer in `module` was created with inputs of different bit widths.
+    MuxBitWidthMismatch {
+        module: String,
+        when_true_bit_width: u32,
+        when_false_bit_width: u32,
+    },
+    /// A clock domain called `clk` was created in `module`, but this name is reserved for the default clock domain.
+    ReservedClockDomainName { module: String },
+    /// A clock domain called `clock_domain` already exists in `module`.
+    ClockDomainAlreadyExists {
+        module: String,
+        clock_domain: String,
+    },
+
+    /// A default value was specified for `register` in `module`, but it already has one.
+    RegisterDefaultValueAlreadySpecified { module: String, register: String },
+    /// A default value was specified for `register` in `module` that doesn't fit into its bit width.
+    RegisterDefaultValueTooWide {
+        module: String,
+        register: String,
+        value: String,
+        bit_width: u32,
+        required_bits: u32,
+    },
+    /// `register` in `module` was driven by a signal from another module.
+    RegisterNextFromAnotherModule { module: String, register: String },
+    /// `register` in `module` was driven by a signal with a different bit width.
+    RegisterNextBitWidthMismatch {
+        module: String,
+        register: String,
+        next_bit_width: u32,
+        bit_width: u32,
+    },
+    /// `register` in `module` was driven, but its next value is already driven.
+    RegisterNextAlreadyDriven { module: String, register: String },
+    /// `register` in `module` was bound to a clock domain from another module.
+    RegisterClockDomainFromAnotherModule { module: String, register: String },
+    /// `register` in `module` was bound to a clock domain, but it's already bound to one.
+    RegisterClockDomainAlreadyBound { module: String, register: String },
+
+    /// Initial contents were specified for `mem` in `module`, but it already has initial contents.
+    MemInitialContentsAlreadySpecified { module: String, mem: String },
+    /// Initial contents with `len` elements were specified for `mem` in `module`, which requires `required_len` elements.
+    MemInitialContentsLengthMismatch {
+        module: String,
+        mem: String,
+        len: usize,
+        address_bit_width: u32,
+        required_len: usize,
+    },
+    /// Initial contents were specified for `mem` in `module`, but the element at `index` doesn't fit into its element bit width.
+    MemInitialContentsElementTooWide {
+        module: String,
+        mem: String,
+        element_bit_width: u32,
+        index: usize,
+        value: String,
+        required_bits: u32,
+    },
+    /// Initial contents were loaded for `mem` in `module` from the memory file at `path`, but the file couldn't be read or isn't a valid memory file, as described by `message`.
+    MemInitialContentsFileInvalid {
+        module: String,
+        mem: String,
+        path: String,
+        message: String,
+    },
+    /// The initial contents of `mem` in `module` were requested, but it doesn't have initial contents.
+    MemWithoutInitialContents { module: String, mem: String },
+    /// A read port was specified for `mem` in `module` with an address signal of the wrong bit width.
+    MemReadPortAddressBitWidthMismatch {
+        module: String,
+        mem: String,
+        bit_width: u32,
+        address_bit_width: u32,
+    },
+    /// A read port was specified for `mem` in `module` with an enable signal that isn't 1 bit wide.
+    MemReadPortEnableBitWidthMismatch {
+        module: String,
+        mem: String,
+        bit_width: u32,
+    },
+    /// A write port was specified for `mem` in `module` with an address signal of the wrong bit width.
+    MemWritePortAddressBitWidthMismatch {
+        module: String,
+        mem: String,
+        bit_width: u32,
+        address_bit_width: u32,
+    },
+    /// A write port was specified for `mem` in `module` with a value signal of the wrong bit width.
+    MemWritePortValueBitWidthMismatch {
+        module: String,
+        mem: String,
+        bit_width: u32,
+        element_bit_width: u32,
+    },
+    /// A write port was specified for `mem` in `module` with an enable signal that isn't 1 bit wide.
+    MemWritePortEnableBitWidthMismatch {
+        module: String,
+        mem: String,
+        bit_width: u32,
+    },
+    /// A masked write port was specified for `mem` in `module` with a mask granularity that doesn't evenly divide its element bit width.
+    MemWritePortMaskGranularityInvalid {
+        module: String,
+        mem: String,
+        granularity: u32,
+        element_bit_width: u32,
+    },
+    /// A masked write port was specified for `mem` in `module` with a mask signal that doesn't have one bit per lane.
+    MemWritePortMaskBitWidthMismatch {
+        module: String,
+        mem: String,
+        bit_width: u32,
+        required_bit_width: u32,
+    },
+    /// `mem` in `module` was bound to a clock domain from another module.
+    MemClockDomainFromAnotherModule { module: String, mem: String },
+    /// `mem` in `module` was bound to a clock domain, but it's already bound to one.
+    MemClockDomainAlreadyBound { module: String, mem: String },
+
+    /// An input of `instance` in `module` was driven by a signal from another module.
+    InstanceInputFromAnotherModule { module: String, instance: String },
+    /// An input called `input` was driven on `instance` in `module`, but `instantiated_module` has no such input.
+    InstanceInputNotFound {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        input: String,
+    },
+    /// An input called `input` was driven on `instance` in `module`, but it's already driven.
+    InstanceInputAlreadyDriven {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        input: String,
+    },
+    /// An input called `input` was driven on `instance` in `module` by a signal with a different bit width.
+    InstanceInputBitWidthMismatch {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        input: String,
+        input_bit_width: u32,
+        bit_width: u32,
+    },
+    /// An output called `output` was requested from `instance` in `module`, but `instantiated_module` has no such output.
+    InstanceOutputNotFound {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        output: String,
+    },
+    /// A clock domain of `instance` in `module` was driven by a clock domain from another module.
+    InstanceClockDomainFromAnotherModule { module: String, instance: String },
+    /// A clock domain called `clock_domain` was driven on `instance` in `module`, but `instantiated_module` has no such clock domain.
+    InstanceClockDomainNotFound {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        clock_domain: String,
+    },
+    /// A clock domain called `clock_domain` was driven on `instance` in `module`, but it's already driven.
+    InstanceClockDomainAlreadyDriven {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        clock_domain: String,
+    },
+    /// The default clock domain of `instance` in `module` was driven, but it's already driven.
+    InstanceDefaultClockDomainAlreadyDriven {
+        module: String,
+        instance: String,
+        instantiated_module: String,
+    },
+
+    /// A property called `property` in `module` was created with a condition from another module.
+    PropertyConditionFromAnotherModule { module: String, property: String },
+    /// A property called `property` in `module` was created with a condition that isn't 1 bit wide.
+    InvalidPropertyConditionBitWidth {
+        module: String,
+        property: String,
+        bit_width: u32,
+    },
+    /// `property` in `module` was bound to a clock domain from another module.
+    PropertyClockDomainFromAnotherModule { module: String, property: String },
+    /// `property` in `module` was bound to a clock domain, but it's already bound to one.
+    PropertyClockDomainAlreadyBound { module: String, property: String },
+
+    /// Code couldn't be generated for `root` because `module` contains an instance called `instance` of one of its own ancestors (or itself).
+    RecursiveDefinition {
+        root: String,
+        module: String,
+        instance: String,
+    },
+    /// Code couldn't be generated for `root` because `instance` in `module` has an undriven input.
+    UndrivenInstanceInput {
+        root: String,
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        input: String,
+    },
+    /// Code couldn't be generated for `root` because `instance` in `module` has an undriven clock domain.
+    UndrivenInstanceClockDomain {
+        root: String,
+        module: String,
+        instance: String,
+        instantiated_module: String,
+        clock_domain: String,
+    },
+    /// Code couldn't be generated for `root` because `register` in `module` isn't driven.
+    UndrivenRegister {
+        root: String,
+        module: String,
+        register: String,
+    },
+    /// Code couldn't be generated for `root` because `mem` in `module` doesn't have any read ports.
+    MemWithoutReadPorts {
+        root: String,
+        module: String,
+        mem: String,
+    },
+    /// Code couldn't be generated for `root` because `module` contains a property called `property`, which isn't a valid identifier (a non-empty sequence of ASCII letters, digits, and `_` that doesn't start with a digit).
+    InvalidPropertyName {
+        root: String,
+        module: String,
+        property: String,
+    },
+    /// Code couldn't be generated for `root` because `module` contains more than one property called `property`.
+    DuplicatePropertyName {
+        root: String,
+        module: String,
+        property: String,
+    },
+    /// Code couldn't be generated for `root` because `mem` in `module` has neither initial contents nor a write port.
+    MemWithoutInitialContentsOrWritePort {
+        root: String,
+        module: String,
+        mem: String,
+    },
+    /// Code couldn't be generated for `root` because `output` in `module` forms a combinational loop with itself.
+    CombinationalLoop {
+        root: String,
+        module: String,
+        output: String,
+    },
+    /// Verilog code couldn't be generated for `root` because `module` has an input or output called `port`, which is also the name of one of the clock or reset ports generated for its clock domains.
+    ClockDomainPortConflict {
+        root: String,
+        module: String,
+        port: String,
+    },
+    /// A Rust simulator couldn't be generated for `root` because its generated state type would be called `state`, which is also the name of a module in the same [`Context`](crate::Context).
+    StateTypeNameConflict { root: String, state: String },
+    /// A Rust simulator couldn't be generated for `root` because the accessor method `accessor` would be generated for both `path` and `other_path`.
+    AccessorNameConflict {
+        root: String,
+        accessor: String,
+        path: String,
+        other_path: String,
+    },
+    /// A Rust simulator couldn't be generated for `root` because the [`GenerationOptions`](crate::sim::GenerationOptions) `option` and `other_option` were both set, and the generated simulator can't support both at once.
+    IncompatibleGenerationOptions {
+        root: String,
+        option: String,
+        other_option: String,
+    },
+}
+
+fn bit_width_bound(bit_width: u32) -> String {
+    if bit_width < MIN_SIGNAL_BIT_WIDTH {
+        format!("narrower than {}", MIN_SIGNAL_BIT_WIDTH)
+    } else {
+        format!("wider than {}", MAX_SIGNAL_BIT_WIDTH)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+
+            Error::ModuleAlreadyExists { module } => write!(f, "A module with the name \"{}\" already exists in this context.", module),
+            Error::ModuleNotFound { instantiated_module, .. } => write!(f, "Attempted to instantiate a module identified by \"{}\", but no such module exists in this context.", instantiated_module),
+
+            Error::InvalidLitBitWidth { bit_width, .. } => write!(f, "Cannot create a literal with {} bit(s). Signals must not be {} bit(s).", bit_width, bit_width_bound(*bit_width)),
+            Error::InvalidInputBitWidth { bit_width, .. } => write!(f, "Cannot create an input with {} bit(s). Signals must not be {} bit(s).", bit_width, bit_width_bound(*bit_width)),
+            Error::InvalidRegisterBitWidth { bit_width, .. } => write!(f, "Cannot create a register with {} bit(s). Signals must not be {} bit(s).", bit_width, bit_width_bound(*bit_width)),
+            Error::InvalidMemAddressBitWidth { bit_width, .. } => write!(f, "Cannot create a memory with {} address bit(s). Signals must not be {} bit(s).", bit_width, bit_width_bound(*bit_width)),
+            Error::InvalidMemElementBitWidth { bit_width, .. } => write!(f, "Cannot create a memory with {} element bit(s). Signals must not be {} bit(s).", bit_width, bit_width_bound(*bit_width)),
+            Error::LitValueTooWide { value, bit_width, required_bits, .. } => write!(f, "Cannot fit the specified value '{}' into the specified bit width '{}'. The value '{}' requires a bit width of at least {} bit(s).", value, bit_width, value, required_bits),
+            Error::OutputFromAnotherModule { .. } => write!(f, "Cannot output a signal from another module."),
+            Error::SignalsFromDifferentModules { .. } => write!(f, "Attempted to combine signals from different modules."),
+            Error::BitIndexOutOfRange { index, bit_width, .. } => write!(f, "Attempted to take bit index {} from a signal with a width of {} bits. Bit indices must be in the range [0, {}] for a signal with a width of {} bits.", index, bit_width, bit_width - 1, bit_width),
+            Error::BitsRangeLowOutOfRange { range_low, bit_width, .. } => write!(f, "Cannot specify a range of bits where the lower bound is greater than or equal to the number of bits in the source signal. The bounds must be in the range [0, {}] for a signal with a width of {} bits, but a lower bound of {} was given.", bit_width - 1, bit_width, range_low),
+            Error::BitsRangeHighOutOfRange { range_high, bit_width, .. } => write!(f, "Cannot specify a range of bits where the upper bound is greater than or equal to the number of bits in the source signal. The bounds must be in the range [0, {}] for a signal with a width of {} bits, but an upper bound of {} was given.", bit_width - 1, bit_width, range_high),
+            Error::BitsRangeInverted { .. } => write!(f, "Cannot specify a range of bits where the lower bound is greater than the upper bound."),
+            Error::InvalidRepeatBitWidth { source_bit_width, count, bit_width, .. } => {
+                if *bit_width < MIN_SIGNAL_BIT_WIDTH as u64 {
+                    write!(f, "Attempted to repeat a {}-bit signal {} times, but this would result in a bit width of {}, which is less than the minimal signal bit width of {} bit(s).", source_bit_width, count, bit_width, MIN_SIGNAL_BIT_WIDTH)
+                } else {
+                    write!(f, "Attempted to repeat a {}-bit signal {} times, but this would result in a bit width of {}, which is greater than the maximum signal bit width of {} bit(s).", source_bit_width, count, bit_width, MAX_SIGNAL_BIT_WIDTH)
+                }
+            }
+            Error::ConcatBitWidthTooWide { lhs_bit_width, rhs_bit_width, bit_width, .. } => write!(f, "Attempted to concatenate signals with {} bit(s) and {} bit(s) respectively, but this would result in a bit width of {}, which is greater than the maximum signal bit width of {} bit(s).", lhs_bit_width, rhs_bit_width, bit_width, MAX_SIGNAL_BIT_WIDTH),
+            Error::MulBitWidthTooWide { lhs_bit_width, rhs_bit_width, bit_width, .. } => write!(f, "Attempted to multiply a {}-bit with a {}-bit signal, but this would result in a bit width of {}, which is greater than the maximum signal bit width of {} bit(s).", lhs_bit_width, rhs_bit_width, bit_width, MAX_SIGNAL_BIT_WIDTH),
+            Error::SignalBitWidthMismatch { lhs_bit_width, rhs_bit_width, .. } => write!(f, "Signals have different bit widths ({} and {}, respectively).", lhs_bit_width, rhs_bit_width),
+            Error::SignedComparisonOfSingleBitSignals { .. } => write!(f, "Cannot perform signed comparison of 1-bit signals."),
+            Error::InvalidMuxConditionBitWidth { .. } => write!(f, "Multiplexer conditionals can only be 1 bit wide."),
+            Error::MuxBitWidthMismatch { when_true_bit_width, when_false_bit_width, .. } => write!(f, "Cannot multiplex signals with different bit widths ({} and {}, respectively).", when_true_bit_width, when_false_bit_width),
+            Error::ReservedClockDomainName { module } => write!(f, "Cannot create a clock domain called \"clk\" in module \"{}\", as this name is reserved for the default clock domain.", module),
+            Error::ClockDomainAlreadyExists { module, clock_domain } => write!(f, "A clock domain called \"{}\" already exists in module \"{}\".", clock_domain, module),
+
+            Error::RegisterDefaultValueAlreadySpecified { module, register } => write!(f, "Attempted to specify a default value for register \"{}\" in module \"{}\", but this register already has a default value.", register, module),
+            Error::RegisterDefaultValueTooWide { register, value, bit_width, required_bits, .. } => write!(f, "Cannot fit the specified value '{}' into register \"{}\"'s bit width '{}'. The value '{}' requires a bit width of at least {} bit(s).", value, register, bit_width, value, required_bits),
+            Error::RegisterNextFromAnotherModule { register, .. } => write!(f, "Attempted to drive register \"{}\"'s next value with a signal from another module.", register),
+            Error::RegisterNextBitWidthMismatch { register, next_bit_width, bit_width, .. } => write!(f, "Attempted to drive register \"{}\"'s next value with a signal that has a different bit width than the register ({} and {}, respectively).", register, next_bit_width, bit_width),
+            Error::RegisterNextAlreadyDriven { module, register } => write!(f, "Attempted to drive register \"{}\"'s next value in module \"{}\", but this register's next value is already driven.", register, module),
+            Error::RegisterClockDomainFromAnotherModule { module, register } => write!(f, "Attempted to bind register \"{}\" in module \"{}\" to a clock domain from another module.", register, module),
+            Error::RegisterClockDomainAlreadyBound { module, register } => write!(f, "Attempted to bind register \"{}\" in module \"{}\" to a clock domain, but this register is already bound to a clock domain.", register, module),
+
+            Error::MemInitialContentsAlreadySpecified { module, mem } => write!(f, "Attempted to specify initial contents for memory \"{}\" in module \"{}\", but this memory already has initial contents.", mem, module),
+            Error::MemInitialContentsLengthMismatch { module, mem, len, address_bit_width, required_len } => write!(f, "Attempted to specify initial contents for memory \"{}\" in module \"{}\" that contains {} element(s), but this memory has {} address bit(s), and requires {} element(s).", mem, module, len, address_bit_width, required_len),
+            Error::MemInitialContentsElementTooWide { module, mem, element_bit_width, index, value, required_bits } => write!(f, "Attempted to specify initial contents for memory \"{}\" in module \"{}\", but this memory has an element width of {} bit(s), and these initial contents specify element {} with value {} which requires {} bit(s).", mem, module, element_bit_width, index, value, required_bits),
+            Error::MemInitialContentsFileInvalid { module, mem, path, message } => write!(f, "Attempted to load initial contents for memory \"{}\" in module \"{}\" from file \"{}\", but this file couldn't be loaded ({}).", mem, module, path, message),
+            Error::MemWithoutInitialContents { module, mem } => write!(f, "Attempted to write the initial contents of memory \"{}\" in module \"{}\", but this memory doesn't have initial contents.", mem, module),
+            Error::MemReadPortAddressBitWidthMismatch { module, mem, bit_width, address_bit_width } => write!(f, "Attempted to specify a read port for memory \"{}\" in module \"{}\" with an address signal with {} bit(s), but this memory has {} address bit(s).", mem, module, bit_width, address_bit_width),
+            Error::MemReadPortEnableBitWidthMismatch { module, mem, bit_width } => write!(f, "Attempted to specify a read port for memory \"{}\" in module \"{}\" with an enable signal with {} bit(s), but memory read/write ports are required to be 1 bit wide.", mem, module, bit_width),
+            Error::MemWritePortAddressBitWidthMismatch { module, mem, bit_width, address_bit_width } => write!(f, "Attempted to specify a write port for memory \"{}\" in module \"{}\" with an address signal with {} bit(s), but this memory has {} address bit(s).", mem, module, bit_width, address_bit_width),
+            Error::MemWritePortValueBitWidthMismatch { module, mem, bit_width, element_bit_width } => write!(f, "Attempted to specify a write port for memory \"{}\" in module \"{}\" with a value signal with {} bit(s), but this memory has {} element bit(s).", mem, module, bit_width, element_bit_width),
+            Error::MemWritePortEnableBitWidthMismatch { module, mem, bit_width } => write!(f, "Attempted to specify a write port for memory \"{}\" in module \"{}\" with an enable signal with {} bit(s), but memory read/write ports are required to be 1 bit wide.", mem, module, bit_width),
+            Error::MemWritePortMaskGranularityInvalid { module, mem, granularity, element_bit_width } => write!(f, "Attempted to specify a masked write port for memory \"{}\" in module \"{}\" with a mask granularity of {} bit(s), but this memory's element bit width ({}) is not a non-zero multiple of it.", mem, module, granularity, element_bit_width),
+            Error::MemWritePortMaskBitWidthMismatch { module, mem, bit_width, required_bit_width } => write!(f, "Attempted to specify a masked write port for memory \"{}\" in module \"{}\" with a mask signal with {} bit(s), but this write port requires a mask with {} bit(s).", mem, module, bit_width, required_bit_width),
+            Error::MemClockDomainFromAnotherModule { module, mem } => write!(f, "Attempted to bind memory \"{}\" in module \"{}\" to a clock domain from another module.", mem, module),
+            Error::MemClockDomainAlreadyBound { module, mem } => write!(f, "Attempted to bind memory \"{}\" in module \"{}\" to a clock domain, but this memory is already bound to a clock domain.", mem, module),
+
+            Error::InstanceInputFromAnotherModule { .. } => write!(f, "Attempted to drive an instance input with a signal from a different module."),
+            Error::InstanceInputNotFound { instantiated_module, input, .. } => write!(f, "Attempted to drive an input called \"{}\" on an instance of \"{}\", but no such input with this name exists on this module.", input, instantiated_module),
+            Error::InstanceInputAlreadyDriven { instantiated_module, input, .. } => write!(f, "Attempted to drive an input called \"{}\" on an instance of \"{}\", but this input is already driven for this instance.", input, instantiated_module),
+            Error::InstanceInputBitWidthMismatch { instantiated_module, input, input_bit_width, bit_width, .. } => write!(f, "Attempted to drive an input called \"{}\" on an instance of \"{}\", but this input and the provided signal have different bit widths ({} and {}, respectively).", input, instantiated_module, input_bit_width, bit_width),
+            Error::InstanceOutputNotFound { instantiated_module, output, .. } => write!(f, "Attempted to create a signal for an output called \"{}\" on an instance of \"{}\", but no such output with this name exists on this module.", output, instantiated_module),
+            Error::InstanceClockDomainFromAnotherModule { .. } => write!(f, "Attempted to drive an instance clock domain with a clock domain from a different module."),
+            Error::InstanceClockDomainNotFound { instantiated_module, clock_domain, .. } => write!(f, "Attempted to drive a clock domain called \"{}\" on an instance of \"{}\", but no such clock domain with this name exists on this module.", clock_domain, instantiated_module),
+            Error::InstanceClockDomainAlreadyDriven { instantiated_module, clock_domain, .. } => write!(f, "Attempted to drive a clock domain called \"{}\" on an instance of \"{}\", but this clock domain is already driven for this instance.", clock_domain, instantiated_module),
+            Error::InstanceDefaultClockDomainAlreadyDriven { instantiated_module, .. } => write!(f, "Attempted to drive the default clock domain on an instance of \"{}\", but this clock domain is already driven for this instance.", instantiated_module),
+
+            Error::PropertyConditionFromAnotherModule { module, property } => write!(f, "Attempted to create a property called \"{}\" in module \"{}\" with a condition from another module.", property, module),
+            Error::InvalidPropertyConditionBitWidth { module, property, bit_width } => write!(f, "Attempted to create a property called \"{}\" in module \"{}\" with a condition that is {} bits wide, but property conditions can only be 1 bit wide.", property, module, bit_width),
+            Error::PropertyClockDomainFromAnotherModule { module, property } => write!(f, "Attempted to bind property \"{}\" in module \"{}\" to a clock domain from another module.", property, module),
+            Error::PropertyClockDomainAlreadyBound { module, property } => write!(f, "Attempted to bind property \"{}\" in module \"{}\" to a clock domain, but this property is already bound to a clock domain.", property, module),
+
+            Error::RecursiveDefinition { root, module, instance } => {
+                if root == module {
+                    write!(f, "Cannot generate code for module \"{}\" because it has a recursive definition formed by an instance of itself called \"{}\".", root, instance)
+                } else {
+                    write!(f, "Cannot generate code for module \"{}\" because it has a recursive definition formed by an instance of itself called \"{}\" in module \"{}\".", root, instance, module)
+                }
+            }
+            Error::UndrivenInstanceInput { root, module, instance, instantiated_module, input } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains an instance of module \"{}\" called \"{}\" whose input \"{}\" is not driven.", root, module, instantiated_module, instance, input),
+            Error::UndrivenInstanceClockDomain { root, module, instance, instantiated_module, clock_domain } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains an instance of module \"{}\" called \"{}\" whose clock domain \"{}\" is not driven.", root, module, instantiated_module, instance, clock_domain),
+            Error::UndrivenRegister { root, module, register } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains a register called \"{}\" which is not driven.", root, module, register),
+            Error::MemWithoutReadPorts { root, module, mem } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains a memory called \"{}\" which doesn't have any read ports.", root, module, mem),
+            Error::InvalidPropertyName { root, module, property } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains a property called \"{}\", which is not a valid identifier.", root, module, property),
+            Error::DuplicatePropertyName { root, module, property } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains more than one property called \"{}\".", root, module, property),
+            Error::MemWithoutInitialContentsOrWritePort { root, module, mem } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains a memory called \"{}\" which doesn't have initial contents or a write port specified. At least one of the two is required.", root, module, mem),
+            Error::CombinationalLoop { root, module, output } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains an output called \"{}\" which forms a combinational loop with itself.", root, module, output),
+            Error::ClockDomainPortConflict { root, module, port } => write!(f, "Cannot generate code for module \"{}\" because module \"{}\" contains an input or output called \"{}\" which has the same name as one of its clock or reset ports.", root, module, port),
+            Error::StateTypeNameConflict { root, state } => write!(f, "Cannot generate code for module \"{}\" because its state type would be called \"{}\", which is also the name of a module in the same context.", root, state),
+            Error::AccessorNameConflict { root, accessor, path, other_path } => write!(f, "Cannot generate code for module \"{}\" because \"{}\" and \"{}\" would both have an accessor called \"{}\".", root, path, other_path, accessor),
+            Error::IncompatibleGenerationOptions { root, option, other_option } => write!(f, "Cannot generate code for module \"{}\" because the \"{}\" and \"{}\" generation options can't be combined.", root, option, other_option),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        match e {
+            Error::Io(e) => e,
+            _ => io::Error::new(io::ErrorKind::InvalidInput, e),
+        }
+    }
+}
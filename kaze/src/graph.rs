@@ -1,17 +1,23 @@
+mod clock_domain;
 mod constant;
 mod context;
 mod instance;
 mod mem;
+mod mem_file;
 mod module;
+mod property;
 mod register;
 mod signal;
 mod sugar;
 
+pub use clock_domain::*;
 pub use constant::*;
 pub use context::*;
 pub use instance::*;
 pub use mem::*;
+pub use mem_file::*;
 pub use module::*;
+pub use property::*;
 pub use register::*;
 pub use signal::*;
 pub use sugar::*;
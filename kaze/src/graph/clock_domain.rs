@@ -0,0 +1,78 @@
+use super::module::*;
+
+use crate::reset::*;
+
+use std::hash::{Hash, Hasher};
+use std::ptr;
+
+/// A clock and reset pair that [`Register`]s and [`Mem`]s can be bound to, created by the [`Module::clock_domain`] method.
+///
+/// Every [`Module`] has an implicit default clock domain (see [`Module::default_clock_domain`]), which is used for any [`Register`] or [`Mem`] that isn't explicitly bound to another clock domain.
+/// In generated code, the default clock domain is represented by the `clk` and `reset_n` ports, and a clock domain called `name` is represented by the `<name>_clk` and `<name>_reset_n` ports (see [`ResetPolarity`] for active-high resets), so a [`Module`]'s inputs and outputs can't have the same names as these ports.
+///
+/// When a [`Module`] with additional clock domains is instantiated, each of its clock domains must be driven by a clock domain of the parent [`Module`] with [`Instance::drive_clock_domain`].
+///
+/// # Examples
+///
+/// ```
+/// use kaze::*;
+///
+/// let c = Context::new();
+///
+/// let m = c.module("MyModule");
+/// let mem_domain = m.clock_domain("mem");
+///
+/// let core_reg = m.reg("core_reg", 32);
+/// core_reg.default_value(0u32);
+/// core_reg.drive_next(m.input("i", 32));
+///
+/// let mem_reg = m.reg("mem_reg", 32);
+/// mem_reg.clock_domain(mem_domain);
+/// mem_reg.default_value(0u32);
+/// mem_reg.drive_next(core_reg.value);
+/// m.output("o", mem_reg.value);
+/// ```
+///
+/// [`Instance::drive_clock_domain`]: crate::Instance::drive_clock_domain
+/// [`Mem`]: crate::Mem
+/// [`Register`]: crate::Register
+#[must_use]
+pub struct ClockDomain<'a> {
+    pub(crate) module: &'a Module<'a>,
+
+    pub(crate) name: Option<String>,
+}
+
+impl<'a> ClockDomain<'a> {
+    pub(crate) fn clock_name(&self) -> String {
+        match self.name {
+            Some(ref name) => format!("{}_clk", name),
+            _ => "clk".into(),
+        }
+    }
+
+    pub(crate) fn reset_name(&self, polarity: ResetPolarity) -> String {
+        let reset_name = match polarity {
+            ResetPolarity::ActiveLow => "reset_n",
+            ResetPolarity::ActiveHigh => "reset",
+        };
+        match self.name {
+            Some(ref name) => format!("{}_{}", name, reset_name),
+            _ => reset_name.into(),
+        }
+    }
+}
+
+impl<'a> Eq for &'a ClockDomain<'a> {}
+
+impl<'a> Hash for &'a ClockDomain<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(*self as *const _ as usize)
+    }
+}
+
+impl<'a> PartialEq for &'a ClockDomain<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(*self, *other)
+    }
+}
@@ -16,6 +16,7 @@
 /// let c = m.reg("data", 20);
 /// c.default_value(5u32);
 /// let d = m.lit(42u32, 8);
+/// let e = m.lit(vec![0xfeedface_deadbeefu64, 0x1234, 0x1], 130);
 /// ```
 pub enum Constant {
     /// Contains a boolean value
@@ -26,25 +27,91 @@ pub enum Constant {
     U64(u64),
     /// Contains an unsigned, 128-bit value
     U128(u128),
+    /// Contains an unsigned value of arbitrary width, stored as 64-bit words in little-endian order (least significant word first)
+    Wide(Vec<u64>),
 }
 
 impl Constant {
     // TODO: Specific tests? I don't necessarily want to make this part of the public API at least.
-    pub(super) fn required_bits(&self) -> u32 {
+    pub(crate) fn required_bits(&self) -> u32 {
         match *self {
             Constant::Bool(value) => 32 - (value as u32).leading_zeros(),
             Constant::U32(value) => 32 - value.leading_zeros(),
             Constant::U64(value) => 64 - value.leading_zeros(),
             Constant::U128(value) => 128 - value.leading_zeros(),
+            Constant::Wide(ref words) => words
+                .iter()
+                .enumerate()
+                .rev()
+                .find(|(_, word)| **word != 0)
+                .map_or(0, |(i, word)| i as u32 * 64 + 64 - word.leading_zeros()),
         }
     }
 
+    /// Returns the lower 128 bits of this `Constant`'s value.
     pub(crate) fn numeric_value(&self) -> u128 {
         match *self {
             Constant::Bool(value) => value.into(),
             Constant::U32(value) => value.into(),
             Constant::U64(value) => value.into(),
             Constant::U128(value) => value,
+            Constant::Wide(ref words) => {
+                let low = words.first().copied().unwrap_or(0) as u128;
+                let high = words.get(1).copied().unwrap_or(0) as u128;
+                (high << 64) | low
+            }
+        }
+    }
+
+    /// Returns this `Constant`'s value as exactly `num_words` 64-bit words in little-endian order, truncating or zero-extending as necessary.
+    pub(crate) fn words(&self, num_words: usize) -> Vec<u64> {
+        let mut ret = match *self {
+            Constant::Wide(ref words) => words.clone(),
+            _ => {
+                let value = self.numeric_value();
+                vec![value as u64, (value >> 64) as u64]
+            }
+        };
+        ret.resize(num_words, 0);
+        ret
+    }
+
+    /// Returns this `Constant`'s value formatted as a lowercase hex string without a prefix.
+    pub(crate) fn hex_string(&self) -> String {
+        let words = self.words((self.required_bits() as usize).div_ceil(64));
+        match words.split_last() {
+            Some((top, rest)) => {
+                let mut ret = format!("{:x}", top);
+                for word in rest.iter().rev() {
+                    ret.push_str(&format!("{:016x}", word));
+                }
+                ret
+            }
+            _ => "0".into(),
+        }
+    }
+
+    /// Returns this `Constant`'s value formatted as a binary string of exactly `bit_width` digits (most significant first), truncating or zero-padding as necessary.
+    pub(crate) fn binary_string(&self, bit_width: u32) -> String {
+        let words = self.words((bit_width as usize).div_ceil(64));
+        (0..bit_width)
+            .rev()
+            .map(|i| {
+                if (words[(i / 64) as usize] >> (i % 64)) & 1 != 0 {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect()
+    }
+
+    /// Returns this `Constant`'s value formatted for use in diagnostic messages; decimal if it fits into 128 bits, hex otherwise.
+    pub(crate) fn numeric_value_string(&self) -> String {
+        if self.required_bits() <= 128 {
+            format!("{}", self.numeric_value())
+        } else {
+            format!("0x{}", self.hex_string())
         }
     }
 }
@@ -84,3 +151,15 @@ impl From<u128> for Constant {
         Constant::U128(value)
     }
 }
+
+impl From<Vec<u64>> for Constant {
+    fn from(value: Vec<u64>) -> Self {
+        Constant::Wide(value)
+    }
+}
+
+impl<'a> From<&'a [u64]> for Constant {
+    fn from(value: &'a [u64]) -> Self {
+        Constant::Wide(value.to_vec())
+    }
+}
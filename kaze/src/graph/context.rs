@@ -1,9 +1,14 @@
+use super::clock_domain::*;
 use super::instance::*;
 use super::mem::*;
 use super::module::*;
+use super::property::*;
 use super::register::*;
 use super::signal::*;
 
+use crate::error::*;
+use crate::simplify::SimplifierCache;
+
 use typed_arena::Arena;
 
 use std::cell::{Ref, RefCell};
@@ -31,8 +36,13 @@ pub struct Context<'a> {
     pub(super) register_arena: Arena<Register<'a>>,
     pub(super) instance_arena: Arena<Instance<'a>>,
     pub(super) mem_arena: Arena<Mem<'a>>,
+    pub(super) clock_domain_arena: Arena<ClockDomain<'a>>,
+    pub(super) property_arena: Arena<Property<'a>>,
+    // Signals created by simplifying logic during code generation, which don't belong to any module
+    pub(crate) rewritten_signal_arena: Arena<Signal<'a>>,
 
     pub(super) modules: RefCell<BTreeMap<String, &'a Module<'a>>>,
+    simplifier_caches: [RefCell<SimplifierCache<'a>>; 2],
 }
 
 impl<'a> Context<'a> {
@@ -53,11 +63,22 @@ impl<'a> Context<'a> {
             register_arena: Arena::new(),
             instance_arena: Arena::new(),
             mem_arena: Arena::new(),
+            clock_domain_arena: Arena::new(),
+            property_arena: Arena::new(),
+            rewritten_signal_arena: Arena::new(),
 
             modules: RefCell::new(BTreeMap::new()),
+            simplifier_caches: Default::default(),
         }
     }
 
+    pub(crate) fn simplifier_cache(
+        &self,
+        preserve_unknowns: bool,
+    ) -> &RefCell<SimplifierCache<'a>> {
+        &self.simplifier_caches[preserve_unknowns as usize]
+    }
+
     /// Creates a new [`Module`] called `name` in this `Context`.
     ///
     /// Conventionally, `name` should be `CamelCase`, though this is not enforced.
@@ -90,17 +111,34 @@ impl<'a> Context<'a> {
     /// let _ = c.module("A"); // Non-unique name, panic!
     /// ```
     pub fn module<S: Into<String>>(&'a self, name: S) -> &Module {
+        self.try_module(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`module`](Self::module), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ModuleAlreadyExists`] if a [`Module`] with the same `name` already exists in this `Context`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// assert!(c.try_module("A").is_ok());
+    /// assert!(matches!(c.try_module("A"), Err(Error::ModuleAlreadyExists { .. })));
+    /// ```
+    pub fn try_module<S: Into<String>>(&'a self, name: S) -> Result<&'a Module<'a>, Error> {
         let name = name.into();
         let mut modules = self.modules.borrow_mut();
         if modules.contains_key(&name) {
-            panic!(
-                "A module with the name \"{}\" already exists in this context.",
-                name
-            );
+            return Err(Error::ModuleAlreadyExists { module: name });
         }
         let module = self.module_arena.alloc(Module::new(self, name.clone()));
         modules.insert(name, module);
-        module
+        Ok(module)
     }
 
     /// Immutably borrows this `Context`'s [`Module`]s.
@@ -1,7 +1,10 @@
+use super::clock_domain::*;
 use super::context::*;
 use super::module::*;
 use super::signal::*;
 
+use crate::error::*;
+
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
@@ -34,6 +37,8 @@ pub struct Instance<'a> {
     pub(crate) instantiated_module: &'a Module<'a>,
     pub(crate) name: String,
     pub(crate) driven_inputs: RefCell<BTreeMap<String, &'a Signal<'a>>>,
+    pub(crate) driven_clock_domains: RefCell<BTreeMap<String, &'a ClockDomain<'a>>>,
+    pub(crate) driven_default_clock_domain: RefCell<Option<&'a ClockDomain<'a>>>,
 }
 
 impl<'a> Instance<'a> {
@@ -59,22 +64,76 @@ impl<'a> Instance<'a> {
     /// inner_inst.drive_input("i", outer.lit(0xfadebabeu32, 32));
     /// ```
     pub fn drive_input<S: Into<String>>(&'a self, name: S, i: &'a Signal<'a>) {
+        self.try_drive_input(name, i)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`drive_input`](Self::drive_input), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InstanceInputFromAnotherModule`], [`Error::InstanceInputNotFound`], [`Error::InstanceInputAlreadyDriven`] or [`Error::InstanceInputBitWidthMismatch`] under the same conditions that [`drive_input`](Self::drive_input) panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let inner = c.module("Inner");
+    /// inner.output("o", inner.input("i", 32));
+    ///
+    /// let outer = c.module("Outer");
+    /// let inner_inst = outer.instance("inner_inst", "Inner");
+    /// assert!(matches!(
+    ///     inner_inst.try_drive_input("nope", outer.lit(0xfadebabeu32, 32)),
+    ///     Err(Error::InstanceInputNotFound { .. })
+    /// ));
+    /// assert!(inner_inst.try_drive_input("i", outer.lit(0xfadebabeu32, 32)).is_ok());
+    /// ```
+    pub fn try_drive_input<S: Into<String>>(
+        &'a self,
+        name: S,
+        i: &'a Signal<'a>,
+    ) -> Result<(), Error> {
         let name = name.into();
         let mut driven_inputs = self.driven_inputs.borrow_mut();
         if !ptr::eq(self.module, i.module) {
-            panic!("Attempted to drive an instance input with a signal from a different module.");
+            return Err(Error::InstanceInputFromAnotherModule {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+            });
         }
         if !self.instantiated_module.inputs.borrow().contains_key(&name) {
-            panic!("Attempted to drive an input called \"{}\" on an instance of \"{}\", but no such input with this name exists on this module.", name, self.instantiated_module.name);
+            return Err(Error::InstanceInputNotFound {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+                instantiated_module: self.instantiated_module.name.clone(),
+                input: name,
+            });
         }
         if driven_inputs.contains_key(&name) {
-            panic!("Attempted to drive an input called \"{}\" on an instance of \"{}\", but this input is already driven for this instance.", name, self.instantiated_module.name);
+            return Err(Error::InstanceInputAlreadyDriven {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+                instantiated_module: self.instantiated_module.name.clone(),
+                input: name,
+            });
         }
         let input_bit_width = self.instantiated_module.inputs.borrow()[&name].bit_width();
         if input_bit_width != i.bit_width() {
-            panic!("Attempted to drive an input called \"{}\" on an instance of \"{}\", but this input and the provided signal have different bit widths ({} and {}, respectively).", name, self.instantiated_module.name, input_bit_width, i.bit_width());
+            return Err(Error::InstanceInputBitWidthMismatch {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+                instantiated_module: self.instantiated_module.name.clone(),
+                input: name,
+                input_bit_width,
+                bit_width: i.bit_width(),
+            });
         }
         driven_inputs.insert(name, i);
+        Ok(())
     }
 
     /// Creates a [`Signal`] that represents this `Instance`'s output called `name`.
@@ -99,10 +158,19 @@ impl<'a> Instance<'a> {
     /// outer.output("o", inner_inst.output("o"));
     /// ```
     pub fn output<S: Into<String>>(&'a self, name: S) -> &Signal<'a> {
+        self.try_output(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`output`](Self::output), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InstanceOutputNotFound`] if `name` specifies an output that doesn't exist on this `Instance`'s [`Module`].
+    pub fn try_output<S: Into<String>>(&'a self, name: S) -> Result<&'a Signal<'a>, Error> {
         let name = name.into();
         let outputs = self.instantiated_module.outputs.borrow();
         match outputs.get(&name) {
-            Some(output) => self.context.signal_arena.alloc(Signal {
+            Some(output) => Ok(self.context.signal_arena.alloc(Signal {
                 context: self.context,
                 module: self.module,
 
@@ -111,8 +179,167 @@ impl<'a> Instance<'a> {
                     name,
                     bit_width: output.bit_width(),
                 },
+            })),
+            _ => Err(Error::InstanceOutputNotFound {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+                instantiated_module: self.instantiated_module.name.clone(),
+                output: name,
             }),
-            _ => panic!("Attempted to create a signal for an output called \"{}\" on an instance of \"{}\", but no such output with this name exists on this module.", name, self.instantiated_module.name)
+        }
+    }
+
+    /// Drives the [`ClockDomain`] of this [`Module`] `Instance` specified by `name` with the given [`ClockDomain`].
+    ///
+    /// Every [`ClockDomain`] created with [`Module::clock_domain`] must be driven on each `Instance` of its [`Module`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `clock_domain` is from a different [`Module`] than `self`, if `name` specifies a clock domain that doesn't exist on this `Instance`'s [`Module`], or if this clock domain is already driven on this `Instance`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let inner = c.module("Inner");
+    /// let inner_mem_domain = inner.clock_domain("mem");
+    /// let r = inner.reg("r", 32);
+    /// r.clock_domain(inner_mem_domain);
+    /// r.drive_next(inner.input("i", 32));
+    /// inner.output("o", r.value);
+    ///
+    /// let outer = c.module("Outer");
+    /// let outer_mem_domain = outer.clock_domain("mem");
+    /// let inner_inst = outer.instance("inner_inst", "Inner");
+    /// inner_inst.drive_clock_domain("mem", outer_mem_domain);
+    /// inner_inst.drive_input("i", outer.input("i", 32));
+    /// outer.output("o", inner_inst.output("o"));
+    /// ```
+    pub fn drive_clock_domain<S: Into<String>>(
+        &'a self,
+        name: S,
+        clock_domain: &'a ClockDomain<'a>,
+    ) {
+        self.try_drive_clock_domain(name, clock_domain)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`drive_clock_domain`](Self::drive_clock_domain), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InstanceClockDomainFromAnotherModule`], [`Error::InstanceClockDomainNotFound`] or [`Error::InstanceClockDomainAlreadyDriven`] under the same conditions that [`drive_clock_domain`](Self::drive_clock_domain) panics.
+    pub fn try_drive_clock_domain<S: Into<String>>(
+        &'a self,
+        name: S,
+        clock_domain: &'a ClockDomain<'a>,
+    ) -> Result<(), Error> {
+        let name = name.into();
+        let mut driven_clock_domains = self.driven_clock_domains.borrow_mut();
+        if !ptr::eq(self.module, clock_domain.module) {
+            return Err(Error::InstanceClockDomainFromAnotherModule {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+            });
+        }
+        if !self
+            .instantiated_module
+            .clock_domains
+            .borrow()
+            .iter()
+            .any(|clock_domain| clock_domain.name.as_ref() == Some(&name))
+        {
+            return Err(Error::InstanceClockDomainNotFound {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+                instantiated_module: self.instantiated_module.name.clone(),
+                clock_domain: name,
+            });
+        }
+        if driven_clock_domains.contains_key(&name) {
+            return Err(Error::InstanceClockDomainAlreadyDriven {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+                instantiated_module: self.instantiated_module.name.clone(),
+                clock_domain: name,
+            });
+        }
+        driven_clock_domains.insert(name, clock_domain);
+        Ok(())
+    }
+
+    /// Drives the [default clock domain](Module::default_clock_domain) of this [`Module`] `Instance` with the given [`ClockDomain`].
+    ///
+    /// By default, an `Instance`'s default clock domain is driven by the default clock domain of the [`Module`] containing the `Instance`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `clock_domain` is from a different [`Module`] than `self`, or if this `Instance`'s default clock domain is already driven.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let inner = c.module("Inner");
+    /// let r = inner.reg("r", 32);
+    /// r.drive_next(inner.input("i", 32));
+    /// inner.output("o", r.value);
+    ///
+    /// let outer = c.module("Outer");
+    /// let slow_domain = outer.clock_domain("slow");
+    /// let inner_inst = outer.instance("inner_inst", "Inner");
+    /// inner_inst.drive_default_clock_domain(slow_domain);
+    /// inner_inst.drive_input("i", outer.input("i", 32));
+    /// outer.output("o", inner_inst.output("o"));
+    /// ```
+    pub fn drive_default_clock_domain(&'a self, clock_domain: &'a ClockDomain<'a>) {
+        self.try_drive_default_clock_domain(clock_domain)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`drive_default_clock_domain`](Self::drive_default_clock_domain), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InstanceClockDomainFromAnotherModule`] or [`Error::InstanceDefaultClockDomainAlreadyDriven`] under the same conditions that [`drive_default_clock_domain`](Self::drive_default_clock_domain) panics.
+    pub fn try_drive_default_clock_domain(
+        &'a self,
+        clock_domain: &'a ClockDomain<'a>,
+    ) -> Result<(), Error> {
+        if !ptr::eq(self.module, clock_domain.module) {
+            return Err(Error::InstanceClockDomainFromAnotherModule {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+            });
+        }
+        if self.driven_default_clock_domain.borrow().is_some() {
+            return Err(Error::InstanceDefaultClockDomainAlreadyDriven {
+                module: self.module.name.clone(),
+                instance: self.name.clone(),
+                instantiated_module: self.instantiated_module.name.clone(),
+            });
+        }
+        *self.driven_default_clock_domain.borrow_mut() = Some(clock_domain);
+        Ok(())
+    }
+
+    /// Returns the [`ClockDomain`] in the containing [`Module`] that drives `clock_domain`, which must belong to this `Instance`'s [`Module`].
+    pub(crate) fn driving_clock_domain(
+        &self,
+        clock_domain: &ClockDomain<'a>,
+    ) -> &'a ClockDomain<'a> {
+        match clock_domain.name {
+            Some(ref name) => self.driven_clock_domains.borrow()[name],
+            _ => self
+                .driven_default_clock_domain
+                .borrow()
+                .unwrap_or_else(|| self.module.default_clock_domain()),
         }
     }
 }
@@ -222,4 +449,96 @@ mod tests {
         // Panic
         let _ = inner_inst.output("nope");
     }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to drive an instance clock domain with a clock domain from a different module."
+    )]
+    fn drive_clock_domain_separate_module_error() {
+        let c = Context::new();
+
+        let inner = c.module("Inner");
+        let _ = inner.clock_domain("a");
+
+        let m1 = c.module("A");
+        let d = m1.clock_domain("a");
+
+        let m2 = c.module("B");
+        let inner_inst = m2.instance("inner_inst", "Inner");
+
+        // Panic
+        inner_inst.drive_clock_domain("a", d);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to drive a clock domain called \"a\" on an instance of \"Inner\", but no such clock domain with this name exists on this module."
+    )]
+    fn drive_clock_domain_nonexistent_clock_domain_error() {
+        let c = Context::new();
+
+        let _ = c.module("Inner");
+
+        let m = c.module("A");
+        let inner_inst = m.instance("inner_inst", "Inner");
+
+        // Panic
+        inner_inst.drive_clock_domain("a", m.clock_domain("a"));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to drive a clock domain called \"a\" on an instance of \"Inner\", but this clock domain is already driven for this instance."
+    )]
+    fn drive_clock_domain_already_driven_error() {
+        let c = Context::new();
+
+        let inner = c.module("Inner");
+        let _ = inner.clock_domain("a");
+
+        let m = c.module("A");
+        let inner_inst = m.instance("inner_inst", "Inner");
+
+        inner_inst.drive_clock_domain("a", m.clock_domain("a1"));
+
+        // Panic
+        inner_inst.drive_clock_domain("a", m.clock_domain("a2"));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to drive an instance clock domain with a clock domain from a different module."
+    )]
+    fn drive_default_clock_domain_separate_module_error() {
+        let c = Context::new();
+
+        let _ = c.module("Inner");
+
+        let m1 = c.module("A");
+        let d = m1.clock_domain("a");
+
+        let m2 = c.module("B");
+        let inner_inst = m2.instance("inner_inst", "Inner");
+
+        // Panic
+        inner_inst.drive_default_clock_domain(d);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to drive the default clock domain on an instance of \"Inner\", but this clock domain is already driven for this instance."
+    )]
+    fn drive_default_clock_domain_already_driven_error() {
+        let c = Context::new();
+
+        let _ = c.module("Inner");
+
+        let m = c.module("A");
+        let inner_inst = m.instance("inner_inst", "Inner");
+
+        inner_inst.drive_default_clock_domain(m.clock_domain("a1"));
+
+        // Panic
+        inner_inst.drive_default_clock_domain(m.clock_domain("a2"));
+    }
 }
@@ -1,23 +1,37 @@
+use super::clock_domain::*;
 use super::constant::*;
 use super::context::*;
+use super::mem_file::{self, *};
 use super::module::*;
 use super::signal::*;
 
+use crate::error::*;
+
 use std::cell::RefCell;
+use std::fs;
 use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::ptr;
 
-/// A synchronous memory, created by the [`Module::mem`] method.
+/// A memory, created by the [`Module::mem`] method.
 ///
-/// Memories in kaze are always sequential/synchronous-read, sequential/synchronous-write memories.
+/// Memories in kaze are sequential/synchronous-write memories, and are sequential/synchronous-read by default.
 /// This means that when a read and/or write is asserted, the read/write will be visible on the cycle immediately following the cycle in which it's asserted.
-/// If both a write and a read to the same location occurs within the same cycle, the read will return the previous value at the memory location, **not** the newly-written value.
+/// By default, if both a write and a read to the same location occurs within the same cycle, the read will return the previous value at the memory location, **not** the newly-written value.
+/// This can be configured per read port with [`read_port_with_read_during_write`](Self::read_port_with_read_during_write).
+///
+/// Memories may also have [asynchronous read ports](Self::async_read_port), which are combinational: their value reflects the current contents of the location they address within the same cycle, which is useful for register files and small LUT-based RAMs.
 ///
-/// Memories must have at least one read port specified.
+/// Memories must have at least one (synchronous or asynchronous) read port specified.
 /// Multiple reads to the same location within the same cycle will return the same value.
 ///
-/// Memories may optionally have initial contents and/or a write port specified.
-/// If either of these are missing, the contents of the memory can't be determined, so this is a logical error.
+/// Memories may optionally have initial contents and/or any number of write ports specified.
+/// If both of these are missing, the contents of the memory can't be determined, so this is a logical error.
+///
+/// If multiple write ports write to the same location within the same cycle, the write port that was specified last takes priority (for masked write ports, this applies to each lane separately).
+///
+/// All reads and writes occur on positive edges of the `Mem`'s [`ClockDomain`]'s clock. A `Mem` belongs to its [`Module`]'s [default clock domain](Module::default_clock_domain) unless it's bound to another one with the [`clock_domain`](Self::clock_domain) method.
 ///
 /// # Examples
 ///
@@ -45,9 +59,55 @@ pub struct Mem<'a> {
     pub(crate) element_bit_width: u32,
 
     pub(crate) initial_contents: RefCell<Option<Vec<Constant>>>,
+    pub(crate) initial_contents_file: RefCell<Option<(PathBuf, MemFileFormat)>>,
+
+    pub(crate) read_ports: RefCell<Vec<ReadPort<'a>>>,
+    pub(crate) async_read_ports: RefCell<Vec<&'a Signal<'a>>>,
+    pub(crate) write_ports: RefCell<Vec<WritePort<'a>>>,
 
-    pub(crate) read_ports: RefCell<Vec<(&'a Signal<'a>, &'a Signal<'a>)>>,
-    pub(crate) write_port: RefCell<Option<(&'a Signal<'a>, &'a Signal<'a>, &'a Signal<'a>)>>,
+    pub(crate) clock_domain: RefCell<Option<&'a ClockDomain<'a>>>,
+}
+
+/// Specifies what a [`Mem`] read port returns when the location it reads is written within the same cycle.
+///
+/// See [`Mem::read_port_with_read_during_write`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ReadDuringWrite {
+    /// The read returns the value stored at the location before the write (also known as read-first or read-old).
+    ///
+    /// This is the behavior of read ports specified with [`Mem::read_port`].
+    #[default]
+    ReadFirst,
+    /// The read returns the newly-written value (also known as write-first or read-new).
+    ///
+    /// If several write ports write the location, the read reflects the one that takes priority, and only the lanes written by masked write ports are affected.
+    WriteFirst,
+    /// The value read is undefined.
+    ///
+    /// This gives synthesis tools the most freedom when inferring memories. Generated Verilog code reads `x`s in this case, as do generated Rust simulators with [`four_state`](crate::sim::GenerationOptions::four_state) simulation. Without it, generated Rust simulators return the new value (as with [`WriteFirst`](Self::WriteFirst)), which should not be relied upon.
+    DontCare,
+}
+
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub(crate) struct ReadPort<'a> {
+    pub address: &'a Signal<'a>,
+    pub enable: &'a Signal<'a>,
+    pub read_during_write: ReadDuringWrite,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct WritePort<'a> {
+    pub address: &'a Signal<'a>,
+    pub value: &'a Signal<'a>,
+    pub enable: &'a Signal<'a>,
+    pub mask: Option<WriteMask<'a>>,
+}
+
+// Each bit of `mask` enables writing the corresponding `granularity`-bit lane of the element, starting from its least significant bits.
+#[derive(Clone, Copy)]
+pub(crate) struct WriteMask<'a> {
+    pub mask: &'a Signal<'a>,
+    pub granularity: u32,
 }
 
 impl<'a> Mem<'a> {
@@ -81,20 +141,158 @@ impl<'a> Mem<'a> {
     /// m.output("my_output", my_mem.read_port(m.high(), m.high()));
     /// ```
     pub fn initial_contents<C: Clone + Into<Constant>>(&'a self, contents: &[C]) {
+        self.try_initial_contents(contents)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`initial_contents`](Self::initial_contents), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemInitialContentsAlreadySpecified`], [`Error::MemInitialContentsLengthMismatch`] or [`Error::MemInitialContentsElementTooWide`] under the same conditions that [`initial_contents`](Self::initial_contents) panics.
+    pub fn try_initial_contents<C: Clone + Into<Constant>>(
+        &'a self,
+        contents: &[C],
+    ) -> Result<(), Error> {
+        self.set_initial_contents(contents.iter().cloned().map(Into::into).collect())
+    }
+
+    /// Specifies the initial contents for this `Mem`, loaded from the memory file at `path` in the specified format.
+    ///
+    /// This behaves like [`initial_contents`](Self::initial_contents), except that the contents are read from a file in the format read by Verilog's `$readmemh`/`$readmemb` system tasks (see [`MemFileFormat`]), which can be produced with [`write_initial_contents`](Self::write_initial_contents).
+    /// The file must specify every element of this `Mem`.
+    ///
+    /// Generated Verilog code loads the file with `$readmemh`/`$readmemb` instead of initializing each element separately, which keeps the generated code small for large memories.
+    /// `path` is emitted as-is, so a relative path is resolved relative to the directory the Verilog tools are run from, rather than the one this method is called from.
+    /// Generated Rust simulators don't read the file; instead, its contents are read by this method and embedded in the generated code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `Mem` already has initial contents specified, if the file can't be read or isn't a valid memory file, if it doesn't specify every element in this `Mem`, or if any of the specified element values don't fit into this `Mem`'s element bit width.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let rom = m.mem("rom", 16, 32);
+    /// rom.initial_contents_file("rom.hex", MemFileFormat::Hex);
+    /// m.output("data", rom.read_port(m.input("address", 16), m.high()));
+    /// ```
+    pub fn initial_contents_file<P: AsRef<Path>>(&'a self, path: P, format: MemFileFormat) {
+        self.try_initial_contents_file(path, format)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`initial_contents_file`](Self::initial_contents_file), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemInitialContentsFileInvalid`] if the file can't be read or isn't a valid memory file, and otherwise returns the same errors as [`try_initial_contents`](Self::try_initial_contents).
+    pub fn try_initial_contents_file<P: AsRef<Path>>(
+        &'a self,
+        path: P,
+        format: MemFileFormat,
+    ) -> Result<(), Error> {
+        if self.initial_contents.borrow().is_some() {
+            return Err(Error::MemInitialContentsAlreadySpecified {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+            });
+        }
+        let path = path.as_ref();
+        let file_error = |message: String| Error::MemInitialContentsFileInvalid {
+            module: self.module.name.clone(),
+            mem: self.name.clone(),
+            path: path.display().to_string(),
+            message,
+        };
+        let source = fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        let contents =
+            mem_file::parse(&source, format, 1 << self.address_bit_width).map_err(file_error)?;
+        self.set_initial_contents(contents)?;
+        *self.initial_contents_file.borrow_mut() = Some((path.to_path_buf(), format));
+        Ok(())
+    }
+
+    fn set_initial_contents(&'a self, contents: Vec<Constant>) -> Result<(), Error> {
         if self.initial_contents.borrow().is_some() {
-            panic!("Attempted to specify initial contents for memory \"{}\" in module \"{}\", but this memory already has initial contents.", self.name, self.module.name);
+            return Err(Error::MemInitialContentsAlreadySpecified {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+            });
         }
         let expected_contents_len = 1 << self.address_bit_width;
         if contents.len() != expected_contents_len {
-            panic!("Attempted to specify initial contents for memory \"{}\" in module \"{}\" that contains {} element(s), but this memory has {} address bit(s), and requires {} element(s).", self.name, self.module.name, contents.len(), self.address_bit_width, expected_contents_len);
+            return Err(Error::MemInitialContentsLengthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                len: contents.len(),
+                address_bit_width: self.address_bit_width,
+                required_len: expected_contents_len,
+            });
         }
-        *self.initial_contents.borrow_mut() = Some(contents.iter().cloned().enumerate().map(|(i, x)| {
-            let ret = x.into();
-            if ret.required_bits() > self.element_bit_width {
-                panic!("Attempted to specify initial contents for memory \"{}\" in module \"{}\", but this memory has an element width of {} bit(s), and these initial contents specify element {} with value {} which requires {} bit(s).", self.name, self.module.name, self.element_bit_width, i, ret.numeric_value(), ret.required_bits());
+        for (index, element) in contents.iter().enumerate() {
+            if element.required_bits() > self.element_bit_width {
+                return Err(Error::MemInitialContentsElementTooWide {
+                    module: self.module.name.clone(),
+                    mem: self.name.clone(),
+                    element_bit_width: self.element_bit_width,
+                    index,
+                    value: element.numeric_value_string(),
+                    required_bits: element.required_bits(),
+                });
             }
-            ret
-        }).collect());
+        }
+        *self.initial_contents.borrow_mut() = Some(contents);
+        Ok(())
+    }
+
+    /// Writes this `Mem`'s initial contents to `w` as a memory file in the specified format, which can be loaded with [`initial_contents_file`](Self::initial_contents_file) or Verilog's `$readmemh`/`$readmemb` system tasks.
+    ///
+    /// Each element is written on its own line, zero-padded to this `Mem`'s element bit width.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemWithoutInitialContents`] if this `Mem` doesn't have initial contents specified, or [`Error::Io`] if writing to `w` fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let my_mem = m.mem("my_mem", 1, 12);
+    /// my_mem.initial_contents(&[0xabcu32, 0x5u32]);
+    ///
+    /// let mut file = Vec::new();
+    /// my_mem.write_initial_contents(MemFileFormat::Hex, &mut file).unwrap();
+    /// assert_eq!(String::from_utf8(file).unwrap(), "abc\n005\n");
+    /// ```
+    pub fn write_initial_contents<W: Write>(
+        &self,
+        format: MemFileFormat,
+        w: W,
+    ) -> Result<(), Error> {
+        match *self.initial_contents.borrow() {
+            Some(ref initial_contents) => Ok(mem_file::write(
+                initial_contents,
+                self.element_bit_width,
+                format,
+                w,
+            )?),
+            None => Err(Error::MemWithoutInitialContents {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+            }),
+        }
     }
 
     /// Specifies a read port for this `Mem` and returns a [`Signal`] representing the data read from this port.
@@ -127,12 +325,87 @@ impl<'a> Mem<'a> {
     /// m.output("my_output", my_mem.read_port(m.high(), m.high()));
     /// ```
     pub fn read_port(&'a self, address: &'a Signal<'a>, enable: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_read_port(address, enable)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`read_port`](Self::read_port), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemReadPortAddressBitWidthMismatch`] or [`Error::MemReadPortEnableBitWidthMismatch`] under the same conditions that [`read_port`](Self::read_port) panics.
+    pub fn try_read_port(
+        &'a self,
+        address: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+    ) -> Result<&'a Signal<'a>, Error> {
+        self.try_read_port_with_read_during_write(address, enable, ReadDuringWrite::ReadFirst)
+    }
+
+    /// Specifies a read port for this `Mem` with the specified [`ReadDuringWrite`] behavior, and returns a [`Signal`] representing the data read from this port.
+    ///
+    /// This behaves like [`read_port`](Self::read_port) (which is equivalent to specifying [`ReadDuringWrite::ReadFirst`]), except for what the read returns when a write port writes the location being read within the same cycle.
+    /// Different read ports of the same `Mem` may use different behaviors.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`read_port`](Self::read_port).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let address = m.input("address", 8);
+    ///
+    /// let my_mem = m.mem("my_mem", 8, 32);
+    /// my_mem.write_port(address, m.input("write_value", 32), m.input("write_enable", 1));
+    /// // Reflects `write_value` on the following cycle when `write_enable` is asserted
+    /// m.output(
+    ///     "read_value",
+    ///     my_mem.read_port_with_read_during_write(address, m.high(), ReadDuringWrite::WriteFirst),
+    /// );
+    /// ```
+    pub fn read_port_with_read_during_write(
+        &'a self,
+        address: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+        read_during_write: ReadDuringWrite,
+    ) -> &'a Signal<'a> {
+        self.try_read_port_with_read_during_write(address, enable, read_during_write)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`read_port_with_read_during_write`](Self::read_port_with_read_during_write), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`try_read_port`](Self::try_read_port).
+    pub fn try_read_port_with_read_during_write(
+        &'a self,
+        address: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+        read_during_write: ReadDuringWrite,
+    ) -> Result<&'a Signal<'a>, Error> {
         // TODO: Limit amount of read ports added?
         if address.bit_width() != self.address_bit_width {
-            panic!("Attempted to specify a read port for memory \"{}\" in module \"{}\" with an address signal with {} bit(s), but this memory has {} address bit(s).", self.name, self.module.name, address.bit_width(), self.address_bit_width);
+            return Err(Error::MemReadPortAddressBitWidthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                bit_width: address.bit_width(),
+                address_bit_width: self.address_bit_width,
+            });
         }
         if enable.bit_width() != 1 {
-            panic!("Attempted to specify a read port for memory \"{}\" in module \"{}\" with an enable signal with {} bit(s), but memory read/write ports are required to be 1 bit wide.", self.name, self.module.name, enable.bit_width());
+            return Err(Error::MemReadPortEnableBitWidthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                bit_width: enable.bit_width(),
+            });
         }
         let ret = self.context.signal_arena.alloc(Signal {
             context: self.context,
@@ -142,15 +415,77 @@ impl<'a> Mem<'a> {
                 mem: self,
                 address,
                 enable,
+                read_during_write,
             },
         });
-        self.read_ports.borrow_mut().push((address, enable));
-        ret
+        self.read_ports.borrow_mut().push(ReadPort {
+            address,
+            enable,
+            read_during_write,
+        });
+        Ok(ret)
+    }
+
+    /// Specifies an asynchronous read port for this `Mem` and returns a [`Signal`] representing the data read from this port.
+    ///
+    /// Unlike [`read_port`](Self::read_port), an asynchronous read port has no enable signal and no internal state: the returned [`Signal`] always reflects the current contents of the location specified by `address`, within the same cycle.
+    /// Writes are still synchronous, so a write to the location being read becomes visible on the cycle following the one in which it's asserted.
+    ///
+    /// Since the returned [`Signal`] depends combinationally on `address`, it can take part in a combinational loop (e.g. when `address` is driven by an [`Instance`](crate::Instance) output that depends on the read value), which the code generators will report as an error.
+    ///
+    /// Note that not all target devices can implement asynchronous reads with dedicated memory resources (e.g. block RAMs); such memories will typically be synthesized into distributed/LUT RAM or registers instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `address`'s bit width doesn't match this `Mem`'s address bit width.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("RegFile");
+    ///
+    /// let regs = m.mem("regs", 5, 32);
+    /// regs.write_port(m.input("rd", 5), m.input("rd_value", 32), m.input("rd_enable", 1));
+    /// m.output("rs1_value", regs.async_read_port(m.input("rs1", 5)));
+    /// m.output("rs2_value", regs.async_read_port(m.input("rs2", 5)));
+    /// ```
+    pub fn async_read_port(&'a self, address: &'a Signal<'a>) -> &'a Signal<'a> {
+        self.try_async_read_port(address)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 
-    /// Specifies a write port for this `Mem`.
+    /// Like [`async_read_port`](Self::async_read_port), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemReadPortAddressBitWidthMismatch`] under the same conditions that [`async_read_port`](Self::async_read_port) panics.
+    pub fn try_async_read_port(&'a self, address: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if address.bit_width() != self.address_bit_width {
+            return Err(Error::MemReadPortAddressBitWidthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                bit_width: address.bit_width(),
+                address_bit_width: self.address_bit_width,
+            });
+        }
+        let ret = self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::MemAsyncReadPortOutput { mem: self, address },
+        });
+        self.async_read_ports.borrow_mut().push(address);
+        Ok(ret)
+    }
+
+    /// Adds a write port to this `Mem`.
     ///
     /// By default, a `Mem` does not have any write ports, and it is not required to specify one unless the `Mem` does not have initial contents.
+    /// A `Mem` may have any number of write ports; if several of them write to the same location within the same cycle, the one that was added last takes priority.
     ///
     /// Write ports always have an `address` signal, a `value` signal, and an `enable` signal.
     /// When `enable` is asserted, the value at the location specified by `address` will reflect the value of the `value` signal on the following cycle.
@@ -158,7 +493,7 @@ impl<'a> Mem<'a> {
     ///
     /// # Panics
     ///
-    /// Panics if this `Mem` already has a write port specified, if `address`'s bit width doesn't match this `Mem`'s address bit width, if `value`'s bit width doesn't match this `Mem`'s element bit width, or if `enable`'s bit width is not `1`.
+    /// Panics if `address`'s bit width doesn't match this `Mem`'s address bit width, if `value`'s bit width doesn't match this `Mem`'s element bit width, or if `enable`'s bit width is not `1`.
     ///
     /// # Examples
     ///
@@ -176,26 +511,238 @@ impl<'a> Mem<'a> {
     /// my_mem.write_port(m.high(), m.lit(0xabad1deau32, 32), m.high());
     /// m.output("my_output", my_mem.read_port(m.high(), m.high()));
     /// ```
-    // TODO: byte/word enable? How might that interface look?
     pub fn write_port(
         &'a self,
         address: &'a Signal<'a>,
         value: &'a Signal<'a>,
         enable: &'a Signal<'a>,
     ) {
-        if self.write_port.borrow().is_some() {
-            panic!("Attempted to specify a write port for memory \"{}\" in module \"{}\", but this memory already has a write port.", self.name, self.module.name);
+        self.try_write_port(address, value, enable)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`write_port`](Self::write_port), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemWritePortAddressBitWidthMismatch`], [`Error::MemWritePortValueBitWidthMismatch`] or [`Error::MemWritePortEnableBitWidthMismatch`] under the same conditions that [`write_port`](Self::write_port) panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let my_mem = m.mem("my_mem", 1, 32);
+    /// assert!(matches!(
+    ///     my_mem.try_write_port(m.high(), m.lit(0xabad1deau32, 32), m.lit(3u32, 2)),
+    ///     Err(Error::MemWritePortEnableBitWidthMismatch { .. })
+    /// ));
+    /// assert!(my_mem.try_write_port(m.high(), m.lit(0xabad1deau32, 32), m.high()).is_ok());
+    /// ```
+    pub fn try_write_port(
+        &'a self,
+        address: &'a Signal<'a>,
+        value: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+    ) -> Result<(), Error> {
+        self.validate_write_port(address, value, enable)?;
+        self.add_write_port(address, value, enable, None);
+        Ok(())
+    }
+
+    /// Adds a masked write port to this `Mem`, which only writes some lanes of the addressed element.
+    ///
+    /// This behaves like [`write_port`](Self::write_port), except that each element is split into lanes of `mask_granularity` bits (e.g. `8` for byte lanes), and `mask` has one bit per lane.
+    /// When `enable` is asserted, only the lanes whose corresponding `mask` bits are set will reflect `value` on the following cycle; the rest will keep their previous contents.
+    /// Bit `0` of `mask` corresponds to the least significant lane.
+    ///
+    /// Masked and unmasked write ports can be freely combined on the same `Mem`, and follow the same priority rules, except that a masked write port only takes priority for the lanes it writes.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`write_port`](Self::write_port), if `mask_granularity` is `0` or doesn't evenly divide this `Mem`'s element bit width, or if `mask`'s bit width doesn't match the number of lanes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let my_mem = m.mem("my_mem", 8, 32);
+    /// // Write the bytes of `data` selected by `byte_enable`
+    /// my_mem.masked_write_port(
+    ///     m.input("address", 8),
+    ///     m.input("data", 32),
+    ///     m.input("write_enable", 1),
+    ///     m.input("byte_enable", 4),
+    ///     8,
+    /// );
+    /// m.output("my_output", my_mem.read_port(m.input("read_address", 8), m.high()));
+    /// ```
+    pub fn masked_write_port(
+        &'a self,
+        address: &'a Signal<'a>,
+        value: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+        mask: &'a Signal<'a>,
+        mask_granularity: u32,
+    ) {
+        self.try_masked_write_port(address, value, enable, mask, mask_granularity)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`masked_write_port`](Self::masked_write_port), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`try_write_port`](Self::try_write_port), as well as [`Error::MemWritePortMaskGranularityInvalid`] or [`Error::MemWritePortMaskBitWidthMismatch`] under the same conditions that [`masked_write_port`](Self::masked_write_port) panics.
+    pub fn try_masked_write_port(
+        &'a self,
+        address: &'a Signal<'a>,
+        value: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+        mask: &'a Signal<'a>,
+        mask_granularity: u32,
+    ) -> Result<(), Error> {
+        self.validate_write_port(address, value, enable)?;
+        if mask_granularity == 0 || !self.element_bit_width.is_multiple_of(mask_granularity) {
+            return Err(Error::MemWritePortMaskGranularityInvalid {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                granularity: mask_granularity,
+                element_bit_width: self.element_bit_width,
+            });
         }
+        let num_lanes = self.element_bit_width / mask_granularity;
+        if mask.bit_width() != num_lanes {
+            return Err(Error::MemWritePortMaskBitWidthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                bit_width: mask.bit_width(),
+                required_bit_width: num_lanes,
+            });
+        }
+        self.add_write_port(
+            address,
+            value,
+            enable,
+            Some(WriteMask {
+                mask,
+                granularity: mask_granularity,
+            }),
+        );
+        Ok(())
+    }
+
+    // Checks the parts of a write port common to masked and unmasked write ports.
+    fn validate_write_port(
+        &'a self,
+        address: &'a Signal<'a>,
+        value: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+    ) -> Result<(), Error> {
         if address.bit_width() != self.address_bit_width {
-            panic!("Attempted to specify a write port for memory \"{}\" in module \"{}\" with an address signal with {} bit(s), but this memory has {} address bit(s).", self.name, self.module.name, address.bit_width(), self.address_bit_width);
+            return Err(Error::MemWritePortAddressBitWidthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                bit_width: address.bit_width(),
+                address_bit_width: self.address_bit_width,
+            });
         }
         if value.bit_width() != self.element_bit_width {
-            panic!("Attempted to specify a write port for memory \"{}\" in module \"{}\" with a value signal with {} bit(s), but this memory has {} element bit(s).", self.name, self.module.name, value.bit_width(), self.element_bit_width);
+            return Err(Error::MemWritePortValueBitWidthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                bit_width: value.bit_width(),
+                element_bit_width: self.element_bit_width,
+            });
         }
         if enable.bit_width() != 1 {
-            panic!("Attempted to specify a write port for memory \"{}\" in module \"{}\" with an enable signal with {} bit(s), but memory read/write ports are required to be 1 bit wide.", self.name, self.module.name, enable.bit_width());
+            return Err(Error::MemWritePortEnableBitWidthMismatch {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+                bit_width: enable.bit_width(),
+            });
         }
-        *self.write_port.borrow_mut() = Some((address, value, enable));
+        Ok(())
+    }
+
+    fn add_write_port(
+        &'a self,
+        address: &'a Signal<'a>,
+        value: &'a Signal<'a>,
+        enable: &'a Signal<'a>,
+        mask: Option<WriteMask<'a>>,
+    ) {
+        self.write_ports.borrow_mut().push(WritePort {
+            address,
+            value,
+            enable,
+            mask,
+        });
+    }
+
+    /// Binds this `Mem` to the specified [`ClockDomain`].
+    ///
+    /// By default, a `Mem` belongs to its [`Module`]'s [default clock domain](Module::default_clock_domain).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` and `clock_domain` belong to different [`Module`]s, or if this `Mem` is already bound to a [`ClockDomain`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    /// let mem_domain = m.clock_domain("mem");
+    ///
+    /// let my_mem = m.mem("my_mem", 1, 32);
+    /// my_mem.clock_domain(mem_domain);
+    /// my_mem.initial_contents(&[0xfadebabeu32, 0xdeadbeefu32]);
+    /// m.output("my_output", my_mem.read_port(m.high(), m.high()));
+    /// ```
+    pub fn clock_domain(&'a self, clock_domain: &'a ClockDomain<'a>) {
+        self.try_clock_domain(clock_domain)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`clock_domain`](Self::clock_domain), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemClockDomainFromAnotherModule`] or [`Error::MemClockDomainAlreadyBound`] under the same conditions that [`clock_domain`](Self::clock_domain) panics.
+    pub fn try_clock_domain(&'a self, clock_domain: &'a ClockDomain<'a>) -> Result<(), Error> {
+        if !ptr::eq(self.module, clock_domain.module) {
+            return Err(Error::MemClockDomainFromAnotherModule {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+            });
+        }
+        if self.clock_domain.borrow().is_some() {
+            return Err(Error::MemClockDomainAlreadyBound {
+                module: self.module.name.clone(),
+                mem: self.name.clone(),
+            });
+        }
+        *self.clock_domain.borrow_mut() = Some(clock_domain);
+        Ok(())
+    }
+
+    pub(crate) fn resolved_clock_domain(&self) -> &'a ClockDomain<'a> {
+        self.clock_domain
+            .borrow()
+            .unwrap_or_else(|| self.module.default_clock_domain())
     }
 }
 
@@ -261,6 +808,89 @@ mod tests {
         mem.initial_contents(&[2u32, 0u32]);
     }
 
+    #[test]
+    #[should_panic(
+        expected = "Attempted to load initial contents for memory \"mem\" in module \"A\" from file \"does_not_exist.hex\", but this file couldn't be loaded ("
+    )]
+    fn initial_contents_file_missing_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 1);
+
+        // Panic
+        mem.initial_contents_file("does_not_exist.hex", MemFileFormat::Hex);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to specify initial contents for memory \"mem\" in module \"A\", but this memory already has initial contents."
+    )]
+    fn initial_contents_file_already_specified_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 1);
+
+        mem.initial_contents(&[true, false]);
+
+        // Panic
+        mem.initial_contents_file("does_not_exist.hex", MemFileFormat::Hex);
+    }
+
+    #[test]
+    fn initial_contents_file_errors() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        // Include the process ID, so that concurrent test runs don't share the file
+        let path = std::env::temp_dir().join(format!(
+            "kaze_initial_contents_file_errors_{}.hex",
+            std::process::id()
+        ));
+
+        std::fs::write(&path, "0 1 2").unwrap();
+        let mem = m.mem("mem1", 1, 2);
+        assert!(matches!(
+            mem.try_initial_contents_file(&path, MemFileFormat::Hex),
+            Err(Error::MemInitialContentsFileInvalid { ref message, .. })
+                if message == "line 1: element 2 is out of range, as the memory only has 2 element(s)"
+        ));
+        assert!(mem.initial_contents.borrow().is_none());
+
+        std::fs::write(&path, "0").unwrap();
+        let mem = m.mem("mem2", 1, 2);
+        assert!(matches!(
+            mem.try_initial_contents_file(&path, MemFileFormat::Hex),
+            Err(Error::MemInitialContentsLengthMismatch { len: 1, .. })
+        ));
+
+        std::fs::write(&path, "0 4").unwrap();
+        let mem = m.mem("mem3", 1, 2);
+        assert!(matches!(
+            mem.try_initial_contents_file(&path, MemFileFormat::Hex),
+            Err(Error::MemInitialContentsElementTooWide { index: 1, .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_initial_contents_without_initial_contents_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 1);
+
+        let e = mem
+            .write_initial_contents(MemFileFormat::Hex, Vec::new())
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Attempted to write the initial contents of memory \"mem\" in module \"A\", but this memory doesn't have initial contents."
+        );
+    }
+
     #[test]
     #[should_panic(
         expected = "Attempted to specify a read port for memory \"mem\" in module \"A\" with an address signal with 2 bit(s), but this memory has 1 address bit(s)."
@@ -291,18 +921,16 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Attempted to specify a write port for memory \"mem\" in module \"A\", but this memory already has a write port."
+        expected = "Attempted to specify a read port for memory \"mem\" in module \"A\" with an address signal with 2 bit(s), but this memory has 1 address bit(s)."
     )]
-    fn write_port_already_specified_error() {
+    fn async_read_port_address_bit_width_error() {
         let c = Context::new();
 
         let m = c.module("A");
         let mem = m.mem("mem", 1, 1);
 
-        mem.write_port(m.low(), m.low(), m.low());
-
         // Panic
-        mem.write_port(m.low(), m.low(), m.low());
+        let _ = mem.async_read_port(m.lit(0u32, 2));
     }
 
     #[test]
@@ -346,4 +974,79 @@ mod tests {
         // Panic
         mem.write_port(m.low(), m.low(), m.lit(0u32, 2));
     }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to specify a masked write port for memory \"mem\" in module \"A\" with a mask granularity of 3 bit(s), but this memory's element bit width (8) is not a non-zero multiple of it."
+    )]
+    fn masked_write_port_granularity_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 8);
+
+        // Panic
+        mem.masked_write_port(m.low(), m.lit(0u32, 8), m.low(), m.lit(0u32, 2), 3);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to specify a masked write port for memory \"mem\" in module \"A\" with a mask granularity of 0 bit(s), but this memory's element bit width (8) is not a non-zero multiple of it."
+    )]
+    fn masked_write_port_zero_granularity_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 8);
+
+        // Panic
+        mem.masked_write_port(m.low(), m.lit(0u32, 8), m.low(), m.lit(0u32, 2), 0);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to specify a masked write port for memory \"mem\" in module \"A\" with a mask signal with 2 bit(s), but this write port requires a mask with 4 bit(s)."
+    )]
+    fn masked_write_port_mask_bit_width_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 8);
+
+        // Panic
+        mem.masked_write_port(m.low(), m.lit(0u32, 8), m.low(), m.lit(0u32, 2), 2);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to bind memory \"mem\" in module \"A\" to a clock domain from another module."
+    )]
+    fn clock_domain_separate_module_error() {
+        let c = Context::new();
+
+        let m1 = c.module("A");
+        let mem = m1.mem("mem", 1, 1);
+
+        let m2 = c.module("B");
+        let d = m2.clock_domain("d");
+
+        // Panic
+        mem.clock_domain(d);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to bind memory \"mem\" in module \"A\" to a clock domain, but this memory is already bound to a clock domain."
+    )]
+    fn clock_domain_already_bound_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 1);
+
+        mem.clock_domain(m.clock_domain("d1"));
+
+        // Panic
+        mem.clock_domain(m.clock_domain("d2"));
+    }
 }
@@ -0,0 +1,311 @@
+use super::constant::*;
+
+use std::io::{self, Write};
+
+/// The format of a memory file, as read by Verilog's `$readmemh`/`$readmemb` system tasks.
+///
+/// Memory files contain one value per element, separated by whitespace, in hexadecimal ([`Hex`](Self::Hex)) or binary ([`Binary`](Self::Binary)) notation without a prefix.
+/// Values may contain `_` separators, and may be preceded by `@<address>` directives (with a hexadecimal address, which must refer to one of the memory's elements) specifying where the following values are placed.
+/// `//` and `/* */` comments are ignored.
+///
+/// See [`Mem::initial_contents_file`](crate::Mem::initial_contents_file) and [`Mem::write_initial_contents`](crate::Mem::write_initial_contents).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemFileFormat {
+    /// Hexadecimal values, as read by `$readmemh`.
+    Hex,
+    /// Binary values, as read by `$readmemb`.
+    Binary,
+}
+
+impl MemFileFormat {
+    pub(crate) fn readmem_task_name(&self) -> &'static str {
+        match self {
+            MemFileFormat::Hex => "$readmemh",
+            MemFileFormat::Binary => "$readmemb",
+        }
+    }
+
+    fn bits_per_digit(&self) -> u32 {
+        match self {
+            MemFileFormat::Hex => 4,
+            MemFileFormat::Binary => 1,
+        }
+    }
+}
+
+/// Parses the contents of a memory file for a memory with `num_elements` elements, returning a description of the first problem found (including the line it was found on, if any) on failure.
+///
+/// Unlike `$readmemh`/`$readmemb`, which leave unspecified elements undefined, every element up to the last one specified is required to be specified, as kaze requires memories' initial contents to be fully defined. The returned contents may be shorter than `num_elements`; this is reported by the caller.
+pub(crate) fn parse(
+    source: &str,
+    format: MemFileFormat,
+    num_elements: usize,
+) -> Result<Vec<Constant>, String> {
+    let mut contents: Vec<Option<Constant>> = Vec::new();
+    let mut address = 0;
+
+    for (line, token) in tokens(source)? {
+        let error = |message: String| format!("line {}: {}", line, message);
+
+        if let Some(address_digits) = token.strip_prefix('@') {
+            let value = parse_digits(address_digits, MemFileFormat::Hex)
+                .map_err(|message| error(format!("invalid address \"{}\" ({})", token, message)))?;
+            if value.required_bits() > 64 {
+                return Err(error(format!(
+                    "invalid address \"{}\" (addresses wider than 64 bits are not supported)",
+                    token
+                )));
+            }
+            let value = value.numeric_value() as u64;
+            if value >= num_elements as u64 {
+                return Err(error(format!(
+                    "address \"{}\" is out of range, as the memory only has {} element(s)",
+                    token, num_elements
+                )));
+            }
+            address = value as usize;
+            continue;
+        }
+
+        let value = parse_digits(&token, format)
+            .map_err(|message| error(format!("invalid value \"{}\" ({})", token, message)))?;
+        if address >= num_elements {
+            return Err(error(format!(
+                "element {} is out of range, as the memory only has {} element(s)",
+                address, num_elements
+            )));
+        }
+        if address >= contents.len() {
+            contents.resize_with(address + 1, || None);
+        }
+        contents[address] = Some(value);
+        address += 1;
+    }
+
+    contents
+        .into_iter()
+        .enumerate()
+        .map(|(index, element)| {
+            element.ok_or_else(|| format!("element {} is not specified", index))
+        })
+        .collect()
+}
+
+// Splits `source` into whitespace-separated tokens, skipping comments.
+fn tokens(source: &str) -> Result<Vec<(usize, String)>, String> {
+    let mut ret = Vec::new();
+    let mut in_block_comment = None;
+
+    for (index, mut line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut token = String::new();
+
+        loop {
+            if in_block_comment.is_some() {
+                match line.find("*/") {
+                    Some(end) => {
+                        in_block_comment = None;
+                        line = &line[end + 2..];
+                    }
+                    None => break,
+                }
+            }
+
+            let comment_start = [line.find("//"), line.find("/*")]
+                .iter()
+                .flatten()
+                .min()
+                .copied();
+            let (code, rest) = match comment_start {
+                Some(start) => (&line[..start], Some(&line[start..])),
+                None => (line, None),
+            };
+            for c in code.chars() {
+                if c.is_whitespace() {
+                    if !token.is_empty() {
+                        ret.push((line_number, token.split_off(0)));
+                    }
+                } else {
+                    token.push(c);
+                }
+            }
+            // Comments also separate tokens
+            if !token.is_empty() {
+                ret.push((line_number, token.split_off(0)));
+            }
+
+            match rest {
+                Some(rest) if rest.starts_with("/*") => {
+                    in_block_comment = Some(line_number);
+                    line = &rest[2..];
+                }
+                _ => break,
+            }
+        }
+    }
+
+    if let Some(line) = in_block_comment {
+        return Err(format!("line {}: unterminated block comment", line));
+    }
+
+    Ok(ret)
+}
+
+fn parse_digits(digits: &str, format: MemFileFormat) -> Result<Constant, String> {
+    let bits_per_digit = format.bits_per_digit();
+    let mut words = vec![0u64];
+    let mut num_digits = 0;
+    for c in digits.chars() {
+        if c == '_' {
+            continue;
+        }
+        let digit = match c.to_digit(1 << bits_per_digit) {
+            Some(digit) => digit as u64,
+            None if "xXzZ".contains(c) => {
+                return Err("unknown (x/z) values are not supported".into());
+            }
+            None => return Err(format!("unexpected character '{}'", c)),
+        };
+        // Shift the accumulated value left by one digit
+        let mut carry = digit;
+        for word in words.iter_mut() {
+            let next_carry = *word >> (64 - bits_per_digit);
+            *word = (*word << bits_per_digit) | carry;
+            carry = next_carry;
+        }
+        if carry != 0 {
+            words.push(carry);
+        }
+        num_digits += 1;
+    }
+    if num_digits == 0 {
+        return Err("no digits".into());
+    }
+
+    Ok(if words.len() <= 2 {
+        Constant::U128(Constant::Wide(words).numeric_value())
+    } else {
+        Constant::Wide(words)
+    })
+}
+
+/// Writes `contents` to `w` in the specified format, one element per line, with each element zero-padded to `element_bit_width` bits.
+pub(crate) fn write<W: Write>(
+    contents: &[Constant],
+    element_bit_width: u32,
+    format: MemFileFormat,
+    mut w: W,
+) -> io::Result<()> {
+    for element in contents.iter() {
+        match format {
+            MemFileFormat::Hex => {
+                let num_digits = element_bit_width.div_ceil(4) as usize;
+                writeln!(w, "{:0>width$}", element.hex_string(), width = num_digits)?;
+            }
+            MemFileFormat::Binary => {
+                writeln!(w, "{}", element.binary_string(element_bit_width))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(contents: Vec<Constant>) -> Vec<u128> {
+        contents.iter().map(|c| c.numeric_value()).collect()
+    }
+
+    #[test]
+    fn parse_hex() {
+        let source = "// Header comment\n01 ab\n  FF_FF /* inline */ 0\n";
+        let contents = parse(source, MemFileFormat::Hex, 4).unwrap();
+        assert_eq!(values(contents), vec![0x01, 0xab, 0xffff, 0]);
+    }
+
+    #[test]
+    fn parse_binary() {
+        let contents = parse("1010\n0001\n", MemFileFormat::Binary, 2).unwrap();
+        assert_eq!(values(contents), vec![0b1010, 0b0001]);
+    }
+
+    #[test]
+    fn parse_address_directives() {
+        let contents = parse("@2 c d\n@0 a b", MemFileFormat::Hex, 4).unwrap();
+        assert_eq!(values(contents), vec![0xa, 0xb, 0xc, 0xd]);
+    }
+
+    #[test]
+    fn parse_multiline_block_comment() {
+        let contents = parse("1 /* 2\n3 */ 4", MemFileFormat::Hex, 2).unwrap();
+        assert_eq!(values(contents), vec![1, 4]);
+    }
+
+    #[test]
+    fn parse_wide() {
+        let contents = parse("1_0000000000000000_0000000000000002", MemFileFormat::Hex, 1).unwrap();
+        assert_eq!(contents[0].words(3), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("0\n1\n2", MemFileFormat::Binary, 4).err(),
+            Some("line 3: invalid value \"2\" (unexpected character '2')".into())
+        );
+        assert_eq!(
+            parse("0 x", MemFileFormat::Hex, 4).err(),
+            Some("line 1: invalid value \"x\" (unknown (x/z) values are not supported)".into())
+        );
+        assert_eq!(
+            parse("0 1\n2", MemFileFormat::Hex, 2).err(),
+            Some("line 2: element 2 is out of range, as the memory only has 2 element(s)".into())
+        );
+        assert_eq!(
+            parse("0\n@2 1", MemFileFormat::Hex, 2).err(),
+            Some(
+                "line 2: address \"@2\" is out of range, as the memory only has 2 element(s)"
+                    .into()
+            )
+        );
+        assert_eq!(
+            parse("@1_0000000000000000 1", MemFileFormat::Hex, 2).err(),
+            Some("line 1: invalid address \"@1_0000000000000000\" (addresses wider than 64 bits are not supported)".into())
+        );
+        assert_eq!(
+            parse("@1 1", MemFileFormat::Hex, 2).err(),
+            Some("element 0 is not specified".into())
+        );
+        assert_eq!(
+            parse("0\n/* 1", MemFileFormat::Hex, 2).err(),
+            Some("line 2: unterminated block comment".into())
+        );
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let contents = vec![
+            Constant::from(0x5u32),
+            Constant::from(0xabcu32),
+            Constant::from(vec![0u64, 1u64]),
+        ];
+        for &format in [MemFileFormat::Hex, MemFileFormat::Binary].iter() {
+            let mut file = Vec::new();
+            write(&contents, 72, format, &mut file).unwrap();
+            let source = String::from_utf8(file).unwrap();
+            let parsed = parse(&source, format, 3).unwrap();
+            assert_eq!(values(parsed), vec![0x5, 0xabc, 1 << 64]);
+        }
+
+        let mut file = Vec::new();
+        write(&contents[..2], 12, MemFileFormat::Hex, &mut file).unwrap();
+        assert_eq!(String::from_utf8(file).unwrap(), "005\nabc\n");
+
+        let mut file = Vec::new();
+        write(&contents[..1], 4, MemFileFormat::Binary, &mut file).unwrap();
+        assert_eq!(String::from_utf8(file).unwrap(), "0101\n");
+    }
+}
@@ -1,10 +1,14 @@
+use super::clock_domain::*;
 use super::constant::*;
 use super::context::*;
 use super::instance::*;
 use super::mem::*;
+use super::property::*;
 use super::register::*;
 use super::signal::*;
 
+use crate::error::*;
+
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::ptr;
@@ -13,7 +17,7 @@ use std::ptr;
 ///
 /// Once a `Module` is specified, it can be [instantiated](Self::instance) in another `Module` to form a hierarchy, or it can be used to generate [Rust simulator code](crate::sim::generate) or a [Verilog module](crate::verilog::generate).
 ///
-/// All `Module`s in kaze have an implicit reset and clock. These are only visible in generated code. It's assumed that all kaze modules operate in the same clock domain.
+/// All `Module`s in kaze have an implicit default [clock domain](Self::default_clock_domain), consisting of a clock and a reset that are only visible in generated code. Additional clock domains can be created with [`clock_domain`](Self::clock_domain), and [`Register`]s and [`Mem`]s can be bound to them.
 ///
 /// # Examples
 ///
@@ -37,6 +41,9 @@ pub struct Module<'a> {
     pub(crate) registers: RefCell<Vec<&'a Signal<'a>>>,
     pub(crate) instances: RefCell<Vec<&'a Instance<'a>>>,
     pub(crate) mems: RefCell<Vec<&'a Mem<'a>>>,
+    pub(crate) properties: RefCell<Vec<&'a Property<'a>>>,
+    pub(crate) clock_domains: RefCell<Vec<&'a ClockDomain<'a>>>,
+    default_clock_domain: RefCell<Option<&'a ClockDomain<'a>>>,
 }
 
 impl<'a> Module<'a> {
@@ -51,9 +58,16 @@ impl<'a> Module<'a> {
             registers: RefCell::new(Vec::new()),
             instances: RefCell::new(Vec::new()),
             mems: RefCell::new(Vec::new()),
+            properties: RefCell::new(Vec::new()),
+            clock_domains: RefCell::new(Vec::new()),
+            default_clock_domain: RefCell::new(None),
         }
     }
 
+    pub(crate) fn context(&self) -> &'a Context<'a> {
+        self.context
+    }
+
     /// Creates a [`Signal`] that represents the constant literal specified by `value` with `bit_width` bits.
     ///
     /// The bit width of the type provided by `value` doesn't need to match `bit_width`, but the value represented by `value` must fit into `bit_width` bits.
@@ -76,30 +90,42 @@ impl<'a> Module<'a> {
     /// let twenty_seven_bit_const = m.lit(true, 27);
     /// ```
     pub fn lit<C: Into<Constant>>(&'a self, value: C, bit_width: u32) -> &Signal<'a> {
-        if bit_width < MIN_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a literal with {} bit(s). Signals must not be narrower than {} bit(s).",
-                bit_width, MIN_SIGNAL_BIT_WIDTH
-            );
-        }
-        if bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a literal with {} bit(s). Signals must not be wider than {} bit(s).",
-                bit_width, MAX_SIGNAL_BIT_WIDTH
-            );
+        self.try_lit(value, bit_width)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`lit`](Self::lit), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidLitBitWidth`] or [`Error::LitValueTooWide`] under the same conditions that [`lit`](Self::lit) panics.
+    pub fn try_lit<C: Into<Constant>>(
+        &'a self,
+        value: C,
+        bit_width: u32,
+    ) -> Result<&'a Signal<'a>, Error> {
+        if !(MIN_SIGNAL_BIT_WIDTH..=MAX_SIGNAL_BIT_WIDTH).contains(&bit_width) {
+            return Err(Error::InvalidLitBitWidth {
+                module: self.name.clone(),
+                bit_width,
+            });
         }
         let value = value.into();
         let required_bits = value.required_bits();
         if required_bits > bit_width {
-            let numeric_value = value.numeric_value();
-            panic!("Cannot fit the specified value '{}' into the specified bit width '{}'. The value '{}' requires a bit width of at least {} bit(s).", numeric_value, bit_width, numeric_value, required_bits);
+            return Err(Error::LitValueTooWide {
+                module: self.name.clone(),
+                value: value.numeric_value_string(),
+                bit_width,
+                required_bits,
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self,
 
             data: SignalData::Lit { value, bit_width },
-        })
+        }))
     }
 
     /// Convenience method to create a [`Signal`] that represents a single `0` bit.
@@ -158,19 +184,28 @@ impl<'a> Module<'a> {
     /// let my_input = m.input("my_input", 80);
     /// ```
     pub fn input<S: Into<String>>(&'a self, name: S, bit_width: u32) -> &Signal<'a> {
+        self.try_input(name, bit_width)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`input`](Self::input), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInputBitWidth`] under the same conditions that [`input`](Self::input) panics.
+    pub fn try_input<S: Into<String>>(
+        &'a self,
+        name: S,
+        bit_width: u32,
+    ) -> Result<&'a Signal<'a>, Error> {
         let name = name.into();
         // TODO: Error if name already exists in this context
-        if bit_width < MIN_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create an input with {} bit(s). Signals must not be narrower than {} bit(s).",
-                bit_width, MIN_SIGNAL_BIT_WIDTH
-            );
-        }
-        if bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create an input with {} bit(s). Signals must not be wider than {} bit(s).",
-                bit_width, MAX_SIGNAL_BIT_WIDTH
-            );
+        if !(MIN_SIGNAL_BIT_WIDTH..=MAX_SIGNAL_BIT_WIDTH).contains(&bit_width) {
+            return Err(Error::InvalidInputBitWidth {
+                module: self.name.clone(),
+                input: name,
+                bit_width,
+            });
         }
         let input = self.context.signal_arena.alloc(Signal {
             context: self.context,
@@ -182,7 +217,7 @@ impl<'a> Module<'a> {
             },
         });
         self.inputs.borrow_mut().insert(name, input);
-        input
+        Ok(input)
     }
 
     /// Creates an output for this `Module` called `name` with the same number of bits as `source`, and drives this output with `source`.
@@ -204,11 +239,47 @@ impl<'a> Module<'a> {
     /// m.output("my_output", some_signal);
     /// ```
     pub fn output<S: Into<String>>(&'a self, name: S, source: &'a Signal<'a>) {
+        self.try_output(name, source)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`output`](Self::output), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutputFromAnotherModule`] if `source` doesn't belong to this `Module`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m1 = c.module("A");
+    /// let m2 = c.module("B");
+    ///
+    /// assert!(m1.try_output("o", m1.high()).is_ok());
+    /// assert!(matches!(
+    ///     m1.try_output("o2", m2.high()),
+    ///     Err(Error::OutputFromAnotherModule { .. })
+    /// ));
+    /// ```
+    pub fn try_output<S: Into<String>>(
+        &'a self,
+        name: S,
+        source: &'a Signal<'a>,
+    ) -> Result<(), Error> {
+        let name = name.into();
         if !ptr::eq(self, source.module) {
-            panic!("Cannot output a signal from another module.");
+            return Err(Error::OutputFromAnotherModule {
+                module: self.name.clone(),
+                output: name,
+            });
         }
         // TODO: Error if name already exists in this context
-        self.outputs.borrow_mut().insert(name.into(), source);
+        self.outputs.borrow_mut().insert(name, source);
+        Ok(())
     }
 
     /// Creates a [`Register`] in this `Module` called `name` with `bit_width` bits.
@@ -232,26 +303,37 @@ impl<'a> Module<'a> {
     /// m.output("my_output", my_reg.value);
     /// ```
     pub fn reg<S: Into<String>>(&'a self, name: S, bit_width: u32) -> &Register<'a> {
+        self.try_reg(name, bit_width)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`reg`](Self::reg), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidRegisterBitWidth`] under the same conditions that [`reg`](Self::reg) panics.
+    pub fn try_reg<S: Into<String>>(
+        &'a self,
+        name: S,
+        bit_width: u32,
+    ) -> Result<&'a Register<'a>, Error> {
         // TODO: Error if name already exists in this context and update docs for Signal::reg_next and Signal::reg_next_with_default to reflect this
-        if bit_width < MIN_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a register with {} bit(s). Signals must not be narrower than {} bit(s).",
-                bit_width, MIN_SIGNAL_BIT_WIDTH
-            );
-        }
-        if bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a register with {} bit(s). Signals must not be wider than {} bit(s).",
-                bit_width, MAX_SIGNAL_BIT_WIDTH
-            );
+        let name = name.into();
+        if !(MIN_SIGNAL_BIT_WIDTH..=MAX_SIGNAL_BIT_WIDTH).contains(&bit_width) {
+            return Err(Error::InvalidRegisterBitWidth {
+                module: self.name.clone(),
+                register: name,
+                bit_width,
+            });
         }
         let data = self.context.register_data_arena.alloc(RegisterData {
             module: self,
 
-            name: name.into(),
+            name,
             initial_value: RefCell::new(None),
             bit_width,
             next: RefCell::new(None),
+            clock_domain: RefCell::new(None),
         });
         let value = self.context.signal_arena.alloc(Signal {
             context: self.context,
@@ -260,7 +342,7 @@ impl<'a> Module<'a> {
             data: SignalData::Reg { data },
         });
         self.registers.borrow_mut().push(value);
-        self.context.register_arena.alloc(Register { data, value })
+        Ok(self.context.register_arena.alloc(Register { data, value }))
     }
 
     /// Creates a 2:1 [multiplexer](https://en.wikipedia.org/wiki/Multiplexer) that represents `when_true`'s value when `cond` is high, and `when_false`'s value when `cond` is low.
@@ -289,31 +371,48 @@ impl<'a> Module<'a> {
         when_true: &'a Signal<'a>,
         when_false: &'a Signal<'a>,
     ) -> &Signal<'a> {
+        self.try_mux(cond, when_true, when_false)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`mux`](Self::mux), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`], [`Error::InvalidMuxConditionBitWidth`] or [`Error::MuxBitWidthMismatch`] under the same conditions that [`mux`](Self::mux) panics.
+    pub fn try_mux(
+        &'a self,
+        cond: &'a Signal<'a>,
+        when_true: &'a Signal<'a>,
+        when_false: &'a Signal<'a>,
+    ) -> Result<&'a Signal<'a>, Error> {
         // TODO: This is an optimization to support sugar; if that doesn't go well, remove this
         if when_true == when_false {
-            return when_true;
+            return Ok(when_true);
         }
 
-        if !ptr::eq(self, cond.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        if !ptr::eq(self, when_true.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        if !ptr::eq(self, when_false.module) {
-            panic!("Attempted to combine signals from different modules.");
+        if !ptr::eq(self, cond.module)
+            || !ptr::eq(self, when_true.module)
+            || !ptr::eq(self, when_false.module)
+        {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.name.clone(),
+            });
         }
         if cond.bit_width() != 1 {
-            panic!("Multiplexer conditionals can only be 1 bit wide.");
+            return Err(Error::InvalidMuxConditionBitWidth {
+                module: self.name.clone(),
+                bit_width: cond.bit_width(),
+            });
         }
         if when_true.bit_width() != when_false.bit_width() {
-            panic!(
-                "Cannot multiplex signals with different bit widths ({} and {}, respectively).",
-                when_true.bit_width(),
-                when_false.bit_width()
-            );
+            return Err(Error::MuxBitWidthMismatch {
+                module: self.name.clone(),
+                when_true_bit_width: when_true.bit_width(),
+                when_false_bit_width: when_false.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self,
 
@@ -323,7 +422,7 @@ impl<'a> Module<'a> {
                 when_false,
                 bit_width: when_true.bit_width(),
             },
-        })
+        }))
     }
 
     /// Creates an [`Instance`] called `instance_name` of the `Module` identified by `module_name` in this [`Context`] inside this `Module` definition.
@@ -354,6 +453,20 @@ impl<'a> Module<'a> {
         instance_name: S,
         module_name: &str,
     ) -> &Instance<'a> {
+        self.try_instance(instance_name, module_name)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`instance`](Self::instance), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ModuleNotFound`] if a [`Module`] identified by `module_name` doesn't exist in the [`Context`] containing this `Module`.
+    pub fn try_instance<S: Into<String>>(
+        &'a self,
+        instance_name: S,
+        module_name: &str,
+    ) -> Result<&'a Instance<'a>, Error> {
         // TODO: Error if instance_name already exists in this context
         match self.context.modules.borrow().get(module_name) {
             Some(instantiated_module) => {
@@ -364,11 +477,17 @@ impl<'a> Module<'a> {
                     instantiated_module,
                     name: instance_name.into(),
                     driven_inputs: RefCell::new(BTreeMap::new()),
+                    driven_clock_domains: RefCell::new(BTreeMap::new()),
+                    driven_default_clock_domain: RefCell::new(None),
                 });
                 self.instances.borrow_mut().push(ret);
-                ret
+                Ok(ret)
             }
-            _ => panic!("Attempted to instantiate a module identified by \"{}\", but no such module exists in this context.", module_name)
+            _ => Err(Error::ModuleNotFound {
+                module: self.name.clone(),
+                instance: instance_name.into(),
+                instantiated_module: module_name.into(),
+            }),
         }
     }
 
@@ -402,46 +521,316 @@ impl<'a> Module<'a> {
         address_bit_width: u32,
         element_bit_width: u32,
     ) -> &Mem<'a> {
+        self.try_mem(name, address_bit_width, element_bit_width)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`mem`](Self::mem), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidMemAddressBitWidth`] or [`Error::InvalidMemElementBitWidth`] under the same conditions that [`mem`](Self::mem) panics.
+    pub fn try_mem<S: Into<String>>(
+        &'a self,
+        name: S,
+        address_bit_width: u32,
+        element_bit_width: u32,
+    ) -> Result<&'a Mem<'a>, Error> {
         // TODO: Error if name already exists in this context
-        if address_bit_width < MIN_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a memory with {} address bit(s). Signals must not be narrower than {} bit(s).",
-                address_bit_width, MIN_SIGNAL_BIT_WIDTH
-            );
-        }
-        if address_bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a memory with {} address bit(s). Signals must not be wider than {} bit(s).",
-                address_bit_width, MAX_SIGNAL_BIT_WIDTH
-            );
-        }
-        if element_bit_width < MIN_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a memory with {} element bit(s). Signals must not be narrower than {} bit(s).",
-                element_bit_width, MIN_SIGNAL_BIT_WIDTH
-            );
+        let name = name.into();
+        if !(MIN_SIGNAL_BIT_WIDTH..=MAX_SIGNAL_BIT_WIDTH).contains(&address_bit_width) {
+            return Err(Error::InvalidMemAddressBitWidth {
+                module: self.name.clone(),
+                mem: name,
+                bit_width: address_bit_width,
+            });
         }
-        if element_bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!(
-                "Cannot create a memory with {} element bit(s). Signals must not be wider than {} bit(s).",
-                element_bit_width, MAX_SIGNAL_BIT_WIDTH
-            );
+        if !(MIN_SIGNAL_BIT_WIDTH..=MAX_SIGNAL_BIT_WIDTH).contains(&element_bit_width) {
+            return Err(Error::InvalidMemElementBitWidth {
+                module: self.name.clone(),
+                mem: name,
+                bit_width: element_bit_width,
+            });
         }
         let ret = self.context.mem_arena.alloc(Mem {
             context: self.context,
             module: self,
 
-            name: name.into(),
+            name,
             address_bit_width,
             element_bit_width,
 
             initial_contents: RefCell::new(None),
+            initial_contents_file: RefCell::new(None),
 
             read_ports: RefCell::new(Vec::new()),
-            write_port: RefCell::new(None),
+            async_read_ports: RefCell::new(Vec::new()),
+            write_ports: RefCell::new(Vec::new()),
+
+            clock_domain: RefCell::new(None),
         });
         self.mems.borrow_mut().push(ret);
-        ret
+        Ok(ret)
+    }
+
+    /// Creates an assertion [`Property`] in this `Module` called `name`, which requires `condition` to be high in every cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `condition` belongs to a different `Module` than `self`, or if `condition`'s bit width is not 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let push = m.input("push", 1);
+    /// let full = m.input("full", 1);
+    /// m.output("o", push);
+    ///
+    /// m.assert("fifo_not_overflow", !(push & full));
+    /// ```
+    pub fn assert<S: Into<String>>(
+        &'a self,
+        name: S,
+        condition: &'a Signal<'a>,
+    ) -> &'a Property<'a> {
+        self.try_assert(name, condition)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`assert`](Self::assert), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PropertyConditionFromAnotherModule`] or [`Error::InvalidPropertyConditionBitWidth`] under the same conditions that [`assert`](Self::assert) panics.
+    pub fn try_assert<S: Into<String>>(
+        &'a self,
+        name: S,
+        condition: &'a Signal<'a>,
+    ) -> Result<&'a Property<'a>, Error> {
+        self.property(name.into(), PropertyKind::Assert, condition)
+    }
+
+    /// Creates an assumption [`Property`] in this `Module` called `name`, which assumes `condition` to be high in every cycle.
+    ///
+    /// Assumptions are typically used to constrain a `Module`'s inputs for formal verification tools.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `condition` belongs to a different `Module` than `self`, or if `condition`'s bit width is not 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let op = m.input("op", 2);
+    /// m.output("o", op);
+    ///
+    /// m.assume("op_valid", op.ne(m.lit(3u32, 2)));
+    /// ```
+    pub fn assume<S: Into<String>>(
+        &'a self,
+        name: S,
+        condition: &'a Signal<'a>,
+    ) -> &'a Property<'a> {
+        self.try_assume(name, condition)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`assume`](Self::assume), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PropertyConditionFromAnotherModule`] or [`Error::InvalidPropertyConditionBitWidth`] under the same conditions that [`assume`](Self::assume) panics.
+    pub fn try_assume<S: Into<String>>(
+        &'a self,
+        name: S,
+        condition: &'a Signal<'a>,
+    ) -> Result<&'a Property<'a>, Error> {
+        self.property(name.into(), PropertyKind::Assume, condition)
+    }
+
+    /// Creates a cover [`Property`] in this `Module` called `name`, which expects `condition` to be high in at least one cycle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `condition` belongs to a different `Module` than `self`, or if `condition`'s bit width is not 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    ///
+    /// let push = m.input("push", 1);
+    /// let full = m.input("full", 1);
+    /// m.output("o", push);
+    ///
+    /// m.cover("push_when_full", push & full);
+    /// ```
+    pub fn cover<S: Into<String>>(
+        &'a self,
+        name: S,
+        condition: &'a Signal<'a>,
+    ) -> &'a Property<'a> {
+        self.try_cover(name, condition)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`cover`](Self::cover), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PropertyConditionFromAnotherModule`] or [`Error::InvalidPropertyConditionBitWidth`] under the same conditions that [`cover`](Self::cover) panics.
+    pub fn try_cover<S: Into<String>>(
+        &'a self,
+        name: S,
+        condition: &'a Signal<'a>,
+    ) -> Result<&'a Property<'a>, Error> {
+        self.property(name.into(), PropertyKind::Cover, condition)
+    }
+
+    fn property(
+        &'a self,
+        name: String,
+        kind: PropertyKind,
+        condition: &'a Signal<'a>,
+    ) -> Result<&'a Property<'a>, Error> {
+        // Names are checked for validity and uniqueness when the module hierarchy is validated
+        if !ptr::eq(self, condition.module) {
+            return Err(Error::PropertyConditionFromAnotherModule {
+                module: self.name.clone(),
+                property: name,
+            });
+        }
+        if condition.bit_width() != 1 {
+            return Err(Error::InvalidPropertyConditionBitWidth {
+                module: self.name.clone(),
+                property: name,
+                bit_width: condition.bit_width(),
+            });
+        }
+        let ret = self.context.property_arena.alloc(Property {
+            module: self,
+
+            name,
+            kind,
+            condition,
+            clock_domain: RefCell::new(None),
+        });
+        self.properties.borrow_mut().push(ret);
+        Ok(ret)
+    }
+
+    /// Creates a [`ClockDomain`] in this `Module` called `name`.
+    ///
+    /// In generated code, this clock domain is represented by the `<name>_clk` and `<name>_reset_n` ports.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`ClockDomain`] called `name` already exists in this `Module`, or if `name` is `"clk"`, which is reserved for the [default clock domain](Self::default_clock_domain).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    /// let mem_domain = m.clock_domain("mem");
+    ///
+    /// let my_reg = m.reg("my_reg", 32);
+    /// my_reg.clock_domain(mem_domain);
+    /// my_reg.drive_next(m.input("i", 32));
+    /// m.output("o", my_reg.value);
+    /// ```
+    pub fn clock_domain<S: Into<String>>(&'a self, name: S) -> &'a ClockDomain<'a> {
+        self.try_clock_domain(name)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`clock_domain`](Self::clock_domain), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReservedClockDomainName`] or [`Error::ClockDomainAlreadyExists`] under the same conditions that [`clock_domain`](Self::clock_domain) panics.
+    pub fn try_clock_domain<S: Into<String>>(
+        &'a self,
+        name: S,
+    ) -> Result<&'a ClockDomain<'a>, Error> {
+        let name = name.into();
+        if name == "clk" {
+            return Err(Error::ReservedClockDomainName {
+                module: self.name.clone(),
+            });
+        }
+        let mut clock_domains = self.clock_domains.borrow_mut();
+        if clock_domains
+            .iter()
+            .any(|clock_domain| clock_domain.name.as_ref() == Some(&name))
+        {
+            return Err(Error::ClockDomainAlreadyExists {
+                module: self.name.clone(),
+                clock_domain: name,
+            });
+        }
+        let ret = self.context.clock_domain_arena.alloc(ClockDomain {
+            module: self,
+
+            name: Some(name),
+        });
+        clock_domains.push(ret);
+        Ok(ret)
+    }
+
+    /// Returns this `Module`'s implicit default [`ClockDomain`].
+    ///
+    /// [`Register`]s and [`Mem`]s that aren't explicitly bound to another [`ClockDomain`] belong to this clock domain. In generated code, it's represented by the `clk` and `reset_n` ports.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let inner = c.module("Inner");
+    /// let r = inner.reg("r", 1);
+    /// r.drive_next(inner.input("i", 1));
+    /// inner.output("o", r.value);
+    ///
+    /// let outer = c.module("Outer");
+    /// let slow_domain = outer.clock_domain("slow");
+    /// let inner_inst = outer.instance("inner_inst", "Inner");
+    /// // Clock inner_inst's default clock domain with outer's "slow" clock domain
+    /// inner_inst.drive_default_clock_domain(slow_domain);
+    /// inner_inst.drive_input("i", outer.input("i", 1));
+    /// outer.output("o", inner_inst.output("o"));
+    /// ```
+    pub fn default_clock_domain(&'a self) -> &'a ClockDomain<'a> {
+        let mut default_clock_domain = self.default_clock_domain.borrow_mut();
+        if default_clock_domain.is_none() {
+            *default_clock_domain = Some(self.context.clock_domain_arena.alloc(ClockDomain {
+                module: self,
+
+                name: None,
+            }));
+        }
+        default_clock_domain.unwrap()
     }
 }
 
@@ -464,7 +853,7 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Cannot create a literal with 129 bit(s). Signals must not be wider than 128 bit(s)."
+        expected = "Cannot create a literal with 65537 bit(s). Signals must not be wider than 65536 bit(s)."
     )]
     fn lit_bit_width_gt_max_error() {
         let c = Context::new();
@@ -472,7 +861,7 @@ mod tests {
         let m = c.module("A");
 
         // Panic
-        let _ = m.lit(false, 129);
+        let _ = m.lit(false, 65537);
     }
 
     #[test]
@@ -542,7 +931,7 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Cannot create an input with 129 bit(s). Signals must not be wider than 128 bit(s)."
+        expected = "Cannot create an input with 65537 bit(s). Signals must not be wider than 65536 bit(s)."
     )]
     fn input_width_gt_max_error() {
         let c = Context::new();
@@ -550,7 +939,7 @@ mod tests {
         let m = c.module("A");
 
         // Panic
-        let _ = m.input("i", 129);
+        let _ = m.input("i", 65537);
     }
 
     #[test]
@@ -582,7 +971,7 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Cannot create a register with 129 bit(s). Signals must not be wider than 128 bit(s)."
+        expected = "Cannot create a register with 65537 bit(s). Signals must not be wider than 65536 bit(s)."
     )]
     fn reg_bit_width_gt_max_error() {
         let c = Context::new();
@@ -590,7 +979,7 @@ mod tests {
         let m = c.module("A");
 
         // Panic
-        let _ = m.reg("r", 129);
+        let _ = m.reg("r", 65537);
     }
 
     #[test]
@@ -699,7 +1088,7 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Cannot create a memory with 129 address bit(s). Signals must not be wider than 128 bit(s)."
+        expected = "Cannot create a memory with 65537 address bit(s). Signals must not be wider than 65536 bit(s)."
     )]
     fn mem_address_bit_width_gt_max_error() {
         let c = Context::new();
@@ -707,7 +1096,7 @@ mod tests {
         let m = c.module("A");
 
         // Panic
-        let _ = m.mem("mem", 129, 1);
+        let _ = m.mem("mem", 65537, 1);
     }
 
     #[test]
@@ -725,7 +1114,7 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Cannot create a memory with 129 element bit(s). Signals must not be wider than 128 bit(s)."
+        expected = "Cannot create a memory with 65537 element bit(s). Signals must not be wider than 65536 bit(s)."
     )]
     fn mem_element_bit_width_gt_max_error() {
         let c = Context::new();
@@ -733,6 +1122,59 @@ mod tests {
         let m = c.module("A");
 
         // Panic
-        let _ = m.mem("mem", 1, 129);
+        let _ = m.mem("mem", 1, 65537);
+    }
+
+    #[test]
+    #[should_panic(expected = "A clock domain called \"d\" already exists in module \"A\".")]
+    fn clock_domain_already_exists_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+
+        let _ = m.clock_domain("d");
+
+        // Panic
+        let _ = m.clock_domain("d");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Cannot create a clock domain called \"clk\" in module \"A\", as this name is reserved for the default clock domain."
+    )]
+    fn clock_domain_reserved_name_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+
+        // Panic
+        let _ = m.clock_domain("clk");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to create a property called \"p\" in module \"B\" with a condition from another module."
+    )]
+    fn property_condition_separate_module_error() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        let b = c.module("B");
+
+        // Panic
+        let _ = b.assert("p", a.high());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to create a property called \"p\" in module \"A\" with a condition that is 2 bits wide, but property conditions can only be 1 bit wide."
+    )]
+    fn property_condition_bit_width_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+
+        // Panic
+        let _ = m.cover("p", m.input("i", 2));
     }
 }
@@ -0,0 +1,146 @@
+use super::clock_domain::*;
+use super::module::*;
+use super::signal::*;
+
+use crate::error::*;
+
+use std::cell::RefCell;
+use std::ptr;
+
+/// The kind of a [`Property`], which determines how its condition is treated in generated code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PropertyKind {
+    /// The condition is required to hold in every cycle, created by the [`Module::assert`] method.
+    Assert,
+    /// The condition is assumed to hold in every cycle, created by the [`Module::assume`] method. Formal tools use assumptions to constrain their inputs; simulators treat them like assertions.
+    Assume,
+    /// The condition is expected to hold in at least one cycle, created by the [`Module::cover`] method.
+    Cover,
+}
+
+/// A named condition that's checked in every cycle of its [`ClockDomain`], created by the [`Module::assert`], [`Module::assume`], and [`Module::cover`] methods.
+///
+/// A `Property` is sampled on each positive edge of its [`ClockDomain`]'s clock (using the values its condition had just before the edge), except while the [`ClockDomain`]'s reset is asserted.
+/// A `Property` belongs to its [`Module`]'s [default clock domain](Module::default_clock_domain) unless it's bound to another one with the [`clock_domain`](Self::clock_domain) method.
+///
+/// Properties don't affect the behavior of a design. In generated Rust simulators, failing assertions and assumptions cause a panic, and covers are counted (see [`sim::generate`](crate::sim::generate)). In generated Verilog code, they're represented by SystemVerilog `assert property`, `assume property`, and `cover property` statements (see [`verilog::GenerationOptions`](crate::verilog::GenerationOptions)).
+///
+/// # Examples
+///
+/// ```
+/// use kaze::*;
+///
+/// let c = Context::new();
+///
+/// let m = c.module("MyModule");
+///
+/// let count = m.reg("count", 4);
+/// count.default_value(0u32);
+/// count.drive_next(count.value + m.lit(1u32, 4));
+/// m.output("count", count.value);
+///
+/// m.assert("count_in_range", count.value.le(m.lit(9u32, 4)));
+/// m.cover("count_wraps", count.value.eq(m.lit(15u32, 4)));
+/// ```
+pub struct Property<'a> {
+    pub(crate) module: &'a Module<'a>,
+
+    pub(crate) name: String,
+    pub(crate) kind: PropertyKind,
+    pub(crate) condition: &'a Signal<'a>,
+    pub(crate) clock_domain: RefCell<Option<&'a ClockDomain<'a>>>,
+}
+
+impl<'a> Property<'a> {
+    /// Binds this `Property` to the specified [`ClockDomain`].
+    ///
+    /// By default, a `Property` belongs to its [`Module`]'s [default clock domain](Module::default_clock_domain).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` and `clock_domain` belong to different [`Module`]s, or if this `Property` is already bound to a [`ClockDomain`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    /// let slow_domain = m.clock_domain("slow");
+    ///
+    /// let valid = m.input("valid", 1);
+    /// let ready = m.input("ready", 1);
+    /// m.output("fire", valid & ready);
+    ///
+    /// m.cover("fire", valid & ready).clock_domain(slow_domain);
+    /// ```
+    pub fn clock_domain(&'a self, clock_domain: &'a ClockDomain<'a>) {
+        self.try_clock_domain(clock_domain)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`clock_domain`](Self::clock_domain), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PropertyClockDomainFromAnotherModule`] or [`Error::PropertyClockDomainAlreadyBound`] under the same conditions that [`clock_domain`](Self::clock_domain) panics.
+    pub fn try_clock_domain(&'a self, clock_domain: &'a ClockDomain<'a>) -> Result<(), Error> {
+        if !ptr::eq(self.module, clock_domain.module) {
+            return Err(Error::PropertyClockDomainFromAnotherModule {
+                module: self.module.name.clone(),
+                property: self.name.clone(),
+            });
+        }
+        if self.clock_domain.borrow().is_some() {
+            return Err(Error::PropertyClockDomainAlreadyBound {
+                module: self.module.name.clone(),
+                property: self.name.clone(),
+            });
+        }
+        *self.clock_domain.borrow_mut() = Some(clock_domain);
+        Ok(())
+    }
+
+    pub(crate) fn resolved_clock_domain(&self) -> &'a ClockDomain<'a> {
+        self.clock_domain
+            .borrow()
+            .unwrap_or_else(|| self.module.default_clock_domain())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to bind property \"p\" in module \"A\" to a clock domain from another module."
+    )]
+    fn clock_domain_separate_module_error() {
+        let c = Context::new();
+
+        let m1 = c.module("A");
+        let m2 = c.module("B");
+        let p = m1.assert("p", m1.high());
+
+        // Panic
+        p.clock_domain(m2.clock_domain("d"));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to bind property \"p\" in module \"A\" to a clock domain, but this property is already bound to a clock domain."
+    )]
+    fn clock_domain_already_bound_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let p = m.cover("p", m.high());
+        p.clock_domain(m.clock_domain("d1"));
+
+        // Panic
+        p.clock_domain(m.clock_domain("d2"));
+    }
+}
@@ -1,7 +1,10 @@
+use super::clock_domain::*;
 use super::constant::*;
 use super::module::*;
 use super::signal::*;
 
+use crate::error::*;
+
 use std::cell::RefCell;
 use std::ptr;
 
@@ -10,9 +13,10 @@ use std::ptr;
 /// A `Register` is a stateful component that behaves like a [D flip-flop](https://en.wikipedia.org/wiki/Flip-flop_(electronics)#D_flip-flop) (more precisely as a [positive-edge-triggered D flip-flop](https://en.wikipedia.org/wiki/Flip-flop_(electronics)#Classical_positive-edge-triggered_D_flip-flop)).
 ///
 /// It always has a current value represented by the [`value`] field (often referred to as `Q`) and a next value specified by the [`drive_next`] method (often referred to as `D`).
-/// It will hold its [`value`] until a positive edge of its [`Module`]'s implicit clock occurs, at which point [`value`] will be updated to reflect the next value.
+/// It will hold its [`value`] until a positive edge of its [`ClockDomain`]'s clock occurs, at which point [`value`] will be updated to reflect the next value.
+/// A `Register` belongs to its [`Module`]'s [default clock domain](Module::default_clock_domain) unless it's bound to another one with the [`clock_domain`] method.
 ///
-/// Optionally, it also has a default value specified by the [`default_value`] method. If at any time its [`Module`]'s implicit reset is driven low, the register's [`value`] will reflect the default value.
+/// Optionally, it also has a default value specified by the [`default_value`] method. If at any time its [`ClockDomain`]'s reset is asserted, the register's [`value`] will reflect the default value (see [`ResetType`](crate::ResetType) and [`ResetPolarity`](crate::ResetPolarity) for how reset is represented in generated code).
 /// Default values are used to provide a known register state on system power-on and reset, but are often omitted to reduce combinational logic (which ultimately is how default values are typically implemented), especially for registers on timing-critical data paths.
 ///
 /// # Examples
@@ -30,6 +34,7 @@ use std::ptr;
 /// m.output("my_output", my_reg.value);
 /// ```
 ///
+/// [`clock_domain`]: Self::clock_domain
 /// [`default_value`]: Self::default_value
 /// [`drive_next`]: Self::drive_next
 /// [`value`]: Self::value
@@ -43,9 +48,9 @@ pub struct Register<'a> {
 impl<'a> Register<'a> {
     /// Specifies the default value for this `Register`.
     ///
-    /// This `Register`'s [`value`] will reflect this default value when this `Register`'s [`Module`]'s implicit reset is asserted.
+    /// This `Register`'s [`value`] will reflect this default value when this `Register`'s [`ClockDomain`]'s reset is asserted.
     ///
-    /// By default, a `Register` does not have a default value, and it is not required to specify one. If a default value is not specified, then this `Register`'s [`value`] will not change when its [`Module`]'s implicit reset is asserted.
+    /// By default, a `Register` does not have a default value, and it is not required to specify one. If a default value is not specified, then this `Register`'s [`value`] will not change when its [`ClockDomain`]'s reset is asserted.
     ///
     /// # Panics
     ///
@@ -68,21 +73,40 @@ impl<'a> Register<'a> {
     ///
     /// [`value`]: Self::value
     pub fn default_value<C: Into<Constant>>(&'a self, value: C) {
+        self.try_default_value(value)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`default_value`](Self::default_value), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RegisterDefaultValueAlreadySpecified`] or [`Error::RegisterDefaultValueTooWide`] under the same conditions that [`default_value`](Self::default_value) panics.
+    pub fn try_default_value<C: Into<Constant>>(&'a self, value: C) -> Result<(), Error> {
         if self.data.initial_value.borrow().is_some() {
-            panic!("Attempted to specify a default value for register \"{}\" in module \"{}\", but this register already has a default value.", self.data.name, self.data.module.name);
+            return Err(Error::RegisterDefaultValueAlreadySpecified {
+                module: self.data.module.name.clone(),
+                register: self.data.name.clone(),
+            });
         }
         let value = value.into();
         let required_bits = value.required_bits();
         if required_bits > self.data.bit_width {
-            let numeric_value = value.numeric_value();
-            panic!("Cannot fit the specified value '{}' into register \"{}\"'s bit width '{}'. The value '{}' requires a bit width of at least {} bit(s).", numeric_value, self.data.name, self.data.bit_width, numeric_value, required_bits);
+            return Err(Error::RegisterDefaultValueTooWide {
+                module: self.data.module.name.clone(),
+                register: self.data.name.clone(),
+                value: value.numeric_value_string(),
+                bit_width: self.data.bit_width,
+                required_bits,
+            });
         }
         *self.data.initial_value.borrow_mut() = Some(value);
+        Ok(())
     }
 
     /// Specifies the next value for this `Register`.
     ///
-    /// A `Register` will hold its [`value`] until a positive edge of its [`Module`]'s implicit clock occurs, at which point [`value`] will be updated to reflect this next value.
+    /// A `Register` will hold its [`value`] until a positive edge of its [`ClockDomain`]'s clock occurs, at which point [`value`] will be updated to reflect this next value.
     ///
     /// # Panics
     ///
@@ -105,16 +129,88 @@ impl<'a> Register<'a> {
     ///
     /// [`value`]: Self::value
     pub fn drive_next(&'a self, n: &'a Signal<'a>) {
+        self.try_drive_next(n).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`drive_next`](Self::drive_next), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RegisterNextFromAnotherModule`], [`Error::RegisterNextBitWidthMismatch`] or [`Error::RegisterNextAlreadyDriven`] under the same conditions that [`drive_next`](Self::drive_next) panics.
+    pub fn try_drive_next(&'a self, n: &'a Signal<'a>) -> Result<(), Error> {
         if !ptr::eq(self.data.module, n.module) {
-            panic!("Attempted to drive register \"{}\"'s next value with a signal from another module.", self.data.name);
+            return Err(Error::RegisterNextFromAnotherModule {
+                module: self.data.module.name.clone(),
+                register: self.data.name.clone(),
+            });
         }
         if n.bit_width() != self.data.bit_width {
-            panic!("Attempted to drive register \"{}\"'s next value with a signal that has a different bit width than the register ({} and {}, respectively).", self.data.name, n.bit_width(), self.data.bit_width);
+            return Err(Error::RegisterNextBitWidthMismatch {
+                module: self.data.module.name.clone(),
+                register: self.data.name.clone(),
+                next_bit_width: n.bit_width(),
+                bit_width: self.data.bit_width,
+            });
         }
         if self.data.next.borrow().is_some() {
-            panic!("Attempted to drive register \"{}\"'s next value in module \"{}\", but this register's next value is already driven.", self.data.name, self.data.module.name);
+            return Err(Error::RegisterNextAlreadyDriven {
+                module: self.data.module.name.clone(),
+                register: self.data.name.clone(),
+            });
         }
         *self.data.next.borrow_mut() = Some(n);
+        Ok(())
+    }
+
+    /// Binds this `Register` to the specified [`ClockDomain`].
+    ///
+    /// By default, a `Register` belongs to its [`Module`]'s [default clock domain](Module::default_clock_domain).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` and `clock_domain` belong to different [`Module`]s, or if this `Register` is already bound to a [`ClockDomain`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kaze::*;
+    ///
+    /// let c = Context::new();
+    ///
+    /// let m = c.module("MyModule");
+    /// let slow_domain = m.clock_domain("slow");
+    ///
+    /// let my_reg = m.reg("my_reg", 32);
+    /// my_reg.clock_domain(slow_domain);
+    /// my_reg.default_value(0xfadebabeu32);
+    /// my_reg.drive_next(!my_reg.value); // my_reg's value will toggle with each positive edge of slow_domain's clock
+    /// m.output("my_output", my_reg.value);
+    /// ```
+    pub fn clock_domain(&'a self, clock_domain: &'a ClockDomain<'a>) {
+        self.try_clock_domain(clock_domain)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`clock_domain`](Self::clock_domain), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RegisterClockDomainFromAnotherModule`] or [`Error::RegisterClockDomainAlreadyBound`] under the same conditions that [`clock_domain`](Self::clock_domain) panics.
+    pub fn try_clock_domain(&'a self, clock_domain: &'a ClockDomain<'a>) -> Result<(), Error> {
+        if !ptr::eq(self.data.module, clock_domain.module) {
+            return Err(Error::RegisterClockDomainFromAnotherModule {
+                module: self.data.module.name.clone(),
+                register: self.data.name.clone(),
+            });
+        }
+        if self.data.clock_domain.borrow().is_some() {
+            return Err(Error::RegisterClockDomainAlreadyBound {
+                module: self.data.module.name.clone(),
+                register: self.data.name.clone(),
+            });
+        }
+        *self.data.clock_domain.borrow_mut() = Some(clock_domain);
+        Ok(())
     }
 }
 
@@ -125,6 +221,15 @@ pub(crate) struct RegisterData<'a> {
     pub initial_value: RefCell<Option<Constant>>,
     pub bit_width: u32,
     pub next: RefCell<Option<&'a Signal<'a>>>,
+    pub clock_domain: RefCell<Option<&'a ClockDomain<'a>>>,
+}
+
+impl<'a> RegisterData<'a> {
+    pub fn resolved_clock_domain(&self) -> &'a ClockDomain<'a> {
+        self.clock_domain
+            .borrow()
+            .unwrap_or_else(|| self.module.default_clock_domain())
+    }
 }
 
 #[cfg(test)]
@@ -251,4 +356,37 @@ mod tests {
         // Panic
         r.drive_next(i);
     }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to bind register \"r\" in module \"A\" to a clock domain from another module."
+    )]
+    fn clock_domain_separate_module_error() {
+        let c = Context::new();
+
+        let m1 = c.module("A");
+        let r = m1.reg("r", 32);
+
+        let m2 = c.module("B");
+        let d = m2.clock_domain("d");
+
+        // Panic
+        r.clock_domain(d);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempted to bind register \"r\" in module \"A\" to a clock domain, but this register is already bound to a clock domain."
+    )]
+    fn clock_domain_already_bound_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let r = m.reg("r", 32);
+
+        r.clock_domain(m.clock_domain("d1"));
+
+        // Panic
+        r.clock_domain(m.clock_domain("d2"));
+    }
 }
@@ -5,6 +5,8 @@ use super::mem::*;
 use super::module::*;
 use super::register::*;
 
+use crate::error::*;
+
 use std::hash::{Hash, Hasher};
 use std::ops::{Add, BitAnd, BitOr, BitXor, Mul, Not, Shl, Shr, Sub};
 use std::ptr;
@@ -15,8 +17,8 @@ use std::ptr;
 pub const MIN_SIGNAL_BIT_WIDTH: u32 = 1;
 /// The maximum allowed bit width for any given [`Signal`].
 ///
-/// This is currently set to `128` to simplify simulator code generation, since it allows the generated code to rely purely on native integer types provided by Rust's standard library for storage, arithmetic, etc. Larger widths may be supported in a future version of this library.
-pub const MAX_SIGNAL_BIT_WIDTH: u32 = 128;
+/// This is currently set to `65536`. Signals up to `128` bits wide are represented using native integer types in generated simulator code, while wider signals are represented as arrays of 64-bit words and rely on the helpers in [`runtime::wide`](crate::runtime::wide) for arithmetic, shifts, comparisons, etc.
+pub const MAX_SIGNAL_BIT_WIDTH: u32 = 65536;
 
 /// A collection of 1 or more bits driven by some source.
 ///
@@ -50,6 +52,15 @@ pub struct Signal<'a> {
 }
 
 impl<'a> Signal<'a> {
+    // Creates a new `Signal` with the specified data in the same module as this one, e.g. for rewriting logic during code generation. The new signal isn't allocated in this signal's `Context`, so the caller is responsible for keeping it alive.
+    pub(crate) fn sibling(&self, data: SignalData<'a>) -> Signal<'a> {
+        Signal {
+            context: self.context,
+            module: self.module,
+            data,
+        }
+    }
+
     /// Returns the bit width of the given `Signal`.
     ///
     /// # Examples
@@ -110,6 +121,7 @@ impl<'a> Signal<'a> {
             SignalData::Mux { bit_width, .. } => bit_width,
             SignalData::InstanceOutput { bit_width, .. } => bit_width,
             SignalData::MemReadPortOutput { mem, .. } => mem.element_bit_width,
+            SignalData::MemAsyncReadPortOutput { mem, .. } => mem.element_bit_width,
         }
     }
 
@@ -135,10 +147,23 @@ impl<'a> Signal<'a> {
     /// let bit_3 = lit.bit(3); // Represents 0
     /// ```
     pub fn bit(&'a self, index: u32) -> &Signal<'a> {
+        self.try_bit(index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`bit`](Self::bit), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::BitIndexOutOfRange`] under the same conditions that [`bit`](Self::bit) panics.
+    pub fn try_bit(&'a self, index: u32) -> Result<&'a Signal<'a>, Error> {
         if index >= self.bit_width() {
-            panic!("Attempted to take bit index {} from a signal with a width of {} bits. Bit indices must be in the range [0, {}] for a signal with a width of {} bits.", index, self.bit_width(), self.bit_width() - 1, self.bit_width());
+            return Err(Error::BitIndexOutOfRange {
+                module: self.module.name.clone(),
+                index,
+                bit_width: self.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -147,7 +172,7 @@ impl<'a> Signal<'a> {
                 range_high: index,
                 range_low: index,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents a contiguous subset of the bits of this `Signal`, starting at `range_low` as the least significant bit and ending at `range_high` as the most significant bit, inclusive.
@@ -173,16 +198,38 @@ impl<'a> Signal<'a> {
     /// let bits_2 = lit.bits(2, 2); // Represents 1, equivalent to lit.bit(2)
     /// ```
     pub fn bits(&'a self, range_high: u32, range_low: u32) -> &Signal<'a> {
+        self.try_bits(range_high, range_low)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`bits`](Self::bits), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::BitsRangeLowOutOfRange`], [`Error::BitsRangeHighOutOfRange`] or [`Error::BitsRangeInverted`] under the same conditions that [`bits`](Self::bits) panics.
+    pub fn try_bits(&'a self, range_high: u32, range_low: u32) -> Result<&'a Signal<'a>, Error> {
         if range_low >= self.bit_width() {
-            panic!("Cannot specify a range of bits where the lower bound is greater than or equal to the number of bits in the source signal. The bounds must be in the range [0, {}] for a signal with a width of {} bits, but a lower bound of {} was given.", self.bit_width() - 1, self.bit_width(), range_low);
+            return Err(Error::BitsRangeLowOutOfRange {
+                module: self.module.name.clone(),
+                range_low,
+                bit_width: self.bit_width(),
+            });
         }
         if range_high >= self.bit_width() {
-            panic!("Cannot specify a range of bits where the upper bound is greater than or equal to the number of bits in the source signal. The bounds must be in the range [0, {}] for a signal with a width of {} bits, but an upper bound of {} was given.", self.bit_width() - 1, self.bit_width(), range_high);
+            return Err(Error::BitsRangeHighOutOfRange {
+                module: self.module.name.clone(),
+                range_high,
+                bit_width: self.bit_width(),
+            });
         }
         if range_low > range_high {
-            panic!("Cannot specify a range of bits where the lower bound is greater than the upper bound.");
+            return Err(Error::BitsRangeInverted {
+                module: self.module.name.clone(),
+                range_high,
+                range_low,
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -191,7 +238,7 @@ impl<'a> Signal<'a> {
                 range_high,
                 range_low,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents this `Signal` repeated `count` times.
@@ -216,23 +263,34 @@ impl<'a> Signal<'a> {
     /// let repeat_8 = lit.repeat(8); // Equivalent to 32-bit lit with value 0xaaaaaaaa
     /// ```
     pub fn repeat(&'a self, count: u32) -> &Signal<'a> {
-        let bit_width = self.bit_width() * count;
-        if bit_width < MIN_SIGNAL_BIT_WIDTH {
-            panic!("Attempted to repeat a {}-bit signal {} times, but this would result in a bit width of {}, which is less than the minimal signal bit width of {} bit(s).", self.bit_width(), count, bit_width, MIN_SIGNAL_BIT_WIDTH);
-        }
-        if bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!("Attempted to repeat a {}-bit signal {} times, but this would result in a bit width of {}, which is greater than the maximum signal bit width of {} bit(s).", self.bit_width(), count, bit_width, MAX_SIGNAL_BIT_WIDTH);
+        self.try_repeat(count).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`repeat`](Self::repeat), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidRepeatBitWidth`] under the same conditions that [`repeat`](Self::repeat) panics.
+    pub fn try_repeat(&'a self, count: u32) -> Result<&'a Signal<'a>, Error> {
+        let bit_width = self.bit_width() as u64 * count as u64;
+        if bit_width < MIN_SIGNAL_BIT_WIDTH as u64 || bit_width > MAX_SIGNAL_BIT_WIDTH as u64 {
+            return Err(Error::InvalidRepeatBitWidth {
+                module: self.module.name.clone(),
+                source_bit_width: self.bit_width(),
+                count,
+                bit_width,
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
             data: SignalData::Repeat {
                 source: self,
                 count,
-                bit_width,
+                bit_width: bit_width as _,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents this `Signal` concatenated with `rhs`.
@@ -259,14 +317,30 @@ impl<'a> Signal<'a> {
     /// let concat_3 = lit_a.concat(lit_a); // Equivalent to 8-bit lit with value 0xaa
     /// ```
     pub fn concat(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_concat(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`concat`](Self::concat), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::ConcatBitWidthTooWide`] under the same conditions that [`concat`](Self::concat) panics.
+    pub fn try_concat(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         let bit_width = self.bit_width() + rhs.bit_width();
         if bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!("Attempted to concatenate signals with {} bit(s) and {} bit(s) respectively, but this would result in a bit width of {}, which is greater than the maximum signal bit width of {} bit(s).", self.bit_width(), rhs.bit_width(), bit_width, MAX_SIGNAL_BIT_WIDTH);
+            return Err(Error::ConcatBitWidthTooWide {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+                bit_width,
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -275,7 +349,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 bit_width,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of a bitwise boolean equality comparison between `self` and `rhs`.
@@ -301,17 +375,28 @@ impl<'a> Signal<'a> {
     /// let eq_4 = lit_b.eq(lit_a); // Equivalent to m.low()
     /// ```
     pub fn eq(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_eq(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`eq`](Self::eq), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that [`eq`](Self::eq) panics.
+    pub fn try_eq(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -320,7 +405,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::Equal,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of a bitwise boolean inequality comparison between `self` and `rhs`.
@@ -346,17 +431,28 @@ impl<'a> Signal<'a> {
     /// let ne_4 = lit_b.ne(lit_a); // Equivalent to m.high()
     /// ```
     pub fn ne(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_ne(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`ne`](Self::ne), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that [`ne`](Self::ne) panics.
+    pub fn try_ne(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -365,7 +461,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::NotEqual,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of an unsigned `<` comparison between `self` and `rhs`.
@@ -391,17 +487,28 @@ impl<'a> Signal<'a> {
     /// let lt_4 = lit_b.lt(lit_a); // Equivalent to m.low()
     /// ```
     pub fn lt(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_lt(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`lt`](Self::lt), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that [`lt`](Self::lt) panics.
+    pub fn try_lt(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -410,7 +517,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::LessThan,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of an unsigned `<=` comparison between `self` and `rhs`.
@@ -436,17 +543,28 @@ impl<'a> Signal<'a> {
     /// let le_4 = lit_b.le(lit_a); // Equivalent to m.low()
     /// ```
     pub fn le(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_le(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`le`](Self::le), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that [`le`](Self::le) panics.
+    pub fn try_le(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -455,7 +573,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::LessThanEqual,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of an unsigned `>` comparison between `self` and `rhs`.
@@ -481,17 +599,28 @@ impl<'a> Signal<'a> {
     /// let gt_4 = lit_b.gt(lit_a); // Equivalent to m.high()
     /// ```
     pub fn gt(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_gt(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`gt`](Self::gt), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that [`gt`](Self::gt) panics.
+    pub fn try_gt(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -500,7 +629,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::GreaterThan,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of an unsigned `>=` comparison between `self` and `rhs`.
@@ -526,17 +655,28 @@ impl<'a> Signal<'a> {
     /// let ge_4 = lit_b.ge(lit_a); // Equivalent to m.high()
     /// ```
     pub fn ge(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_ge(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`ge`](Self::ge), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that [`ge`](Self::ge) panics.
+    pub fn try_ge(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -545,7 +685,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::GreaterThanEqual,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of a signed `<` comparison between `self` and `rhs`.
@@ -571,20 +711,33 @@ impl<'a> Signal<'a> {
     /// let lt_signed_4 = lit_b.lt_signed(lit_a); // Equivalent to m.low()
     /// ```
     pub fn lt_signed(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_lt_signed(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`lt_signed`](Self::lt_signed), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`], [`Error::SignalBitWidthMismatch`] or [`Error::SignedComparisonOfSingleBitSignals`] under the same conditions that [`lt_signed`](Self::lt_signed) panics.
+    pub fn try_lt_signed(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
         if self.bit_width() == 1 {
-            panic!("Cannot perform signed comparison of 1-bit signals.");
+            return Err(Error::SignedComparisonOfSingleBitSignals {
+                module: self.module.name.clone(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -593,7 +746,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::LessThanSigned,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of a signed `<=` comparison between `self` and `rhs`.
@@ -619,20 +772,33 @@ impl<'a> Signal<'a> {
     /// let le_signed_4 = lit_b.le_signed(lit_a); // Equivalent to m.low()
     /// ```
     pub fn le_signed(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_le_signed(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`le_signed`](Self::le_signed), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`], [`Error::SignalBitWidthMismatch`] or [`Error::SignedComparisonOfSingleBitSignals`] under the same conditions that [`le_signed`](Self::le_signed) panics.
+    pub fn try_le_signed(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
         if self.bit_width() == 1 {
-            panic!("Cannot perform signed comparison of 1-bit signals.");
+            return Err(Error::SignedComparisonOfSingleBitSignals {
+                module: self.module.name.clone(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -641,7 +807,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::LessThanEqualSigned,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of a signed `>` comparison between `self` and `rhs`.
@@ -667,20 +833,33 @@ impl<'a> Signal<'a> {
     /// let gt_signed_4 = lit_b.gt_signed(lit_a); // Equivalent to m.high()
     /// ```
     pub fn gt_signed(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_gt_signed(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`gt_signed`](Self::gt_signed), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`], [`Error::SignalBitWidthMismatch`] or [`Error::SignedComparisonOfSingleBitSignals`] under the same conditions that [`gt_signed`](Self::gt_signed) panics.
+    pub fn try_gt_signed(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
         if self.bit_width() == 1 {
-            panic!("Cannot perform signed comparison of 1-bit signals.");
+            return Err(Error::SignedComparisonOfSingleBitSignals {
+                module: self.module.name.clone(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -689,7 +868,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::GreaterThanSigned,
             },
-        })
+        }))
     }
 
     /// Creates a `Signal` that represents the single-bit result of a signed `>=` comparison between `self` and `rhs`.
@@ -715,20 +894,33 @@ impl<'a> Signal<'a> {
     /// let ge_signed_4 = lit_b.ge_signed(lit_a); // Equivalent to m.high()
     /// ```
     pub fn ge_signed(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_ge_signed(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`ge_signed`](Self::ge_signed), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`], [`Error::SignalBitWidthMismatch`] or [`Error::SignedComparisonOfSingleBitSignals`] under the same conditions that [`ge_signed`](Self::ge_signed) panics.
+    pub fn try_ge_signed(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
         }
         if self.bit_width() == 1 {
-            panic!("Cannot perform signed comparison of 1-bit signals.");
+            return Err(Error::SignedComparisonOfSingleBitSignals {
+                module: self.module.name.clone(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -737,7 +929,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 op: ComparisonBinOp::GreaterThanEqualSigned,
             },
-        })
+        }))
     }
 
     /// Combines two `Signal`s, producing a new `Signal` that represents `self` arithmetically shifted right by `rhs` bits.
@@ -762,10 +954,22 @@ impl<'a> Signal<'a> {
     /// let shifted = lhs.shr_arithmetic(rhs); // Equivalent to m.lit(0xc0000000u32, 32)
     /// ```
     pub fn shr_arithmetic(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_shr_arithmetic(rhs)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`shr_arithmetic`](Self::shr_arithmetic), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] under the same conditions that [`shr_arithmetic`](Self::shr_arithmetic) panics.
+    pub fn try_shr_arithmetic(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -775,7 +979,7 @@ impl<'a> Signal<'a> {
                 op: ShiftBinOp::ShrArithmetic,
                 bit_width: self.bit_width(),
             },
-        })
+        }))
     }
 
     /// Combines two `Signal`s, producing a new `Signal` that represents the signed product of the original two `Signal`s.
@@ -800,14 +1004,30 @@ impl<'a> Signal<'a> {
     /// let sum = lhs.mul_signed(rhs); // Equivalent to m.lit(108u32, 7), -20
     /// ```
     pub fn mul_signed(&'a self, rhs: &'a Signal<'a>) -> &Signal<'a> {
+        self.try_mul_signed(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like [`mul_signed`](Self::mul_signed), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::MulBitWidthTooWide`] under the same conditions that [`mul_signed`](Self::mul_signed) panics.
+    pub fn try_mul_signed(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
         if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
         }
         let bit_width = self.bit_width() + rhs.bit_width();
         if bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!("Attempted to multiply a {}-bit with a {}-bit signal, but this would result in a bit width of {}, which is greater than the maximum signal bit width of {} bit(s).", self.bit_width(), rhs.bit_width(), bit_width, MAX_SIGNAL_BIT_WIDTH);
+            return Err(Error::MulBitWidthTooWide {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+                bit_width,
+            });
         }
-        self.context.signal_arena.alloc(Signal {
+        Ok(self.context.signal_arena.alloc(Signal {
             context: self.context,
             module: self.module,
 
@@ -816,7 +1036,7 @@ impl<'a> Signal<'a> {
                 rhs,
                 bit_width,
             },
-        })
+        }))
     }
 
     /// Creates a 2:1 [multiplexer](https://en.wikipedia.org/wiki/Multiplexer) that represents `when_true`'s value when `self` is high, and `when_false`'s value when `self` is low.
@@ -846,6 +1066,256 @@ impl<'a> Signal<'a> {
         self.module.mux(self, when_true, when_false)
     }
 
+    /// Like [`mux`](Self::mux), but returns an [`Error`] instead of panicking.
+    ///
+    /// This is a convenience wrapper for [`Module::try_mux`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`], [`Error::InvalidMuxConditionBitWidth`] or [`Error::MuxBitWidthMismatch`] under the same conditions that [`mux`](Self::mux) panics.
+    pub fn try_mux(
+        &'a self,
+        when_true: &'a Signal<'a>,
+        when_false: &'a Signal<'a>,
+    ) -> Result<&'a Signal<'a>, Error> {
+        self.module.try_mux(self, when_true, when_false)
+    }
+
+    /// Like `self + rhs` (see the [`Add`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that `self + rhs` panics.
+    pub fn try_add(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        if self.bit_width() != rhs.bit_width() {
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::AdditiveBinOp {
+                lhs: self,
+                rhs,
+                op: AdditiveBinOp::Add,
+                bit_width: self.bit_width(),
+            },
+        }))
+    }
+
+    /// Like `self & rhs` (see the [`BitAnd`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that `self & rhs` panics.
+    pub fn try_bitand(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        if self.bit_width() != rhs.bit_width() {
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::SimpleBinOp {
+                lhs: self,
+                rhs,
+                op: SimpleBinOp::BitAnd,
+                bit_width: self.bit_width(),
+            },
+        }))
+    }
+
+    /// Like `self | rhs` (see the [`BitOr`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that `self | rhs` panics.
+    pub fn try_bitor(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        if self.bit_width() != rhs.bit_width() {
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::SimpleBinOp {
+                lhs: self,
+                rhs,
+                op: SimpleBinOp::BitOr,
+                bit_width: self.bit_width(),
+            },
+        }))
+    }
+
+    /// Like `self ^ rhs` (see the [`BitXor`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that `self ^ rhs` panics.
+    pub fn try_bitxor(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        if self.bit_width() != rhs.bit_width() {
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::SimpleBinOp {
+                lhs: self,
+                rhs,
+                op: SimpleBinOp::BitXor,
+                bit_width: self.bit_width(),
+            },
+        }))
+    }
+
+    /// Like `self * rhs` (see the [`Mul`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::MulBitWidthTooWide`] under the same conditions that `self * rhs` panics.
+    pub fn try_mul(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        let bit_width = self.bit_width() + rhs.bit_width();
+        if bit_width > MAX_SIGNAL_BIT_WIDTH {
+            return Err(Error::MulBitWidthTooWide {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+                bit_width,
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::Mul {
+                lhs: self,
+                rhs,
+                bit_width,
+            },
+        }))
+    }
+
+    /// Like `self << rhs` (see the [`Shl`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] under the same conditions that `self << rhs` panics.
+    pub fn try_shl(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::ShiftBinOp {
+                lhs: self,
+                rhs,
+                op: ShiftBinOp::Shl,
+                bit_width: self.bit_width(),
+            },
+        }))
+    }
+
+    /// Like `self >> rhs` (see the [`Shr`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] under the same conditions that `self >> rhs` panics.
+    pub fn try_shr(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::ShiftBinOp {
+                lhs: self,
+                rhs,
+                op: ShiftBinOp::Shr,
+                bit_width: self.bit_width(),
+            },
+        }))
+    }
+
+    /// Like `self - rhs` (see the [`Sub`] implementation for `&Signal`), but returns an [`Error`] instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SignalsFromDifferentModules`] or [`Error::SignalBitWidthMismatch`] under the same conditions that `self - rhs` panics.
+    pub fn try_sub(&'a self, rhs: &'a Signal<'a>) -> Result<&'a Signal<'a>, Error> {
+        if !ptr::eq(self.module, rhs.module) {
+            return Err(Error::SignalsFromDifferentModules {
+                module: self.module.name.clone(),
+            });
+        }
+        if self.bit_width() != rhs.bit_width() {
+            return Err(Error::SignalBitWidthMismatch {
+                module: self.module.name.clone(),
+                lhs_bit_width: self.bit_width(),
+                rhs_bit_width: rhs.bit_width(),
+            });
+        }
+        Ok(self.context.signal_arena.alloc(Signal {
+            context: self.context,
+            module: self.module,
+
+            data: SignalData::AdditiveBinOp {
+                lhs: self,
+                rhs,
+                op: AdditiveBinOp::Sub,
+                bit_width: self.bit_width(),
+            },
+        }))
+    }
+
     /// Creates a [`Signal`] that represents the same value as this [`Signal`], but delayed by one cycle.
     ///
     /// This is achieved by creating a new [`Register`] called `name`, and specifying this [`Signal`] as the next value for the [`Register`]. Note that no default value is provided for this [`Register`], so the returned [`Signal`]'s value is undefined until the first clock edge, and its value is not affected by its [`Module`]'s implicit reset. If a default value is desired, use [`reg_next_with_default`] instead.
@@ -993,6 +1463,12 @@ pub(crate) enum SignalData<'a> {
         mem: &'a Mem<'a>,
         address: &'a Signal<'a>,
         enable: &'a Signal<'a>,
+        read_during_write: ReadDuringWrite,
+    },
+
+    MemAsyncReadPortOutput {
+        mem: &'a Mem<'a>,
+        address: &'a Signal<'a>,
     },
 }
 
@@ -1029,27 +1505,7 @@ impl<'a> Add for &'a Signal<'a> {
     ///
     /// [`concat`]: Signal::concat
     fn add(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::AdditiveBinOp {
-                lhs: self,
-                rhs,
-                op: AdditiveBinOp::Add,
-                bit_width: self.bit_width(),
-            },
-        })
+        self.try_add(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -1080,27 +1536,7 @@ impl<'a> BitAnd for &'a Signal<'a> {
     /// let multi_bitand = lhs & rhs;
     /// ```
     fn bitand(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::SimpleBinOp {
-                lhs: self,
-                rhs,
-                op: SimpleBinOp::BitAnd,
-                bit_width: self.bit_width(),
-            },
-        })
+        self.try_bitand(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -1131,27 +1567,7 @@ impl<'a> BitOr for &'a Signal<'a> {
     /// let multi_bitor = lhs | rhs;
     /// ```
     fn bitor(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::SimpleBinOp {
-                lhs: self,
-                rhs,
-                op: SimpleBinOp::BitOr,
-                bit_width: self.bit_width(),
-            },
-        })
+        self.try_bitor(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -1182,27 +1598,7 @@ impl<'a> BitXor for &'a Signal<'a> {
     /// let multi_bitxor = lhs ^ rhs;
     /// ```
     fn bitxor(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::SimpleBinOp {
-                lhs: self,
-                rhs,
-                op: SimpleBinOp::BitXor,
-                bit_width: self.bit_width(),
-            },
-        })
+        self.try_bitxor(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -1245,23 +1641,7 @@ impl<'a> Mul for &'a Signal<'a> {
     /// let sum = lhs * rhs; // Equivalent to m.lit(20u32, 7)
     /// ```
     fn mul(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        let bit_width = self.bit_width() + rhs.bit_width();
-        if bit_width > MAX_SIGNAL_BIT_WIDTH {
-            panic!("Attempted to multiply a {}-bit with a {}-bit signal, but this would result in a bit width of {}, which is greater than the maximum signal bit width of {} bit(s).", self.bit_width(), rhs.bit_width(), bit_width, MAX_SIGNAL_BIT_WIDTH);
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::Mul {
-                lhs: self,
-                rhs,
-                bit_width,
-            },
-        })
+        self.try_mul(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -1324,20 +1704,7 @@ impl<'a> Shl for &'a Signal<'a> {
     /// let shifted = lhs << rhs; // Equivalent to m.lit(12u32, 32)
     /// ```
     fn shl(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::ShiftBinOp {
-                lhs: self,
-                rhs,
-                op: ShiftBinOp::Shl,
-                bit_width: self.bit_width(),
-            },
-        })
+        self.try_shl(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -1366,20 +1733,7 @@ impl<'a> Shr for &'a Signal<'a> {
     /// let shifted = lhs >> rhs; // Equivalent to m.lit(3u32, 32)
     /// ```
     fn shr(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::ShiftBinOp {
-                lhs: self,
-                rhs,
-                op: ShiftBinOp::Shr,
-                bit_width: self.bit_width(),
-            },
-        })
+        self.try_shr(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -1408,43 +1762,23 @@ impl<'a> Sub for &'a Signal<'a> {
     /// let difference = lhs - rhs; // Equivalent to m.lit(1u32, 32)
     /// ```
     fn sub(self, rhs: Self) -> Self {
-        if !ptr::eq(self.module, rhs.module) {
-            panic!("Attempted to combine signals from different modules.");
-        }
-        if self.bit_width() != rhs.bit_width() {
-            panic!(
-                "Signals have different bit widths ({} and {}, respectively).",
-                self.bit_width(),
-                rhs.bit_width()
-            );
-        }
-        self.context.signal_arena.alloc(Signal {
-            context: self.context,
-            module: self.module,
-
-            data: SignalData::AdditiveBinOp {
-                lhs: self,
-                rhs,
-                op: AdditiveBinOp::Sub,
-                bit_width: self.bit_width(),
-            },
-        })
+        self.try_sub(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub(crate) enum UnOp {
     Not,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub(crate) enum SimpleBinOp {
     BitAnd,
     BitOr,
     BitXor,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub(crate) enum ComparisonBinOp {
     Equal,
     GreaterThan,
@@ -1458,13 +1792,13 @@ pub(crate) enum ComparisonBinOp {
     NotEqual,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub(crate) enum AdditiveBinOp {
     Add,
     Sub,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub(crate) enum ShiftBinOp {
     Shl,
     Shr,
@@ -1550,7 +1884,7 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Attempted to repeat a 1-bit signal 129 times, but this would result in a bit width of 129, which is greater than the maximum signal bit width of 128 bit(s)."
+        expected = "Attempted to repeat a 1-bit signal 65537 times, but this would result in a bit width of 65537, which is greater than the maximum signal bit width of 65536 bit(s)."
     )]
     fn repeat_count_oob_error() {
         let c = Context::new();
@@ -1559,7 +1893,7 @@ mod tests {
         let i = m.input("i", 1);
 
         // Panic
-        let _ = i.repeat(129);
+        let _ = i.repeat(65537);
     }
 
     #[test]
@@ -1579,13 +1913,13 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Attempted to concatenate signals with 128 bit(s) and 1 bit(s) respectively, but this would result in a bit width of 129, which is greater than the maximum signal bit width of 128 bit(s)."
+        expected = "Attempted to concatenate signals with 65536 bit(s) and 1 bit(s) respectively, but this would result in a bit width of 65537, which is greater than the maximum signal bit width of 65536 bit(s)."
     )]
     fn concat_oob_error() {
         let c = Context::new();
 
         let m = c.module("A");
-        let i1 = m.input("i1", 128);
+        let i1 = m.input("i1", 65536);
         let i2 = m.input("i2", 1);
 
         // Panic
@@ -1956,13 +2290,13 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Attempted to multiply a 128-bit with a 1-bit signal, but this would result in a bit width of 129, which is greater than the maximum signal bit width of 128 bit(s)."
+        expected = "Attempted to multiply a 65536-bit with a 1-bit signal, but this would result in a bit width of 65537, which is greater than the maximum signal bit width of 65536 bit(s)."
     )]
     fn mul_signed_oob_error() {
         let c = Context::new();
 
         let m = c.module("A");
-        let i1 = m.input("a", 128);
+        let i1 = m.input("a", 65536);
         let i2 = m.input("b", 1);
 
         // Panic
@@ -2176,13 +2510,13 @@ mod tests {
 
     #[test]
     #[should_panic(
-        expected = "Attempted to multiply a 128-bit with a 1-bit signal, but this would result in a bit width of 129, which is greater than the maximum signal bit width of 128 bit(s)."
+        expected = "Attempted to multiply a 65536-bit with a 1-bit signal, but this would result in a bit width of 65537, which is greater than the maximum signal bit width of 65536 bit(s)."
     )]
     fn mul_oob_error() {
         let c = Context::new();
 
         let m = c.module("A");
-        let i1 = m.input("a", 128);
+        let i1 = m.input("a", 65536);
         let i2 = m.input("b", 1);
 
         // Panic
@@ -2246,4 +2580,58 @@ mod tests {
         // Panic
         let _ = i1 - i2;
     }
+
+    #[test]
+    fn try_ops_errors() {
+        let c = Context::new();
+
+        let m1 = c.module("A");
+        let i1 = m1.input("a", 3);
+        let i2 = m1.input("b", 5);
+        let i3 = m1.input("c", 1);
+
+        let m2 = c.module("B");
+        let i4 = m2.input("d", 3);
+
+        assert!(matches!(
+            i1.try_bit(3),
+            Err(Error::BitIndexOutOfRange {
+                index: 3,
+                bit_width: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            i1.try_bits(0, 1),
+            Err(Error::BitsRangeInverted { .. })
+        ));
+        assert!(matches!(
+            i1.try_repeat(0),
+            Err(Error::InvalidRepeatBitWidth { bit_width: 0, .. })
+        ));
+        assert!(matches!(
+            i1.try_add(i4),
+            Err(Error::SignalsFromDifferentModules { .. })
+        ));
+        assert!(matches!(
+            i1.try_bitxor(i2),
+            Err(Error::SignalBitWidthMismatch {
+                lhs_bit_width: 3,
+                rhs_bit_width: 5,
+                ..
+            })
+        ));
+        assert!(matches!(
+            i3.try_lt_signed(i3),
+            Err(Error::SignedComparisonOfSingleBitSignals { .. })
+        ));
+        assert!(matches!(
+            i2.try_mux(i1, !i1),
+            Err(Error::InvalidMuxConditionBitWidth { bit_width: 5, .. })
+        ));
+
+        assert_eq!(i1.try_concat(i2).unwrap().bit_width(), 8);
+        assert_eq!(i1.try_mul(i2).unwrap().bit_width(), 8);
+        assert_eq!(i1.try_eq(i1).unwrap().bit_width(), 1);
+    }
 }
@@ -1,6 +1,6 @@
 //! An [HDL](https://en.wikipedia.org/wiki/Hardware_description_language) embedded in [Rust](https://www.rust-lang.org/).
 //!
-//! kaze provides an API to describe [`Module`]s composed of [`Signal`]s, which can then be used to generate [Rust simulator code](sim::generate) or [Verilog modules](verilog::generate).
+//! kaze provides an API to describe [`Module`]s composed of [`Signal`]s, which can then be used to generate [Rust simulator code](sim::generate), [Verilog modules](verilog::generate), or [BTOR2 models](btor2::generate) for formal verification.
 //!
 //! kaze's API is designed to be as minimal as possible while still being expressive.
 //! It's designed to prevent the user from being able to describe buggy or incorrect hardware as much as possible.
@@ -31,7 +31,7 @@
 //! sim::generate(inverter, sim::GenerationOptions::default(), std::io::stdout())?;
 //!
 //! // Generate Verilog code
-//! verilog::generate(inverter, std::io::stdout())?;
+//! verilog::generate(inverter, verilog::GenerationOptions::default(), std::io::stdout())?;
 //! # Ok(())
 //! # }
 //! ```
@@ -39,12 +39,21 @@
 // Must be kept up-to-date with version in Cargo.toml
 #![doc(html_root_url = "https://docs.rs/kaze/0.1.19")]
 
+pub mod btor2;
 mod code_writer;
+mod diagnostic;
+mod error;
 mod graph;
+pub mod lint;
 mod module_context;
+mod reset;
 pub mod runtime;
 pub mod sim;
+mod simplify;
 mod validation;
 pub mod verilog;
 
+pub use diagnostic::*;
+pub use error::*;
 pub use graph::*;
+pub use reset::*;
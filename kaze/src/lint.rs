@@ -0,0 +1,771 @@
+//! Lints for likely mistakes in module hierarchies.
+//!
+//! Unlike the errors reported by [`diagnose`] and the code generators, the problems reported here don't prevent code from being generated; they merely point out logic that is dead or redundant, or width manipulations that discard what they just built, which often indicates a bug in the design. They're reported as [`Diagnostic`]s with [`Severity::Warning`].
+
+use typed_arena::Arena;
+
+use crate::diagnostic::*;
+use crate::error::*;
+use crate::graph;
+use crate::module_context::*;
+use crate::simplify::*;
+use crate::validation::*;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ptr;
+
+/// The kind of problem reported by [`check`], as a [`DiagnosticKind::Lint`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LintKind {
+    /// An input called `input` in `module` is never read by any logic that reaches an output.
+    UnusedInput { module: String, input: String },
+    /// The value of a register called `register` in `module` never reaches an output.
+    DeadRegister { module: String, register: String },
+    /// A memory called `mem` in `module` has a write port, but none of its read ports reach an output, so the written values are never observed.
+    UnusedMemWritePort { module: String, mem: String },
+    /// A multiplexer in `module` has a condition that folds to a constant, so it always selects the same input.
+    ConstantMuxCondition { module: String },
+    /// A multiplexer in `module` selects between two inputs that fold to the same value.
+    IdenticalMuxInputs { module: String },
+    /// A register called `register` in `module` is driven by its own value, so it never changes after reset.
+    SelfDrivenRegister { module: String, register: String },
+    /// A [`bits`](crate::Signal::bits) call in `module` selects every bit of its source, so it has no effect.
+    RedundantBits { module: String },
+    /// A [`repeat`](crate::Signal::repeat) call in `module` has a count of 1, so it has no effect.
+    RedundantRepeat { module: String },
+    /// A [`bits`](crate::Signal::bits) call in `module` selects bits from only one side of a [`concat`](crate::Signal::concat), so the other side (e.g. the bits added by an extension) is truncated away.
+    TruncatedConcat { module: String },
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintKind::UnusedInput { module, input } => write!(
+                f,
+                "Module \"{}\" contains an input called \"{}\" which is never used.",
+                module, input
+            ),
+            LintKind::DeadRegister { module, register } => write!(
+                f,
+                "Module \"{}\" contains a register called \"{}\" whose value never reaches an output.",
+                module, register
+            ),
+            LintKind::UnusedMemWritePort { module, mem } => write!(
+                f,
+                "Module \"{}\" contains a memory called \"{}\" whose write port is unused, as none of its read ports reach an output.",
+                module, mem
+            ),
+            LintKind::ConstantMuxCondition { module } => write!(
+                f,
+                "Module \"{}\" contains a mux with a constant condition.",
+                module
+            ),
+            LintKind::IdenticalMuxInputs { module } => write!(
+                f,
+                "Module \"{}\" contains a mux whose inputs are identical.",
+                module
+            ),
+            LintKind::SelfDrivenRegister { module, register } => write!(
+                f,
+                "Module \"{}\" contains a register called \"{}\" which is driven by its own value.",
+                module, register
+            ),
+            LintKind::RedundantBits { module } => write!(
+                f,
+                "Module \"{}\" contains a bit range which selects every bit of its source.",
+                module
+            ),
+            LintKind::RedundantRepeat { module } => write!(
+                f,
+                "Module \"{}\" contains a repeat with a count of 1.",
+                module
+            ),
+            LintKind::TruncatedConcat { module } => write!(
+                f,
+                "Module \"{}\" contains a bit range which discards one side of a concatenation.",
+                module
+            ),
+        }
+    }
+}
+
+/// Checks `m` and every [`Module`](crate::Module) instantiated (directly or indirectly) within it for dead or redundant logic.
+///
+/// Logic is considered live if it (transitively) reaches one of `m`'s outputs, or the condition of a [`Property`](crate::Property) anywhere in `m`'s hierarchy. Problems are reported for each instance separately, in hierarchy order, as [`Diagnostic`]s with [`Severity::Warning`].
+///
+/// Mux conditions and inputs are checked after constant folding (the same folding the code generators perform), so e.g. a mux whose condition is `m.high() & m.high()` is reported as well.
+///
+/// Width manipulations are checked as written: [`bits`](crate::Signal::bits) calls that select every bit of their source or only one side of a [`concat`](crate::Signal::concat) (such as an extension that's immediately truncated back), and [`repeat`](crate::Signal::repeat) calls with a count of 1.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if `m`'s hierarchy fails validation (see [`diagnose`]).
+///
+/// # Examples
+///
+/// ```
+/// use kaze::*;
+///
+/// let c = Context::new();
+///
+/// let m = c.module("Top");
+/// let a = m.input("a", 1);
+/// let _ = m.input("b", 1);
+/// m.output("o", a);
+///
+/// let lints = lint::check(m).unwrap();
+/// assert_eq!(lints.len(), 1);
+/// assert_eq!(lints[0].severity, Severity::Warning);
+/// assert_eq!(lints[0].path, "Top.b");
+/// assert!(matches!(
+///     lints[0].kind,
+///     DiagnosticKind::Lint(lint::LintKind::UnusedInput { .. })
+/// ));
+/// ```
+pub fn check<'a>(m: &'a graph::Module<'a>) -> Result<Vec<Diagnostic>, Error> {
+    validate_module_hierarchy(m)?;
+
+    Ok(collect_lints(m))
+}
+
+// Assumes that `m`'s hierarchy has already been validated.
+pub(crate) fn collect_lints<'a>(m: &'a graph::Module<'a>) -> Vec<Diagnostic> {
+    let context_arena = Arena::new();
+    let root_context = context_arena.alloc(ModuleContext::new());
+
+    let mut reachable = Reachable::new();
+    for (_, output) in m.outputs.borrow().iter() {
+        reachable.trace(output, root_context, &context_arena);
+    }
+    reachable.trace_properties(m, root_context, &context_arena);
+
+    let mut simplifier = Simplifier::new(m.context(), true, false);
+    let mut lints = Vec::new();
+    check_module(
+        m,
+        root_context,
+        &context_arena,
+        m,
+        &reachable,
+        &mut simplifier,
+        &mut lints,
+    );
+
+    lints
+}
+
+fn lint(path: String, kind: LintKind) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Warning,
+        path,
+        kind: DiagnosticKind::Lint(kind),
+    }
+}
+
+struct Reachable<'graph, 'arena> {
+    signals: HashSet<(
+        *const ModuleContext<'graph, 'arena>,
+        *const graph::Signal<'graph>,
+    )>,
+    mems: HashSet<(
+        *const ModuleContext<'graph, 'arena>,
+        *const graph::Mem<'graph>,
+    )>,
+    muxes: HashMap<*const ModuleContext<'graph, 'arena>, Vec<&'graph graph::Signal<'graph>>>,
+    width_ops: HashMap<*const ModuleContext<'graph, 'arena>, Vec<&'graph graph::Signal<'graph>>>,
+}
+
+struct Frame<'graph, 'arena> {
+    signal: &'graph graph::Signal<'graph>,
+    context: &'arena ModuleContext<'graph, 'arena>,
+}
+
+impl<'graph, 'arena> Reachable<'graph, 'arena> {
+    fn new() -> Reachable<'graph, 'arena> {
+        Reachable {
+            signals: HashSet::new(),
+            mems: HashSet::new(),
+            muxes: HashMap::new(),
+            width_ops: HashMap::new(),
+        }
+    }
+
+    // Properties observe the logic driving their conditions, so that logic is live as well.
+    fn trace_properties(
+        &mut self,
+        m: &'graph graph::Module<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+    ) {
+        for property in m.properties.borrow().iter() {
+            self.trace(property.condition, context, context_arena);
+        }
+        for instance in m.instances.borrow().iter() {
+            self.trace_properties(
+                instance.instantiated_module,
+                context.get_child(instance, context_arena),
+                context_arena,
+            );
+        }
+    }
+
+    fn trace(
+        &mut self,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+    ) {
+        let mut frames = Vec::new();
+        frames.push(Frame { signal, context });
+
+        while let Some(frame) = frames.pop() {
+            let signal = frame.signal;
+            let context = frame.context;
+
+            if !self
+                .signals
+                .insert((context as *const _, signal as *const _))
+            {
+                continue;
+            }
+
+            match signal.data {
+                graph::SignalData::Lit { .. } => (),
+
+                graph::SignalData::Input { ref name, .. } => {
+                    if let Some((instance, parent)) = context.instance_and_parent {
+                        frames.push(Frame {
+                            signal: instance.driven_inputs.borrow()[name],
+                            context: parent,
+                        });
+                    }
+                }
+
+                graph::SignalData::Reg { data } => {
+                    frames.push(Frame {
+                        signal: data.next.borrow().unwrap(),
+                        context,
+                    });
+                }
+
+                graph::SignalData::UnOp { source, .. } => {
+                    frames.push(Frame {
+                        signal: source,
+                        context,
+                    });
+                }
+                graph::SignalData::Bits { source, .. }
+                | graph::SignalData::Repeat { source, .. } => {
+                    self.width_ops
+                        .entry(context as *const _)
+                        .or_default()
+                        .push(signal);
+                    frames.push(Frame {
+                        signal: source,
+                        context,
+                    });
+                }
+                graph::SignalData::SimpleBinOp { lhs, rhs, .. }
+                | graph::SignalData::AdditiveBinOp { lhs, rhs, .. }
+                | graph::SignalData::ComparisonBinOp { lhs, rhs, .. }
+                | graph::SignalData::ShiftBinOp { lhs, rhs, .. }
+                | graph::SignalData::Mul { lhs, rhs, .. }
+                | graph::SignalData::MulSigned { lhs, rhs, .. }
+                | graph::SignalData::Concat { lhs, rhs, .. } => {
+                    frames.push(Frame {
+                        signal: lhs,
+                        context,
+                    });
+                    frames.push(Frame {
+                        signal: rhs,
+                        context,
+                    });
+                }
+
+                graph::SignalData::Mux {
+                    cond,
+                    when_true,
+                    when_false,
+                    ..
+                } => {
+                    self.muxes
+                        .entry(context as *const _)
+                        .or_default()
+                        .push(signal);
+                    frames.push(Frame {
+                        signal: cond,
+                        context,
+                    });
+                    frames.push(Frame {
+                        signal: when_true,
+                        context,
+                    });
+                    frames.push(Frame {
+                        signal: when_false,
+                        context,
+                    });
+                }
+
+                graph::SignalData::InstanceOutput {
+                    instance, ref name, ..
+                } => {
+                    let output = instance.instantiated_module.outputs.borrow()[name];
+                    let context = context.get_child(instance, context_arena);
+                    frames.push(Frame {
+                        signal: output,
+                        context,
+                    });
+                }
+
+                graph::SignalData::MemReadPortOutput {
+                    mem,
+                    address,
+                    enable,
+                    ..
+                } => {
+                    frames.push(Frame {
+                        signal: address,
+                        context,
+                    });
+                    frames.push(Frame {
+                        signal: enable,
+                        context,
+                    });
+                    self.trace_mem(mem, context, &mut frames);
+                }
+                graph::SignalData::MemAsyncReadPortOutput { mem, address } => {
+                    frames.push(Frame {
+                        signal: address,
+                        context,
+                    });
+                    self.trace_mem(mem, context, &mut frames);
+                }
+            }
+        }
+    }
+
+    // A memory's write ports determine the values read from it, so they're live if any of its read
+    //  ports are.
+    fn trace_mem(
+        &mut self,
+        mem: &'graph graph::Mem<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        frames: &mut Vec<Frame<'graph, 'arena>>,
+    ) {
+        if !self.mems.insert((context as *const _, mem as *const _)) {
+            return;
+        }
+        for write_port in mem.write_ports.borrow().iter() {
+            frames.push(Frame {
+                signal: write_port.address,
+                context,
+            });
+            frames.push(Frame {
+                signal: write_port.value,
+                context,
+            });
+            frames.push(Frame {
+                signal: write_port.enable,
+                context,
+            });
+            if let Some(mask) = write_port.mask {
+                frames.push(Frame {
+                    signal: mask.mask,
+                    context,
+                });
+            }
+        }
+    }
+
+    fn contains_signal(
+        &self,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        signal: &'graph graph::Signal<'graph>,
+    ) -> bool {
+        self.signals
+            .contains(&(context as *const _, signal as *const _))
+    }
+}
+
+fn check_module<'graph, 'arena>(
+    m: &'graph graph::Module<'graph>,
+    context: &'arena ModuleContext<'graph, 'arena>,
+    context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+    root: &graph::Module<'graph>,
+    reachable: &Reachable<'graph, 'arena>,
+    simplifier: &mut Simplifier<'graph>,
+    lints: &mut Vec<Diagnostic>,
+) {
+    let path = context.path(root);
+
+    for (name, input) in m.inputs.borrow().iter() {
+        if !reachable.contains_signal(context, input) {
+            lints.push(lint(
+                format!("{}.{}", path, name),
+                LintKind::UnusedInput {
+                    module: m.name.clone(),
+                    input: name.clone(),
+                },
+            ));
+        }
+    }
+
+    for register in m.registers.borrow().iter() {
+        let data = match register.data {
+            graph::SignalData::Reg { data } => data,
+            _ => unreachable!(),
+        };
+
+        if !reachable.contains_signal(context, register) {
+            lints.push(lint(
+                format!("{}.{}", path, data.name),
+                LintKind::DeadRegister {
+                    module: m.name.clone(),
+                    register: data.name.clone(),
+                },
+            ));
+        }
+
+        if ptr::eq(data.next.borrow().unwrap(), *register) {
+            lints.push(lint(
+                format!("{}.{}", path, data.name),
+                LintKind::SelfDrivenRegister {
+                    module: m.name.clone(),
+                    register: data.name.clone(),
+                },
+            ));
+        }
+    }
+
+    for mem in m.mems.borrow().iter() {
+        if !mem.write_ports.borrow().is_empty()
+            && !reachable
+                .mems
+                .contains(&(context as *const _, *mem as *const _))
+        {
+            lints.push(lint(
+                format!("{}.{}", path, mem.name),
+                LintKind::UnusedMemWritePort {
+                    module: m.name.clone(),
+                    mem: mem.name.clone(),
+                },
+            ));
+        }
+    }
+
+    if let Some(muxes) = reachable.muxes.get(&(context as *const _)) {
+        for mux in muxes {
+            let (cond, when_true, when_false) = match mux.data {
+                graph::SignalData::Mux {
+                    cond,
+                    when_true,
+                    when_false,
+                    ..
+                } => (cond, when_true, when_false),
+                _ => unreachable!(),
+            };
+
+            if simplifier.constant_value(cond).is_some() {
+                lints.push(lint(
+                    path.clone(),
+                    LintKind::ConstantMuxCondition {
+                        module: m.name.clone(),
+                    },
+                ));
+            } else if is_same_value(simplifier, when_true, when_false) {
+                lints.push(lint(
+                    path.clone(),
+                    LintKind::IdenticalMuxInputs {
+                        module: m.name.clone(),
+                    },
+                ));
+            }
+        }
+    }
+
+    if let Some(width_ops) = reachable.width_ops.get(&(context as *const _)) {
+        for width_op in width_ops {
+            if let Some(kind) = check_width_op(m, width_op) {
+                lints.push(lint(path.clone(), kind));
+            }
+        }
+    }
+
+    for instance in m.instances.borrow().iter() {
+        let instantiated_module = instance.instantiated_module;
+
+        let context = context.get_child(instance, context_arena);
+
+        check_module(
+            instantiated_module,
+            context,
+            context_arena,
+            root,
+            reachable,
+            simplifier,
+            lints,
+        );
+    }
+}
+
+fn check_width_op<'graph>(
+    m: &graph::Module<'graph>,
+    width_op: &graph::Signal<'graph>,
+) -> Option<LintKind> {
+    match width_op.data {
+        graph::SignalData::Bits {
+            source,
+            range_high,
+            range_low,
+        } => {
+            if range_low == 0 && range_high == source.bit_width() - 1 {
+                return Some(LintKind::RedundantBits {
+                    module: m.name.clone(),
+                });
+            }
+            if let graph::SignalData::Concat { rhs, .. } = source.data {
+                let rhs_bit_width = rhs.bit_width();
+                if range_high < rhs_bit_width || range_low >= rhs_bit_width {
+                    return Some(LintKind::TruncatedConcat {
+                        module: m.name.clone(),
+                    });
+                }
+            }
+            None
+        }
+        graph::SignalData::Repeat { count: 1, .. } => Some(LintKind::RedundantRepeat {
+            module: m.name.clone(),
+        }),
+        _ => None,
+    }
+}
+
+fn is_same_value<'graph>(
+    simplifier: &mut Simplifier<'graph>,
+    a: &'graph graph::Signal<'graph>,
+    b: &'graph graph::Signal<'graph>,
+) -> bool {
+    if ptr::eq(simplifier.simplify(a), simplifier.simplify(b)) {
+        return true;
+    }
+    match (simplifier.constant_value(a), simplifier.constant_value(b)) {
+        (Some(a_value), Some(b_value)) => a_value == b_value,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::*;
+
+    fn check_lints<'a>(m: &'a graph::Module<'a>) -> Vec<(String, LintKind)> {
+        check(m)
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| {
+                assert_eq!(diagnostic.severity, Severity::Warning);
+                match diagnostic.kind {
+                    DiagnosticKind::Lint(kind) => (diagnostic.path, kind),
+                    _ => unreachable!(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_lints() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let i = m.input("i", 1);
+        let r = m.reg("r", 1);
+        r.drive_next(i);
+        m.output("o", r.value);
+
+        assert!(check_lints(m).is_empty());
+    }
+
+    #[test]
+    fn invalid_hierarchy_error() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let _ = m.reg("r", 1);
+
+        assert!(matches!(check(m), Err(Error::UndrivenRegister { .. })));
+    }
+
+    #[test]
+    fn unused_inputs_and_dead_registers() {
+        let c = Context::new();
+
+        let inner = c.module("Inner");
+        let i = inner.input("i", 1);
+        let r = inner.reg("r", 1);
+        r.drive_next(i);
+        inner.output("o", r.value);
+
+        let top = c.module("Top");
+        let a = top.input("a", 1);
+        let b = top.input("b", 1);
+        let used = top.instance("used", "Inner");
+        used.drive_input("i", a);
+        let unused = top.instance("unused", "Inner");
+        unused.drive_input("i", b);
+        top.output("o", used.output("o"));
+
+        assert_eq!(
+            check_lints(top),
+            vec![
+                (
+                    "Top.b".into(),
+                    LintKind::UnusedInput {
+                        module: "Top".into(),
+                        input: "b".into(),
+                    },
+                ),
+                (
+                    "Top.unused.i".into(),
+                    LintKind::UnusedInput {
+                        module: "Inner".into(),
+                        input: "i".into(),
+                    },
+                ),
+                (
+                    "Top.unused.r".into(),
+                    LintKind::DeadRegister {
+                        module: "Inner".into(),
+                        register: "r".into(),
+                    },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_mem_write_port() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let mem = m.mem("mem", 1, 1);
+        mem.write_port(m.low(), m.high(), m.high());
+        let _ = mem.read_port(m.low(), m.high());
+        m.output("o", m.low());
+
+        assert_eq!(
+            check_lints(m),
+            vec![(
+                "A.mem".into(),
+                LintKind::UnusedMemWritePort {
+                    module: "A".into(),
+                    mem: "mem".into(),
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn logic_observed_by_properties_is_live() {
+        let c = Context::new();
+
+        let inner = c.module("Inner");
+        let r = inner.reg("r", 1);
+        r.drive_next(inner.input("i", 1));
+        inner.output("o", inner.low());
+        inner.assert("r_low", !r.value);
+
+        let top = c.module("Top");
+        let inner_inst = top.instance("inner", "Inner");
+        inner_inst.drive_input("i", top.input("a", 1));
+        top.output("o", inner_inst.output("o"));
+        top.cover("b_high", top.input("b", 1));
+
+        assert_eq!(check_lints(top), vec![]);
+    }
+
+    #[test]
+    fn redundant_muxes() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let i = m.input("i", 1);
+        let j = m.input("j", 1);
+        m.output("o1", m.high().mux(i, j));
+        m.output("o2", i.mux(m.lit(3u32, 2), m.lit(3u32, 2)));
+        m.output("o3", i.mux(j, m.lit(0u32, 1)));
+        m.output("o4", (j & m.low()).mux(i, j));
+        m.output("o5", i.mux(j ^ m.low(), j));
+
+        assert_eq!(
+            check_lints(m),
+            vec![
+                (
+                    "A".into(),
+                    LintKind::ConstantMuxCondition { module: "A".into() },
+                ),
+                (
+                    "A".into(),
+                    LintKind::IdenticalMuxInputs { module: "A".into() },
+                ),
+                (
+                    "A".into(),
+                    LintKind::ConstantMuxCondition { module: "A".into() },
+                ),
+                (
+                    "A".into(),
+                    LintKind::IdenticalMuxInputs { module: "A".into() },
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn width_smells() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let i = m.input("i", 8);
+        let j = m.input("j", 4);
+        m.output("o1", i.bits(7, 0));
+        m.output("o2", j.repeat(1));
+        m.output("o3", m.lit(0u32, 8).concat(i).bits(7, 0));
+        m.output("o4", i.concat(j).bits(11, 4));
+        m.output("o5", i.concat(j).bits(5, 2));
+        m.output("o6", i.concat(j).bits(11, 0));
+        m.output("o7", j.bit(3).repeat(4).concat(j).bits(5, 0));
+
+        assert_eq!(
+            check_lints(m),
+            vec![
+                ("A".into(), LintKind::RedundantBits { module: "A".into() },),
+                ("A".into(), LintKind::RedundantRepeat { module: "A".into() },),
+                ("A".into(), LintKind::TruncatedConcat { module: "A".into() },),
+                ("A".into(), LintKind::TruncatedConcat { module: "A".into() },),
+                ("A".into(), LintKind::RedundantBits { module: "A".into() },),
+            ]
+        );
+    }
+
+    #[test]
+    fn self_driven_register() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let r = m.reg("r", 1);
+        r.default_value(false);
+        r.drive_next(r.value);
+        m.output("o", r.value);
+
+        let lints = check(m).unwrap();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].path, "A.r");
+        assert_eq!(
+            lints[0].to_string(),
+            "warning: A.r: Module \"A\" contains a register called \"r\" which is driven by its own value."
+        );
+    }
+}
@@ -40,6 +40,72 @@ impl<'graph, 'arena> ModuleContext<'graph, 'arena> {
         self.children.borrow()[&key]
     }
 
+    // Resolves `clock_domain`, which belongs to this context's module, to the clock domain of the root module that drives it.
+    pub fn root_clock_domain(
+        &self,
+        clock_domain: &'graph graph::ClockDomain<'graph>,
+    ) -> &'graph graph::ClockDomain<'graph> {
+        let mut clock_domain = clock_domain;
+        let mut context = self;
+        while let Some((instance, parent)) = context.instance_and_parent {
+            clock_domain = instance.driving_clock_domain(clock_domain);
+            context = parent;
+        }
+        clock_domain
+    }
+
+    // Returns the hierarchical path of this context, e.g. `top.cpu.alu`, where `root` is the module of the root context.
+    pub fn path(&self, root: &graph::Module<'graph>) -> String {
+        let mut path = root.name.clone();
+        for instance_name in self.instance_names() {
+            path.push('.');
+            path.push_str(instance_name);
+        }
+        path
+    }
+
+    // Returns `name` prefixed by the names of the instances leading to this context, separated by `__`, e.g. `cpu__alu__r`, which is suitable for use in generated identifiers. Names that don't start or end with `_` or contain `__` can't run together across separators, so their qualified names are distinct as long as their paths are.
+    pub fn qualified_name(&self, name: &str) -> String {
+        self.join_instance_names(name, "__")
+    }
+
+    // Returns `name` prefixed by the names of the instances leading to this context, separated by `.`, e.g. `cpu.alu.r`. Unlike `path`, this doesn't include the root module's name.
+    pub fn relative_path(&self, name: &str) -> String {
+        self.join_instance_names(name, ".")
+    }
+
+    // Returns the names of the instances leading to this context, separated by `.`, e.g. `cpu.alu`, or an empty string for the root context.
+    pub fn instance_path(&self) -> String {
+        self.instance_names().join(".")
+    }
+
+    // Returns the number of instances leading to this context from the root context.
+    pub fn depth(&self) -> u32 {
+        self.instance_names().len() as u32
+    }
+
+    fn join_instance_names(&self, name: &str, separator: &str) -> String {
+        let mut ret = String::new();
+        for instance_name in self.instance_names() {
+            ret.push_str(instance_name);
+            ret.push_str(separator);
+        }
+        ret.push_str(name);
+        ret
+    }
+
+    // Returns the names of the instances leading to this context from the root context, outermost first.
+    fn instance_names(&self) -> Vec<&'graph str> {
+        let mut instance_names = Vec::new();
+        let mut context = self;
+        while let Some((instance, parent)) = context.instance_and_parent {
+            instance_names.push(instance.name.as_str());
+            context = parent;
+        }
+        instance_names.reverse();
+        instance_names
+    }
+
     pub fn children(
         &self,
     ) -> Ref<HashMap<*const graph::Instance<'graph>, &'arena ModuleContext<'graph, 'arena>>> {
@@ -0,0 +1,23 @@
+/// Specifies when a [`ClockDomain`](crate::ClockDomain)'s reset takes effect in generated code.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ResetType {
+    /// [`Register`](crate::Register)s are reset as soon as reset is asserted, regardless of the clock.
+    ///
+    /// In generated Rust simulators, calling `reset()` updates [`Register`](crate::Register)s immediately.
+    #[default]
+    Asynchronous,
+    /// [`Register`](crate::Register)s are reset on the next positive clock edge while reset is asserted.
+    ///
+    /// In generated Rust simulators, calling `reset()` asserts reset for one cycle, and [`Register`](crate::Register)s are updated to reflect their default values on the next call to `posedge_clk()` (or the corresponding method for the clock domain).
+    Synchronous,
+}
+
+/// Specifies the level at which a [`ClockDomain`](crate::ClockDomain)'s reset is asserted in generated Verilog code.
+///
+/// Active-low resets are represented by `reset_n` (or `<name>_reset_n`) ports, and active-high resets are represented by `reset` (or `<name>_reset`) ports.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ResetPolarity {
+    #[default]
+    ActiveLow,
+    ActiveHigh,
+}
@@ -1,3 +1,8 @@
-//! Rust simulator runtime dependencies. These are only required for simulators with tracing enabled.
+//! Rust simulator runtime dependencies. These are only required for simulators with tracing enabled, with signals wider than 128 bits, with random initial state, or with partitions evaluated in parallel, and for serializing simulator state snapshots.
 
+pub mod random;
+pub mod snapshot;
 pub mod tracing;
+pub mod wide;
+#[doc(hidden)]
+pub mod workers;
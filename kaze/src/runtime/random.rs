@@ -0,0 +1,50 @@
+//! Pseudorandom number generation for initializing state in generated simulators.
+
+/// A small, fast pseudorandom number generator (SplitMix64), used by generated simulators to initialize registers and memories that lack default values or initial contents.
+///
+/// The same seed always produces the same sequence of values, so simulations are reproducible.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new `Rng` whose sequence is determined by `seed`.
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Returns the next 64 bits of the sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns the next bit of the sequence.
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u64() >> 63 != 0
+    }
+
+    /// Returns the next 32 bits of the sequence.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as _
+    }
+
+    /// Returns the next 128 bits of the sequence.
+    pub fn next_u128(&mut self) -> u128 {
+        let low = self.next_u64() as u128;
+        let high = self.next_u64() as u128;
+        (high << 64) | low
+    }
+
+    /// Returns the next `N` words of the sequence as a wide value (see [`wide`](super::wide)).
+    pub fn next_wide<const N: usize>(&mut self) -> [u64; N] {
+        let mut ret = [0; N];
+        for word in ret.iter_mut() {
+            *word = self.next_u64();
+        }
+        ret
+    }
+}
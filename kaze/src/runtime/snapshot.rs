@@ -0,0 +1,233 @@
+//! Serialization of the state snapshots taken by generated simulators.
+//!
+//! A serialized snapshot starts with [`MAGIC`], followed by a 64-bit fingerprint of the layout of the generated simulator's state (so that snapshots can't be restored into simulators with different state), and then each of the state's members in turn. All values are stored little-endian, and boxed slices and `Vec`s are prefixed with their length as a `u64`.
+
+use std::convert::TryInto;
+use std::io;
+
+/// The bytes every serialized snapshot starts with.
+pub const MAGIC: &[u8; 8] = b"kazesnap";
+
+/// A value that's part of a generated simulator's state, and can be written to and read from a serialized snapshot.
+pub trait StateValue: Sized {
+    /// Appends this value's serialized representation to `bytes`.
+    fn write_bytes(&self, bytes: &mut Vec<u8>);
+
+    /// Reads a value from the start of `bytes`, and advances `bytes` past it. Returns `None` if `bytes` is too short.
+    fn read_bytes(bytes: &mut &[u8]) -> Option<Self>;
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if bytes.len() < len {
+        return None;
+    }
+    let (ret, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Some(ret)
+}
+
+impl StateValue for bool {
+    fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self as u8);
+    }
+
+    fn read_bytes(bytes: &mut &[u8]) -> Option<Self> {
+        match take(bytes, 1)?[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_state_value_for_int {
+    ($t:ty) => {
+        impl StateValue for $t {
+            fn write_bytes(&self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_bytes(bytes: &mut &[u8]) -> Option<Self> {
+                Some(<$t>::from_le_bytes(
+                    take(bytes, std::mem::size_of::<$t>())?.try_into().unwrap(),
+                ))
+            }
+        }
+    };
+}
+
+impl_state_value_for_int!(u32);
+impl_state_value_for_int!(u64);
+impl_state_value_for_int!(u128);
+
+impl<T: StateValue, const N: usize> StateValue for [T; N] {
+    fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        for element in self.iter() {
+            element.write_bytes(bytes);
+        }
+    }
+
+    fn read_bytes(bytes: &mut &[u8]) -> Option<Self> {
+        let elements = (0..N)
+            .map(|_| T::read_bytes(bytes))
+            .collect::<Option<Vec<_>>>()?;
+        elements.try_into().ok()
+    }
+}
+
+impl<T: StateValue> StateValue for Vec<T> {
+    fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        (self.len() as u64).write_bytes(bytes);
+        for element in self.iter() {
+            element.write_bytes(bytes);
+        }
+    }
+
+    fn read_bytes(bytes: &mut &[u8]) -> Option<Self> {
+        let len = u64::read_bytes(bytes)?;
+        // Each element takes at least one byte, so this guards against allocating huge `Vec`s for
+        //  corrupt lengths
+        if len > bytes.len() as u64 {
+            return None;
+        }
+        (0..len).map(|_| T::read_bytes(bytes)).collect()
+    }
+}
+
+impl<T: StateValue> StateValue for Box<[T]> {
+    fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        (self.len() as u64).write_bytes(bytes);
+        for element in self.iter() {
+            element.write_bytes(bytes);
+        }
+    }
+
+    fn read_bytes(bytes: &mut &[u8]) -> Option<Self> {
+        Vec::read_bytes(bytes).map(Vec::into_boxed_slice)
+    }
+}
+
+/// Starts a serialized snapshot of a simulator's state whose layout has the given `fingerprint`. Used by generated code.
+pub fn begin_write(fingerprint: u64) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    fingerprint.write_bytes(&mut bytes);
+    bytes
+}
+
+/// Checks that `bytes` starts a serialized snapshot of a simulator's state whose layout has the given `fingerprint`, and returns the rest of the snapshot. Used by generated code.
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData) if `bytes` doesn't start with [`MAGIC`], or if the snapshot was taken from a simulator with a different layout.
+pub fn begin_read(mut bytes: &[u8], fingerprint: u64) -> io::Result<&[u8]> {
+    if take(&mut bytes, MAGIC.len()) != Some(&MAGIC[..]) {
+        return Err(invalid_data("not a kaze simulator snapshot"));
+    }
+    if u64::read_bytes(&mut bytes) != Some(fingerprint) {
+        return Err(invalid_data(
+            "snapshot was taken from a simulator with a different state layout",
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Reads a member of a serialized snapshot. Used by generated code.
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData) if the snapshot ends before the member, or contains an invalid value for it.
+pub fn read<T: StateValue>(bytes: &mut &[u8]) -> io::Result<T> {
+    T::read_bytes(bytes).ok_or_else(|| invalid_data("snapshot is truncated or corrupt"))
+}
+
+/// Checks a condition on the members read from a serialized snapshot (e.g. that mem contents have the expected number of elements). Used by generated code.
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData) if `condition` is `false`.
+pub fn check(condition: bool) -> io::Result<()> {
+    if !condition {
+        return Err(invalid_data("snapshot is truncated or corrupt"));
+    }
+    Ok(())
+}
+
+/// Checks that `bytes`, the rest of a serialized snapshot after its last member, is empty. Used by generated code.
+///
+/// # Errors
+///
+/// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData) if `bytes` isn't empty.
+pub fn end_read(bytes: &[u8]) -> io::Result<()> {
+    if !bytes.is_empty() {
+        return Err(invalid_data("snapshot has trailing data"));
+    }
+    Ok(())
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: StateValue>(value: &T) -> T {
+        let mut bytes = Vec::new();
+        value.write_bytes(&mut bytes);
+        let mut rest = &bytes[..];
+        let ret = T::read_bytes(&mut rest).unwrap();
+        assert!(rest.is_empty());
+        ret
+    }
+
+    #[test]
+    fn values() {
+        assert!(round_trip(&true));
+        assert_eq!(round_trip(&0xdeadbeefu32), 0xdeadbeef);
+        assert_eq!(round_trip(&(u128::MAX - 1)), u128::MAX - 1);
+        assert_eq!(
+            round_trip(&[[1u64, 2, 3], [4, 5, 6]]),
+            [[1, 2, 3], [4, 5, 6]]
+        );
+        assert_eq!(
+            round_trip(&vec![vec![1u32, 2].into_boxed_slice(); 2]),
+            vec![vec![1, 2].into_boxed_slice(); 2]
+        );
+
+        let mut bytes = Vec::new();
+        0x1234u32.write_bytes(&mut bytes);
+        assert_eq!(bytes, [0x34, 0x12, 0, 0]);
+        assert_eq!(u64::read_bytes(&mut &bytes[..]), None);
+        assert_eq!(bool::read_bytes(&mut &[2u8][..]), None);
+        assert_eq!(Vec::<bool>::read_bytes(&mut &[0xffu8; 8][..]), None);
+    }
+
+    #[test]
+    fn headers() {
+        let mut bytes = begin_write(42);
+        true.write_bytes(&mut bytes);
+
+        let mut rest = begin_read(&bytes, 42).unwrap();
+        assert!(read::<bool>(&mut rest).unwrap());
+        end_read(rest).unwrap();
+
+        assert_eq!(
+            begin_read(&bytes, 43).unwrap_err().to_string(),
+            "snapshot was taken from a simulator with a different state layout"
+        );
+        assert_eq!(
+            begin_read(&bytes[1..], 42).unwrap_err().to_string(),
+            "not a kaze simulator snapshot"
+        );
+        let mut rest = begin_read(&bytes[..bytes.len() - 1], 42).unwrap();
+        assert_eq!(
+            read::<bool>(&mut rest).unwrap_err().to_string(),
+            "snapshot is truncated or corrupt"
+        );
+        assert_eq!(
+            end_read(&bytes).unwrap_err().to_string(),
+            "snapshot has trailing data"
+        );
+    }
+}
@@ -6,7 +6,7 @@ use std::io;
 
 // TODO: Do we want to re-use graph::Constant for this? They're equivalent but currently distinct in their usage, so I'm not sure it's the right API design decision.
 #[derive(Debug, Eq, PartialEq)]
-pub enum TraceValue {
+pub enum TraceValue<'a> {
     /// Contains a boolean value
     Bool(bool),
     /// Contains an unsigned, 32-bit value
@@ -15,6 +15,8 @@ pub enum TraceValue {
     U64(u64),
     /// Contains an unsigned, 128-bit value
     U128(u128),
+    /// Contains an unsigned value wider than 128 bits, stored as 64-bit words in little-endian order (least significant word first)
+    Wide(&'a [u64]),
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -23,6 +25,7 @@ pub enum TraceValueType {
     U32,
     U64,
     U128,
+    Wide,
 }
 
 impl TraceValueType {
@@ -36,7 +39,7 @@ impl TraceValueType {
         } else if bit_width <= 128 {
             TraceValueType::U128
         } else {
-            unreachable!()
+            TraceValueType::Wide
         }
     }
 }
@@ -54,5 +57,9 @@ pub trait Trace {
     ) -> io::Result<Self::SignalId>;
 
     fn update_time_stamp(&mut self, time_stamp: u64) -> io::Result<()>;
-    fn update_signal(&mut self, signal_id: &Self::SignalId, value: TraceValue) -> io::Result<()>;
+    fn update_signal(
+        &mut self,
+        signal_id: &Self::SignalId,
+        value: TraceValue<'_>,
+    ) -> io::Result<()>;
 }
@@ -32,6 +32,8 @@ pub struct VcdTrace<W: io::Write> {
     module_hierarchy_depth: u32,
 
     signals: Vec<VcdTraceSignal>,
+    // Reused for each vector value change to avoid allocating on every update
+    scalar_values: Vec<vcd::Value>,
 
     w: vcd::Writer<W>,
 }
@@ -46,6 +48,7 @@ impl<W: io::Write> VcdTrace<W> {
             module_hierarchy_depth: 0,
 
             signals: Vec::new(),
+            scalar_values: Vec::new(),
 
             w,
         })
@@ -97,7 +100,11 @@ impl<W: io::Write> Trace for VcdTrace<W> {
         self.w.timestamp(time_stamp)
     }
 
-    fn update_signal(&mut self, signal_id: &Self::SignalId, value: TraceValue) -> io::Result<()> {
+    fn update_signal(
+        &mut self,
+        signal_id: &Self::SignalId,
+        value: TraceValue<'_>,
+    ) -> io::Result<()> {
         // TODO: Type check incoming value!
         let signal = &self.signals[*signal_id];
 
@@ -106,22 +113,27 @@ impl<W: io::Write> Trace for VcdTrace<W> {
                 signal.id,
                 match value {
                     TraceValue::Bool(value) => value,
-                    TraceValue::U32(_) | TraceValue::U64(_) | TraceValue::U128(_) => unreachable!(),
+                    TraceValue::U32(_)
+                    | TraceValue::U64(_)
+                    | TraceValue::U128(_)
+                    | TraceValue::Wide(_) => unreachable!(),
                 },
             )?;
         } else {
-            let value = match value {
-                TraceValue::Bool(_) => unreachable!(),
-                TraceValue::U32(value) => value as _,
-                TraceValue::U64(value) => value as _,
-                TraceValue::U128(value) => value,
+            let bit = |i: usize| -> bool {
+                match value {
+                    TraceValue::Bool(_) => unreachable!(),
+                    TraceValue::U32(value) => (value >> i) & 1 != 0,
+                    TraceValue::U64(value) => (value >> i) & 1 != 0,
+                    TraceValue::U128(value) => (value >> i) & 1 != 0,
+                    TraceValue::Wide(words) => (words[i / 64] >> (i % 64)) & 1 != 0,
+                }
             };
-            let mut scalar_values = [vcd::Value::V0; 128];
-            for i in 0..signal.bit_width as usize {
-                scalar_values[i] = ((value >> (signal.bit_width as usize - 1 - i)) & 1 != 0).into();
-            }
-            self.w
-                .change_vector(signal.id, &scalar_values[0..signal.bit_width as usize])?;
+            let bit_width = signal.bit_width as usize;
+            self.scalar_values.clear();
+            self.scalar_values
+                .extend((0..bit_width).map(|i| vcd::Value::from(bit(bit_width - 1 - i))));
+            self.w.change_vector(signal.id, &self.scalar_values)?;
         }
 
         Ok(())
@@ -0,0 +1,358 @@
+//! Helpers for values wider than 128 bits in generated simulators.
+//!
+//! Wide values are stored as arrays of 64-bit words, least significant word first. All helpers operate on the full width of the array; generated code is responsible for masking results down to the width of the signal they represent, and for sign-extending operands before calling the signed helpers.
+
+use std::cmp::Ordering;
+
+/// Converts a `u128` to a wide value, truncating if `N` is less than `2`.
+pub fn from_u128<const N: usize>(value: u128) -> [u64; N] {
+    let mut ret = [0; N];
+    for (i, word) in ret.iter_mut().enumerate().take(2) {
+        *word = (value >> (i * 64)) as u64;
+    }
+    ret
+}
+
+/// Returns the lower 128 bits of a wide value.
+pub fn to_u128<const N: usize>(value: [u64; N]) -> u128 {
+    value
+        .iter()
+        .take(2)
+        .enumerate()
+        .fold(0, |acc, (i, word)| acc | ((*word as u128) << (i * 64)))
+}
+
+/// Converts a wide value to a wide value with a different number of words, truncating or zero-extending as necessary.
+pub fn resize<const N: usize, const M: usize>(value: [u64; N]) -> [u64; M] {
+    let mut ret = [0; M];
+    for (dst, src) in ret.iter_mut().zip(value.iter()) {
+        *dst = *src;
+    }
+    ret
+}
+
+/// Clears all bits at or above `bit_width`.
+pub fn mask<const N: usize>(mut value: [u64; N], bit_width: u32) -> [u64; N] {
+    for (i, word) in value.iter_mut().enumerate() {
+        let word_low = i as u32 * 64;
+        if bit_width <= word_low {
+            *word = 0;
+        } else if bit_width - word_low < 64 {
+            *word &= (1 << (bit_width - word_low)) - 1;
+        }
+    }
+    value
+}
+
+/// Replicates bit `bit_width - 1` into all bits above it.
+pub fn sign_extend<const N: usize>(value: [u64; N], bit_width: u32) -> [u64; N] {
+    let value = mask(value, bit_width);
+    let sign_bit = bit_width - 1;
+    if (value[(sign_bit / 64) as usize] >> (sign_bit % 64)) & 1 == 0 {
+        return value;
+    }
+    not(mask(not(value), bit_width))
+}
+
+pub fn not<const N: usize>(mut value: [u64; N]) -> [u64; N] {
+    for word in value.iter_mut() {
+        *word = !*word;
+    }
+    value
+}
+
+pub fn and<const N: usize>(mut lhs: [u64; N], rhs: [u64; N]) -> [u64; N] {
+    for (l, r) in lhs.iter_mut().zip(rhs.iter()) {
+        *l &= *r;
+    }
+    lhs
+}
+
+pub fn or<const N: usize>(mut lhs: [u64; N], rhs: [u64; N]) -> [u64; N] {
+    for (l, r) in lhs.iter_mut().zip(rhs.iter()) {
+        *l |= *r;
+    }
+    lhs
+}
+
+pub fn xor<const N: usize>(mut lhs: [u64; N], rhs: [u64; N]) -> [u64; N] {
+    for (l, r) in lhs.iter_mut().zip(rhs.iter()) {
+        *l ^= *r;
+    }
+    lhs
+}
+
+/// Wrapping addition.
+pub fn add<const N: usize>(mut lhs: [u64; N], rhs: [u64; N]) -> [u64; N] {
+    let mut carry = false;
+    for (l, r) in lhs.iter_mut().zip(rhs.iter()) {
+        let (sum, carry_a) = l.overflowing_add(*r);
+        let (sum, carry_b) = sum.overflowing_add(carry as u64);
+        *l = sum;
+        carry = carry_a || carry_b;
+    }
+    lhs
+}
+
+/// Wrapping subtraction.
+pub fn sub<const N: usize>(mut lhs: [u64; N], rhs: [u64; N]) -> [u64; N] {
+    let mut borrow = false;
+    for (l, r) in lhs.iter_mut().zip(rhs.iter()) {
+        let (difference, borrow_a) = l.overflowing_sub(*r);
+        let (difference, borrow_b) = difference.overflowing_sub(borrow as u64);
+        *l = difference;
+        borrow = borrow_a || borrow_b;
+    }
+    lhs
+}
+
+/// Wrapping multiplication.
+pub fn mul<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> [u64; N] {
+    let mut ret = [0; N];
+    for i in 0..N {
+        let mut carry = 0u128;
+        for j in 0..N - i {
+            let product = (lhs[i] as u128) * (rhs[j] as u128) + (ret[i + j] as u128) + carry;
+            ret[i + j] = product as u64;
+            carry = product >> 64;
+        }
+    }
+    ret
+}
+
+/// Logical shift left. Shifting by at least the full width of the array results in `0`.
+pub fn shl<const N: usize>(value: [u64; N], shift: u32) -> [u64; N] {
+    let mut ret = [0; N];
+    let word_shift = (shift / 64) as usize;
+    let bit_shift = shift % 64;
+    for i in word_shift..N {
+        ret[i] = value[i - word_shift] << bit_shift;
+        if bit_shift != 0 && i > word_shift {
+            ret[i] |= value[i - word_shift - 1] >> (64 - bit_shift);
+        }
+    }
+    ret
+}
+
+/// Logical shift right. Shifting by at least the full width of the array results in `0`.
+pub fn shr<const N: usize>(value: [u64; N], shift: u32) -> [u64; N] {
+    let mut ret = [0; N];
+    let word_shift = (shift / 64) as usize;
+    let bit_shift = shift % 64;
+    for i in 0..N.saturating_sub(word_shift) {
+        ret[i] = value[i + word_shift] >> bit_shift;
+        if bit_shift != 0 && i + word_shift + 1 < N {
+            ret[i] |= value[i + word_shift + 1] << (64 - bit_shift);
+        }
+    }
+    ret
+}
+
+/// Arithmetic shift right, treating the most significant bit of the array as the sign bit.
+pub fn shr_arithmetic<const N: usize>(value: [u64; N], shift: u32) -> [u64; N] {
+    if !is_negative(value) {
+        return shr(value, shift);
+    }
+    not(shr(not(value), shift))
+}
+
+/// Saturates a wide value to a `u32` shift amount.
+pub fn to_shift_amount<const N: usize>(value: [u64; N]) -> u32 {
+    if value.iter().skip(1).any(|word| *word != 0) || value[0] > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        value[0] as u32
+    }
+}
+
+fn is_negative<const N: usize>(value: [u64; N]) -> bool {
+    (value[N - 1] >> 63) != 0
+}
+
+fn cmp<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> Ordering {
+    lhs.iter().rev().cmp(rhs.iter().rev())
+}
+
+fn cmp_signed<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> Ordering {
+    match (is_negative(lhs), is_negative(rhs)) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        _ => cmp(lhs, rhs),
+    }
+}
+
+pub fn lt<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp(lhs, rhs) == Ordering::Less
+}
+
+pub fn le<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp(lhs, rhs) != Ordering::Greater
+}
+
+pub fn gt<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp(lhs, rhs) == Ordering::Greater
+}
+
+pub fn ge<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp(lhs, rhs) != Ordering::Less
+}
+
+/// Signed less-than, treating the most significant bit of the array as the sign bit.
+pub fn lt_signed<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp_signed(lhs, rhs) == Ordering::Less
+}
+
+/// Signed less-than-or-equal, treating the most significant bit of the array as the sign bit.
+pub fn le_signed<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp_signed(lhs, rhs) != Ordering::Greater
+}
+
+/// Signed greater-than, treating the most significant bit of the array as the sign bit.
+pub fn gt_signed<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp_signed(lhs, rhs) == Ordering::Greater
+}
+
+/// Signed greater-than-or-equal, treating the most significant bit of the array as the sign bit.
+pub fn ge_signed<const N: usize>(lhs: [u64; N], rhs: [u64; N]) -> bool {
+    cmp_signed(lhs, rhs) != Ordering::Less
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Values around the 64- and 128-bit word boundaries
+    const VALUES: [u128; 10] = [
+        0,
+        1,
+        0xffff_ffff_ffff_ffff,
+        0x1_0000_0000_0000_0000,
+        0x1_0000_0000_0000_0001,
+        0x7fff_ffff_ffff_ffff_ffff_ffff_ffff_ffff,
+        0x8000_0000_0000_0000_0000_0000_0000_0000,
+        0x8000_0000_0000_0000_ffff_ffff_ffff_ffff,
+        0x0123_4567_89ab_cdef_fedc_ba98_7654_3210,
+        0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff,
+    ];
+
+    const SHIFTS: [u32; 9] = [0, 1, 63, 64, 65, 127, 128, 129, 200];
+
+    fn wide(value: u128) -> [u64; 2] {
+        from_u128(value)
+    }
+
+    #[test]
+    fn mul_matches_u128() {
+        for &lhs in VALUES.iter() {
+            for &rhs in VALUES.iter() {
+                assert_eq!(to_u128(mul(wide(lhs), wide(rhs))), lhs.wrapping_mul(rhs));
+            }
+        }
+    }
+
+    #[test]
+    fn mul_carries_across_words() {
+        // (2^64 - 1)^2 == 2^128 - 2^65 + 1
+        let max_word = [u64::MAX, 0, 0];
+        assert_eq!(mul(max_word, max_word), [1, u64::MAX - 1, 0]);
+        assert_eq!(mul([0, 1 << 63, 0], [2, 0, 0]), [0, 0, 1]);
+        // Products wrap at the full width of the array
+        assert_eq!(mul([0, 0, 1], [0, 1, 0]), [0, 0, 0]);
+        assert_eq!(mul([u64::MAX; 3], [u64::MAX; 3]), [1, 0, 0]);
+    }
+
+    #[test]
+    fn shifts_match_u128() {
+        for &value in VALUES.iter() {
+            for &shift in SHIFTS.iter() {
+                assert_eq!(
+                    to_u128(shl(wide(value), shift)),
+                    value.checked_shl(shift).unwrap_or(0)
+                );
+                assert_eq!(
+                    to_u128(shr(wide(value), shift)),
+                    value.checked_shr(shift).unwrap_or(0)
+                );
+                assert_eq!(
+                    to_u128(shr_arithmetic(wide(value), shift)) as i128,
+                    (value as i128) >> shift.min(127)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shifts_cross_words() {
+        assert_eq!(shl([1 << 63, 0, 0], 1), [0, 1, 0]);
+        assert_eq!(shl([0, 1 << 63, 0], 1), [0, 0, 1]);
+        assert_eq!(shl([1, 0, 0], 129), [0, 0, 2]);
+        assert_eq!(shl([1, 0, 0], 192), [0, 0, 0]);
+        assert_eq!(shr([0, 0, 2], 129), [1, 0, 0]);
+        assert_eq!(shr([0, 0, 1], 1), [0, 1 << 63, 0]);
+        assert_eq!(shr([u64::MAX; 3], 192), [0, 0, 0]);
+        assert_eq!(shr_arithmetic([0, 0, 1 << 63], 64), [0, 1 << 63, u64::MAX]);
+        assert_eq!(shr_arithmetic([0, 0, 1 << 63], 191), [u64::MAX; 3]);
+        assert_eq!(shr_arithmetic([0, 0, 1 << 63], 192), [u64::MAX; 3]);
+        assert_eq!(shr_arithmetic([0, 0, 1 << 62], 64), [0, 1 << 62, 0]);
+
+        // 130-bit values are shifted within the array, and masked by generated code
+        assert_eq!(mask(shl([0, 0, 3], 1), 130), [0, 0, 2]);
+        assert_eq!(
+            shr_arithmetic(sign_extend([0, 0, 2], 130), 65),
+            [0, u64::MAX, u64::MAX]
+        );
+    }
+
+    #[test]
+    fn signed_comparisons_match_i128() {
+        for &lhs in VALUES.iter() {
+            for &rhs in VALUES.iter() {
+                let (signed_lhs, signed_rhs) = (lhs as i128, rhs as i128);
+                assert_eq!(lt_signed(wide(lhs), wide(rhs)), signed_lhs < signed_rhs);
+                assert_eq!(le_signed(wide(lhs), wide(rhs)), signed_lhs <= signed_rhs);
+                assert_eq!(gt_signed(wide(lhs), wide(rhs)), signed_lhs > signed_rhs);
+                assert_eq!(ge_signed(wide(lhs), wide(rhs)), signed_lhs >= signed_rhs);
+                assert_eq!(lt(wide(lhs), wide(rhs)), lhs < rhs);
+                assert_eq!(ge(wide(lhs), wide(rhs)), lhs >= rhs);
+            }
+        }
+    }
+
+    #[test]
+    fn signed_comparisons_of_sign_extended_values() {
+        // The most negative and most positive 130-bit values
+        let min = sign_extend([0, 0, 2], 130);
+        let max = mask([u64::MAX; 3], 129);
+        let minus_one = sign_extend(mask([u64::MAX; 3], 130), 130);
+        let zero = [0; 3];
+        assert_eq!(min, [0, 0, u64::MAX << 1]);
+        assert_eq!(minus_one, [u64::MAX; 3]);
+        assert!(lt_signed(min, minus_one));
+        assert!(lt_signed(minus_one, zero));
+        assert!(lt_signed(zero, max));
+        assert!(gt_signed(max, min));
+        assert!(le_signed(min, min) && ge_signed(min, min));
+        assert!(gt(min, max));
+
+        // 100-bit values, whose sign bit is within a word
+        let min = sign_extend(from_u128::<2>(1 << 99), 100);
+        let max = from_u128::<2>((1 << 99) - 1);
+        assert_eq!(to_u128(min) as i128, -(1 << 99));
+        assert!(lt_signed(min, max));
+        assert!(gt(min, max));
+
+        // 64-bit values, whose sign bit is at the top of a word
+        let min = sign_extend([1 << 63, 0], 64);
+        assert_eq!(min, [1 << 63, u64::MAX]);
+        assert!(lt_signed(min, [u64::MAX >> 1, 0]));
+        assert!(ge_signed([0, 0], min));
+    }
+
+    #[test]
+    fn mask_at_word_boundaries() {
+        assert_eq!(mask([u64::MAX; 3], 64), [u64::MAX, 0, 0]);
+        assert_eq!(mask([u64::MAX; 3], 128), [u64::MAX, u64::MAX, 0]);
+        assert_eq!(mask([u64::MAX; 3], 130), [u64::MAX, u64::MAX, 3]);
+        assert_eq!(mask([u64::MAX; 3], 192), [u64::MAX; 3]);
+    }
+}
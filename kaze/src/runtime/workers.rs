@@ -0,0 +1,489 @@
+//! Worker threads for generated simulators that evaluate partitions of their hierarchy in parallel (see [`GenerationOptions::partition_depth`](crate::sim::GenerationOptions::partition_depth)). This is an implementation detail of generated code, and isn't meant to be used directly.
+
+use std::any::Any;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+type Job<'a> = dyn FnMut(&Barrier) + Send + 'a;
+
+/// A pool of long-lived threads that run jobs in parallel with the thread that submits them.
+///
+/// The threads are spawned when the pool is created and live as long as the pool does, so submitting jobs only costs a couple of synchronizations, rather than spawning a thread for each job. As [`run`](Workers::run) takes the pool mutably, only one set of jobs can run on it at a time.
+pub struct Workers {
+    shared: Arc<Shared>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Signaled when jobs are submitted, when all of them have finished, and on shutdown
+    condvar: Condvar,
+    barrier: Barrier,
+}
+
+struct State {
+    // Incremented each time jobs are submitted, so that each thread runs its job once per submission
+    generation: u64,
+    jobs: Vec<JobPtr>,
+    num_running: usize,
+    panic: Option<Box<dyn Any + Send>>,
+    shutdown: bool,
+}
+
+// A job submitted by `Workers::run`, whose lifetime is erased so that it can be handed to the pool's
+//  threads. `run` doesn't return until every job has finished, and can't be called again until it
+//  returns, so the jobs outlive their use.
+struct JobPtr(*mut Job<'static>);
+
+// Safety: jobs are `Send`, and each job is only ever run by one thread.
+unsafe impl Send for JobPtr {}
+
+impl Workers {
+    /// Creates a pool with `num_threads` threads, which, together with the thread that calls [`run`](Workers::run), can run up to `num_threads + 1` jobs at a time.
+    pub fn new(num_threads: usize) -> Workers {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                generation: 0,
+                jobs: Vec::new(),
+                num_running: 0,
+                panic: None,
+                shutdown: false,
+            }),
+            condvar: Condvar::new(),
+            barrier: Barrier::new(),
+        });
+        let threads = (0..num_threads)
+            .map(|index| {
+                let shared = shared.clone();
+                thread::spawn(move || shared.work(index + 1))
+            })
+            .collect();
+        Workers { shared, threads }
+    }
+
+    /// Runs `jobs` in parallel, where the first job runs on the calling thread, and the rest run on the pool's threads, and returns once all of them have finished. Each job is passed a [`Barrier`] for all of `jobs`, which they can use to wait for each other between steps of their work. If any of the jobs panics, the barrier is poisoned, and the panic is resumed on the calling thread once all of the jobs have finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `num_threads + 1` jobs.
+    pub fn run(&mut self, jobs: &mut [&mut Job]) {
+        assert!(
+            jobs.len() <= self.threads.len() + 1,
+            "Can't run {} jobs with {} threads",
+            jobs.len(),
+            self.threads.len()
+        );
+        let num_jobs = jobs.len();
+        let (first, rest) = match jobs.split_first_mut() {
+            Some(split) => split,
+            _ => return,
+        };
+
+        self.shared.barrier.reset(num_jobs);
+        {
+            let mut state = self.shared.lock_state();
+            // Safety: the lifetimes erased here outlive the jobs' use, as we wait for all of them to
+            //  finish below, even if any of them panics, and `self` is borrowed mutably until then,
+            //  so no other jobs can be submitted in the meantime.
+            state.jobs = rest
+                .iter_mut()
+                .map(|job| {
+                    JobPtr(unsafe { mem::transmute::<*mut Job, *mut Job<'static>>(&mut **job) })
+                })
+                .collect();
+            state.num_running = state.jobs.len();
+            state.generation += 1;
+        }
+        self.shared.condvar.notify_all();
+
+        let barrier = &self.shared.barrier;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| first(barrier)));
+
+        let mut state = self.shared.lock_state();
+        if let Err(payload) = result {
+            self.shared.barrier.poison();
+            state.panic.get_or_insert(payload);
+        }
+        while state.num_running > 0 {
+            state = self
+                .shared
+                .condvar
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        state.jobs.clear();
+        if let Some(payload) = state.panic.take() {
+            drop(state);
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.shared.lock_state().shutdown = true;
+        self.shared.condvar.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Shared {
+    fn lock_state(&self) -> MutexGuard<'_, State> {
+        // Jobs never run while the state is locked, so it's never left inconsistent by a panic
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Runs the job at `index` of each submission, until the pool is dropped.
+    fn work(&self, index: usize) {
+        let mut generation = 0;
+        loop {
+            let job = {
+                let mut state = self.lock_state();
+                while state.generation == generation && !state.shutdown {
+                    state = self.condvar.wait(state).unwrap_or_else(|e| e.into_inner());
+                }
+                if state.shutdown {
+                    return;
+                }
+                generation = state.generation;
+                match state.jobs.get(index - 1) {
+                    Some(job) => job.0,
+                    _ => continue,
+                }
+            };
+
+            // Safety: `run` keeps the job alive until we report that it's finished, and no other
+            //  thread accesses it in the meantime.
+            let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe { (*job)(&self.barrier) }));
+
+            let mut state = self.lock_state();
+            if let Err(payload) = result {
+                self.barrier.poison();
+                state.panic.get_or_insert(payload);
+            }
+            state.num_running -= 1;
+            if state.num_running == 0 {
+                self.condvar.notify_all();
+            }
+        }
+    }
+}
+
+/// A reusable barrier for the jobs run by [`Workers::run`], which makes each job wait until all of them reach it.
+///
+/// Unlike [`std::sync::Barrier`], it's poisoned if any of the jobs panics, so that the others don't wait for it forever.
+pub struct Barrier {
+    state: Mutex<BarrierState>,
+    condvar: Condvar,
+}
+
+struct BarrierState {
+    num_jobs: usize,
+    num_waiting: usize,
+    generation: u64,
+    poisoned: bool,
+}
+
+impl Barrier {
+    fn new() -> Barrier {
+        Barrier {
+            state: Mutex::new(BarrierState {
+                num_jobs: 0,
+                num_waiting: 0,
+                generation: 0,
+                poisoned: false,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Waits until all of the jobs reach the barrier. Returns `false` if the barrier was poisoned by a job that panicked, in which case the caller should return without doing any more work.
+    pub fn wait(&self) -> bool {
+        let mut state = self.lock_state();
+        if state.poisoned {
+            return false;
+        }
+        state.num_waiting += 1;
+        if state.num_waiting == state.num_jobs {
+            state.num_waiting = 0;
+            state.generation += 1;
+            self.condvar.notify_all();
+            return true;
+        }
+        let generation = state.generation;
+        while state.generation == generation && !state.poisoned {
+            state = self.condvar.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        state.generation != generation
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, BarrierState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn poison(&self) {
+        self.lock_state().poisoned = true;
+        self.condvar.notify_all();
+    }
+
+    // Prepares the barrier for a `Workers::run` with `num_jobs` jobs, clearing any waiting jobs and
+    //  poisoning from a previous one
+    fn reset(&self, num_jobs: usize) {
+        let mut state = self.lock_state();
+        state.num_jobs = num_jobs;
+        state.num_waiting = 0;
+        state.poisoned = false;
+    }
+}
+
+/// A value that one of the jobs run by [`Workers::run`] passes on to others, which read it after waiting on the [`Barrier`] once it's been written.
+///
+/// The value is stored in atomic words, which the barrier orders, so passing it doesn't require locking, and slots are only allocated once, along with the simulator that owns them.
+pub struct Slot<T: SlotValue> {
+    words: T::Words,
+}
+
+impl<T: SlotValue> Slot<T> {
+    /// Creates a slot holding zero (or `false`).
+    pub fn new() -> Slot<T> {
+        Slot {
+            words: T::new_words(),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        T::load(&self.words)
+    }
+
+    pub fn set(&self, value: T) {
+        value.store(&self.words)
+    }
+}
+
+impl<T: SlotValue> Default for Slot<T> {
+    fn default() -> Slot<T> {
+        Slot::new()
+    }
+}
+
+/// The types of values that can be passed between jobs in a [`Slot`].
+pub trait SlotValue: Copy {
+    type Words;
+
+    fn new_words() -> Self::Words;
+    fn load(words: &Self::Words) -> Self;
+    fn store(self, words: &Self::Words);
+}
+
+impl SlotValue for bool {
+    type Words = AtomicU64;
+
+    fn new_words() -> AtomicU64 {
+        AtomicU64::new(0)
+    }
+
+    fn load(words: &AtomicU64) -> bool {
+        words.load(Ordering::Relaxed) != 0
+    }
+
+    fn store(self, words: &AtomicU64) {
+        words.store(self as u64, Ordering::Relaxed)
+    }
+}
+
+impl SlotValue for u32 {
+    type Words = AtomicU64;
+
+    fn new_words() -> AtomicU64 {
+        AtomicU64::new(0)
+    }
+
+    fn load(words: &AtomicU64) -> u32 {
+        words.load(Ordering::Relaxed) as u32
+    }
+
+    fn store(self, words: &AtomicU64) {
+        words.store(self as u64, Ordering::Relaxed)
+    }
+}
+
+impl SlotValue for u64 {
+    type Words = AtomicU64;
+
+    fn new_words() -> AtomicU64 {
+        AtomicU64::new(0)
+    }
+
+    fn load(words: &AtomicU64) -> u64 {
+        words.load(Ordering::Relaxed)
+    }
+
+    fn store(self, words: &AtomicU64) {
+        words.store(self, Ordering::Relaxed)
+    }
+}
+
+impl SlotValue for u128 {
+    type Words = [AtomicU64; 2];
+
+    fn new_words() -> [AtomicU64; 2] {
+        [AtomicU64::new(0), AtomicU64::new(0)]
+    }
+
+    fn load(words: &[AtomicU64; 2]) -> u128 {
+        (words[0].load(Ordering::Relaxed) as u128)
+            | ((words[1].load(Ordering::Relaxed) as u128) << 64)
+    }
+
+    fn store(self, words: &[AtomicU64; 2]) {
+        words[0].store(self as u64, Ordering::Relaxed);
+        words[1].store((self >> 64) as u64, Ordering::Relaxed);
+    }
+}
+
+impl<const N: usize> SlotValue for [u64; N] {
+    type Words = [AtomicU64; N];
+
+    fn new_words() -> [AtomicU64; N] {
+        [0; N].map(AtomicU64::new)
+    }
+
+    fn load(words: &[AtomicU64; N]) -> [u64; N] {
+        let mut value = [0; N];
+        for (word, slot_word) in value.iter_mut().zip(words.iter()) {
+            *word = slot_word.load(Ordering::Relaxed);
+        }
+        value
+    }
+
+    fn store(self, words: &[AtomicU64; N]) {
+        for (&word, slot_word) in self.iter().zip(words.iter()) {
+            slot_word.store(word, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn runs_each_job_once() {
+        let mut workers = Workers::new(3);
+        let counts = (0..4).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+        for _ in 0..100 {
+            let mut jobs = counts
+                .iter()
+                .map(|count| {
+                    move |_: &Barrier| {
+                        count.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+                .collect::<Vec<_>>();
+            let mut jobs = jobs
+                .iter_mut()
+                .map(|job| job as &mut Job)
+                .collect::<Vec<_>>();
+            workers.run(&mut jobs);
+        }
+        for count in counts.iter() {
+            assert_eq!(count.load(Ordering::Relaxed), 100);
+        }
+    }
+
+    #[test]
+    fn runs_fewer_jobs_than_threads() {
+        let mut workers = Workers::new(3);
+        let mut a = 0;
+        let mut b = 0;
+        workers.run(&mut [&mut |_: &Barrier| a += 1, &mut |_: &Barrier| b += 2]);
+        assert_eq!((a, b), (1, 2));
+    }
+
+    #[test]
+    fn barrier_orders_steps() {
+        let mut workers = Workers::new(3);
+        let value = Slot::<u128>::new();
+        let mut read = 0;
+        for index in 0..100 {
+            // The barrier only waits for the jobs that are run, rather than every thread
+            workers.run(&mut [
+                &mut |barrier: &Barrier| {
+                    value.set(index << 64 | index);
+                    assert!(barrier.wait());
+                },
+                &mut |barrier: &Barrier| {
+                    assert!(barrier.wait());
+                    read += value.get();
+                },
+            ]);
+        }
+        assert_eq!(read, (0..100).map(|index| index << 64 | index).sum());
+    }
+
+    #[test]
+    fn panics_are_resumed_and_poison_barrier() {
+        let mut workers = Workers::new(1);
+        let mut waited = None;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            workers.run(&mut [
+                &mut |barrier: &Barrier| waited = Some(barrier.wait()),
+                &mut |_: &Barrier| panic!("job"),
+            ]);
+        }));
+        assert_eq!(*result.unwrap_err().downcast::<&str>().unwrap(), "job");
+        assert_eq!(waited, Some(false));
+
+        // The pool is still usable afterwards
+        let mut waited_a = false;
+        let mut waited_b = false;
+        workers.run(&mut [
+            &mut |barrier: &Barrier| waited_a = barrier.wait(),
+            &mut |barrier: &Barrier| waited_b = barrier.wait(),
+        ]);
+        assert!(waited_a && waited_b);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't run 3 jobs with 1 threads")]
+    fn too_many_jobs() {
+        let mut workers = Workers::new(1);
+        workers.run(&mut [
+            &mut |_: &Barrier| (),
+            &mut |_: &Barrier| (),
+            &mut |_: &Barrier| (),
+        ]);
+    }
+
+    #[test]
+    fn slots_hold_values() {
+        let slot = Slot::<bool>::new();
+        assert!(!slot.get());
+        slot.set(true);
+        assert!(slot.get());
+
+        let slot = Slot::<u32>::new();
+        slot.set(0xdeadbeef);
+        assert_eq!(slot.get(), 0xdeadbeef);
+
+        let slot = Slot::<u64>::new();
+        slot.set(0xfedcba9876543210);
+        assert_eq!(slot.get(), 0xfedcba9876543210);
+
+        let slot = Slot::<u128>::new();
+        slot.set(0x0123456789abcdeffedcba9876543210);
+        assert_eq!(slot.get(), 0x0123456789abcdeffedcba9876543210);
+
+        let slot = Slot::<[u64; 3]>::new();
+        assert_eq!(slot.get(), [0; 3]);
+        slot.set([1, 2, 3]);
+        assert_eq!(slot.get(), [1, 2, 3]);
+    }
+}
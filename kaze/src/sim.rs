@@ -1,649 +1,1688 @@
 //! Rust simulator code generation.
 
+mod activity;
+mod chunks;
+mod clock_domains;
 mod compiler;
+mod four_state;
 mod ir;
+mod lanes;
+mod partition;
+mod random_state;
+mod snapshot;
 mod state_elements;
+mod trace;
 
+use activity::*;
+use clock_domains::*;
 use compiler::*;
+use four_state::UnknownMember;
 use ir::*;
+use lanes::*;
+use partition::PropPartitions;
+use snapshot::*;
 use state_elements::*;
+use trace::*;
 
 use typed_arena::Arena;
 
 use crate::code_writer;
+use crate::error::*;
 use crate::graph;
 use crate::module_context::*;
-use crate::runtime::tracing::*;
+use crate::reset::*;
+use crate::simplify::*;
 use crate::validation::*;
 
-use std::collections::HashMap;
-use std::io::{Result, Write};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 
-#[derive(Default)]
 pub struct GenerationOptions {
     pub tracing: bool,
+    pub reset_type: ResetType,
+    /// If set, `prop`'s body is split into helper functions of at most this many statements each, which `prop` calls in order. As `prop` otherwise contains the logic for `m`'s entire (flattened) hierarchy in a single function, this can greatly reduce the time it takes `rustc` to compile simulators for large designs, at the cost of some simulation performance.
+    pub prop_chunk_size: Option<usize>,
+    /// If `true`, `prop` only re-evaluates the logic that depends on inputs and state elements (registers, memories, and read ports) whose values changed since the previous call to `prop`, and leaves the values it computed previously intact otherwise. This can greatly speed up simulations of designs where most of the logic is idle most of the time, at the cost of some overhead for each call to `prop` (and for each clock edge), which can make simulations of very active designs slower.
+    pub activity_tracking: bool,
+    /// If set, `prop` evaluates `m`'s hierarchy in parallel, split into partitions at the instances this many levels below `m` (e.g. `Some(1)` places each of `m`'s instances and everything within it in its own partition), with one more partition for everything outside of these instances. Each partition is evaluated on its own thread (the simulator creates these threads once, when it's constructed), and `prop` returns once all of them have been evaluated, so clock edges are only ever applied between evaluations. Clock edges likewise update each partition's registers and memories on its own thread. Logic that's shared between partitions is evaluated by only one of them, and the others wait for its result, so `prop` is evaluated in stages, with partitions synchronizing between each of them. This can speed up simulations of designs with several large, loosely coupled instances (e.g. cores), though the overhead of synchronizing threads on each call to `prop` outweighs the benefit for small designs or tightly coupled partitions. This can't be combined with `prop_chunk_size` or `activity_tracking`.
+    pub partition_depth: Option<u32>,
+    /// If set, the generated simulator holds this many independent copies ("lanes") of `m`'s state, stored as arrays with one element per lane (so each input, output, and register is an array, and each memory is a `Vec` of arrays of its elements), and each call to `prop` (or to a clock domain's `posedge_*`/`reset_*`) advances all of them at once. Each accessor method (e.g. `peek_*`, `poke_*`, `load_mem_*`, `dump_mem_*`, `signal`, `set_signal`, and `cover_count`) takes the index of the lane it applies to as its first argument. This is useful for running many independent tests (e.g. with different stimuli) against the same design, as the lanes share a single simulator and are evaluated in tight loops that `rustc` can vectorize. If `tracing` is `true`, only lane 0 is traced. This can't be combined with `activity_tracking` or `partition_depth`.
+    pub lanes: Option<usize>,
+    /// If `true`, the generated simulator tracks which bits of each value are unknown (X), propagating them through logic according to Verilog's rules (e.g. an AND with a known 0 bit is known, but an addition with any unknown input bits is entirely unknown). Registers without a [`default_value`](crate::Register::default_value) and [`Mem`](crate::Mem)s without [`initial_contents`](crate::Mem::initial_contents) start out unknown, and become known once they're written. Like in Verilog, memory writes with unknown enables or addresses are ignored, and [`DontCare`](crate::ReadDuringWrite::DontCare) read ports read entirely unknown values from locations written in the same cycle (without `four_state`, they read the new value instead). The unknown bits of values can be read with the generated `signal_unknown` and `peek_mem_unknown_*` methods (which mirror `signal` and `peek_mem_*`), and set for inputs and registers with `set_signal_unknown`; writing a value in any other way (e.g. with `poke_reg_*`) makes it known. The values of unknown bits are unspecified. Logic is only simplified in ways that keep unknown bits unknown, so e.g. `x ^ x` and `x * 0` are unknown if `x` has any unknown bits, like in Verilog. This can't be combined with `activity_tracking`.
+    pub four_state: bool,
+    /// If `true` (and `four_state` is `true`), `prop` panics if any bits of `m`'s outputs are unknown.
+    pub assert_known_outputs: bool,
+    /// If `true`, registers without a [`default_value`](crate::Register::default_value) and [`Mem`](crate::Mem)s without [`initial_contents`](crate::Mem::initial_contents) are initialized with pseudorandom values instead of zero, so that running the same test with several seeds can expose logic that depends on uninitialized state. The generated simulator gets a `new_with_seed` constructor, which takes the seed as an additional `u64` argument, and `new` is equivalent to `new_with_seed` with a seed of `0`. Each lane (see `lanes`) is initialized with different values. With `four_state`, this state is still considered unknown; only the values of its unknown bits are randomized.
+    pub random_initial_state: bool,
+    /// Selects which of the signals in `m`'s hierarchy are traced when `tracing` is `true` (by default, all of them are).
+    pub trace_filter: TraceFilter,
+    /// If `true` (the default), the generated simulator includes every register and [`Mem`](crate::Mem) in `m`'s hierarchy, even those that don't affect any of `m`'s outputs or properties, and stores the value of every instance output in `m`'s hierarchy on each call to `prop`, so that all of them can be accessed with the generated accessor methods (see [`generate`]), regardless of how the hierarchy's logic is connected. If `false`, registers and memories that don't affect `m`'s outputs or properties are left out of the generated simulator (along with their accessors), and instance outputs aren't accessible, which avoids the overhead of simulating dead state and storing instance outputs.
+    pub full_hierarchy_access: bool,
+    /// If `true`, logic isn't simplified (by folding constants, eliminating identity and absorbing elements, and merging common subexpressions) before code generation, so the generated code mirrors the module graph exactly. This is mostly useful for comparing against simplified output when debugging.
+    pub disable_simplification: bool,
 }
 
+impl Default for GenerationOptions {
+    fn default() -> GenerationOptions {
+        GenerationOptions {
+            tracing: false,
+            reset_type: ResetType::default(),
+            prop_chunk_size: None,
+            activity_tracking: false,
+            partition_depth: None,
+            lanes: None,
+            four_state: false,
+            assert_known_outputs: false,
+            random_initial_state: false,
+            trace_filter: TraceFilter::default(),
+            full_hierarchy_access: true,
+            disable_simplification: false,
+        }
+    }
+}
+
+/// Selects signals to be traced by hierarchical path and depth, where each signal's path is its name prefixed by the names of the instances leading to it, separated by `.` (e.g. `cpu.alu.r`), and `m`'s own inputs and outputs are referred to by name.
+///
+/// Patterns are globs, where `*` matches any sequence of characters (including `.`), `?` matches any single character, and all other characters match themselves (e.g. `cpu.*` matches every signal within `cpu`, and `*.r` matches every signal called `r` within any instance).
+///
+/// Instances that are deeper than `max_depth` are left out of the trace entirely, and instances whose signals are all filtered out appear as empty scopes.
+#[derive(Clone, Debug, Default)]
+pub struct TraceFilter {
+    /// If non-empty, only signals whose paths match at least one of these patterns are traced.
+    pub include: Vec<String>,
+    /// Signals whose paths match any of these patterns aren't traced, even if they match `include`.
+    pub exclude: Vec<String>,
+    /// If set, only signals at most this many instances below `m` are traced (e.g. `Some(0)` traces only `m`'s own inputs, outputs, and registers).
+    pub max_depth: Option<u32>,
+}
+
+impl TraceFilter {
+    fn includes_scope(&self, context: &ModuleContext) -> bool {
+        match self.max_depth {
+            Some(max_depth) => context.depth() <= max_depth,
+            _ => true,
+        }
+    }
+
+    fn includes_signal(&self, context: &ModuleContext, name: &str) -> bool {
+        if !self.includes_scope(context) {
+            return false;
+        }
+        let path = context.relative_path(name);
+        (self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| glob_matches(pattern, &path)))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| glob_matches(pattern, &path))
+    }
+}
+
+// Returns whether `s` matches the glob `pattern`, where `*` matches any sequence of characters
+//  and `?` matches any single character.
+fn glob_matches(pattern: &str, s: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let s = s.chars().collect::<Vec<_>>();
+    let (mut p, mut i) = (0, 0);
+    // Position of the last `*` in `pattern` and the position in `s` it's currently matched up to,
+    //  so that it can be made to match one more character if the rest of the pattern fails to match
+    let mut star = None;
+    while i < s.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == s[i]) {
+            p += 1;
+            i += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, i));
+            p += 1;
+        } else if let Some((star_p, star_i)) = star {
+            star = Some((star_p, star_i + 1));
+            p = star_p + 1;
+            i = star_i + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Generates a Rust simulator for `m` and writes it to `w`.
+///
+/// The generated struct has a public member for each of `m`'s inputs and outputs, and `prop`, `reset`, and `posedge_clk` methods (the latter two for each clock domain) for driving the simulation.
+///
+/// Every [`Mem`](crate::Mem) in `m`'s hierarchy that affects `m`'s outputs or properties (or every one, if [`GenerationOptions::full_hierarchy_access`] is `true`, as it is by default) can also be accessed from the generated struct through the following methods, where `<name>` is the memory's name prefixed with the names of the instances leading to it, separated by `__` (e.g. `cpu__imem` for a memory called `imem` in an instance called `cpu`):
+///
+/// - `peek_mem_<name>(&self, address) -> element` returns the element at `address`.
+/// - `poke_mem_<name>(&mut self, address, element)` replaces the element at `address`.
+/// - `load_mem_<name>(&mut self, address, &[element])` replaces the elements starting at `address` (e.g. to preload a program).
+/// - `dump_mem_<name>(&self) -> &[element]` returns all of the memory's elements.
+///
+/// These methods take and return values of the same types as the generated struct's members of the same bit widths, and panic if the accessed elements are out of range.
+/// Values written with these methods are expected to fit into the memory's element bit width.
+/// Changes to memory contents are reflected by asynchronous read ports on the next call to `prop`, and by (synchronous) read ports on the next clock edge.
+///
+/// Registers in `m`'s hierarchy (and instance outputs, if [`GenerationOptions::full_hierarchy_access`] is `true`, as it is by default) are similarly accessible, with `<name>` formed the same way:
+///
+/// - `peek_reg_<name>(&self) -> value` returns the register's current value.
+/// - `poke_reg_<name>(&mut self, value)` replaces the register's current value. The new value is reflected by signals that depend on it on the next call to `prop`, and is replaced by the register's next value on the next clock edge.
+/// - `peek_output_<name>(&self) -> value` returns the value of an instance's output as of the last call to `prop` (`m`'s own outputs are the generated struct's public members).
+///
+/// They can also be looked up by hierarchical path, where each path is a name prefixed by the names of the instances leading to it, separated by `.` (e.g. `cpu.pc`), and `m`'s own outputs are referred to by name:
+///
+/// - `signal(&self, path: &str) -> Option<u128>` returns the value of the register or output at `path`, or `None` if there isn't one, or if its bit width is greater than 128. If a register and an output share a path, the register's value is returned.
+/// - `set_signal(&mut self, path: &str, value: u128) -> bool` replaces the value of the register at `path` like `poke_reg_<name>`, truncating `value` to the register's bit width, and returns `false` if there isn't one (or if its bit width is greater than 128).
+///
+/// Every [`Property`](crate::Property) in `m`'s hierarchy is checked on each positive edge of its clock domain's clock (except when a synchronous reset is applied on that edge), using the values computed by the preceding call to `prop`. A failing assertion or assumption causes a panic whose message includes the property's hierarchical path (formed like the paths above) and the number of preceding positive edges of its clock domain's clock, e.g. `Assertion "fifo.not_overflow" failed in cycle 42`. The number of positive edges in which a cover's condition held is returned by `cover_count(&self, path: &str) -> Option<u64>`, which returns `None` if there's no cover at `path` (and is only generated if there are any covers in `m`'s hierarchy).
+///
+/// The simulator's entire state (including its inputs and outputs, and the state of every instance in `m`'s hierarchy, but not its tracer) can be captured and restored, e.g. to checkpoint a simulation and fork several simulations from the checkpoint:
+///
+/// - `snapshot(&self) -> <module>State` returns a copy of the simulator's current state, where `<module>State` is a generated struct that implements `Clone`.
+/// - `restore(&mut self, state: &<module>State)` replaces the simulator's current state with `state`.
+///
+/// Snapshots can also be serialized (e.g. to save them to files) with `<module>State::to_bytes(&self) -> Vec<u8>`, and deserialized with `<module>State::from_bytes(bytes: &[u8]) -> std::io::Result<<module>State>`, which returns an error if `bytes` isn't a snapshot of a simulator with the same state (see [`runtime::snapshot`](crate::runtime::snapshot) for the format).
+///
+/// If [`GenerationOptions::tracing`] is `true`, the generated struct's constructor takes an instance name and a [`Trace`](crate::runtime::tracing::Trace), and `update_trace(&mut self, time_stamp: u64)` records the current values of the traced signals (see [`GenerationOptions::trace_filter`]). What's recorded can also be changed at runtime:
+///
+/// - `set_trace_scope_enabled(&mut self, scope: &str, enabled: bool) -> bool` enables or disables recording the signals in the instance at `scope` (formed like the paths above, e.g. `cpu.alu`, with `""` referring to `m` itself) and every instance within it, and returns `false` if there's no such scope in the trace. Signals whose recording is disabled keep their last recorded values in the trace.
+/// - `set_trace_window(&mut self, start: u64, end: Option<u64>)` limits recording to calls to `update_trace` with time stamps in `start..end` (or `start..` if `end` is `None`), and ignores calls with any other time stamps.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if `m`'s hierarchy fails validation, if `options` combines options that can't be used together (see [`GenerationOptions`]), if two accessor methods would have the same name (which is only possible if some names start or end with `_` or contain `__`), if `<module>State` is also the name of a module in `m`'s [`Context`](crate::Context), or if writing to `w` fails.
 // TODO: Note that mutable writer reference can be passed, see https://rust-lang.github.io/api-guidelines/interoperability.html#c-rw-value
 pub fn generate<'a, W: Write>(
     m: &'a graph::Module<'a>,
     options: GenerationOptions,
     w: W,
-) -> Result<()> {
-    validate_module_hierarchy(m);
+) -> Result<(), Error> {
+    generate_simulator(m, options, w, None)
+}
+
+/// Like [`generate`], but writes the simulator to a `<module name>.rs` file in `dir`, which is the only file that needs to be included by the crate using the simulator.
+///
+/// If [`GenerationOptions::prop_chunk_size`] is set, each of `prop`'s helper functions is written to its own `<module name>_prop_chunk_<n>.rs` file in `dir` instead, which `<module name>.rs` includes by relative path, so the files must be kept together.
+pub fn generate_files<'a, P: AsRef<Path>>(
+    m: &'a graph::Module<'a>,
+    options: GenerationOptions,
+    dir: P,
+) -> Result<(), Error> {
+    let dir = dir.as_ref();
+    let mut file = BufWriter::new(File::create(dir.join(format!("{}.rs", m.name)))?);
+    generate_simulator(m, options, &mut file, Some(dir))?;
+    file.flush()?;
+
+    Ok(())
+}
+
+// Generates a simulator for `m`, writing `prop`'s helper functions (if any) to separate files in
+//  `prop_chunk_dir` if it's specified, or to `w` otherwise.
+fn generate_simulator<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: GenerationOptions,
+    w: W,
+    prop_chunk_dir: Option<&Path>,
+) -> Result<(), Error> {
+    validate_module_hierarchy(m)?;
+
+    check_generation_options(m, &options)?;
+
+    let lanes = Lanes::new(options.lanes);
 
     let context_arena = Arena::new();
     let root_context = context_arena.alloc(ModuleContext::new());
 
     let mut state_elements = StateElements::new();
     let mut signal_reference_counts = HashMap::new();
-    for (_, output) in m.outputs.borrow().iter() {
-        state_elements.gather(
-            &output,
-            root_context,
-            &context_arena,
-            &mut signal_reference_counts,
-        );
-    }
+    let simplifier = gather_state_elements(
+        m,
+        &options,
+        root_context,
+        &context_arena,
+        &mut state_elements,
+        &mut signal_reference_counts,
+    );
 
-    struct TraceSignal {
-        name: String,
-        member_name: String,
-        value_name: String,
-        bit_width: u32,
-        type_: TraceValueType,
+    check_accessor_names(m, &state_elements, options.four_state)?;
+    let state_name = format!("{}State", m.name);
+    if m.context().modules().contains_key(&state_name) {
+        return Err(Error::StateTypeNameConflict {
+            root: m.name.clone(),
+            state: state_name,
+        });
     }
-    let mut trace_signals: HashMap<&ModuleContext, Vec<TraceSignal>> = HashMap::new();
-    let mut num_trace_signals = 0;
-    let mut add_trace_signal = |context, name, value_name, bit_width| {
-        if options.tracing {
-            let member_name = format!("__trace_signal_id_{}_{}", name, num_trace_signals);
-            let context_trace_signals = trace_signals.entry(context).or_insert(Vec::new());
-            context_trace_signals.push(TraceSignal {
-                name,
-                member_name,
-                value_name,
-                bit_width,
-                type_: TraceValueType::from_bit_width(bit_width),
-            });
-            num_trace_signals += 1;
-        }
+
+    let mut trace_signals = TraceSignals::new(options.tracing, &options.trace_filter);
+    trace_signals.gather(m, &state_elements, root_context);
+
+    let prop_roots = gather_prop_roots(m, &state_elements, root_context, options.four_state);
+    let unknown_members = if options.four_state {
+        four_state::gather_unknown_members(m, &state_elements, &prop_roots)
+    } else {
+        Vec::new()
     };
 
     let expr_arena = Arena::new();
-    let mut prop_context = AssignmentContext::new(&expr_arena);
     let mut c = Compiler::new(
         &state_elements,
         &signal_reference_counts,
+        simplifier,
         &context_arena,
         &expr_arena,
     );
-    for (name, input) in m.inputs.borrow().iter() {
-        add_trace_signal(root_context, name.clone(), name.clone(), input.bit_width());
-    }
-    for (name, output) in m.outputs.borrow().iter() {
-        let expr = c.compile_signal(&output, root_context, &mut prop_context);
-        prop_context.push(Assignment {
-            target: expr_arena.alloc(Expr::Ref {
-                name: name.clone(),
-                scope: Scope::Member,
-            }),
-            expr,
-        });
 
-        add_trace_signal(root_context, name.clone(), name.clone(), output.bit_width());
+    let mut prop_context = AssignmentContext::new(&expr_arena);
+    let prop_partitions = options.partition_depth.and_then(|partition_depth| {
+        partition::partition_prop_roots(
+            &mut c,
+            &prop_roots,
+            partition_depth,
+            options.four_state,
+            &expr_arena,
+        )
+    });
+    if prop_partitions.is_none() {
+        for root in prop_roots.iter() {
+            compile_prop_root(&mut c, root, Scope::Member, &expr_arena, &mut prop_context);
+        }
     }
-    for ((context, _), mem) in state_elements.mems.iter() {
-        for ((address, enable), read_signal_names) in mem.read_signal_names.iter() {
-            let address = c.compile_signal(address, context, &mut prop_context);
-            prop_context.push(Assignment {
-                target: expr_arena.alloc(Expr::Ref {
-                    name: read_signal_names.address_name.clone(),
-                    scope: Scope::Member,
-                }),
-                expr: address,
-            });
-            let enable = c.compile_signal(enable, context, &mut prop_context);
-            prop_context.push(Assignment {
-                target: expr_arena.alloc(Expr::Ref {
-                    name: read_signal_names.enable_name.clone(),
-                    scope: Scope::Member,
-                }),
-                expr: enable,
-            });
 
-            add_trace_signal(
-                context,
-                read_signal_names.address_name.clone(),
-                read_signal_names.address_name.clone(),
+    let prop_chunks = if options.prop_chunk_size.is_some() || options.activity_tracking {
+        Some(prop_context.chunks(options.prop_chunk_size, options.activity_tracking))
+    } else {
+        None
+    };
+    let prop_temps = chunks::prop_temps(&prop_context, prop_chunks.as_deref().unwrap_or(&[]));
+
+    let mut tracked_values = TrackedValues::new();
+    if let Some(ref prop_chunks) = prop_chunks {
+        if options.activity_tracking {
+            tracked_values.gather(m, &state_elements, prop_chunks);
+        }
+    }
+
+    let clock_domains = clock_domains::gen_clock_domains(
+        m,
+        &mut c,
+        &state_elements,
+        prop_partitions.as_ref(),
+        &tracked_values,
+        &options,
+        &expr_arena,
+    );
+
+    trace_signals.gather_scopes(root_context);
+
+    let sim = Simulator {
+        m,
+        options: &options,
+        lanes,
+        root_context,
+        state_elements: &state_elements,
+        unknown_members: &unknown_members,
+        prop_context: &prop_context,
+        prop_partitions: prop_partitions.as_ref(),
+        prop_chunks: prop_chunks.as_deref(),
+        prop_temps: &prop_temps,
+        tracked_values: &tracked_values,
+        clock_domains: &clock_domains,
+        trace_signals: &trace_signals,
+    };
+
+    let mut w = code_writer::CodeWriter::new(w);
+
+    let mut state_members = Vec::new();
+    sim.write_struct(&mut state_members, &mut w)?;
+
+    snapshot::write_state_type(
+        &state_name,
+        &mut state_members,
+        lanes,
+        options.four_state,
+        &mut w,
+    )?;
+
+    write_impl_header(m, &options, &mut w)?;
+
+    sim.write_new(&mut w)?;
+
+    for clock_domain in clock_domains.iter() {
+        clock_domain.write_methods(lanes, &mut w)?;
+    }
+
+    sim.write_prop(&mut w)?;
+
+    sim.write_accessors(&mut w)?;
+
+    snapshot::write_methods(
+        &state_name,
+        &state_members,
+        lanes,
+        options.activity_tracking,
+        &mut w,
+    )?;
+
+    if options.tracing {
+        trace_signals.write_methods(lanes, &mut w)?;
+    }
+
+    w.unindent();
+    w.append_line("}")?;
+    w.append_newline()?;
+
+    if let Some(ref prop_chunks) = prop_chunks {
+        if options.prop_chunk_size.is_some() {
+            chunks::write_prop_chunks(m, &options, prop_chunks, prop_chunk_dir, &mut w)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Gathers the state elements (and the number of references to each signal) that the outputs of `m`
+//  depend on, along with the rest of its hierarchy if `options.full_hierarchy_access` is `true`,
+//  and returns the simplifier that's used to gather them, which the compiler has to use as well.
+fn gather_state_elements<'graph, 'arena>(
+    m: &'graph graph::Module<'graph>,
+    options: &GenerationOptions,
+    root_context: &'arena ModuleContext<'graph, 'arena>,
+    context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+    state_elements: &mut StateElements<'graph, 'arena>,
+    signal_reference_counts: &mut SignalReferenceCounts<'graph, 'arena>,
+) -> Simplifier<'graph> {
+    let mut simplifier = Simplifier::new(
+        m.context(),
+        !options.disable_simplification,
+        options.four_state,
+    );
+    for (_, output) in m.outputs.borrow().iter() {
+        state_elements.gather(
+            output,
+            root_context,
+            context_arena,
+            signal_reference_counts,
+            &mut simplifier,
+        );
+    }
+    for (signal, context) in state_elements.gather_hierarchy(
+        m,
+        root_context,
+        context_arena,
+        options.full_hierarchy_access,
+    ) {
+        state_elements.gather(
+            signal,
+            context,
+            context_arena,
+            signal_reference_counts,
+            &mut simplifier,
+        );
+    }
+
+    simplifier
+}
+
+// Returns every value computed by `prop` (including their unknown bits, with four-state
+//  simulation), each of which is assigned to a member.
+fn gather_prop_roots<'graph, 'arena>(
+    m: &'graph graph::Module<'graph>,
+    state_elements: &StateElements<'graph, 'arena>,
+    root_context: &'arena ModuleContext<'graph, 'arena>,
+    four_state: bool,
+) -> Vec<PropRoot<'graph, 'arena>> {
+    let mut prop_roots = Vec::new();
+
+    for (name, &output) in m.outputs.borrow().iter() {
+        prop_roots.push(PropRoot::new(
+            name,
+            output.bit_width(),
+            output,
+            root_context,
+        ));
+    }
+    for ((context, _), mem) in state_elements.mems.iter() {
+        for (read_port, read_signal_names) in mem.read_signal_names.iter() {
+            prop_roots.push(PropRoot::new(
+                &read_signal_names.address_name,
                 mem.mem.address_bit_width,
-            );
-            add_trace_signal(
+                read_port.address,
                 context,
-                read_signal_names.enable_name.clone(),
-                read_signal_names.enable_name.clone(),
+            ));
+            prop_roots.push(PropRoot::new(
+                &read_signal_names.enable_name,
                 1,
-            );
-        }
-        if let Some((address, value, enable)) = *mem.mem.write_port.borrow() {
-            let address = c.compile_signal(address, context, &mut prop_context);
-            prop_context.push(Assignment {
-                target: expr_arena.alloc(Expr::Ref {
-                    name: mem.write_address_name.clone(),
-                    scope: Scope::Member,
-                }),
-                expr: address,
-            });
-            let value = c.compile_signal(value, context, &mut prop_context);
-            prop_context.push(Assignment {
-                target: expr_arena.alloc(Expr::Ref {
-                    name: mem.write_value_name.clone(),
-                    scope: Scope::Member,
-                }),
-                expr: value,
-            });
-            let enable = c.compile_signal(enable, context, &mut prop_context);
-            prop_context.push(Assignment {
-                target: expr_arena.alloc(Expr::Ref {
-                    name: mem.write_enable_name.clone(),
-                    scope: Scope::Member,
-                }),
-                expr: enable,
-            });
-
-            add_trace_signal(
+                read_port.enable,
                 context,
-                mem.write_address_name.clone(),
-                mem.write_address_name.clone(),
+            ));
+        }
+        for (write_port, write_signal_names) in mem
+            .mem
+            .write_ports
+            .borrow()
+            .iter()
+            .zip(mem.write_signal_names.iter())
+        {
+            prop_roots.push(PropRoot::new(
+                &write_signal_names.address_name,
                 mem.mem.address_bit_width,
-            );
-            add_trace_signal(
+                write_port.address,
                 context,
-                mem.write_value_name.clone(),
-                mem.write_value_name.clone(),
+            ));
+            prop_roots.push(PropRoot::new(
+                &write_signal_names.value_name,
                 mem.mem.element_bit_width,
-            );
-            add_trace_signal(
+                write_port.value,
                 context,
-                mem.write_enable_name.clone(),
-                mem.write_enable_name.clone(),
+            ));
+            prop_roots.push(PropRoot::new(
+                &write_signal_names.enable_name,
                 1,
-            );
+                write_port.enable,
+                context,
+            ));
+            if let Some(mask) = write_port.mask {
+                prop_roots.push(PropRoot {
+                    mask_granularity: Some(mask.granularity),
+                    ..PropRoot::new(
+                        &write_signal_names.mask_name,
+                        mem.mem.element_bit_width,
+                        mask.mask,
+                        context,
+                    )
+                });
+            }
         }
     }
     for ((context, _), reg) in state_elements.regs.iter() {
-        let signal = reg.data.next.borrow().unwrap();
-        let expr = c.compile_signal(signal, context, &mut prop_context);
-        prop_context.push(Assignment {
-            target: expr_arena.alloc(Expr::Ref {
-                name: reg.next_name.clone(),
-                scope: Scope::Member,
-            }),
-            expr,
-        });
-
-        add_trace_signal(
+        prop_roots.push(PropRoot::new(
+            &reg.next_name,
+            reg.data.bit_width,
+            reg.data.next.borrow().unwrap(),
             context,
-            reg.data.name.clone(),
-            reg.value_name.clone(),
-            signal.bit_width(),
-        );
+        ));
+    }
+    for instance_output in state_elements.instance_outputs.iter() {
+        prop_roots.push(PropRoot::new(
+            &instance_output.value_name,
+            instance_output.signal.bit_width(),
+            instance_output.signal,
+            instance_output.context,
+        ));
+    }
+    if four_state {
+        let unknown_roots = four_state::unknown_roots(&prop_roots);
+        prop_roots.extend(unknown_roots);
+    }
+    for property in state_elements.properties.iter() {
+        prop_roots.push(PropRoot::new(
+            &property.value_name,
+            1,
+            property.property.condition,
+            property.context,
+        ));
     }
 
-    let mut w = code_writer::CodeWriter::new(w);
+    prop_roots
+}
 
-    w.append_indent()?;
-    w.append(&format!("pub struct {}", m.name))?;
-    if options.tracing {
-        w.append("<T: kaze::runtime::tracing::Trace>")?;
-    }
-    w.append("{")?;
-    w.append_newline()?;
-    w.indent();
+// Everything that's been gathered and compiled for a simulator, from which its code is written
+struct Simulator<'a, 'graph, 'arena, 'expr_arena> {
+    m: &'graph graph::Module<'graph>,
+    options: &'a GenerationOptions,
+    lanes: Lanes,
+    root_context: &'arena ModuleContext<'graph, 'arena>,
+    state_elements: &'a StateElements<'graph, 'arena>,
+    unknown_members: &'a [UnknownMember],
+    prop_context: &'a AssignmentContext<'expr_arena>,
+    prop_partitions: Option<&'a PropPartitions<'graph, 'arena, 'expr_arena>>,
+    prop_chunks: Option<&'a [AssignmentChunk<'a, 'expr_arena>]>,
+    prop_temps: &'a [(&'a str, ValueType)],
+    tracked_values: &'a TrackedValues<'a>,
+    clock_domains: &'a [ClockDomainLogic<'a, 'graph, 'arena, 'expr_arena>],
+    trace_signals: &'a TraceSignals<'graph, 'arena, 'a>,
+}
 
-    let inputs = m.inputs.borrow();
-    if !inputs.is_empty() {
-        w.append_line("// Inputs")?;
-        for (name, input) in inputs.iter() {
-            w.append_line(&format!(
-                "pub {}: {}, // {} bit(s)",
-                name,
-                ValueType::from_bit_width(input.bit_width()).name(),
-                input.bit_width()
-            ))?;
+impl<'a, 'graph, 'arena, 'expr_arena> Simulator<'a, 'graph, 'arena, 'expr_arena> {
+    // Writes the simulator's struct, and adds the members that are part of its state to
+    //  `state_members`.
+    fn write_struct<W: Write>(
+        &self,
+        state_members: &mut Vec<StateMember>,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> io::Result<()> {
+        let m = self.m;
+        let options = self.options;
+        let lanes = self.lanes;
+        let state_elements = self.state_elements;
+
+        if options.four_state {
+            four_state::write_struct_attributes(w)?;
         }
-    }
-
-    let outputs = m.outputs.borrow();
-    if !outputs.is_empty() {
-        w.append_line("// Outputs")?;
-        for (name, output) in outputs.iter() {
-            w.append_line(&format!(
-                "pub {}: {}, // {} bit(s)",
-                name,
-                ValueType::from_bit_width(output.bit_width()).name(),
-                output.bit_width()
-            ))?;
+        w.append_indent()?;
+        w.append(&format!("pub struct {}", m.name))?;
+        if options.tracing {
+            w.append("<T: kaze::runtime::tracing::Trace>")?;
         }
-    }
-
-    if !state_elements.regs.is_empty() {
+        w.append("{")?;
         w.append_newline()?;
-        w.append_line("// Regs")?;
-        for (_, reg) in state_elements.regs.iter() {
-            let type_name = ValueType::from_bit_width(reg.data.bit_width).name();
-            w.append_line(&format!(
-                "{}: {}, // {} bit(s)",
-                reg.value_name, type_name, reg.data.bit_width
-            ))?;
-            w.append_line(&format!("{}: {},", reg.next_name, type_name))?;
+        w.indent();
+
+        let inputs = m.inputs.borrow();
+        if !inputs.is_empty() {
+            w.append_line("// Inputs")?;
+            for (name, input) in inputs.iter() {
+                let type_name = lanes.array(ValueType::from_bit_width(input.bit_width()).name());
+                state_members.push(StateMember::new(name.clone(), type_name.clone()));
+                w.append_line(&format!(
+                    "pub {}: {}, // {} bit(s)",
+                    name,
+                    type_name,
+                    input.bit_width()
+                ))?;
+            }
         }
-    }
 
-    if !state_elements.mems.is_empty() {
-        w.append_newline()?;
-        w.append_line("// Mems")?;
-        for (_, mem) in state_elements.mems.iter() {
-            let address_type_name = ValueType::from_bit_width(mem.mem.address_bit_width).name();
-            let element_type_name = ValueType::from_bit_width(mem.mem.element_bit_width).name();
-            w.append_line(&format!(
-                "{}: Box<[{}]>, // {} bit elements",
-                mem.mem_name, element_type_name, mem.mem.element_bit_width
-            ))?;
-            for (_, read_signal_names) in mem.read_signal_names.iter() {
+        let outputs = m.outputs.borrow();
+        if !outputs.is_empty() {
+            w.append_line("// Outputs")?;
+            for (name, output) in outputs.iter() {
+                let type_name = lanes.array(ValueType::from_bit_width(output.bit_width()).name());
+                state_members.push(StateMember::new(name.clone(), type_name.clone()));
                 w.append_line(&format!(
-                    "{}: {},",
-                    read_signal_names.address_name, address_type_name
+                    "pub {}: {}, // {} bit(s)",
+                    name,
+                    type_name,
+                    output.bit_width()
                 ))?;
+            }
+        }
+
+        if !state_elements.regs.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Regs")?;
+            for (_, reg) in state_elements.regs.iter() {
+                let type_name = lanes.array(ValueType::from_bit_width(reg.data.bit_width).name());
+                state_members.push(StateMember::new(reg.value_name.clone(), type_name.clone()));
+                state_members.push(StateMember::new(reg.next_name.clone(), type_name.clone()));
                 w.append_line(&format!(
-                    "{}: {},",
-                    read_signal_names.enable_name,
-                    ValueType::Bool.name()
+                    "{}: {}, // {} bit(s)",
+                    reg.value_name, type_name, reg.data.bit_width
                 ))?;
+                w.append_line(&format!("{}: {},", reg.next_name, type_name))?;
+            }
+        }
+
+        if !state_elements.instance_outputs.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Instance outputs")?;
+            for instance_output in state_elements.instance_outputs.iter() {
+                let bit_width = instance_output.signal.bit_width();
+                let type_name = lanes.array(ValueType::from_bit_width(bit_width).name());
+                state_members.push(StateMember::new(
+                    instance_output.value_name.clone(),
+                    type_name.clone(),
+                ));
                 w.append_line(&format!(
-                    "{}: {},",
-                    read_signal_names.value_name, element_type_name
+                    "{}: {}, // {} bit(s)",
+                    instance_output.value_name, type_name, bit_width
                 ))?;
             }
-            if mem.mem.write_port.borrow().is_some() {
+        }
+
+        if !state_elements.mems.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Mems")?;
+            for (_, mem) in state_elements.mems.iter() {
+                self.write_mem_members(mem, state_members, w)?;
+            }
+        }
+
+        if !state_elements.properties.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Properties")?;
+            for property in state_elements.properties.iter() {
+                state_members.push(StateMember::new(
+                    property.value_name.clone(),
+                    lanes.array(ValueType::Bool.name()),
+                ));
                 w.append_line(&format!(
                     "{}: {},",
-                    mem.write_address_name, address_type_name
+                    property.value_name,
+                    lanes.array(ValueType::Bool.name())
                 ))?;
-                w.append_line(&format!("{}: {},", mem.write_value_name, element_type_name))?;
+                if property.property.kind == graph::PropertyKind::Cover {
+                    let count_type_name = lanes.array("u64".into());
+                    state_members.push(StateMember::new(
+                        property.count_name.clone(),
+                        count_type_name.clone(),
+                    ));
+                    w.append_line(&format!("{}: {},", property.count_name, count_type_name))?;
+                }
+            }
+            for cycle_name in self.cycle_names() {
+                state_members.push(StateMember::new(cycle_name.clone(), "u64".into()));
+                w.append_line(&format!("{}: u64,", cycle_name))?;
+            }
+        }
+
+        if !self.reset_pending_names().is_empty() {
+            w.append_newline()?;
+            w.append_line("// Resets")?;
+            for reset_pending_name in self.reset_pending_names() {
+                state_members.push(StateMember::new(
+                    reset_pending_name.clone(),
+                    ValueType::Bool.name(),
+                ));
                 w.append_line(&format!(
                     "{}: {},",
-                    mem.write_enable_name,
+                    reset_pending_name,
                     ValueType::Bool.name()
                 ))?;
             }
         }
-    }
 
-    if options.tracing {
-        w.append_newline()?;
-        w.append_line("__trace: T,")?;
-        for context_trace_signals in trace_signals.values() {
-            for trace_signal in context_trace_signals.iter() {
-                w.append_line(&format!("{}: T::SignalId,", trace_signal.member_name))?;
-            }
+        if options.four_state {
+            four_state::write_members(
+                self.unknown_members,
+                state_elements,
+                lanes,
+                state_members,
+                w,
+            )?;
         }
-    }
-
-    w.unindent();
-    w.append_line("}")?;
-    w.append_newline()?;
-
-    w.append_line("#[allow(unused_parens)]")?;
-    w.append_line("#[automatically_derived]")?;
-    w.append_indent()?;
-    w.append("impl")?;
-    if options.tracing {
-        w.append("<T: kaze::runtime::tracing::Trace>")?;
-    }
-    w.append(&format!(" {}", m.name))?;
-    if options.tracing {
-        w.append("<T>")?;
-    }
-    w.append(" {")?;
-    w.append_newline()?;
-    w.indent();
 
-    w.append_indent()?;
-    w.append("pub fn new(")?;
-    if options.tracing {
-        w.append(&format!(
-            "instance_name: &'static str, mut trace: T) -> std::io::Result<{}<T>> {{",
-            m.name
-        ))?;
-    } else {
-        w.append(&format!(") -> {} {{", m.name))?;
-    }
-    w.append_newline()?;
-    w.indent();
-
-    if options.tracing {
-        fn visit_context<'graph, 'arena, W: Write>(
-            context: &'arena ModuleContext<'graph, 'arena>,
-            trace_signals: &HashMap<&'arena ModuleContext<'graph, 'arena>, Vec<TraceSignal>>,
-            w: &mut code_writer::CodeWriter<W>,
-        ) -> Result<()> {
-            let module_name = if let Some((instance, _)) = context.instance_and_parent {
-                format!("\"{}\"", instance.name)
-            } else {
-                "instance_name".into()
-            };
-            w.append_line(&format!("trace.push_module({})?;", module_name))?;
-
-            if let Some(module_trace_signals) = trace_signals.get(&context) {
-                for trace_signal in module_trace_signals.iter() {
-                    w.append_line(&format!("let {} = trace.add_signal(\"{}\", {}, kaze::runtime::tracing::TraceValueType::{})?;", trace_signal.member_name, trace_signal.name, trace_signal.bit_width, match trace_signal.type_ {
-                        TraceValueType::Bool => "Bool",
-                        TraceValueType::U32 => "U32",
-                        TraceValueType::U64 => "U64",
-                        TraceValueType::U128 => "U128",
-                    }))?;
-                }
-            }
+        if !self.prop_temps.is_empty() {
+            chunks::write_prop_temp_members(self.prop_temps, lanes, w)?;
+        }
 
-            for child in context.children().values() {
-                visit_context(child, trace_signals, w)?;
-            }
+        if options.activity_tracking {
+            self.tracked_values.write_members(w)?;
+        }
 
-            w.append_line("trace.pop_module()?;")?;
+        if let Some(prop_partitions) = self.prop_partitions {
+            prop_partitions.write_members(w)?;
+        }
 
-            Ok(())
+        if options.tracing {
+            self.trace_signals.write_members(w)?;
         }
-        visit_context(root_context, &trace_signals, &mut w)?;
+
+        w.unindent();
+        w.append_line("}")?;
         w.append_newline()?;
-    }
 
-    w.append_indent()?;
-    if options.tracing {
-        w.append("Ok(")?;
+        Ok(())
     }
-    w.append(&format!("{} {{", m.name))?;
-    w.append_newline()?;
-    w.indent();
 
-    if !inputs.is_empty() {
-        w.append_line("// Inputs")?;
-        for (name, input) in inputs.iter() {
+    fn write_mem_members<W: Write>(
+        &self,
+        mem: &Mem,
+        state_members: &mut Vec<StateMember>,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> io::Result<()> {
+        let lanes = self.lanes;
+
+        let address_type_name =
+            lanes.array(ValueType::from_bit_width(mem.mem.address_bit_width).name());
+        let element_type_name = ValueType::from_bit_width(mem.mem.element_bit_width).name();
+        let contents_type_name = lanes.vec(format!("Box<[{}]>", element_type_name));
+        let element_type_name = lanes.array(element_type_name);
+        state_members.push(StateMember {
+            len: Some(1usize << mem.mem.address_bit_width),
+            ..StateMember::new(mem.mem_name.clone(), contents_type_name.clone())
+        });
+        w.append_line(&format!(
+            "{}: {}, // {} bit elements",
+            mem.mem_name, contents_type_name, mem.mem.element_bit_width
+        ))?;
+        for (_, read_signal_names) in mem.read_signal_names.iter() {
+            state_members.push(StateMember::new(
+                read_signal_names.address_name.clone(),
+                address_type_name.clone(),
+            ));
             w.append_line(&format!(
-                "{}: {}, // {} bit(s)",
-                name,
-                ValueType::from_bit_width(input.bit_width()).zero_str(),
-                input.bit_width()
+                "{}: {},",
+                read_signal_names.address_name, address_type_name
             ))?;
-        }
-    }
-
-    if !outputs.is_empty() {
-        w.append_line("// Outputs")?;
-        for (name, output) in outputs.iter() {
+            state_members.push(StateMember::new(
+                read_signal_names.enable_name.clone(),
+                lanes.array(ValueType::Bool.name()),
+            ));
             w.append_line(&format!(
-                "{}: {}, // {} bit(s)",
-                name,
-                ValueType::from_bit_width(output.bit_width()).zero_str(),
-                output.bit_width()
+                "{}: {},",
+                read_signal_names.enable_name,
+                lanes.array(ValueType::Bool.name())
+            ))?;
+            state_members.push(StateMember::new(
+                read_signal_names.value_name.clone(),
+                element_type_name.clone(),
+            ));
+            w.append_line(&format!(
+                "{}: {},",
+                read_signal_names.value_name, element_type_name
             ))?;
         }
-    }
-
-    if !state_elements.regs.is_empty() {
-        w.append_newline()?;
-        w.append_line("// Regs")?;
-        for (_, reg) in state_elements.regs.iter() {
+        for (write_port, write_signal_names) in mem
+            .mem
+            .write_ports
+            .borrow()
+            .iter()
+            .zip(mem.write_signal_names.iter())
+        {
+            state_members.push(StateMember::new(
+                write_signal_names.address_name.clone(),
+                address_type_name.clone(),
+            ));
             w.append_line(&format!(
-                "{}: {}, // {} bit(s)",
-                reg.value_name,
-                ValueType::from_bit_width(reg.data.bit_width).zero_str(),
-                reg.data.bit_width
+                "{}: {},",
+                write_signal_names.address_name, address_type_name
             ))?;
+            state_members.push(StateMember::new(
+                write_signal_names.value_name.clone(),
+                element_type_name.clone(),
+            ));
             w.append_line(&format!(
                 "{}: {},",
-                reg.next_name,
-                ValueType::from_bit_width(reg.data.bit_width).zero_str()
+                write_signal_names.value_name, element_type_name
             ))?;
+            state_members.push(StateMember::new(
+                write_signal_names.enable_name.clone(),
+                lanes.array(ValueType::Bool.name()),
+            ));
+            w.append_line(&format!(
+                "{}: {},",
+                write_signal_names.enable_name,
+                lanes.array(ValueType::Bool.name())
+            ))?;
+            if write_port.mask.is_some() {
+                state_members.push(StateMember::new(
+                    write_signal_names.mask_name.clone(),
+                    element_type_name.clone(),
+                ));
+                w.append_line(&format!(
+                    "{}: {},",
+                    write_signal_names.mask_name, element_type_name
+                ))?;
+            }
         }
+
+        Ok(())
     }
 
-    if !state_elements.mems.is_empty() {
+    // Writes the simulator's constructor.
+    fn write_new<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> io::Result<()> {
+        let m = self.m;
+        let options = self.options;
+        let lanes = self.lanes;
+        let state_elements = self.state_elements;
+
+        if options.random_initial_state {
+            random_state::write_new(m, options, w)?;
+        }
+
+        let (new_name, seed_param) = if options.random_initial_state {
+            ("new_with_seed", "seed: u64")
+        } else {
+            ("new", "")
+        };
+        w.append_indent()?;
+        w.append(&format!("pub fn {}(", new_name))?;
+        if options.tracing {
+            w.append(&format!(
+                "instance_name: &'static str, mut trace: T{}) -> std::io::Result<{}<T>> {{",
+                if options.random_initial_state {
+                    format!(", {}", seed_param)
+                } else {
+                    String::new()
+                },
+                m.name
+            ))?;
+        } else {
+            w.append(&format!("{}) -> {} {{", seed_param, m.name))?;
+        }
         w.append_newline()?;
-        w.append_line("// Mems")?;
-        for (_, mem) in state_elements.mems.iter() {
-            let address_type = ValueType::from_bit_width(mem.mem.address_bit_width);
-            let element_type = ValueType::from_bit_width(mem.mem.element_bit_width);
-            if let Some(ref initial_contents) = *mem.mem.initial_contents.borrow() {
-                w.append_line(&format!("{}: vec![", mem.mem_name))?;
-                w.indent();
-                for element in initial_contents.iter() {
-                    w.append_line(&match *element {
-                        graph::Constant::Bool(value) => format!("{},", value),
-                        graph::Constant::U32(value) => format!("0x{:x},", value),
-                        graph::Constant::U64(value) => format!("0x{:x},", value),
-                        graph::Constant::U128(value) => format!("0x{:x},", value),
-                    })?;
-                }
-                w.unindent();
-                w.append_line("].into_boxed_slice(),")?;
-            } else {
+        w.indent();
+
+        if options.random_initial_state {
+            random_state::write_rng(w)?;
+        }
+
+        if options.tracing {
+            self.trace_signals
+                .write_signal_registration(self.root_context, w)?;
+        }
+
+        w.append_indent()?;
+        if options.tracing {
+            w.append("Ok(")?;
+        }
+        w.append(&format!("{} {{", m.name))?;
+        w.append_newline()?;
+        w.indent();
+
+        let inputs = m.inputs.borrow();
+        if !inputs.is_empty() {
+            w.append_line("// Inputs")?;
+            for (name, input) in inputs.iter() {
                 w.append_line(&format!(
-                    "{}: vec![{}; {}].into_boxed_slice(),",
-                    mem.mem_name,
-                    element_type.zero_str(),
-                    1 << mem.mem.address_bit_width
+                    "{}: {}, // {} bit(s)",
+                    name,
+                    lanes.array(ValueType::from_bit_width(input.bit_width()).zero_str()),
+                    input.bit_width()
                 ))?;
             }
-            for (_, read_signal_names) in mem.read_signal_names.iter() {
+        }
+
+        let outputs = m.outputs.borrow();
+        if !outputs.is_empty() {
+            w.append_line("// Outputs")?;
+            for (name, output) in outputs.iter() {
                 w.append_line(&format!(
-                    "{}: {},",
-                    read_signal_names.address_name,
-                    address_type.zero_str()
+                    "{}: {}, // {} bit(s)",
+                    name,
+                    lanes.array(ValueType::from_bit_width(output.bit_width()).zero_str()),
+                    output.bit_width()
                 ))?;
+            }
+        }
+
+        if !state_elements.regs.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Regs")?;
+            for (_, reg) in state_elements.regs.iter() {
+                let initial_value = if reg.data.initial_value.borrow().is_some() {
+                    lanes.array(ValueType::from_bit_width(reg.data.bit_width).zero_str())
+                } else {
+                    random_state::initial_value_str(
+                        reg.data.bit_width,
+                        options.random_initial_state,
+                        lanes,
+                    )
+                };
                 w.append_line(&format!(
-                    "{}: {},",
-                    read_signal_names.enable_name,
-                    ValueType::Bool.zero_str()
+                    "{}: {}, // {} bit(s)",
+                    reg.value_name, initial_value, reg.data.bit_width
                 ))?;
                 w.append_line(&format!(
                     "{}: {},",
-                    read_signal_names.value_name,
-                    element_type.zero_str()
+                    reg.next_name,
+                    lanes.array(ValueType::from_bit_width(reg.data.bit_width).zero_str())
                 ))?;
             }
-            if mem.mem.write_port.borrow().is_some() {
+        }
+
+        if !state_elements.instance_outputs.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Instance outputs")?;
+            for instance_output in state_elements.instance_outputs.iter() {
                 w.append_line(&format!(
                     "{}: {},",
-                    mem.write_address_name,
-                    address_type.zero_str()
+                    instance_output.value_name,
+                    lanes.array(
+                        ValueType::from_bit_width(instance_output.signal.bit_width()).zero_str()
+                    )
                 ))?;
+            }
+        }
+
+        if !state_elements.mems.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Mems")?;
+            for (_, mem) in state_elements.mems.iter() {
+                self.write_mem_initializers(mem, w)?;
+            }
+        }
+
+        if !state_elements.properties.is_empty() {
+            w.append_newline()?;
+            w.append_line("// Properties")?;
+            for property in state_elements.properties.iter() {
                 w.append_line(&format!(
                     "{}: {},",
-                    mem.write_value_name,
-                    element_type.zero_str()
+                    property.value_name,
+                    lanes.array(ValueType::Bool.zero_str())
                 ))?;
+                if property.property.kind == graph::PropertyKind::Cover {
+                    w.append_line(&format!(
+                        "{}: {},",
+                        property.count_name,
+                        lanes.array("0".into())
+                    ))?;
+                }
+            }
+            for cycle_name in self.cycle_names() {
+                w.append_line(&format!("{}: 0,", cycle_name))?;
+            }
+        }
+
+        if !self.reset_pending_names().is_empty() {
+            w.append_newline()?;
+            w.append_line("// Resets")?;
+            for reset_pending_name in self.reset_pending_names() {
                 w.append_line(&format!(
                     "{}: {},",
-                    mem.write_enable_name,
+                    reset_pending_name,
                     ValueType::Bool.zero_str()
                 ))?;
             }
         }
-    }
 
-    if options.tracing {
-        w.append_newline()?;
-        w.append_line("__trace: trace,")?;
-        for context_trace_signals in trace_signals.values() {
-            for trace_signal in context_trace_signals.iter() {
-                w.append_line(&format!("{},", trace_signal.member_name))?;
-            }
+        if options.four_state {
+            four_state::write_initializers(self.unknown_members, state_elements, lanes, w)?;
+        }
+
+        if !self.prop_temps.is_empty() {
+            chunks::write_prop_temp_initializers(self.prop_temps, lanes, w)?;
+        }
+
+        if options.activity_tracking {
+            self.tracked_values.write_initializers(w)?;
+        }
+
+        if let Some(prop_partitions) = self.prop_partitions {
+            prop_partitions.write_initializers(w)?;
+        }
+
+        if options.tracing {
+            self.trace_signals.write_initializers(w)?;
+        }
+
+        w.unindent();
+        w.append_indent()?;
+        w.append("}")?;
+        if options.tracing {
+            w.append(")")?;
+        }
+        w.append_newline()?;
+        w.unindent();
+        w.append_line("}")?;
+
+        Ok(())
+    }
+
+    fn write_mem_initializers<W: Write>(
+        &self,
+        mem: &Mem,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> io::Result<()> {
+        let lanes = self.lanes;
+
+        let address_type = ValueType::from_bit_width(mem.mem.address_bit_width);
+        let element_type = ValueType::from_bit_width(mem.mem.element_bit_width);
+        if let Some(ref initial_contents) = *mem.mem.initial_contents.borrow() {
+            w.append_line(&format!("{}: {}vec![", mem.mem_name, lanes.vec_prefix()))?;
+            w.indent();
+            for element in initial_contents.iter() {
+                w.append_line(&match (element, element_type) {
+                    (_, ValueType::Wide(num_words)) => format!(
+                        "[{}],",
+                        element
+                            .words(num_words as _)
+                            .iter()
+                            .map(|word| format!("0x{:x}", word))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    (graph::Constant::Bool(value), _) => format!("{},", value),
+                    (graph::Constant::U32(value), _) => format!("0x{:x},", value),
+                    (graph::Constant::U64(value), _) => format!("0x{:x},", value),
+                    (graph::Constant::U128(value), _) => format!("0x{:x},", value),
+                    (graph::Constant::Wide(_), _) => {
+                        format!("0x{:x},", element.numeric_value())
+                    }
+                })?;
+            }
+            w.unindent();
+            w.append_line(&format!("].into_boxed_slice(){},", lanes.vec_suffix()))?;
+        } else if self.options.random_initial_state {
+            w.append_line(&format!(
+                "{}: {},",
+                mem.mem_name,
+                random_state::mem_contents_str(mem.mem, lanes)
+            ))?;
+        } else {
+            w.append_line(&format!(
+                "{}: {}vec![{}; {}].into_boxed_slice(){},",
+                mem.mem_name,
+                lanes.vec_prefix(),
+                element_type.zero_str(),
+                1 << mem.mem.address_bit_width,
+                lanes.vec_suffix()
+            ))?;
+        }
+        for (_, read_signal_names) in mem.read_signal_names.iter() {
+            w.append_line(&format!(
+                "{}: {},",
+                read_signal_names.address_name,
+                lanes.array(address_type.zero_str())
+            ))?;
+            w.append_line(&format!(
+                "{}: {},",
+                read_signal_names.enable_name,
+                lanes.array(ValueType::Bool.zero_str())
+            ))?;
+            w.append_line(&format!(
+                "{}: {},",
+                read_signal_names.value_name,
+                lanes.array(element_type.zero_str())
+            ))?;
+        }
+        for (write_port, write_signal_names) in mem
+            .mem
+            .write_ports
+            .borrow()
+            .iter()
+            .zip(mem.write_signal_names.iter())
+        {
+            w.append_line(&format!(
+                "{}: {},",
+                write_signal_names.address_name,
+                lanes.array(address_type.zero_str())
+            ))?;
+            w.append_line(&format!(
+                "{}: {},",
+                write_signal_names.value_name,
+                lanes.array(element_type.zero_str())
+            ))?;
+            w.append_line(&format!(
+                "{}: {},",
+                write_signal_names.enable_name,
+                lanes.array(ValueType::Bool.zero_str())
+            ))?;
+            if write_port.mask.is_some() {
+                w.append_line(&format!(
+                    "{}: {},",
+                    write_signal_names.mask_name,
+                    lanes.array(element_type.zero_str())
+                ))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_prop<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> io::Result<()> {
+        let options = self.options;
+        let lanes = self.lanes;
+
+        w.append_newline()?;
+        w.append_line("pub fn prop(&mut self) {")?;
+        w.indent();
+
+        if let Some(prop_partitions) = self.prop_partitions {
+            prop_partitions.write_prop_body(w)?;
+        } else if let Some(prop_chunks) = self.prop_chunks {
+            chunks::write_prop_body(options, prop_chunks, self.tracked_values, w)?;
+        } else {
+            lanes.write_loop_begin(w)?;
+            self.prop_context.write(w, lanes.loop_lane())?;
+            lanes.write_loop_end(w)?;
+        }
+
+        let outputs = self.m.outputs.borrow();
+        if options.four_state && options.assert_known_outputs && !outputs.is_empty() {
+            four_state::write_known_outputs_check(&outputs, lanes, w)?;
+        }
+
+        w.unindent();
+        w.append_line("}")?;
+
+        Ok(())
+    }
+
+    // Writes the methods that access the simulator's registers, instance outputs, covers, and
+    //  mems, by name and by path.
+    fn write_accessors<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> io::Result<()> {
+        let mut regs = self.state_elements.regs.values().collect::<Vec<_>>();
+        regs.sort_by(|a, b| a.accessor_name.cmp(&b.accessor_name));
+        for reg in regs.iter() {
+            self.write_reg_accessors(reg, w)?;
+        }
+
+        let mut instance_outputs = self
+            .state_elements
+            .instance_outputs
+            .iter()
+            .collect::<Vec<_>>();
+        instance_outputs.sort_by(|a, b| a.accessor_name.cmp(&b.accessor_name));
+        for instance_output in instance_outputs.iter() {
+            self.write_instance_output_accessor(instance_output, w)?;
+        }
+
+        self.write_path_accessors(&regs, &instance_outputs, w)?;
+
+        self.write_cover_count(w)?;
+
+        let mut mems = self.state_elements.mems.values().collect::<Vec<_>>();
+        mems.sort_by(|a, b| a.accessor_name.cmp(&b.accessor_name));
+        for mem in mems {
+            self.write_mem_accessors(mem, w)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_reg_accessors<W: Write>(
+        &self,
+        reg: &Register,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> io::Result<()> {
+        let lanes = self.lanes;
+        let type_name = ValueType::from_bit_width(reg.data.bit_width).name();
+
+        w.append_newline()?;
+        w.append_line(&format!(
+            "pub fn peek_reg_{}(&self{}) -> {} {{",
+            reg.accessor_name,
+            lanes.param(),
+            type_name
+        ))?;
+        w.indent();
+        w.append_line(&format!("self.{}{}", reg.value_name, lanes.index()))?;
+        w.unindent();
+        w.append_line("}")?;
+
+        w.append_newline()?;
+        w.append_line(&format!(
+            "pub fn poke_reg_{}(&mut self{}, value: {}) {{",
+            reg.accessor_name,
+            lanes.param(),
+            type_name
+        ))?;
+        w.indent();
+        w.append_line(&format!(
+            "self.{}{} = value;",
+            reg.value_name,
+            lanes.index()
+        ))?;
+        if self.options.four_state {
+            four_state::write_accessor_write(&reg.value_name, reg.data.bit_width, lanes, w)?;
+        }
+        w.unindent();
+        w.append_line("}")?;
+
+        Ok(())
+    }
+
+    fn write_instance_output_accessor<W: Write>(
+        &self,
+        instance_output: &InstanceOutput,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> io::Result<()> {
+        let lanes = self.lanes;
+
+        w.append_newline()?;
+        w.append_line(&format!(
+            "pub fn peek_output_{}(&self{}) -> {} {{",
+            instance_output.accessor_name,
+            lanes.param(),
+            ValueType::from_bit_width(instance_output.signal.bit_width()).name()
+        ))?;
+        w.indent();
+        w.append_line(&format!(
+            "self.{}{}",
+            instance_output.value_name,
+            lanes.index()
+        ))?;
+        w.unindent();
+        w.append_line("}")?;
+
+        Ok(())
+    }
+
+    // Writes `signal` and `set_signal` (and their unknown-bit counterparts, with four-state
+    //  simulation), which look up `regs`, `instance_outputs`, and `m`'s outputs by path.
+    fn write_path_accessors<W: Write>(
+        &self,
+        regs: &[&Register],
+        instance_outputs: &[&InstanceOutput],
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> io::Result<()> {
+        let lanes = self.lanes;
+
+        // Path lookups only cover values that fit in a u128. Registers come first so that they
+        //  take priority over outputs with the same path.
+        let mut path_lookups = Vec::new();
+        for reg in regs.iter() {
+            path_lookups.push((reg.path.clone(), reg.value_name.clone(), reg.data.bit_width));
+        }
+        for instance_output in instance_outputs.iter() {
+            path_lookups.push((
+                instance_output.path.clone(),
+                instance_output.value_name.clone(),
+                instance_output.signal.bit_width(),
+            ));
+        }
+        for (name, output) in self.m.outputs.borrow().iter() {
+            path_lookups.push((name.clone(), name.clone(), output.bit_width()));
+        }
+        path_lookups.retain(|&(_, _, bit_width)| !ValueType::from_bit_width(bit_width).is_wide());
+        let mut seen_paths = HashSet::new();
+        path_lookups.retain(|(path, _, _)| seen_paths.insert(path.clone()));
+
+        w.append_newline()?;
+        if path_lookups.is_empty() && lanes.is_some() {
+            w.append_line("#[allow(unused_variables)]")?;
+        }
+        w.append_line(&format!(
+            "pub fn signal(&self{}, path: &str) -> Option<u128> {{",
+            lanes.param()
+        ))?;
+        w.indent();
+        w.append_line("match path {")?;
+        w.indent();
+        for (path, value_name, _) in path_lookups.iter() {
+            w.append_line(&format!(
+                "\"{}\" => Some(self.{}{} as u128),",
+                path,
+                value_name,
+                lanes.index()
+            ))?;
+        }
+        w.append_line("_ => None,")?;
+        w.unindent();
+        w.append_line("}")?;
+        w.unindent();
+        w.append_line("}")?;
+
+        let reg_paths = regs
+            .iter()
+            .map(|reg| reg.path.clone())
+            .collect::<HashSet<_>>();
+        let set_signal_lookups = path_lookups
+            .iter()
+            .filter(|(path, _, _)| reg_paths.contains(path))
+            .collect::<Vec<_>>();
+        w.append_newline()?;
+        if set_signal_lookups.is_empty() {
+            w.append_line("#[allow(unused_variables)]")?;
+        }
+        w.append_line(&format!(
+            "pub fn set_signal(&mut self{}, path: &str, value: u128) -> bool {{",
+            lanes.param()
+        ))?;
+        w.indent();
+        w.append_line("match path {")?;
+        w.indent();
+        for (path, value_name, bit_width) in set_signal_lookups.iter() {
+            w.append_line(&format!("\"{}\" => {{", path))?;
+            w.indent();
+            w.append_line(&format!(
+                "self.{}{} = {};",
+                value_name,
+                lanes.index(),
+                from_u128_str("value", *bit_width)
+            ))?;
+            if self.options.four_state {
+                four_state::write_accessor_write(value_name, *bit_width, lanes, w)?;
+            }
+            w.append_line("true")?;
+            w.unindent();
+            w.append_line("}")?;
+        }
+        w.append_line("_ => false,")?;
+        w.unindent();
+        w.append_line("}")?;
+        w.unindent();
+        w.append_line("}")?;
+
+        if self.options.four_state {
+            four_state::write_signal_unknown_accessors(
+                &self.m.inputs.borrow(),
+                &path_lookups,
+                &reg_paths,
+                lanes,
+                w,
+            )?;
         }
-    }
 
-    w.unindent();
-    w.append_indent()?;
-    w.append("}")?;
-    if options.tracing {
-        w.append(")")?;
+        Ok(())
     }
-    w.append_newline()?;
-    w.unindent();
-    w.append_line("}")?;
-
-    let mut reset_context = AssignmentContext::new(&expr_arena);
-    let mut posedge_clk_context = AssignmentContext::new(&expr_arena);
 
-    for (_, reg) in state_elements.regs.iter() {
-        let target = expr_arena.alloc(Expr::Ref {
-            name: reg.value_name.clone(),
-            scope: Scope::Member,
-        });
+    fn write_cover_count<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> io::Result<()> {
+        let lanes = self.lanes;
+
+        let mut covers = self
+            .state_elements
+            .properties
+            .iter()
+            .filter(|property| property.property.kind == graph::PropertyKind::Cover)
+            .collect::<Vec<_>>();
+        covers.sort_by(|a, b| a.path.cmp(&b.path));
+        if covers.is_empty() {
+            return Ok(());
+        }
 
-        if let Some(ref initial_value) = *reg.data.initial_value.borrow() {
-            reset_context.push(Assignment {
-                target,
-                expr: Expr::from_constant(initial_value, reg.data.bit_width, &expr_arena),
-            });
+        w.append_newline()?;
+        w.append_line(&format!(
+            "pub fn cover_count(&self{}, path: &str) -> Option<u64> {{",
+            lanes.param()
+        ))?;
+        w.indent();
+        w.append_line("match path {")?;
+        w.indent();
+        for cover in covers {
+            w.append_line(&format!(
+                "{:?} => Some(self.{}{}),",
+                cover.path,
+                cover.count_name,
+                lanes.index()
+            ))?;
         }
+        w.append_line("_ => None,")?;
+        w.unindent();
+        w.append_line("}")?;
+        w.unindent();
+        w.append_line("}")?;
 
-        posedge_clk_context.push(Assignment {
-            target,
-            expr: expr_arena.alloc(Expr::Ref {
-                name: reg.next_name.clone(),
-                scope: Scope::Member,
-            }),
-        });
+        Ok(())
     }
 
-    for (_, mem) in state_elements.mems.iter() {
-        for (_, read_signal_names) in mem.read_signal_names.iter() {
-            let address = expr_arena.alloc(Expr::Ref {
-                name: read_signal_names.address_name.clone(),
-                scope: Scope::Member,
-            });
-            let enable = expr_arena.alloc(Expr::Ref {
-                name: read_signal_names.enable_name.clone(),
-                scope: Scope::Member,
-            });
-            let value = expr_arena.alloc(Expr::Ref {
-                name: read_signal_names.value_name.clone(),
-                scope: Scope::Member,
-            });
-            let element = expr_arena.alloc(Expr::ArrayIndex {
-                target: expr_arena.alloc(Expr::Ref {
-                    name: mem.mem_name.clone(),
-                    scope: Scope::Member,
-                }),
-                index: address,
-            });
-            // TODO: Conditional assign statement instead of always writing ternary
-            posedge_clk_context.push(Assignment {
-                target: value,
-                expr: expr_arena.alloc(Expr::Ternary {
-                    cond: enable,
-                    when_true: element,
-                    when_false: value,
-                }),
-            });
-        }
-        if mem.mem.write_port.borrow().is_some() {
-            let address = expr_arena.alloc(Expr::Ref {
-                name: mem.write_address_name.clone(),
-                scope: Scope::Member,
-            });
-            let value = expr_arena.alloc(Expr::Ref {
-                name: mem.write_value_name.clone(),
-                scope: Scope::Member,
-            });
-            let enable = expr_arena.alloc(Expr::Ref {
-                name: mem.write_enable_name.clone(),
-                scope: Scope::Member,
-            });
-            let element = expr_arena.alloc(Expr::ArrayIndex {
-                target: expr_arena.alloc(Expr::Ref {
-                    name: mem.mem_name.clone(),
-                    scope: Scope::Member,
-                }),
-                index: address,
-            });
-            // TODO: Conditional assign statement instead of always writing ternary
-            posedge_clk_context.push(Assignment {
-                target: element,
-                expr: expr_arena.alloc(Expr::Ternary {
-                    cond: enable,
-                    when_true: value,
-                    when_false: element,
-                }),
-            });
-        }
-    }
+    fn write_mem_accessors<W: Write>(
+        &self,
+        mem: &Mem,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> io::Result<()> {
+        let lanes = self.lanes;
+        let address_type_name = ValueType::from_bit_width(mem.mem.address_bit_width).name();
+        let element_type_name = ValueType::from_bit_width(mem.mem.element_bit_width).name();
 
-    if !reset_context.is_empty() {
         w.append_newline()?;
-        w.append_line("pub fn reset(&mut self) {")?;
+        w.append_line(&format!(
+            "pub fn peek_mem_{}(&self{}, address: {}) -> {} {{",
+            mem.accessor_name,
+            lanes.param(),
+            address_type_name,
+            element_type_name
+        ))?;
         w.indent();
-
-        reset_context.write(&mut w)?;
-
+        w.append_line(&format!(
+            "self.{}{}[address as usize]",
+            mem.mem_name,
+            lanes.index()
+        ))?;
         w.unindent();
         w.append_line("}")?;
-    }
 
-    if !posedge_clk_context.is_empty() {
         w.append_newline()?;
-        w.append_line("pub fn posedge_clk(&mut self) {")?;
+        w.append_line(&format!(
+            "pub fn poke_mem_{}(&mut self{}, address: {}, value: {}) {{",
+            mem.accessor_name,
+            lanes.param(),
+            address_type_name,
+            element_type_name
+        ))?;
         w.indent();
+        w.append_line(&format!(
+            "self.{}{}[address as usize] = value;",
+            mem.mem_name,
+            lanes.index()
+        ))?;
+        if self.options.four_state {
+            four_state::write_poke_mem_unknown(mem, lanes, w)?;
+        }
+        self.tracked_values
+            .write_mem_accessor_write(&mem.mem_name, w)?;
+        w.unindent();
+        w.append_line("}")?;
 
-        posedge_clk_context.write(&mut w)?;
-
+        w.append_newline()?;
+        w.append_line(&format!(
+            "pub fn load_mem_{}(&mut self{}, address: {}, contents: &[{}]) {{",
+            mem.accessor_name,
+            lanes.param(),
+            address_type_name,
+            element_type_name
+        ))?;
+        w.indent();
+        w.append_line("let address = address as usize;")?;
+        w.append_line(&format!(
+            "self.{}{}[address..address + contents.len()].copy_from_slice(contents);",
+            mem.mem_name,
+            lanes.index()
+        ))?;
+        if self.options.four_state {
+            four_state::write_load_mem_unknown(mem, lanes, w)?;
+        }
+        self.tracked_values
+            .write_mem_accessor_write(&mem.mem_name, w)?;
         w.unindent();
         w.append_line("}")?;
-    }
 
-    w.append_newline()?;
-    w.append_line("pub fn prop(&mut self) {")?;
-    w.indent();
+        w.append_newline()?;
+        w.append_line(&format!(
+            "pub fn dump_mem_{}(&self{}) -> &[{}] {{",
+            mem.accessor_name,
+            lanes.param(),
+            element_type_name
+        ))?;
+        w.indent();
+        w.append_line(&format!("&self.{}{}", mem.mem_name, lanes.index()))?;
+        w.unindent();
+        w.append_line("}")?;
 
-    prop_context.write(&mut w)?;
+        if self.options.four_state {
+            four_state::write_peek_mem_unknown(mem, lanes, w)?;
+        }
 
-    w.unindent();
-    w.append_line("}")?;
+        Ok(())
+    }
 
-    if options.tracing {
-        w.append_newline()?;
-        w.append_line("pub fn update_trace(&mut self, time_stamp: u64) -> std::io::Result<()> {")?;
-        w.indent();
+    fn cycle_names(&self) -> Vec<&String> {
+        self.clock_domains
+            .iter()
+            .filter_map(|clock_domain| clock_domain.cycle_name.as_ref())
+            .collect()
+    }
 
-        w.append_line("self.__trace.update_time_stamp(time_stamp)?;")?;
-        w.append_newline()?;
+    fn reset_pending_names(&self) -> Vec<&String> {
+        self.clock_domains
+            .iter()
+            .filter_map(|clock_domain| clock_domain.reset_pending_name.as_ref())
+            .collect()
+    }
+}
 
-        for context_trace_signals in trace_signals.values() {
-            for trace_signal in context_trace_signals.iter() {
-                w.append_line(&format!("self.__trace.update_signal(&self.{}, kaze::runtime::tracing::TraceValue::{}(self.{}))?;", trace_signal.member_name, match trace_signal.type_ {
-                    TraceValueType::Bool => "Bool",
-                    TraceValueType::U32 => "U32",
-                    TraceValueType::U64 => "U64",
-                    TraceValueType::U128 => "U128",
-                }, trace_signal.value_name))?;
-            }
+// Returns an error if `options` combines options that the generated simulator can't support at
+//  the same time.
+fn check_generation_options<'a>(
+    m: &'a graph::Module<'a>,
+    options: &GenerationOptions,
+) -> Result<(), Error> {
+    // Lanes are evaluated in lockstep, so they can't be partitioned or skip evaluating idle logic,
+    //  partitions are evaluated by their own functions rather than chunks, and activity tracking
+    //  only tracks changes to values, not to their unknown bits
+    let combinations = [
+        (
+            "lanes",
+            options.lanes.is_some(),
+            "partition_depth",
+            options.partition_depth.is_some(),
+        ),
+        (
+            "lanes",
+            options.lanes.is_some(),
+            "activity_tracking",
+            options.activity_tracking,
+        ),
+        (
+            "partition_depth",
+            options.partition_depth.is_some(),
+            "prop_chunk_size",
+            options.prop_chunk_size.is_some(),
+        ),
+        (
+            "partition_depth",
+            options.partition_depth.is_some(),
+            "activity_tracking",
+            options.activity_tracking,
+        ),
+        (
+            "four_state",
+            options.four_state,
+            "activity_tracking",
+            options.activity_tracking,
+        ),
+    ];
+    for (option, is_set, other_option, other_is_set) in combinations {
+        if is_set && other_is_set {
+            return Err(Error::IncompatibleGenerationOptions {
+                root: m.name.clone(),
+                option: option.to_string(),
+                other_option: other_option.to_string(),
+            });
         }
-        w.append_newline()?;
+    }
 
-        w.append_line("Ok(())")?;
+    Ok(())
+}
 
-        w.unindent();
-        w.append_line("}")?;
+// Returns an error if any two of the accessor methods generated for `state_elements` would have
+//  the same name, which is only possible for names that start or end with `_` or contain `__`.
+fn check_accessor_names<'graph, 'arena>(
+    m: &graph::Module,
+    state_elements: &StateElements<'graph, 'arena>,
+    four_state: bool,
+) -> Result<(), Error> {
+    let mut accessors = Vec::new();
+    for reg in state_elements.regs.values() {
+        for prefix in ["peek_reg_", "poke_reg_"].iter() {
+            accessors.push((format!("{}{}", prefix, reg.accessor_name), &reg.path));
+        }
+    }
+    for instance_output in state_elements.instance_outputs.iter() {
+        accessors.push((
+            format!("peek_output_{}", instance_output.accessor_name),
+            &instance_output.path,
+        ));
+    }
+    for mem in state_elements.mems.values() {
+        for prefix in ["peek_mem_", "poke_mem_", "load_mem_", "dump_mem_"].iter() {
+            accessors.push((format!("{}{}", prefix, mem.accessor_name), &mem.path));
+        }
+        if four_state {
+            accessors.push((format!("peek_mem_unknown_{}", mem.accessor_name), &mem.path));
+        }
+    }
+    // Sorted so that the reported conflict doesn't depend on hash map iteration order
+    accessors.sort();
+
+    let mut paths: HashMap<String, &String> = HashMap::new();
+    for (accessor, path) in accessors {
+        if let Some(other_path) = paths.insert(accessor.clone(), path) {
+            return Err(Error::AccessorNameConflict {
+                root: m.name.clone(),
+                accessor,
+                path: other_path.clone(),
+                other_path: path.clone(),
+            });
+        }
     }
 
-    w.unindent();
-    w.append_line("}")?;
+    Ok(())
+}
+
+fn write_impl_header<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: &GenerationOptions,
+    w: &mut code_writer::CodeWriter<W>,
+) -> io::Result<()> {
+    // Accessors' names join hierarchical paths with `__`, and not every simulator uses every method
+    w.append_line("#[allow(dead_code, non_snake_case, unused_parens)]")?;
+    if options.four_state {
+        // Logic that only looks redundant (e.g. `x ^ x`) is kept as it is with four-state
+        //  simulation, as it isn't redundant for unknown bits
+        w.append_line("#[allow(clippy::eq_op, clippy::erasing_op)]")?;
+    }
+    w.append_line("#[automatically_derived]")?;
+    w.append_indent()?;
+    w.append("impl")?;
+    if options.tracing {
+        w.append("<T: kaze::runtime::tracing::Trace>")?;
+    }
+    w.append(&format!(" {}", m.name))?;
+    if options.tracing {
+        w.append("<T>")?;
+    }
+    w.append(" {")?;
     w.append_newline()?;
+    w.indent();
 
     Ok(())
 }
 
+struct PropRoot<'graph, 'arena> {
+    target_name: String,
+    value_type: ValueType,
+    signal: &'graph graph::Signal<'graph>,
+    context: &'arena ModuleContext<'graph, 'arena>,
+    // The granularity of a mem write port's mask, which is expanded to a bit mask
+    mask_granularity: Option<u32>,
+    // Whether this root's value is the unknown bit mask of `signal` rather than its value
+    is_unknown: bool,
+}
+
+impl<'graph, 'arena> PropRoot<'graph, 'arena> {
+    fn new(
+        target_name: &str,
+        bit_width: u32,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+    ) -> PropRoot<'graph, 'arena> {
+        PropRoot {
+            target_name: target_name.into(),
+            value_type: ValueType::from_bit_width(bit_width),
+            signal,
+            context,
+            mask_granularity: None,
+            is_unknown: false,
+        }
+    }
+}
+
+fn compile_prop_root<'graph, 'context_arena, 'expr_arena>(
+    c: &mut Compiler<'graph, 'context_arena, 'expr_arena>,
+    root: &PropRoot<'graph, 'context_arena>,
+    target_scope: Scope,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+    a: &mut AssignmentContext<'expr_arena>,
+) {
+    let expr = match (root.mask_granularity, root.is_unknown) {
+        (Some(granularity), false) => {
+            c.compile_write_mask(root.signal, granularity, root.context, a)
+        }
+        (Some(granularity), true) => {
+            c.compile_write_mask_unknown(root.signal, granularity, root.context, a)
+        }
+        (None, false) => c.compile_signal(root.signal, root.context, a),
+        (None, true) => c.compile_signal_unknown(root.signal, root.context, a),
+    };
+    a.push(Assignment {
+        target: expr_arena.alloc(Expr::Ref {
+            name: root.target_name.clone(),
+            scope: target_scope,
+        }),
+        expr,
+    });
+}
+
+// Returns a Rust expression converting the `u128` named `value` to a `bit_width`-bit value.
+fn from_u128_str(value: &str, bit_width: u32) -> String {
+    match ValueType::from_bit_width(bit_width) {
+        ValueType::Bool => format!("{} & 1 != 0", value),
+        value_type if bit_width < value_type.bit_width() => format!(
+            "({} & 0x{:x}) as {}",
+            value,
+            (1u128 << bit_width) - 1,
+            value_type.name()
+        ),
+        value_type => format!("{} as {}", value, value_type.name()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -651,9 +1690,6 @@ mod tests {
     use crate::*;
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\"."
-    )]
     fn recursive_module_definition_error1() {
         let c = Context::new();
 
@@ -661,14 +1697,15 @@ mod tests {
 
         let _ = a.instance("a", "A");
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\"."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\" in module \"B\"."
-    )]
     fn recursive_module_definition_error2() {
         let c = Context::new();
 
@@ -678,14 +1715,15 @@ mod tests {
         let _ = a.instance("b", "B");
         let _ = b.instance("a", "A");
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\" in module \"B\"."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains an instance of module \"B\" called \"b\" whose input \"i\" is not driven."
-    )]
     fn undriven_instance_input_error() {
         let c = Context::new();
 
@@ -695,28 +1733,48 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains an instance of module \"B\" called \"b\" whose input \"i\" is not driven."
+        );
+    }
+
+    #[test]
+    fn undriven_instance_clock_domain_error() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        let b = c.module("B");
+        let _ = b.clock_domain("d");
+
+        let _ = a.instance("b", "B");
+
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains an instance of module \"B\" called \"b\" whose clock domain \"d\" is not driven."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains a register called \"r\" which is not driven."
-    )]
     fn undriven_register_error1() {
         let c = Context::new();
 
         let a = c.module("A");
         let _ = a.reg("r", 1);
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains a register called \"r\" which is not driven."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"B\" contains a register called \"r\" which is not driven."
-    )]
     fn undriven_register_error2() {
         let c = Context::new();
 
@@ -726,28 +1784,30 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"B\" contains a register called \"r\" which is not driven."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have any read ports."
-    )]
     fn mem_without_read_ports_error1() {
         let c = Context::new();
 
         let a = c.module("A");
         let _ = a.mem("m", 1, 1);
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have any read ports."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have any read ports."
-    )]
     fn mem_without_read_ports_error2() {
         let c = Context::new();
 
@@ -757,14 +1817,15 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have any read ports."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
-    )]
     fn mem_without_initial_contents_or_write_port_error1() {
         let c = Context::new();
 
@@ -772,14 +1833,15 @@ mod tests {
         let m = a.mem("m", 1, 1);
         let _ = m.read_port(a.low(), a.low());
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
-    )]
     fn mem_without_initial_contents_or_write_port_error2() {
         let c = Context::new();
 
@@ -790,14 +1852,15 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"b\" because module \"a\" contains an output called \"o\" which forms a combinational loop with itself."
-    )]
     fn combinational_loop_error() {
         let c = Context::new();
 
@@ -809,7 +1872,325 @@ mod tests {
         let a_inst_o = a_inst.output("o");
         a_inst.drive_input("i", a_inst_o);
 
-        // Panic
-        generate(b, GenerationOptions::default(), Vec::new()).unwrap();
+        // Error
+        let e = generate(b, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"b\" because module \"a\" contains an output called \"o\" which forms a combinational loop with itself."
+        );
+    }
+
+    #[test]
+    fn async_read_port_combinational_loop_error() {
+        let c = Context::new();
+
+        let a = c.module("a");
+        a.output("o", a.input("i", 1));
+
+        let b = c.module("b");
+        let a_inst = b.instance("a_inst", "a");
+        let m = b.mem("m", 1, 1);
+        m.initial_contents(&[false, true]);
+        a_inst.drive_input("i", m.async_read_port(a_inst.output("o")));
+        b.output("o", a_inst.output("o"));
+
+        // Error
+        let e = generate(b, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"b\" because module \"a\" contains an output called \"o\" which forms a combinational loop with itself."
+        );
+    }
+
+    #[test]
+    fn invalid_property_name_error() {
+        let c = Context::new();
+
+        let inner = c.module("inner");
+        inner.output("o", inner.input("i", 1));
+        inner.assert("0ok", inner.high());
+
+        let a = c.module("a");
+        let inner_inst = a.instance("inner", "inner");
+        inner_inst.drive_input("i", a.input("i", 1));
+        a.output("o", inner_inst.output("o"));
+
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"a\" because module \"inner\" contains a property called \"0ok\", which is not a valid identifier."
+        );
+    }
+
+    #[test]
+    fn duplicate_property_name_error() {
+        let c = Context::new();
+
+        let a = c.module("a");
+        let i = a.input("i", 1);
+        a.output("o", i);
+        a.assert("p", i);
+        a.cover("p", !i);
+
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"a\" because module \"a\" contains more than one property called \"p\"."
+        );
+    }
+
+    #[test]
+    fn cover_count_only_with_covers() {
+        let c = Context::new();
+
+        let a = c.module("a");
+        let i = a.input("i", 1);
+        a.output("o", i);
+        a.assert("p", i);
+
+        let mut code = Vec::new();
+        generate(a, GenerationOptions::default(), &mut code).unwrap();
+        assert!(!String::from_utf8(code).unwrap().contains("cover_count"));
+
+        a.cover("q", !i);
+
+        let mut code = Vec::new();
+        generate(a, GenerationOptions::default(), &mut code).unwrap();
+        assert!(String::from_utf8(code)
+            .unwrap()
+            .contains("pub fn cover_count(&self, path: &str) -> Option<u64> {"));
+    }
+
+    #[test]
+    fn accessor_names_are_distinct() {
+        let c = Context::new();
+
+        let inner = c.module("inner");
+        let r = inner.reg("b_c", 1);
+        r.default_value(false);
+        r.drive_next(inner.input("i", 1));
+        inner.output("o", r.value);
+
+        let a = c.module("a");
+        let r = a.reg("a_b", 1);
+        r.default_value(false);
+        r.drive_next(a.input("i", 1));
+        let a_inst = a.instance("a", "inner");
+        a_inst.drive_input("i", r.value);
+        a.output("o", a_inst.output("o"));
+
+        let mut code = Vec::new();
+        generate(a, GenerationOptions::default(), &mut code).unwrap();
+        let code = String::from_utf8(code).unwrap();
+        assert!(code.contains("pub fn peek_reg_a_b("));
+        assert!(code.contains("pub fn peek_reg_a__b_c("));
+    }
+
+    #[test]
+    fn accessor_name_conflict_error() {
+        let c = Context::new();
+
+        let inner = c.module("inner");
+        let r = inner.reg("b", 1);
+        r.default_value(false);
+        r.drive_next(inner.input("i", 1));
+        inner.output("o", r.value);
+
+        let a = c.module("a");
+        let r = a.reg("a__b", 1);
+        r.default_value(false);
+        r.drive_next(a.input("i", 1));
+        let a_inst = a.instance("a", "inner");
+        a_inst.drive_input("i", r.value);
+        a.output("o", a_inst.output("o"));
+
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"a\" because \"a.b\" and \"a__b\" would both have an accessor called \"peek_reg_a__b\"."
+        );
+    }
+
+    #[test]
+    fn mem_accessor_name_conflict_error() {
+        let c = Context::new();
+
+        let a = c.module("a");
+        let addr = a.input("addr", 1);
+        for name in ["m", "unknown_m"].iter() {
+            let mem = a.mem(*name, 1, 1);
+            mem.initial_contents(&[false, true]);
+            a.output(*name, mem.read_port(addr, a.high()));
+        }
+
+        generate(a, GenerationOptions::default(), Vec::new()).unwrap();
+
+        // Error
+        let e = generate(
+            a,
+            GenerationOptions {
+                four_state: true,
+                ..GenerationOptions::default()
+            },
+            Vec::new(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"a\" because \"m\" and \"unknown_m\" would both have an accessor called \"peek_mem_unknown_m\"."
+        );
+    }
+
+    #[test]
+    fn state_type_name_conflict_error() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        a.output("o", a.input("i", 1));
+        let _ = c.module("AState");
+
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because its state type would be called \"AState\", which is also the name of a module in the same context."
+        );
+    }
+
+    #[test]
+    fn incompatible_generation_options_error() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        a.output("o", a.input("i", 1));
+
+        let combinations = [
+            (
+                GenerationOptions {
+                    lanes: Some(4),
+                    partition_depth: Some(1),
+                    ..Default::default()
+                },
+                "lanes",
+                "partition_depth",
+            ),
+            (
+                GenerationOptions {
+                    lanes: Some(4),
+                    activity_tracking: true,
+                    ..Default::default()
+                },
+                "lanes",
+                "activity_tracking",
+            ),
+            (
+                GenerationOptions {
+                    partition_depth: Some(1),
+                    prop_chunk_size: Some(10),
+                    ..Default::default()
+                },
+                "partition_depth",
+                "prop_chunk_size",
+            ),
+            (
+                GenerationOptions {
+                    partition_depth: Some(1),
+                    activity_tracking: true,
+                    ..Default::default()
+                },
+                "partition_depth",
+                "activity_tracking",
+            ),
+            (
+                GenerationOptions {
+                    four_state: true,
+                    activity_tracking: true,
+                    ..Default::default()
+                },
+                "four_state",
+                "activity_tracking",
+            ),
+        ];
+        for (options, option, other_option) in combinations {
+            // Error
+            let e = generate(a, options, Vec::new()).unwrap_err();
+            assert_eq!(
+                e.to_string(),
+                format!("Cannot generate code for module \"A\" because the \"{}\" and \"{}\" generation options can't be combined.", option, other_option)
+            );
+        }
+    }
+
+    #[test]
+    fn full_hierarchy_access() {
+        let c = Context::new();
+
+        let inner = c.module("inner");
+        let dead = inner.reg("dead", 1);
+        dead.default_value(false);
+        dead.drive_next(!dead.value);
+        let mem = inner.mem("mem", 1, 1);
+        mem.initial_contents(&[false, true]);
+        inner.output("o", inner.input("i", 1));
+        inner.output("unused", mem.read_port(inner.low(), inner.high()));
+
+        let a = c.module("a");
+        let a_inst = a.instance("a", "inner");
+        a_inst.drive_input("i", a.input("i", 1));
+        a.output("o", a_inst.output("o"));
+
+        let mut code = Vec::new();
+        generate(
+            a,
+            GenerationOptions {
+                full_hierarchy_access: false,
+                ..GenerationOptions::default()
+            },
+            &mut code,
+        )
+        .unwrap();
+        let code = String::from_utf8(code).unwrap();
+        assert!(!code.contains("peek_reg_a__dead"));
+        assert!(!code.contains("peek_mem_a__mem"));
+        assert!(!code.contains("peek_output_a__o"));
+        assert!(!code.contains("__instance_output_"));
+
+        let mut code = Vec::new();
+        generate(a, GenerationOptions::default(), &mut code).unwrap();
+        let code = String::from_utf8(code).unwrap();
+        assert!(code.contains("pub fn peek_reg_a__dead("));
+        assert!(code.contains("pub fn peek_mem_a__mem("));
+        assert!(code.contains("pub fn peek_output_a__o("));
+        assert!(code.contains("pub fn peek_output_a__unused("));
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_matches("o", "o"));
+        assert!(!glob_matches("o", "o2"));
+        assert!(!glob_matches("o2", "o"));
+        assert!(glob_matches("", ""));
+        assert!(!glob_matches("", "o"));
+
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("*", "cpu.alu.r"));
+        assert!(glob_matches("cpu.*", "cpu.alu.r"));
+        assert!(!glob_matches("cpu.*", "cpu"));
+        assert!(!glob_matches("cpu.*", "gpu.r"));
+        assert!(glob_matches("*.r", "cpu.alu.r"));
+        assert!(!glob_matches("*.r", "cpu.alu.r2"));
+        assert!(glob_matches("cpu*r", "cpu.alu.r"));
+        assert!(glob_matches("*a*a*", "banana"));
+        assert!(!glob_matches("*a*a*a*a*", "banana"));
+
+        assert!(glob_matches("r?", "r1"));
+        assert!(!glob_matches("r?", "r"));
+        assert!(!glob_matches("r?", "r10"));
+        assert!(glob_matches("core?.*", "core0.pc"));
+        assert!(glob_matches("?*?", "ab"));
+        assert!(!glob_matches("?*?", "a"));
     }
 }
@@ -0,0 +1,243 @@
+use super::ir::*;
+use super::state_elements::*;
+
+use crate::code_writer;
+use crate::graph;
+
+use typed_arena::Arena;
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Result, Write};
+
+// With activity tracking, each value that `prop` reads is checked for changes at the start of
+//  `prop`, either by comparing it to its value as of the previous call, or (for mems, which are
+//  too large to compare) by a flag that's set whenever the mem is written. Each of `prop`'s chunks
+//  is then only evaluated if any of the values it reads changed.
+pub(super) struct TrackedValues<'a> {
+    values: Vec<TrackedValue<'a>>,
+    indices: HashMap<&'a str, usize>,
+}
+
+struct TrackedValue<'a> {
+    name: &'a str,
+    changed_name: String,
+    kind: TrackedValueKind,
+}
+
+enum TrackedValueKind {
+    Compared { prev_name: String, type_: ValueType },
+    Flagged { dirty_name: String },
+}
+
+impl<'a> TrackedValues<'a> {
+    pub fn new() -> TrackedValues<'a> {
+        TrackedValues {
+            values: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    // Tracks every value read by any of `prop_chunks`.
+    pub fn gather<'graph>(
+        &mut self,
+        m: &'graph graph::Module<'graph>,
+        state_elements: &StateElements<'graph, '_>,
+        prop_chunks: &[AssignmentChunk<'a, '_>],
+    ) {
+        let inputs = m.inputs.borrow();
+        let mut value_types = HashMap::new();
+        for (name, input) in inputs.iter() {
+            value_types.insert(name.as_str(), ValueType::from_bit_width(input.bit_width()));
+        }
+        for (_, reg) in state_elements.regs.iter() {
+            value_types.insert(
+                reg.value_name.as_str(),
+                ValueType::from_bit_width(reg.data.bit_width),
+            );
+        }
+        let mut mem_names = HashSet::new();
+        for (_, mem) in state_elements.mems.iter() {
+            mem_names.insert(mem.mem_name.as_str());
+            for (_, read_signal_names) in mem.read_signal_names.iter() {
+                value_types.insert(
+                    read_signal_names.value_name.as_str(),
+                    ValueType::from_bit_width(mem.mem.element_bit_width),
+                );
+            }
+        }
+
+        for chunk in prop_chunks.iter() {
+            for &name in chunk.read_members.iter() {
+                if self.indices.contains_key(name) {
+                    continue;
+                }
+                let index = self.values.len();
+                self.indices.insert(name, index);
+                self.values.push(TrackedValue {
+                    name,
+                    changed_name: format!("__changed_{}", index),
+                    kind: if mem_names.contains(name) {
+                        TrackedValueKind::Flagged {
+                            dirty_name: format!("__dirty_{}", index),
+                        }
+                    } else {
+                        TrackedValueKind::Compared {
+                            prev_name: format!("__prev_{}", index),
+                            type_: value_types[name],
+                        }
+                    },
+                });
+            }
+        }
+    }
+
+    // Returns the name of the flag that's set when the mem with contents member `mem_name` is
+    //  written, if its changes are tracked.
+    fn dirty_name(&self, mem_name: &str) -> Option<&str> {
+        match self
+            .indices
+            .get(mem_name)
+            .map(|&index| &self.values[index].kind)
+        {
+            Some(TrackedValueKind::Flagged { dirty_name }) => Some(dirty_name),
+            _ => None,
+        }
+    }
+
+    // Generates the assignment that sets the flag of the mem with contents member `mem_name` when
+    //  one of its write ports writes it (with `enable`), if its changes are tracked.
+    pub fn gen_mem_write_assignment<'expr_arena>(
+        &self,
+        mem_name: &str,
+        enable: &'expr_arena Expr<'expr_arena>,
+        expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+        a: &mut AssignmentContext<'expr_arena>,
+    ) {
+        if let Some(dirty_name) = self.dirty_name(mem_name) {
+            let dirty = expr_arena.alloc(Expr::Ref {
+                name: dirty_name.into(),
+                scope: Scope::Member,
+            });
+            a.push(Assignment {
+                target: dirty,
+                expr: expr_arena.alloc(Expr::InfixBinOp {
+                    lhs: dirty,
+                    rhs: enable,
+                    op: InfixBinOp::BitOr,
+                }),
+            });
+        }
+    }
+
+    // Writes the statement that sets the flag of the mem with contents member `mem_name` in its
+    //  accessors that write it, if its changes are tracked.
+    pub fn write_mem_accessor_write<W: Write>(
+        &self,
+        mem_name: &str,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> Result<()> {
+        if let Some(dirty_name) = self.dirty_name(mem_name) {
+            w.append_line(&format!("self.{} = true;", dirty_name))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_members<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        w.append_newline()?;
+        w.append_line("// Activity tracking")?;
+        w.append_line(&format!("__propagated: {},", ValueType::Bool.name()))?;
+        for value in self.values.iter() {
+            match value.kind {
+                TrackedValueKind::Compared {
+                    ref prev_name,
+                    type_,
+                } => {
+                    w.append_line(&format!("{}: {},", prev_name, type_.name()))?;
+                }
+                TrackedValueKind::Flagged { ref dirty_name } => {
+                    w.append_line(&format!("{}: {},", dirty_name, ValueType::Bool.name()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write_initializers<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        w.append_newline()?;
+        w.append_line("// Activity tracking")?;
+        w.append_line(&format!("__propagated: {},", ValueType::Bool.zero_str()))?;
+        for value in self.values.iter() {
+            match value.kind {
+                TrackedValueKind::Compared {
+                    ref prev_name,
+                    type_,
+                } => {
+                    w.append_line(&format!("{}: {},", prev_name, type_.zero_str()))?;
+                }
+                TrackedValueKind::Flagged { ref dirty_name } => {
+                    w.append_line(&format!("{}: {},", dirty_name, ValueType::Bool.zero_str()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Writes the start of `prop`, which determines which of the tracked values changed.
+    pub fn write_change_detection<W: Write>(
+        &self,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> Result<()> {
+        // Everything is evaluated on the first call, and after the state is restored
+        w.append_line("let __propagate_all = !self.__propagated;")?;
+        w.append_line("self.__propagated = true;")?;
+        for value in self.values.iter() {
+            match value.kind {
+                TrackedValueKind::Compared { ref prev_name, .. } => {
+                    w.append_line(&format!(
+                        "let {} = self.{} != self.{};",
+                        value.changed_name, value.name, prev_name
+                    ))?;
+                    w.append_line(&format!("self.{} = self.{};", prev_name, value.name))?;
+                }
+                TrackedValueKind::Flagged { ref dirty_name } => {
+                    w.append_line(&format!(
+                        "let {} = self.{};",
+                        value.changed_name, dirty_name
+                    ))?;
+                    w.append_line(&format!("self.{} = false;", dirty_name))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Writes the start of a block that's only evaluated if any of the values `chunk` reads changed.
+    pub fn write_chunk_begin<W: Write>(
+        &self,
+        chunk: &AssignmentChunk,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> Result<()> {
+        let mut cond = "__propagate_all".to_string();
+        for name in chunk.read_members.iter() {
+            cond.push_str(&format!(
+                " || {}",
+                self.values[self.indices[name]].changed_name
+            ));
+        }
+        w.append_line(&format!("if {} {{", cond))?;
+        w.indent();
+
+        Ok(())
+    }
+
+    pub fn write_chunk_end<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        w.unindent();
+        w.append_line("}")?;
+
+        Ok(())
+    }
+}
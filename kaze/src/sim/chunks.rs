@@ -0,0 +1,156 @@
+use super::activity::*;
+use super::ir::*;
+use super::lanes::*;
+use super::{write_impl_header, GenerationOptions};
+
+use crate::code_writer;
+use crate::graph;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Returns the temps that are passed from one of `prop`'s chunks to another, along with their types.
+pub(super) fn prop_temps<'a>(
+    prop_context: &AssignmentContext,
+    prop_chunks: &'a [AssignmentChunk],
+) -> Vec<(&'a str, ValueType)> {
+    prop_chunks
+        .iter()
+        .flat_map(|chunk| chunk.exported_temps.iter())
+        .map(|&name| (name, prop_context.temp_type(name)))
+        .collect()
+}
+
+pub(super) fn write_prop_temp_members<W: Write>(
+    prop_temps: &[(&str, ValueType)],
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> io::Result<()> {
+    w.append_newline()?;
+    w.append_line("// Prop temps")?;
+    for (name, type_) in prop_temps.iter() {
+        w.append_line(&format!("{}: {},", name, lanes.array(type_.name())))?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn write_prop_temp_initializers<W: Write>(
+    prop_temps: &[(&str, ValueType)],
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> io::Result<()> {
+    w.append_newline()?;
+    w.append_line("// Prop temps")?;
+    for (name, type_) in prop_temps.iter() {
+        w.append_line(&format!("{}: {},", name, lanes.array(type_.zero_str())))?;
+    }
+
+    Ok(())
+}
+
+// Writes the `prop_chunk_<n>` functions, each in its own `impl` block, either to separate files in
+//  `prop_chunk_dir` (which `w` includes) if it's specified, or to `w` otherwise.
+pub(super) fn write_prop_chunks<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: &GenerationOptions,
+    prop_chunks: &[AssignmentChunk],
+    prop_chunk_dir: Option<&Path>,
+    w: &mut code_writer::CodeWriter<W>,
+) -> io::Result<()> {
+    for (chunk_index, chunk) in prop_chunks.iter().enumerate() {
+        if let Some(prop_chunk_dir) = prop_chunk_dir {
+            let file_name = format!("{}_prop_chunk_{}.rs", m.name, chunk_index);
+            w.append_line(&format!("include!({:?});", file_name))?;
+            w.append_newline()?;
+
+            let mut file = BufWriter::new(File::create(prop_chunk_dir.join(file_name))?);
+            let mut chunk_w = code_writer::CodeWriter::new(&mut file);
+            write_prop_chunk(m, options, chunk_index, chunk, &mut chunk_w)?;
+            file.flush()?;
+        } else {
+            write_prop_chunk(m, options, chunk_index, chunk, w)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_prop_chunk<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: &GenerationOptions,
+    chunk_index: usize,
+    chunk: &AssignmentChunk,
+    w: &mut code_writer::CodeWriter<W>,
+) -> io::Result<()> {
+    write_impl_header(m, options, w)?;
+
+    w.append_line(&format!("fn prop_chunk_{}(&mut self) {{", chunk_index))?;
+    w.indent();
+
+    write_prop_chunk_body(chunk, Lanes::new(options.lanes), w)?;
+
+    w.unindent();
+    w.append_line("}")?;
+
+    w.unindent();
+    w.append_line("}")?;
+    w.append_newline()?;
+
+    Ok(())
+}
+
+// Writes the body of `prop`, which evaluates each of `prop_chunks` in order, either by calling its
+//  helper function (with `prop_chunk_size`) or inline, and (with activity tracking) only if any of
+//  the values it reads changed.
+pub(super) fn write_prop_body<W: Write>(
+    options: &GenerationOptions,
+    prop_chunks: &[AssignmentChunk],
+    tracked_values: &TrackedValues,
+    w: &mut code_writer::CodeWriter<W>,
+) -> io::Result<()> {
+    if options.activity_tracking {
+        tracked_values.write_change_detection(w)?;
+    }
+    for (chunk_index, chunk) in prop_chunks.iter().enumerate() {
+        if options.activity_tracking {
+            tracked_values.write_chunk_begin(chunk, w)?;
+        }
+        if options.prop_chunk_size.is_some() {
+            w.append_line(&format!("self.prop_chunk_{}();", chunk_index))?;
+        } else {
+            write_prop_chunk_body(chunk, Lanes::new(options.lanes), w)?;
+        }
+        if options.activity_tracking {
+            tracked_values.write_chunk_end(w)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_prop_chunk_body<W: Write>(
+    chunk: &AssignmentChunk,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> io::Result<()> {
+    lanes.write_loop_begin(w)?;
+    for name in chunk.imported_temps.iter() {
+        w.append_line(&format!(
+            "let {} = self.{}{};",
+            name,
+            name,
+            lanes.loop_index()
+        ))?;
+    }
+    for assignment in chunk.assignments.iter() {
+        assignment.write(w, lanes.loop_lane())?;
+    }
+    for name in chunk.exported_temps.iter() {
+        w.append_line(&format!("self.{}{} = {};", name, lanes.loop_index(), name))?;
+    }
+    lanes.write_loop_end(w)?;
+
+    Ok(())
+}
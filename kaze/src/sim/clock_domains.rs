@@ -0,0 +1,466 @@
+use super::activity::*;
+use super::compiler::*;
+use super::four_state;
+use super::ir::*;
+use super::lanes::*;
+use super::partition::{self, PropPartitions};
+use super::state_elements::*;
+use super::GenerationOptions;
+
+use crate::code_writer;
+use crate::graph;
+use crate::reset::*;
+
+use typed_arena::Arena;
+
+use std::io::{Result, Write};
+
+// The logic that resets and applies clock edges to a clock domain's state elements, along with the
+//  names of the methods that evaluate it and the members they use. The default clock domain's
+//  methods are only generated if they're non-empty, whereas explicit clock domains always get them
+//  so they can be driven uniformly.
+pub(super) struct ClockDomainLogic<'a, 'graph, 'arena, 'expr_arena> {
+    pub reset_context: AssignmentContext<'expr_arena>,
+    // Each partition's state elements are updated by a separate context
+    pub posedge_contexts: Vec<AssignmentContext<'expr_arena>>,
+    pub properties: Vec<&'a Property<'graph, 'arena>>,
+    pub reset_name: Option<String>,
+    pub posedge_name: Option<String>,
+    pub reset_pending_name: Option<String>,
+    pub cycle_name: Option<String>,
+}
+
+// Generates the logic for each of `m`'s clock domains (starting with its default clock domain),
+//  which resets and updates the registers and mems in `state_elements` and checks its properties.
+pub(super) fn gen_clock_domains<'a, 'graph, 'arena, 'expr_arena>(
+    m: &'graph graph::Module<'graph>,
+    c: &mut Compiler<'graph, 'arena, 'expr_arena>,
+    state_elements: &'a StateElements<'graph, 'arena>,
+    prop_partitions: Option<&PropPartitions<'graph, 'arena, 'expr_arena>>,
+    tracked_values: &TrackedValues,
+    options: &GenerationOptions,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+) -> Vec<ClockDomainLogic<'a, 'graph, 'arena, 'expr_arena>> {
+    let mut clock_domains = vec![m.default_clock_domain()];
+    clock_domains.extend(m.clock_domains.borrow().iter());
+    let mut reset_contexts = clock_domains
+        .iter()
+        .map(|_| AssignmentContext::new(expr_arena))
+        .collect::<Vec<_>>();
+    let num_partitions =
+        prop_partitions.map_or(1, |prop_partitions| prop_partitions.num_partitions());
+    let mut posedge_contexts = clock_domains
+        .iter()
+        .map(|_| {
+            (0..num_partitions)
+                .map(|_| AssignmentContext::new(expr_arena))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let partition_index = |context| {
+        prop_partitions.map_or(0, |prop_partitions| {
+            prop_partitions.partition_index(context)
+        })
+    };
+    let clock_domain_index = |root_clock_domain: &'graph graph::ClockDomain<'graph>| {
+        clock_domains
+            .iter()
+            .position(|clock_domain| *clock_domain == root_clock_domain)
+            .unwrap()
+    };
+
+    for ((context, _), reg) in state_elements.regs.iter() {
+        let index = clock_domain_index(context.root_clock_domain(reg.data.resolved_clock_domain()));
+        let posedge_context = &mut posedge_contexts[index][partition_index(context)];
+
+        let target = expr_arena.alloc(Expr::Ref {
+            name: reg.value_name.clone(),
+            scope: Scope::Member,
+        });
+
+        if let Some(ref initial_value) = *reg.data.initial_value.borrow() {
+            reset_contexts[index].push(Assignment {
+                target,
+                expr: Expr::from_constant(initial_value, reg.data.bit_width, expr_arena),
+            });
+            if options.four_state {
+                four_state::gen_reg_reset_assignment(reg, expr_arena, &mut reset_contexts[index]);
+            }
+        }
+
+        posedge_context.push(Assignment {
+            target,
+            expr: expr_arena.alloc(Expr::Ref {
+                name: reg.next_name.clone(),
+                scope: Scope::Member,
+            }),
+        });
+        if options.four_state {
+            four_state::gen_reg_posedge_assignment(reg, expr_arena, posedge_context);
+        }
+    }
+
+    let mut clock_domain_properties = clock_domains.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+    for property in state_elements.properties.iter() {
+        let index = clock_domain_index(
+            property
+                .context
+                .root_clock_domain(property.property.resolved_clock_domain()),
+        );
+        clock_domain_properties[index].push(property);
+    }
+
+    for ((context, _), mem) in state_elements.mems.iter() {
+        let index = clock_domain_index(context.root_clock_domain(mem.mem.resolved_clock_domain()));
+        let posedge_context = &mut posedge_contexts[index][partition_index(context)];
+
+        // Read-first read ports are read before writes are applied so that they return the
+        //  previous value, while other read ports are read afterwards instead, so that they return
+        //  the new value (don't-care reads are additionally made unknown with four-state
+        //  simulation, like in Verilog)
+        for (read_port, read_signal_names) in mem.read_signal_names.iter() {
+            if read_port.read_during_write == graph::ReadDuringWrite::ReadFirst {
+                gen_read_port_assignments(
+                    c,
+                    mem,
+                    read_port,
+                    read_signal_names,
+                    options.four_state,
+                    expr_arena,
+                    posedge_context,
+                );
+            }
+        }
+        for (write_port, write_signal_names) in mem
+            .mem
+            .write_ports
+            .borrow()
+            .iter()
+            .zip(mem.write_signal_names.iter())
+        {
+            let address = expr_arena.alloc(Expr::Ref {
+                name: write_signal_names.address_name.clone(),
+                scope: Scope::Member,
+            });
+            let value = expr_arena.alloc(Expr::Ref {
+                name: write_signal_names.value_name.clone(),
+                scope: Scope::Member,
+            });
+            let mut enable = &*expr_arena.alloc(Expr::Ref {
+                name: write_signal_names.enable_name.clone(),
+                scope: Scope::Member,
+            });
+            let element = expr_arena.alloc(Expr::ArrayIndex {
+                target: expr_arena.alloc(Expr::Ref {
+                    name: mem.mem_name.clone(),
+                    scope: Scope::Member,
+                }),
+                index: address,
+            });
+            let mut bit_mask = write_port.mask.map(|_| {
+                &*expr_arena.alloc(Expr::Ref {
+                    name: write_signal_names.mask_name.clone(),
+                    scope: Scope::Member,
+                })
+            });
+            if options.four_state {
+                (enable, bit_mask) = four_state::gen_write_port_assignment(
+                    c,
+                    mem,
+                    write_signal_names,
+                    enable,
+                    bit_mask,
+                    expr_arena,
+                    posedge_context,
+                );
+            }
+            let value = match bit_mask {
+                Some(bit_mask) => {
+                    c.gen_masked_merge(element, value, bit_mask, mem.mem.element_bit_width)
+                }
+                _ => value,
+            };
+            // TODO: Conditional assign statement instead of always writing ternary
+            posedge_context.push(Assignment {
+                target: element,
+                expr: expr_arena.alloc(Expr::Ternary {
+                    cond: enable,
+                    when_true: value,
+                    when_false: element,
+                }),
+            });
+            tracked_values.gen_mem_write_assignment(
+                &mem.mem_name,
+                enable,
+                expr_arena,
+                posedge_context,
+            );
+        }
+        for (read_port, read_signal_names) in mem.read_signal_names.iter() {
+            if read_port.read_during_write != graph::ReadDuringWrite::ReadFirst {
+                gen_read_port_assignments(
+                    c,
+                    mem,
+                    read_port,
+                    read_signal_names,
+                    options.four_state,
+                    expr_arena,
+                    posedge_context,
+                );
+            }
+        }
+    }
+
+    clock_domains
+        .iter()
+        .zip(reset_contexts)
+        .zip(posedge_contexts)
+        .zip(clock_domain_properties)
+        .map(
+            |(((clock_domain, reset_context), posedge_contexts), properties)| {
+                let (reset_name, posedge_name, reset_pending_name, cycle_name) =
+                    match clock_domain.name {
+                        Some(ref name) => (
+                            format!("reset_{}", name),
+                            format!("posedge_{}", name),
+                            format!("__reset_pending_{}", name),
+                            format!("__cycle_{}", name),
+                        ),
+                        _ => (
+                            "reset".into(),
+                            "posedge_clk".into(),
+                            "__reset_pending".into(),
+                            "__cycle".into(),
+                        ),
+                    };
+                let is_default = clock_domain.name.is_none();
+                let has_reset = !is_default || !reset_context.is_empty();
+                let has_posedge = !is_default
+                    || posedge_contexts.iter().any(|context| !context.is_empty())
+                    || !properties.is_empty();
+                ClockDomainLogic {
+                    reset_name: if has_reset { Some(reset_name) } else { None },
+                    posedge_name: if has_posedge {
+                        Some(posedge_name)
+                    } else {
+                        None
+                    },
+                    reset_pending_name: if has_reset && options.reset_type == ResetType::Synchronous
+                    {
+                        Some(reset_pending_name)
+                    } else {
+                        None
+                    },
+                    cycle_name: if !properties.is_empty() {
+                        Some(cycle_name)
+                    } else {
+                        None
+                    },
+                    reset_context,
+                    posedge_contexts,
+                    properties,
+                }
+            },
+        )
+        .collect()
+}
+
+impl<'a, 'graph, 'arena, 'expr_arena> ClockDomainLogic<'a, 'graph, 'arena, 'expr_arena> {
+    // Writes the clock domain's `reset_*` and `posedge_*` methods (if it has them).
+    pub fn write_methods<W: Write>(
+        &self,
+        lanes: Lanes,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> Result<()> {
+        if let Some(ref reset_name) = self.reset_name {
+            w.append_newline()?;
+            w.append_line(&format!("pub fn {}(&mut self) {{", reset_name))?;
+            w.indent();
+
+            if let Some(ref reset_pending_name) = self.reset_pending_name {
+                w.append_line(&format!("self.{} = true;", reset_pending_name))?;
+            } else {
+                lanes.write_loop_begin(w)?;
+                self.reset_context.write(w, lanes.loop_lane())?;
+                lanes.write_loop_end(w)?;
+            }
+
+            w.unindent();
+            w.append_line("}")?;
+        }
+
+        if let Some(ref posedge_name) = self.posedge_name {
+            w.append_newline()?;
+            w.append_line(&format!("pub fn {}(&mut self) {{", posedge_name))?;
+            w.indent();
+
+            if let Some(ref cycle_name) = self.cycle_name {
+                self.write_property_checks(cycle_name, lanes, w)?;
+            }
+
+            if self
+                .posedge_contexts
+                .iter()
+                .filter(|context| !context.is_empty())
+                .count()
+                > 1
+            {
+                partition::write_posedge_body(&self.posedge_contexts, w)?;
+            } else {
+                lanes.write_loop_begin(w)?;
+                for posedge_context in self.posedge_contexts.iter() {
+                    posedge_context.write(w, lanes.loop_lane())?;
+                }
+                lanes.write_loop_end(w)?;
+            }
+
+            if let Some(ref reset_pending_name) = self.reset_pending_name {
+                w.append_line(&format!("if self.{} {{", reset_pending_name))?;
+                w.indent();
+
+                lanes.write_loop_begin(w)?;
+                self.reset_context.write(w, lanes.loop_lane())?;
+                lanes.write_loop_end(w)?;
+                w.append_line(&format!("self.{} = false;", reset_pending_name))?;
+
+                w.unindent();
+                w.append_line("}")?;
+            }
+
+            w.unindent();
+            w.append_line("}")?;
+        }
+
+        Ok(())
+    }
+
+    // Writes the part of the `posedge_*` method that checks the clock domain's properties and
+    //  counts its cycles, where `cycle_name` is the member holding the number of cycles.
+    fn write_property_checks<W: Write>(
+        &self,
+        cycle_name: &str,
+        lanes: Lanes,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> Result<()> {
+        // Properties aren't checked while reset is asserted
+        if let Some(ref reset_pending_name) = self.reset_pending_name {
+            w.append_line(&format!("if !self.{} {{", reset_pending_name))?;
+            w.indent();
+        }
+        if !self.properties.is_empty() {
+            lanes.write_loop_begin(w)?;
+        }
+        for property in self.properties.iter() {
+            let failure = match property.property.kind {
+                graph::PropertyKind::Assert => "Assertion {:?} failed",
+                graph::PropertyKind::Assume => "Assumption {:?} violated",
+                graph::PropertyKind::Cover => {
+                    w.append_line(&format!(
+                        "if self.{}{} {{",
+                        property.value_name,
+                        lanes.loop_index()
+                    ))?;
+                    w.indent();
+                    w.append_line(&format!(
+                        "self.{}{} += 1;",
+                        property.count_name,
+                        lanes.loop_index()
+                    ))?;
+                    w.unindent();
+                    w.append_line("}")?;
+                    continue;
+                }
+            };
+            w.append_line(&format!(
+                "if !self.{}{} {{",
+                property.value_name,
+                lanes.loop_index()
+            ))?;
+            w.indent();
+            if lanes.is_some() {
+                w.append_line(&format!(
+                    "panic!(\"{} in cycle {{}} (lane {{}})\", {:?}, self.{}, __lane);",
+                    failure, property.path, cycle_name
+                ))?;
+            } else {
+                w.append_line(&format!(
+                    "panic!(\"{} in cycle {{}}\", {:?}, self.{});",
+                    failure, property.path, cycle_name
+                ))?;
+            }
+            w.unindent();
+            w.append_line("}")?;
+        }
+        if !self.properties.is_empty() {
+            lanes.write_loop_end(w)?;
+        }
+        if self.reset_pending_name.is_some() {
+            w.unindent();
+            w.append_line("}")?;
+        }
+        w.append_line(&format!("self.{} += 1;", cycle_name))?;
+
+        Ok(())
+    }
+}
+
+fn gen_read_port_assignments<'graph, 'context_arena, 'expr_arena>(
+    c: &mut Compiler<'graph, 'context_arena, 'expr_arena>,
+    mem: &Mem,
+    read_port: &graph::ReadPort,
+    read_signal_names: &ReadSignalNames,
+    four_state: bool,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+    a: &mut AssignmentContext<'expr_arena>,
+) {
+    let address = expr_arena.alloc(Expr::Ref {
+        name: read_signal_names.address_name.clone(),
+        scope: Scope::Member,
+    });
+    let mut enable = &*expr_arena.alloc(Expr::Ref {
+        name: read_signal_names.enable_name.clone(),
+        scope: Scope::Member,
+    });
+    let value = expr_arena.alloc(Expr::Ref {
+        name: read_signal_names.value_name.clone(),
+        scope: Scope::Member,
+    });
+    let element = expr_arena.alloc(Expr::ArrayIndex {
+        target: expr_arena.alloc(Expr::Ref {
+            name: mem.mem_name.clone(),
+            scope: Scope::Member,
+        }),
+        index: address,
+    });
+    if four_state {
+        enable = four_state::gen_read_port_assignment(
+            c,
+            mem,
+            read_signal_names,
+            address,
+            enable,
+            expr_arena,
+            a,
+        );
+        if read_port.read_during_write == graph::ReadDuringWrite::DontCare {
+            four_state::gen_dont_care_read_assignment(
+                c,
+                mem,
+                read_signal_names,
+                address,
+                enable,
+                expr_arena,
+                a,
+            );
+        }
+    }
+    // TODO: Conditional assign statement instead of always writing ternary
+    a.push(Assignment {
+        target: value,
+        expr: expr_arena.alloc(Expr::Ternary {
+            cond: enable,
+            when_true: element,
+            when_false: value,
+        }),
+    });
+}
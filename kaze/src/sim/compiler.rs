@@ -1,8 +1,10 @@
 use super::ir::*;
+use super::partition::*;
 use super::state_elements::*;
 
 use crate::graph;
 use crate::module_context::*;
+use crate::simplify::*;
 
 use typed_arena::Arena;
 
@@ -10,46 +12,129 @@ use std::collections::HashMap;
 
 pub(super) struct Compiler<'graph, 'context_arena, 'expr_arena> {
     state_elements: &'context_arena StateElements<'graph, 'context_arena>,
-    signal_reference_counts: &'context_arena HashMap<
+    signal_reference_counts: &'context_arena SignalReferenceCounts<'graph, 'context_arena>,
+    simplifier: Simplifier<'graph>,
+    context_arena: &'context_arena Arena<ModuleContext<'graph, 'context_arena>>,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+
+    signal_exprs: HashMap<
         (
             &'context_arena ModuleContext<'graph, 'context_arena>,
             &'graph graph::Signal<'graph>,
         ),
-        u32,
+        &'expr_arena Expr<'expr_arena>,
     >,
-    context_arena: &'context_arena Arena<ModuleContext<'graph, 'context_arena>>,
-    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
-
-    signal_exprs: HashMap<
+    signal_unknown_exprs: HashMap<
         (
             &'context_arena ModuleContext<'graph, 'context_arena>,
             &'graph graph::Signal<'graph>,
         ),
         &'expr_arena Expr<'expr_arena>,
     >,
+
+    partitioning: Option<Partitioning<'graph, 'context_arena>>,
 }
 
 impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr_arena> {
     pub fn new(
         state_elements: &'context_arena StateElements<'graph, 'context_arena>,
-        signal_reference_counts: &'context_arena HashMap<
-            (
-                &'context_arena ModuleContext<'graph, 'context_arena>,
-                &'graph graph::Signal<'graph>,
-            ),
-            u32,
-        >,
+        signal_reference_counts: &'context_arena SignalReferenceCounts<'graph, 'context_arena>,
+        simplifier: Simplifier<'graph>,
         context_arena: &'context_arena Arena<ModuleContext<'graph, 'context_arena>>,
         expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
     ) -> Compiler<'graph, 'context_arena, 'expr_arena> {
         Compiler {
             state_elements,
             signal_reference_counts,
+            simplifier,
             context_arena,
             expr_arena,
 
             signal_exprs: HashMap::new(),
+            signal_unknown_exprs: HashMap::new(),
+
+            partitioning: None,
+        }
+    }
+
+    // With partitioning, signals that are reached through instance ports and computed by another
+    //  partition than the one being compiled are read from the values that partition passes on,
+    //  rather than being compiled again.
+    pub fn set_partitioning(&mut self, partitioning: Partitioning<'graph, 'context_arena>) {
+        self.partitioning = Some(partitioning);
+    }
+
+    pub fn take_partitioning(&mut self) -> Option<Partitioning<'graph, 'context_arena>> {
+        self.partitioning.take()
+    }
+
+    pub fn simplify(
+        &mut self,
+        signal: &'graph graph::Signal<'graph>,
+    ) -> &'graph graph::Signal<'graph> {
+        self.simplifier.simplify(signal)
+    }
+
+    // Follows `signal` in `context` through the instance ports it's connected to (if any), and
+    //  returns the (simplified) signal that drives it, along with that signal's context.
+    pub fn resolve_ports(
+        &mut self,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'context_arena ModuleContext<'graph, 'context_arena>,
+    ) -> (
+        &'graph graph::Signal<'graph>,
+        &'context_arena ModuleContext<'graph, 'context_arena>,
+    ) {
+        let mut signal = self.simplifier.simplify(signal);
+        let mut context = context;
+        loop {
+            match signal.data {
+                graph::SignalData::Input { ref name, .. } => match context.instance_and_parent {
+                    Some((instance, parent)) => {
+                        signal = instance.driven_inputs.borrow()[name];
+                        context = parent;
+                    }
+                    _ => break,
+                },
+                graph::SignalData::InstanceOutput {
+                    instance, ref name, ..
+                } => {
+                    signal = instance.instantiated_module.outputs.borrow()[name];
+                    context = context.get_child(instance, self.context_arena);
+                }
+                _ => break,
+            }
+            signal = self.simplifier.simplify(signal);
         }
+        (signal, context)
+    }
+
+    // Returns a reference to the value (or unknown mask) of `signal` in `context`, which is reached
+    //  through an instance port, if it's computed by another partition than the one being compiled.
+    fn cross_partition_ref(
+        &mut self,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'context_arena ModuleContext<'graph, 'context_arena>,
+        is_unknown: bool,
+    ) -> Option<&'expr_arena Expr<'expr_arena>> {
+        self.partitioning.as_ref()?;
+        let (signal, context) = self.resolve_ports(signal, context);
+        let name = self
+            .partitioning
+            .as_mut()
+            .unwrap()
+            .cross_value_ref(signal, context, is_unknown)?;
+        Some(self.expr_arena.alloc(Expr::Ref {
+            name,
+            scope: Scope::Local,
+        }))
+    }
+
+    // Forgets which expressions previously compiled signals were compiled to, so that signals
+    //  compiled afterwards don't refer to temps in another assignment context.
+    pub fn clear_signal_exprs(&mut self) {
+        self.signal_exprs.clear();
+        self.signal_unknown_exprs.clear();
     }
 
     pub fn compile_signal(
@@ -77,6 +162,7 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
         while let Some(frame) = frames.pop() {
             if let Some((key, mut expr)) = match frame {
                 Frame::Enter { signal, context } => {
+                    let signal = self.simplifier.simplify(signal);
                     let key = (context, signal);
                     if let Some(expr) = self.signal_exprs.get(&key) {
                         results.push(*expr);
@@ -93,7 +179,9 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             ref name,
                             bit_width,
                         } => {
-                            if let Some((instance, parent)) = context.instance_and_parent {
+                            if let Some(expr) = self.cross_partition_ref(signal, context, false) {
+                                Some((key, expr))
+                            } else if let Some((instance, parent)) = context.instance_and_parent {
                                 frames.push(Frame::Enter {
                                     signal: instance.driven_inputs.borrow()[name],
                                     context: parent,
@@ -254,21 +342,30 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                         graph::SignalData::InstanceOutput {
                             instance, ref name, ..
                         } => {
-                            let output = instance.instantiated_module.outputs.borrow()[name];
-                            frames.push(Frame::Enter {
-                                signal: output,
-                                context: context.get_child(instance, self.context_arena),
-                            });
-                            None
+                            if let Some(expr) = self.cross_partition_ref(signal, context, false) {
+                                Some((key, expr))
+                            } else {
+                                let output = instance.instantiated_module.outputs.borrow()[name];
+                                frames.push(Frame::Enter {
+                                    signal: output,
+                                    context: context.get_child(instance, self.context_arena),
+                                });
+                                None
+                            }
                         }
 
                         graph::SignalData::MemReadPortOutput {
                             mem,
                             address,
                             enable,
+                            read_during_write,
                         } => {
                             let mem = &self.state_elements.mems[&(context, mem)];
-                            let read_signal_names = &mem.read_signal_names[&(address, enable)];
+                            let read_signal_names = &mem.read_signal_names[&graph::ReadPort {
+                                address,
+                                enable,
+                                read_during_write,
+                            }];
                             Some((
                                 key,
                                 &*self.expr_arena.alloc(Expr::Ref {
@@ -277,6 +374,15 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                                 }),
                             ))
                         }
+
+                        graph::SignalData::MemAsyncReadPortOutput { address, .. } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: address,
+                                context,
+                            });
+                            None
+                        }
                     }
                 }
                 Frame::Leave { signal, context } => {
@@ -291,30 +397,41 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
 
                         graph::SignalData::UnOp { op, bit_width, .. } => {
                             let expr = results.pop().unwrap();
-                            let expr = self.expr_arena.alloc(Expr::UnOp {
-                                source: expr,
-                                op: match op {
-                                    graph::UnOp::Not => UnOp::Not,
-                                },
-                            });
-
                             let target_type = ValueType::from_bit_width(bit_width);
+                            let expr = if target_type.is_wide() {
+                                self.gen_wide_call(
+                                    match op {
+                                        graph::UnOp::Not => "not",
+                                    },
+                                    vec![expr],
+                                )
+                            } else {
+                                self.expr_arena.alloc(Expr::UnOp {
+                                    source: expr,
+                                    op: match op {
+                                        graph::UnOp::Not => UnOp::Not,
+                                    },
+                                })
+                            };
+
                             Some((key, self.gen_mask(expr, bit_width, target_type)))
                         }
-                        graph::SignalData::SimpleBinOp { op, .. } => {
+                        graph::SignalData::SimpleBinOp { op, bit_width, .. } => {
                             let lhs = results.pop().unwrap();
                             let rhs = results.pop().unwrap();
+                            let op = match op {
+                                graph::SimpleBinOp::BitAnd => InfixBinOp::BitAnd,
+                                graph::SimpleBinOp::BitOr => InfixBinOp::BitOr,
+                                graph::SimpleBinOp::BitXor => InfixBinOp::BitXor,
+                            };
                             Some((
                                 key,
-                                &*self.expr_arena.alloc(Expr::InfixBinOp {
+                                self.gen_bitwise_op(
                                     lhs,
                                     rhs,
-                                    op: match op {
-                                        graph::SimpleBinOp::BitAnd => InfixBinOp::BitAnd,
-                                        graph::SimpleBinOp::BitOr => InfixBinOp::BitOr,
-                                        graph::SimpleBinOp::BitXor => InfixBinOp::BitXor,
-                                    },
-                                }),
+                                    op,
+                                    ValueType::from_bit_width(bit_width),
+                                ),
                             ))
                         }
                         graph::SignalData::AdditiveBinOp { lhs, op, .. } => {
@@ -328,14 +445,24 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             };
                             let lhs = self.gen_cast(lhs, source_type, op_input_type);
                             let rhs = self.gen_cast(rhs, source_type, op_input_type);
-                            let expr = self.expr_arena.alloc(Expr::UnaryMemberCall {
-                                target: lhs,
-                                name: match op {
-                                    graph::AdditiveBinOp::Add => "wrapping_add".into(),
-                                    graph::AdditiveBinOp::Sub => "wrapping_sub".into(),
-                                },
-                                arg: rhs,
-                            });
+                            let expr = if op_input_type.is_wide() {
+                                self.gen_wide_call(
+                                    match op {
+                                        graph::AdditiveBinOp::Add => "add",
+                                        graph::AdditiveBinOp::Sub => "sub",
+                                    },
+                                    vec![lhs, rhs],
+                                )
+                            } else {
+                                self.expr_arena.alloc(Expr::UnaryMemberCall {
+                                    target: lhs,
+                                    name: match op {
+                                        graph::AdditiveBinOp::Add => "wrapping_add".into(),
+                                        graph::AdditiveBinOp::Sub => "wrapping_sub".into(),
+                                    },
+                                    arg: rhs,
+                                })
+                            };
                             let op_output_type = op_input_type;
                             let target_bit_width = signal.bit_width();
                             let target_type = ValueType::from_bit_width(target_bit_width);
@@ -347,54 +474,52 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             let source_type = ValueType::from_bit_width(source_bit_width);
                             let mut lhs = results.pop().unwrap();
                             let mut rhs = results.pop().unwrap();
-                            match op {
+                            let signed = match op {
                                 graph::ComparisonBinOp::GreaterThanEqualSigned
                                 | graph::ComparisonBinOp::GreaterThanSigned
                                 | graph::ComparisonBinOp::LessThanEqualSigned
                                 | graph::ComparisonBinOp::LessThanSigned => {
-                                    let source_type_signed = source_type.to_signed();
-                                    lhs = self.gen_cast(lhs, source_type, source_type_signed);
-                                    rhs = self.gen_cast(rhs, source_type, source_type_signed);
-                                    lhs = self.gen_sign_extend_shifts(
-                                        lhs,
-                                        source_bit_width,
-                                        source_type_signed,
-                                    );
-                                    rhs = self.gen_sign_extend_shifts(
-                                        rhs,
-                                        source_bit_width,
-                                        source_type_signed,
-                                    );
+                                    if source_type.is_wide() {
+                                        lhs = self.gen_wide_sign_extend(lhs, source_bit_width);
+                                        rhs = self.gen_wide_sign_extend(rhs, source_bit_width);
+                                    } else {
+                                        let source_type_signed = source_type.to_signed();
+                                        lhs = self.gen_cast(lhs, source_type, source_type_signed);
+                                        rhs = self.gen_cast(rhs, source_type, source_type_signed);
+                                        lhs = self.gen_sign_extend_shifts(
+                                            lhs,
+                                            source_bit_width,
+                                            source_type_signed,
+                                        );
+                                        rhs = self.gen_sign_extend_shifts(
+                                            rhs,
+                                            source_bit_width,
+                                            source_type_signed,
+                                        );
+                                    }
+                                    true
                                 }
-                                _ => (),
-                            }
-                            Some((
-                                key,
-                                &*self.expr_arena.alloc(Expr::InfixBinOp {
-                                    lhs,
-                                    rhs,
-                                    op: match op {
-                                        graph::ComparisonBinOp::Equal => InfixBinOp::Equal,
-                                        graph::ComparisonBinOp::NotEqual => InfixBinOp::NotEqual,
-                                        graph::ComparisonBinOp::LessThan
-                                        | graph::ComparisonBinOp::LessThanSigned => {
-                                            InfixBinOp::LessThan
-                                        }
-                                        graph::ComparisonBinOp::LessThanEqual
-                                        | graph::ComparisonBinOp::LessThanEqualSigned => {
-                                            InfixBinOp::LessThanEqual
-                                        }
-                                        graph::ComparisonBinOp::GreaterThan
-                                        | graph::ComparisonBinOp::GreaterThanSigned => {
-                                            InfixBinOp::GreaterThan
-                                        }
-                                        graph::ComparisonBinOp::GreaterThanEqual
-                                        | graph::ComparisonBinOp::GreaterThanEqualSigned => {
-                                            InfixBinOp::GreaterThanEqual
-                                        }
-                                    },
-                                }),
-                            ))
+                                _ => false,
+                            };
+                            let op = match op {
+                                graph::ComparisonBinOp::Equal => InfixBinOp::Equal,
+                                graph::ComparisonBinOp::NotEqual => InfixBinOp::NotEqual,
+                                graph::ComparisonBinOp::LessThan
+                                | graph::ComparisonBinOp::LessThanSigned => InfixBinOp::LessThan,
+                                graph::ComparisonBinOp::LessThanEqual
+                                | graph::ComparisonBinOp::LessThanEqualSigned => {
+                                    InfixBinOp::LessThanEqual
+                                }
+                                graph::ComparisonBinOp::GreaterThan
+                                | graph::ComparisonBinOp::GreaterThanSigned => {
+                                    InfixBinOp::GreaterThan
+                                }
+                                graph::ComparisonBinOp::GreaterThanEqual
+                                | graph::ComparisonBinOp::GreaterThanEqualSigned => {
+                                    InfixBinOp::GreaterThanEqual
+                                }
+                            };
+                            Some((key, self.gen_comparison(lhs, rhs, op, source_type, signed)))
                         }
                         graph::SignalData::ShiftBinOp {
                             lhs,
@@ -408,100 +533,22 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             let rhs_source_type = ValueType::from_bit_width(rhs_source_bit_width);
                             let lhs = results.pop().unwrap();
                             let rhs = results.pop().unwrap();
-                            let lhs_op_input_type = match lhs_source_type {
-                                ValueType::Bool => ValueType::U32,
-                                _ => lhs_source_type,
-                            };
-                            let lhs = self.gen_cast(lhs, lhs_source_type, lhs_op_input_type);
-                            let lhs = match op {
-                                graph::ShiftBinOp::Shl | graph::ShiftBinOp::Shr => lhs,
-                                graph::ShiftBinOp::ShrArithmetic => {
-                                    let lhs_op_input_type_signed = lhs_op_input_type.to_signed();
-                                    let lhs = self.gen_cast(
-                                        lhs,
-                                        lhs_op_input_type,
-                                        lhs_op_input_type_signed,
-                                    );
-                                    self.gen_sign_extend_shifts(
-                                        lhs,
-                                        lhs_source_bit_width,
-                                        lhs_op_input_type_signed,
-                                    )
-                                }
-                            };
-                            let rhs_op_input_type = match rhs_source_type {
-                                ValueType::Bool => ValueType::U32,
-                                _ => rhs_source_type,
-                            };
-                            let rhs = self.gen_cast(rhs, rhs_source_type, rhs_op_input_type);
-                            let rhs = self.expr_arena.alloc(Expr::BinaryFunctionCall {
-                                name: "std::cmp::min".into(),
-                                lhs: rhs,
-                                rhs: self.expr_arena.alloc(Expr::Constant {
-                                    value: match rhs_op_input_type {
-                                        ValueType::Bool
-                                        | ValueType::I32
-                                        | ValueType::I64
-                                        | ValueType::I128 => unreachable!(),
-                                        ValueType::U32 => Constant::U32(std::u32::MAX),
-                                        ValueType::U64 => Constant::U64(std::u32::MAX as _),
-                                        ValueType::U128 => Constant::U128(std::u32::MAX as _),
-                                    },
-                                }),
-                            });
-                            let rhs = self.gen_cast(rhs, lhs_op_input_type, ValueType::U32);
-                            let expr = self.expr_arena.alloc(Expr::UnaryMemberCall {
-                                target: lhs,
-                                name: match op {
-                                    graph::ShiftBinOp::Shl => "checked_shl".into(),
-                                    graph::ShiftBinOp::Shr | graph::ShiftBinOp::ShrArithmetic => {
-                                        "checked_shr".into()
-                                    }
-                                },
-                                arg: rhs,
-                            });
-                            let expr = self.expr_arena.alloc(Expr::UnaryMemberCall {
-                                target: expr,
-                                name: "unwrap_or".into(),
-                                arg: match op {
-                                    graph::ShiftBinOp::Shl | graph::ShiftBinOp::Shr => {
-                                        self.expr_arena.alloc(Expr::Constant {
-                                            value: match lhs_op_input_type {
-                                                ValueType::Bool
-                                                | ValueType::I32
-                                                | ValueType::I64
-                                                | ValueType::I128 => unreachable!(),
-                                                ValueType::U32 => Constant::U32(0),
-                                                ValueType::U64 => Constant::U64(0),
-                                                ValueType::U128 => Constant::U128(0),
-                                            },
-                                        })
-                                    }
-                                    graph::ShiftBinOp::ShrArithmetic => {
-                                        self.expr_arena.alloc(Expr::InfixBinOp {
-                                            lhs,
-                                            rhs: self.expr_arena.alloc(Expr::Constant {
-                                                value: Constant::U32(
-                                                    lhs_op_input_type.bit_width() - 1,
-                                                ),
-                                            }),
-                                            op: InfixBinOp::Shr,
-                                        })
-                                    }
-                                },
-                            });
-                            let op_output_type = lhs_op_input_type;
-                            let expr = match op {
-                                graph::ShiftBinOp::Shl | graph::ShiftBinOp::Shr => expr,
-                                graph::ShiftBinOp::ShrArithmetic => {
-                                    let lhs_op_output_type_signed = op_output_type.to_signed();
-                                    self.gen_cast(expr, lhs_op_output_type_signed, op_output_type)
-                                }
+                            let rhs = self.gen_shift_amount(rhs, rhs_source_type);
+                            let expr = if lhs_source_type.is_wide() {
+                                let (name, lhs) = match op {
+                                    graph::ShiftBinOp::Shl => ("shl", lhs),
+                                    graph::ShiftBinOp::Shr => ("shr", lhs),
+                                    graph::ShiftBinOp::ShrArithmetic => (
+                                        "shr_arithmetic",
+                                        self.gen_wide_sign_extend(lhs, lhs_source_bit_width),
+                                    ),
+                                };
+                                self.gen_wide_call(name, vec![lhs, rhs])
+                            } else {
+                                self.gen_native_shift(lhs, rhs, op, lhs_source_bit_width, bit_width)
                             };
-                            let target_bit_width = bit_width;
-                            let target_type = ValueType::from_bit_width(target_bit_width);
-                            let expr = self.gen_cast(expr, op_output_type, target_type);
-                            Some((key, self.gen_mask(expr, target_bit_width, target_type)))
+                            let target_type = ValueType::from_bit_width(bit_width);
+                            Some((key, self.gen_mask(expr, bit_width, target_type)))
                         }
 
                         graph::SignalData::Mul {
@@ -516,14 +563,18 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             let target_type = ValueType::from_bit_width(bit_width);
                             let lhs = self.gen_cast(lhs, lhs_type, target_type);
                             let rhs = self.gen_cast(rhs, rhs_type, target_type);
-                            Some((
-                                key,
-                                &*self.expr_arena.alloc(Expr::InfixBinOp {
-                                    lhs,
-                                    rhs,
-                                    op: InfixBinOp::Mul,
-                                }),
-                            ))
+                            if target_type.is_wide() {
+                                Some((key, self.gen_wide_call("mul", vec![lhs, rhs])))
+                            } else {
+                                Some((
+                                    key,
+                                    &*self.expr_arena.alloc(Expr::InfixBinOp {
+                                        lhs,
+                                        rhs,
+                                        op: InfixBinOp::Mul,
+                                    }),
+                                ))
+                            }
                         }
                         graph::SignalData::MulSigned {
                             lhs,
@@ -538,19 +589,33 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             let rhs = results.pop().unwrap();
                             let target_bit_width = bit_width;
                             let target_type = ValueType::from_bit_width(target_bit_width);
-                            let target_type_signed = target_type.to_signed();
-                            let lhs = self.gen_cast(lhs, lhs_type, target_type_signed);
-                            let rhs = self.gen_cast(rhs, rhs_type, target_type_signed);
-                            let lhs =
-                                self.gen_sign_extend_shifts(lhs, lhs_bit_width, target_type_signed);
-                            let rhs =
-                                self.gen_sign_extend_shifts(rhs, rhs_bit_width, target_type_signed);
-                            let expr = self.expr_arena.alloc(Expr::InfixBinOp {
-                                lhs,
-                                rhs,
-                                op: InfixBinOp::Mul,
-                            });
-                            let expr = self.gen_cast(expr, target_type_signed, target_type);
+                            let expr = if target_type.is_wide() {
+                                let lhs = self.gen_cast(lhs, lhs_type, target_type);
+                                let rhs = self.gen_cast(rhs, rhs_type, target_type);
+                                let lhs = self.gen_wide_sign_extend(lhs, lhs_bit_width);
+                                let rhs = self.gen_wide_sign_extend(rhs, rhs_bit_width);
+                                self.gen_wide_call("mul", vec![lhs, rhs])
+                            } else {
+                                let target_type_signed = target_type.to_signed();
+                                let lhs = self.gen_cast(lhs, lhs_type, target_type_signed);
+                                let rhs = self.gen_cast(rhs, rhs_type, target_type_signed);
+                                let lhs = self.gen_sign_extend_shifts(
+                                    lhs,
+                                    lhs_bit_width,
+                                    target_type_signed,
+                                );
+                                let rhs = self.gen_sign_extend_shifts(
+                                    rhs,
+                                    rhs_bit_width,
+                                    target_type_signed,
+                                );
+                                let expr = self.expr_arena.alloc(Expr::InfixBinOp {
+                                    lhs,
+                                    rhs,
+                                    op: InfixBinOp::Mul,
+                                });
+                                self.gen_cast(expr, target_type_signed, target_type)
+                            };
                             Some((key, self.gen_mask(expr, target_bit_width, target_type)))
                         }
 
@@ -558,14 +623,11 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             source, range_low, ..
                         } => {
                             let expr = results.pop().unwrap();
-                            let expr = self.gen_shift_right(expr, range_low);
+                            let source_type = ValueType::from_bit_width(source.bit_width());
+                            let expr = self.gen_shift_right(expr, range_low, source_type);
                             let target_bit_width = signal.bit_width();
                             let target_type = ValueType::from_bit_width(target_bit_width);
-                            let expr = self.gen_cast(
-                                expr,
-                                ValueType::from_bit_width(source.bit_width()),
-                                target_type,
-                            );
+                            let expr = self.gen_cast(expr, source_type, target_type);
                             Some((key, self.gen_mask(expr, target_bit_width, target_type)))
                         }
 
@@ -575,23 +637,28 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             bit_width,
                         } => {
                             let expr = results.pop().unwrap();
+                            let target_type = ValueType::from_bit_width(bit_width);
                             let mut expr = self.gen_cast(
                                 expr,
                                 ValueType::from_bit_width(source.bit_width()),
-                                ValueType::from_bit_width(bit_width),
+                                target_type,
                             );
 
                             if count > 1 {
-                                let source_expr = a.gen_temp(expr);
+                                let source_expr = a.gen_temp(expr, target_type);
 
                                 for i in 1..count {
-                                    let rhs =
-                                        self.gen_shift_left(source_expr, i * source.bit_width());
-                                    expr = self.expr_arena.alloc(Expr::InfixBinOp {
-                                        lhs: expr,
+                                    let rhs = self.gen_shift_left(
+                                        source_expr,
+                                        i * source.bit_width(),
+                                        target_type,
+                                    );
+                                    expr = self.gen_bitwise_op(
+                                        expr,
                                         rhs,
-                                        op: InfixBinOp::BitOr,
-                                    });
+                                        InfixBinOp::BitOr,
+                                        target_type,
+                                    );
                                 }
                             }
 
@@ -610,14 +677,10 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                             let target_type = ValueType::from_bit_width(bit_width);
                             let lhs = self.gen_cast(lhs, lhs_type, target_type);
                             let rhs = self.gen_cast(rhs, rhs_type, target_type);
-                            let lhs = self.gen_shift_left(lhs, rhs_bit_width);
+                            let lhs = self.gen_shift_left(lhs, rhs_bit_width, target_type);
                             Some((
                                 key,
-                                &*self.expr_arena.alloc(Expr::InfixBinOp {
-                                    lhs,
-                                    rhs,
-                                    op: InfixBinOp::BitOr,
-                                }),
+                                self.gen_bitwise_op(lhs, rhs, InfixBinOp::BitOr, target_type),
                             ))
                         }
 
@@ -638,12 +701,27 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
                         graph::SignalData::InstanceOutput { .. } => unreachable!(),
 
                         graph::SignalData::MemReadPortOutput { .. } => unreachable!(),
+
+                        graph::SignalData::MemAsyncReadPortOutput { mem, .. } => {
+                            let address = results.pop().unwrap();
+                            let mem = &self.state_elements.mems[&(context, mem)];
+                            Some((
+                                key,
+                                &*self.expr_arena.alloc(Expr::ArrayIndex {
+                                    target: self.expr_arena.alloc(Expr::Ref {
+                                        name: mem.mem_name.clone(),
+                                        scope: Scope::Member,
+                                    }),
+                                    index: address,
+                                }),
+                            ))
+                        }
                     }
                 }
             } {
                 // Generate a temp if this signal is referenced more than once
-                if self.signal_reference_counts[&key] > 1 {
-                    expr = a.gen_temp(expr);
+                if self.signal_reference_counts[&(key.0 as *const _, key.1 as *const _)] > 1 {
+                    expr = a.gen_temp(expr, ValueType::from_bit_width(key.1.bit_width()));
                 }
                 self.signal_exprs.insert(key, expr);
                 results.push(expr);
@@ -653,88 +731,904 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
         results.pop().unwrap()
     }
 
-    fn gen_mask(
+    // Compiles an expression for the mask of `signal`'s unknown (X) bits, following Verilog's
+    //  X-propagation rules. Unknown bits' values are unspecified, so values are only consulted
+    //  where they can make a result known (e.g. a known 0 bit in a bitwise AND).
+    pub fn compile_signal_unknown(
         &mut self,
-        expr: &'expr_arena Expr<'expr_arena>,
-        bit_width: u32,
-        target_type: ValueType,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'context_arena ModuleContext<'graph, 'context_arena>,
+        a: &mut AssignmentContext<'expr_arena>,
     ) -> &'expr_arena Expr<'expr_arena> {
-        if bit_width == target_type.bit_width() {
-            return expr;
+        enum Frame<'graph, 'context_arena> {
+            Enter {
+                signal: &'graph graph::Signal<'graph>,
+                context: &'context_arena ModuleContext<'graph, 'context_arena>,
+            },
+            Leave {
+                signal: &'graph graph::Signal<'graph>,
+                context: &'context_arena ModuleContext<'graph, 'context_arena>,
+            },
         }
 
-        let mask = (1u128 << bit_width) - 1;
-        self.expr_arena.alloc(Expr::InfixBinOp {
-            lhs: expr,
-            rhs: self.expr_arena.alloc(Expr::Constant {
-                value: match target_type {
-                    ValueType::Bool | ValueType::I32 | ValueType::I64 | ValueType::I128 => {
-                        unreachable!()
-                    }
-                    ValueType::U32 => Constant::U32(mask as _),
-                    ValueType::U64 => Constant::U64(mask as _),
-                    ValueType::U128 => Constant::U128(mask),
-                },
-            }),
-            op: InfixBinOp::BitAnd,
-        })
-    }
-
-    fn gen_shift_left(
-        &mut self,
-        expr: &'expr_arena Expr<'expr_arena>,
-        shift: u32,
-    ) -> &'expr_arena Expr<'expr_arena> {
-        if shift == 0 {
-            return expr;
-        }
+        let mut frames = Vec::new();
+        frames.push(Frame::Enter { signal, context });
 
-        self.expr_arena.alloc(Expr::InfixBinOp {
-            lhs: expr,
-            rhs: self.expr_arena.alloc(Expr::Constant {
-                value: Constant::U32(shift),
-            }),
-            op: InfixBinOp::Shl,
-        })
-    }
+        let mut results = Vec::new();
 
-    fn gen_shift_right(
-        &mut self,
-        expr: &'expr_arena Expr<'expr_arena>,
-        shift: u32,
-    ) -> &'expr_arena Expr<'expr_arena> {
-        if shift == 0 {
-            return expr;
-        }
+        while let Some(frame) = frames.pop() {
+            if let Some((key, mut expr)) = match frame {
+                Frame::Enter { signal, context } => {
+                    let signal = self.simplifier.simplify(signal);
+                    let key = (context, signal);
+                    if let Some(expr) = self.signal_unknown_exprs.get(&key) {
+                        results.push(*expr);
+                        continue;
+                    }
 
-        self.expr_arena.alloc(Expr::InfixBinOp {
-            lhs: expr,
-            rhs: self.expr_arena.alloc(Expr::Constant {
-                value: Constant::U32(shift),
-            }),
-            op: InfixBinOp::Shr,
-        })
-    }
+                    match signal.data {
+                        graph::SignalData::Lit { bit_width, .. } => Some((
+                            key,
+                            Expr::from_constant(
+                                &graph::Constant::U32(0),
+                                bit_width,
+                                self.expr_arena,
+                            ),
+                        )),
 
-    fn gen_cast(
-        &mut self,
-        expr: &'expr_arena Expr<'expr_arena>,
-        source_type: ValueType,
-        target_type: ValueType,
-    ) -> &'expr_arena Expr<'expr_arena> {
-        if source_type == target_type {
-            return expr;
-        }
+                        graph::SignalData::Input {
+                            ref name,
+                            bit_width,
+                        } => {
+                            if let Some(expr) = self.cross_partition_ref(signal, context, true) {
+                                Some((key, expr))
+                            } else if let Some((instance, parent)) = context.instance_and_parent {
+                                frames.push(Frame::Enter {
+                                    signal: instance.driven_inputs.borrow()[name],
+                                    context: parent,
+                                });
+                                None
+                            } else {
+                                let target_type = ValueType::from_bit_width(bit_width);
+                                let expr = self.expr_arena.alloc(Expr::Ref {
+                                    name: unknown_name(name),
+                                    scope: Scope::Member,
+                                });
+                                Some((key, self.gen_mask(expr, bit_width, target_type)))
+                            }
+                        }
 
-        if target_type == ValueType::Bool {
+                        graph::SignalData::Reg { .. } => Some((
+                            key,
+                            &*self.expr_arena.alloc(Expr::Ref {
+                                name: unknown_name(&self.state_elements.regs[&key].value_name),
+                                scope: Scope::Member,
+                            }),
+                        )),
+
+                        graph::SignalData::UnOp { source, .. }
+                        | graph::SignalData::Bits { source, .. }
+                        | graph::SignalData::Repeat { source, .. } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: source,
+                                context,
+                            });
+                            None
+                        }
+                        graph::SignalData::SimpleBinOp { lhs, rhs, .. }
+                        | graph::SignalData::AdditiveBinOp { lhs, rhs, .. }
+                        | graph::SignalData::ComparisonBinOp { lhs, rhs, .. }
+                        | graph::SignalData::ShiftBinOp { lhs, rhs, .. }
+                        | graph::SignalData::Mul { lhs, rhs, .. }
+                        | graph::SignalData::MulSigned { lhs, rhs, .. }
+                        | graph::SignalData::Concat { lhs, rhs, .. } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: lhs,
+                                context,
+                            });
+                            frames.push(Frame::Enter {
+                                signal: rhs,
+                                context,
+                            });
+                            None
+                        }
+
+                        graph::SignalData::Mux {
+                            cond,
+                            when_true,
+                            when_false,
+                            ..
+                        } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: cond,
+                                context,
+                            });
+                            frames.push(Frame::Enter {
+                                signal: when_true,
+                                context,
+                            });
+                            frames.push(Frame::Enter {
+                                signal: when_false,
+                                context,
+                            });
+                            None
+                        }
+
+                        graph::SignalData::InstanceOutput {
+                            instance, ref name, ..
+                        } => {
+                            if let Some(expr) = self.cross_partition_ref(signal, context, true) {
+                                Some((key, expr))
+                            } else {
+                                let output = instance.instantiated_module.outputs.borrow()[name];
+                                frames.push(Frame::Enter {
+                                    signal: output,
+                                    context: context.get_child(instance, self.context_arena),
+                                });
+                                None
+                            }
+                        }
+
+                        graph::SignalData::MemReadPortOutput {
+                            mem,
+                            address,
+                            enable,
+                            read_during_write,
+                        } => {
+                            let mem = &self.state_elements.mems[&(context, mem)];
+                            let read_signal_names = &mem.read_signal_names[&graph::ReadPort {
+                                address,
+                                enable,
+                                read_during_write,
+                            }];
+                            Some((
+                                key,
+                                &*self.expr_arena.alloc(Expr::Ref {
+                                    name: unknown_name(&read_signal_names.value_name),
+                                    scope: Scope::Member,
+                                }),
+                            ))
+                        }
+
+                        graph::SignalData::MemAsyncReadPortOutput { address, .. } => {
+                            frames.push(Frame::Leave { signal, context });
+                            frames.push(Frame::Enter {
+                                signal: address,
+                                context,
+                            });
+                            None
+                        }
+                    }
+                }
+                Frame::Leave { signal, context } => {
+                    let key = (context, signal);
+                    let bit_width = signal.bit_width();
+                    let value_type = ValueType::from_bit_width(bit_width);
+
+                    match signal.data {
+                        graph::SignalData::Lit { .. } => unreachable!(),
+
+                        graph::SignalData::Input { .. } => unreachable!(),
+
+                        graph::SignalData::Reg { .. } => unreachable!(),
+
+                        // Inverting a bit doesn't change whether or not it's known
+                        graph::SignalData::UnOp { .. } => Some((key, results.pop().unwrap())),
+                        graph::SignalData::SimpleBinOp { lhs, rhs, op, .. } => {
+                            let lhs_unknown = results.pop().unwrap();
+                            let rhs_unknown = results.pop().unwrap();
+                            let unknown = self.gen_bitwise_op(
+                                lhs_unknown,
+                                rhs_unknown,
+                                InfixBinOp::BitOr,
+                                value_type,
+                            );
+                            let expr = match op {
+                                graph::SimpleBinOp::BitXor => unknown,
+                                // Known 0 bits make the corresponding bits of an AND known, and
+                                //  known 1 bits make the corresponding bits of an OR known
+                                graph::SimpleBinOp::BitAnd | graph::SimpleBinOp::BitOr => {
+                                    let mut lhs = self.compile_signal(lhs, context, a);
+                                    let mut rhs = self.compile_signal(rhs, context, a);
+                                    if let graph::SimpleBinOp::BitOr = op {
+                                        lhs = self.gen_not(lhs, value_type);
+                                        rhs = self.gen_not(rhs, value_type);
+                                    }
+                                    let lhs = self.gen_bitwise_op(
+                                        lhs,
+                                        lhs_unknown,
+                                        InfixBinOp::BitOr,
+                                        value_type,
+                                    );
+                                    let rhs = self.gen_bitwise_op(
+                                        rhs,
+                                        rhs_unknown,
+                                        InfixBinOp::BitOr,
+                                        value_type,
+                                    );
+                                    let expr = self.gen_bitwise_op(
+                                        unknown,
+                                        lhs,
+                                        InfixBinOp::BitAnd,
+                                        value_type,
+                                    );
+                                    self.gen_bitwise_op(expr, rhs, InfixBinOp::BitAnd, value_type)
+                                }
+                            };
+                            Some((key, expr))
+                        }
+                        // Any unknown bits in either operand of an arithmetic op make the entire
+                        //  result unknown
+                        graph::SignalData::AdditiveBinOp { lhs, rhs, .. }
+                        | graph::SignalData::Mul { lhs, rhs, .. }
+                        | graph::SignalData::MulSigned { lhs, rhs, .. } => {
+                            let lhs_unknown = results.pop().unwrap();
+                            let rhs_unknown = results.pop().unwrap();
+                            let lhs_any = self.gen_any_unknown(lhs_unknown, lhs.bit_width());
+                            let rhs_any = self.gen_any_unknown(rhs_unknown, rhs.bit_width());
+                            let any = self.expr_arena.alloc(Expr::InfixBinOp {
+                                lhs: lhs_any,
+                                rhs: rhs_any,
+                                op: InfixBinOp::BitOr,
+                            });
+                            Some((key, self.gen_unknown_fill(any, bit_width)))
+                        }
+                        graph::SignalData::ComparisonBinOp { lhs, rhs, op, .. } => {
+                            let source_bit_width = lhs.bit_width();
+                            let source_type = ValueType::from_bit_width(source_bit_width);
+                            let lhs_unknown = results.pop().unwrap();
+                            let rhs_unknown = results.pop().unwrap();
+                            let unknown = self.gen_bitwise_op(
+                                lhs_unknown,
+                                rhs_unknown,
+                                InfixBinOp::BitOr,
+                                source_type,
+                            );
+                            let unknown = a.gen_temp(unknown, source_type);
+                            let any = self.gen_any_unknown(unknown, source_bit_width);
+                            let expr = match op {
+                                // Equality is known if any known bits differ
+                                graph::ComparisonBinOp::Equal
+                                | graph::ComparisonBinOp::NotEqual => {
+                                    let lhs = self.compile_signal(lhs, context, a);
+                                    let rhs = self.compile_signal(rhs, context, a);
+                                    let diff = self.gen_bitwise_op(
+                                        lhs,
+                                        rhs,
+                                        InfixBinOp::BitXor,
+                                        source_type,
+                                    );
+                                    let known = self.gen_not(unknown, source_type);
+                                    let known_diff = self.gen_bitwise_op(
+                                        diff,
+                                        known,
+                                        InfixBinOp::BitAnd,
+                                        source_type,
+                                    );
+                                    let any_known_diff =
+                                        self.gen_any_unknown(known_diff, source_bit_width);
+                                    let no_known_diff =
+                                        self.gen_not(any_known_diff, ValueType::Bool);
+                                    &*self.expr_arena.alloc(Expr::InfixBinOp {
+                                        lhs: any,
+                                        rhs: no_known_diff,
+                                        op: InfixBinOp::BitAnd,
+                                    })
+                                }
+                                _ => any,
+                            };
+                            Some((key, expr))
+                        }
+                        graph::SignalData::ShiftBinOp { lhs, rhs, op, .. } => {
+                            let lhs_source_bit_width = lhs.bit_width();
+                            let lhs_source_type = ValueType::from_bit_width(lhs_source_bit_width);
+                            let rhs_source_bit_width = rhs.bit_width();
+                            let rhs_source_type = ValueType::from_bit_width(rhs_source_bit_width);
+                            let lhs_unknown = results.pop().unwrap();
+                            let rhs_unknown = results.pop().unwrap();
+                            // Unknown bits are shifted along with the value, so long as the shift
+                            //  amount is known
+                            let rhs = self.compile_signal(rhs, context, a);
+                            let rhs = self.gen_shift_amount(rhs, rhs_source_type);
+                            let expr = if lhs_source_type.is_wide() {
+                                let (name, lhs_unknown) = match op {
+                                    graph::ShiftBinOp::Shl => ("shl", lhs_unknown),
+                                    graph::ShiftBinOp::Shr => ("shr", lhs_unknown),
+                                    graph::ShiftBinOp::ShrArithmetic => (
+                                        "shr_arithmetic",
+                                        self.gen_wide_sign_extend(
+                                            lhs_unknown,
+                                            lhs_source_bit_width,
+                                        ),
+                                    ),
+                                };
+                                self.gen_wide_call(name, vec![lhs_unknown, rhs])
+                            } else {
+                                self.gen_native_shift(
+                                    lhs_unknown,
+                                    rhs,
+                                    op,
+                                    lhs_source_bit_width,
+                                    bit_width,
+                                )
+                            };
+                            let expr = self.gen_mask(expr, bit_width, value_type);
+                            let any = self.gen_any_unknown(rhs_unknown, rhs_source_bit_width);
+                            let fill = self.gen_unknown_fill(any, bit_width);
+                            Some((
+                                key,
+                                &*self.expr_arena.alloc(Expr::Ternary {
+                                    cond: any,
+                                    when_true: fill,
+                                    when_false: expr,
+                                }),
+                            ))
+                        }
+
+                        // Unknown bits are moved around in the same way as the value's bits
+                        graph::SignalData::Bits {
+                            source, range_low, ..
+                        } => {
+                            let expr = results.pop().unwrap();
+                            let source_type = ValueType::from_bit_width(source.bit_width());
+                            let expr = self.gen_shift_right(expr, range_low, source_type);
+                            let expr = self.gen_cast(expr, source_type, value_type);
+                            Some((key, self.gen_mask(expr, bit_width, value_type)))
+                        }
+                        graph::SignalData::Repeat { source, count, .. } => {
+                            let expr = results.pop().unwrap();
+                            let mut expr = self.gen_cast(
+                                expr,
+                                ValueType::from_bit_width(source.bit_width()),
+                                value_type,
+                            );
+
+                            if count > 1 {
+                                let source_expr = a.gen_temp(expr, value_type);
+
+                                for i in 1..count {
+                                    let rhs = self.gen_shift_left(
+                                        source_expr,
+                                        i * source.bit_width(),
+                                        value_type,
+                                    );
+                                    expr = self.gen_bitwise_op(
+                                        expr,
+                                        rhs,
+                                        InfixBinOp::BitOr,
+                                        value_type,
+                                    );
+                                }
+                            }
+
+                            Some((key, expr))
+                        }
+                        graph::SignalData::Concat { lhs, rhs, .. } => {
+                            let lhs_type = ValueType::from_bit_width(lhs.bit_width());
+                            let rhs_bit_width = rhs.bit_width();
+                            let rhs_type = ValueType::from_bit_width(rhs_bit_width);
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            let lhs = self.gen_cast(lhs, lhs_type, value_type);
+                            let rhs = self.gen_cast(rhs, rhs_type, value_type);
+                            let lhs = self.gen_shift_left(lhs, rhs_bit_width, value_type);
+                            Some((
+                                key,
+                                self.gen_bitwise_op(lhs, rhs, InfixBinOp::BitOr, value_type),
+                            ))
+                        }
+
+                        // If the condition is unknown, only bits that are known and equal in
+                        //  both inputs are known
+                        graph::SignalData::Mux {
+                            cond,
+                            when_true,
+                            when_false,
+                            ..
+                        } => {
+                            let cond_unknown = results.pop().unwrap();
+                            let when_true_unknown = results.pop().unwrap();
+                            let when_false_unknown = results.pop().unwrap();
+                            let cond = self.compile_signal(cond, context, a);
+                            let when_true = self.compile_signal(when_true, context, a);
+                            let when_false = self.compile_signal(when_false, context, a);
+                            let diff = self.gen_bitwise_op(
+                                when_true,
+                                when_false,
+                                InfixBinOp::BitXor,
+                                value_type,
+                            );
+                            let unknown = self.gen_bitwise_op(
+                                when_true_unknown,
+                                when_false_unknown,
+                                InfixBinOp::BitOr,
+                                value_type,
+                            );
+                            let unknown =
+                                self.gen_bitwise_op(unknown, diff, InfixBinOp::BitOr, value_type);
+                            Some((
+                                key,
+                                &*self.expr_arena.alloc(Expr::Ternary {
+                                    cond: cond_unknown,
+                                    when_true: unknown,
+                                    when_false: self.expr_arena.alloc(Expr::Ternary {
+                                        cond,
+                                        when_true: when_true_unknown,
+                                        when_false: when_false_unknown,
+                                    }),
+                                }),
+                            ))
+                        }
+
+                        graph::SignalData::InstanceOutput { .. } => unreachable!(),
+
+                        graph::SignalData::MemReadPortOutput { .. } => unreachable!(),
+
+                        // Reads from unknown addresses are entirely unknown
+                        graph::SignalData::MemAsyncReadPortOutput { mem, address, .. } => {
+                            let address_unknown = results.pop().unwrap();
+                            let any = self.gen_any_unknown(address_unknown, address.bit_width());
+                            let fill = self.gen_unknown_fill(any, bit_width);
+                            let address = self.compile_signal(address, context, a);
+                            let mem = &self.state_elements.mems[&(context, mem)];
+                            Some((
+                                key,
+                                &*self.expr_arena.alloc(Expr::Ternary {
+                                    cond: any,
+                                    when_true: fill,
+                                    when_false: self.expr_arena.alloc(Expr::ArrayIndex {
+                                        target: self.expr_arena.alloc(Expr::Ref {
+                                            name: unknown_name(&mem.mem_name),
+                                            scope: Scope::Member,
+                                        }),
+                                        index: address,
+                                    }),
+                                }),
+                            ))
+                        }
+                    }
+                }
+            } {
+                // Generate a temp if this signal is referenced more than once
+                if self.signal_reference_counts[&(key.0 as *const _, key.1 as *const _)] > 1 {
+                    expr = a.gen_temp(expr, ValueType::from_bit_width(key.1.bit_width()));
+                }
+                self.signal_unknown_exprs.insert(key, expr);
+                results.push(expr);
+            }
+        }
+
+        results.pop().unwrap()
+    }
+
+    // Expands each bit of `mask` into a `granularity`-bit lane, producing a bit mask suitable for `gen_masked_merge`.
+    pub fn compile_write_mask(
+        &mut self,
+        mask: &'graph graph::Signal<'graph>,
+        granularity: u32,
+        context: &'context_arena ModuleContext<'graph, 'context_arena>,
+        a: &mut AssignmentContext<'expr_arena>,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        let expr = self.compile_signal(mask, context, a);
+        self.gen_write_mask(expr, mask.bit_width(), granularity, a)
+    }
+
+    // Expands each bit of `mask`'s unknown bit mask into a `granularity`-bit lane, in the same way
+    //  as `compile_write_mask`.
+    pub fn compile_write_mask_unknown(
+        &mut self,
+        mask: &'graph graph::Signal<'graph>,
+        granularity: u32,
+        context: &'context_arena ModuleContext<'graph, 'context_arena>,
+        a: &mut AssignmentContext<'expr_arena>,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        let expr = self.compile_signal_unknown(mask, context, a);
+        self.gen_write_mask(expr, mask.bit_width(), granularity, a)
+    }
+
+    fn gen_write_mask(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        num_lanes: u32,
+        granularity: u32,
+        a: &mut AssignmentContext<'expr_arena>,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if granularity == 1 {
+            return expr;
+        }
+
+        let mask_type = ValueType::from_bit_width(num_lanes);
+        let bit_width = num_lanes * granularity;
+        let target_type = ValueType::from_bit_width(bit_width);
+        let num_words = (bit_width as usize).div_ceil(64);
+        let mask_expr = if num_lanes > 1 {
+            a.gen_temp(expr, mask_type)
+        } else {
+            expr
+        };
+        let zero = Expr::from_constant(&graph::Constant::U32(0), bit_width, self.expr_arena);
+
+        let mut ret = None;
+        for lane in 0..num_lanes {
+            let mut lane_words = vec![0u64; num_words];
+            for bit in lane * granularity..(lane + 1) * granularity {
+                lane_words[(bit / 64) as usize] |= 1 << (bit % 64);
+            }
+            let lane_bits = Expr::from_constant(
+                &graph::Constant::Wide(lane_words),
+                bit_width,
+                self.expr_arena,
+            );
+            let enabled = self.gen_shift_right(mask_expr, lane, mask_type);
+            let enabled = self.gen_cast(enabled, mask_type, ValueType::Bool);
+            let lane_expr = self.expr_arena.alloc(Expr::Ternary {
+                cond: enabled,
+                when_true: lane_bits,
+                when_false: zero,
+            });
+            ret = Some(match ret {
+                Some(expr) => self.gen_bitwise_op(expr, lane_expr, InfixBinOp::BitOr, target_type),
+                _ => lane_expr,
+            });
+        }
+        ret.unwrap()
+    }
+
+    // Returns `old` with the bits set in `bit_mask` replaced by the corresponding bits of `new`.
+    pub fn gen_masked_merge(
+        &mut self,
+        old: &'expr_arena Expr<'expr_arena>,
+        new: &'expr_arena Expr<'expr_arena>,
+        bit_mask: &'expr_arena Expr<'expr_arena>,
+        bit_width: u32,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        let value_type = ValueType::from_bit_width(bit_width);
+        let inverted_bit_mask = if value_type.is_wide() {
+            self.gen_wide_call("not", vec![bit_mask])
+        } else {
+            self.expr_arena.alloc(Expr::UnOp {
+                source: bit_mask,
+                op: UnOp::Not,
+            })
+        };
+        let old = self.gen_bitwise_op(old, inverted_bit_mask, InfixBinOp::BitAnd, value_type);
+        let new = self.gen_bitwise_op(new, bit_mask, InfixBinOp::BitAnd, value_type);
+        self.gen_bitwise_op(old, new, InfixBinOp::BitOr, value_type)
+    }
+
+    pub fn gen_not(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        value_type: ValueType,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if value_type.is_wide() {
+            return self.gen_wide_call("not", vec![expr]);
+        }
+
+        self.expr_arena.alloc(Expr::UnOp {
+            source: expr,
+            op: UnOp::Not,
+        })
+    }
+
+    // Returns whether any bits of the `bit_width`-bit value `expr` are set.
+    pub fn gen_any_unknown(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        bit_width: u32,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if bit_width == 1 {
+            return expr;
+        }
+
+        self.expr_arena.alloc(Expr::InfixBinOp {
+            lhs: expr,
+            rhs: Expr::from_constant(&graph::Constant::U32(0), bit_width, self.expr_arena),
+            op: InfixBinOp::NotEqual,
+        })
+    }
+
+    // Returns a `bit_width`-bit value with all bits set if `cond` is true, and none set otherwise.
+    pub fn gen_unknown_fill(
+        &mut self,
+        cond: &'expr_arena Expr<'expr_arena>,
+        bit_width: u32,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if bit_width == 1 {
+            return cond;
+        }
+
+        self.expr_arena.alloc(Expr::Ternary {
+            cond,
+            when_true: Expr::from_constant(&all_ones(bit_width), bit_width, self.expr_arena),
+            when_false: Expr::from_constant(&graph::Constant::U32(0), bit_width, self.expr_arena),
+        })
+    }
+
+    fn gen_mask(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        bit_width: u32,
+        target_type: ValueType,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if bit_width == target_type.bit_width() {
+            return expr;
+        }
+
+        if target_type.is_wide() {
+            let bit_width = self.expr_arena.alloc(Expr::Constant {
+                value: Constant::U32(bit_width),
+            });
+            return self.gen_wide_call("mask", vec![expr, bit_width]);
+        }
+
+        let mask = (1u128 << bit_width) - 1;
+        self.expr_arena.alloc(Expr::InfixBinOp {
+            lhs: expr,
+            rhs: self.expr_arena.alloc(Expr::Constant {
+                value: match target_type {
+                    ValueType::Bool
+                    | ValueType::I32
+                    | ValueType::I64
+                    | ValueType::I128
+                    | ValueType::Wide(_) => unreachable!(),
+                    ValueType::U32 => Constant::U32(mask as _),
+                    ValueType::U64 => Constant::U64(mask as _),
+                    ValueType::U128 => Constant::U128(mask),
+                },
+            }),
+            op: InfixBinOp::BitAnd,
+        })
+    }
+
+    fn gen_shift_left(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        shift: u32,
+        target_type: ValueType,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if shift == 0 {
+            return expr;
+        }
+
+        let shift = self.expr_arena.alloc(Expr::Constant {
+            value: Constant::U32(shift),
+        });
+        if target_type.is_wide() {
+            return self.gen_wide_call("shl", vec![expr, shift]);
+        }
+
+        self.expr_arena.alloc(Expr::InfixBinOp {
+            lhs: expr,
+            rhs: shift,
+            op: InfixBinOp::Shl,
+        })
+    }
+
+    fn gen_shift_right(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        shift: u32,
+        target_type: ValueType,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if shift == 0 {
+            return expr;
+        }
+
+        let shift = self.expr_arena.alloc(Expr::Constant {
+            value: Constant::U32(shift),
+        });
+        if target_type.is_wide() {
+            return self.gen_wide_call("shr", vec![expr, shift]);
+        }
+
+        self.expr_arena.alloc(Expr::InfixBinOp {
+            lhs: expr,
+            rhs: shift,
+            op: InfixBinOp::Shr,
+        })
+    }
+
+    fn gen_shift_amount(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        source_type: ValueType,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if source_type.is_wide() {
+            return self.gen_wide_call("to_shift_amount", vec![expr]);
+        }
+
+        let op_input_type = match source_type {
+            ValueType::Bool => ValueType::U32,
+            _ => source_type,
+        };
+        let expr = self.gen_cast(expr, source_type, op_input_type);
+        let expr = self.expr_arena.alloc(Expr::BinaryFunctionCall {
+            name: "std::cmp::min".into(),
+            lhs: expr,
+            rhs: self.expr_arena.alloc(Expr::Constant {
+                value: match op_input_type {
+                    ValueType::Bool
+                    | ValueType::I32
+                    | ValueType::I64
+                    | ValueType::I128
+                    | ValueType::Wide(_) => unreachable!(),
+                    ValueType::U32 => Constant::U32(u32::MAX),
+                    ValueType::U64 => Constant::U64(u32::MAX as _),
+                    ValueType::U128 => Constant::U128(u32::MAX as _),
+                },
+            }),
+        });
+        self.gen_cast(expr, op_input_type, ValueType::U32)
+    }
+
+    fn gen_native_shift(
+        &mut self,
+        lhs: &'expr_arena Expr<'expr_arena>,
+        rhs: &'expr_arena Expr<'expr_arena>,
+        op: graph::ShiftBinOp,
+        lhs_source_bit_width: u32,
+        target_bit_width: u32,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        let lhs_source_type = ValueType::from_bit_width(lhs_source_bit_width);
+        let lhs_op_input_type = match lhs_source_type {
+            ValueType::Bool => ValueType::U32,
+            _ => lhs_source_type,
+        };
+        let lhs = self.gen_cast(lhs, lhs_source_type, lhs_op_input_type);
+        let lhs = match op {
+            graph::ShiftBinOp::Shl | graph::ShiftBinOp::Shr => lhs,
+            graph::ShiftBinOp::ShrArithmetic => {
+                let lhs_op_input_type_signed = lhs_op_input_type.to_signed();
+                let lhs = self.gen_cast(lhs, lhs_op_input_type, lhs_op_input_type_signed);
+                self.gen_sign_extend_shifts(lhs, lhs_source_bit_width, lhs_op_input_type_signed)
+            }
+        };
+        let expr = self.expr_arena.alloc(Expr::UnaryMemberCall {
+            target: lhs,
+            name: match op {
+                graph::ShiftBinOp::Shl => "checked_shl".into(),
+                graph::ShiftBinOp::Shr | graph::ShiftBinOp::ShrArithmetic => "checked_shr".into(),
+            },
+            arg: rhs,
+        });
+        let expr = self.expr_arena.alloc(Expr::UnaryMemberCall {
+            target: expr,
+            name: "unwrap_or".into(),
+            arg: match op {
+                graph::ShiftBinOp::Shl | graph::ShiftBinOp::Shr => {
+                    self.expr_arena.alloc(Expr::Constant {
+                        value: match lhs_op_input_type {
+                            ValueType::Bool
+                            | ValueType::I32
+                            | ValueType::I64
+                            | ValueType::I128
+                            | ValueType::Wide(_) => unreachable!(),
+                            ValueType::U32 => Constant::U32(0),
+                            ValueType::U64 => Constant::U64(0),
+                            ValueType::U128 => Constant::U128(0),
+                        },
+                    })
+                }
+                graph::ShiftBinOp::ShrArithmetic => self.expr_arena.alloc(Expr::InfixBinOp {
+                    lhs,
+                    rhs: self.expr_arena.alloc(Expr::Constant {
+                        value: Constant::U32(lhs_op_input_type.bit_width() - 1),
+                    }),
+                    op: InfixBinOp::Shr,
+                }),
+            },
+        });
+        let op_output_type = lhs_op_input_type;
+        let expr = match op {
+            graph::ShiftBinOp::Shl | graph::ShiftBinOp::Shr => expr,
+            graph::ShiftBinOp::ShrArithmetic => {
+                let lhs_op_output_type_signed = op_output_type.to_signed();
+                self.gen_cast(expr, lhs_op_output_type_signed, op_output_type)
+            }
+        };
+        let target_type = ValueType::from_bit_width(target_bit_width);
+        self.gen_cast(expr, op_output_type, target_type)
+    }
+
+    pub fn gen_bitwise_op(
+        &mut self,
+        lhs: &'expr_arena Expr<'expr_arena>,
+        rhs: &'expr_arena Expr<'expr_arena>,
+        op: InfixBinOp,
+        value_type: ValueType,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if value_type.is_wide() {
+            let name = match op {
+                InfixBinOp::BitAnd => "and",
+                InfixBinOp::BitOr => "or",
+                InfixBinOp::BitXor => "xor",
+                _ => unreachable!(),
+            };
+            return self.gen_wide_call(name, vec![lhs, rhs]);
+        }
+
+        self.expr_arena.alloc(Expr::InfixBinOp { lhs, rhs, op })
+    }
+
+    fn gen_comparison(
+        &mut self,
+        lhs: &'expr_arena Expr<'expr_arena>,
+        rhs: &'expr_arena Expr<'expr_arena>,
+        op: InfixBinOp,
+        source_type: ValueType,
+        signed: bool,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        // Arrays implement == and != directly, but their ordering isn't numeric ordering
+        if source_type.is_wide() {
+            let name = match op {
+                InfixBinOp::Equal | InfixBinOp::NotEqual => None,
+                InfixBinOp::LessThan => Some("lt"),
+                InfixBinOp::LessThanEqual => Some("le"),
+                InfixBinOp::GreaterThan => Some("gt"),
+                InfixBinOp::GreaterThanEqual => Some("ge"),
+                _ => unreachable!(),
+            };
+            if let Some(name) = name {
+                let name = if signed {
+                    format!("{}_signed", name)
+                } else {
+                    name.into()
+                };
+                return self.gen_wide_call(&name, vec![lhs, rhs]);
+            }
+        }
+
+        self.expr_arena.alloc(Expr::InfixBinOp { lhs, rhs, op })
+    }
+
+    fn gen_cast(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        source_type: ValueType,
+        target_type: ValueType,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        if source_type == target_type {
+            return expr;
+        }
+
+        if let ValueType::Wide(source_num_words) = source_type {
+            if let ValueType::Wide(target_num_words) = target_type {
+                return self.expr_arena.alloc(Expr::FunctionCall {
+                    name: format!(
+                        "kaze::runtime::wide::resize::<{}, {}>",
+                        source_num_words, target_num_words
+                    ),
+                    args: vec![expr],
+                });
+            }
+
+            let expr = self.gen_wide_call("to_u128", vec![expr]);
+            return self.gen_cast(expr, ValueType::U128, target_type);
+        }
+
+        if let ValueType::Wide(target_num_words) = target_type {
+            let expr = self.gen_cast(expr, source_type, ValueType::U128);
+            return self.expr_arena.alloc(Expr::FunctionCall {
+                name: format!("kaze::runtime::wide::from_u128::<{}>", target_num_words),
+                args: vec![expr],
+            });
+        }
+
+        if target_type == ValueType::Bool {
             let expr = self.gen_mask(expr, 1, source_type);
             return self.expr_arena.alloc(Expr::InfixBinOp {
                 lhs: expr,
                 rhs: self.expr_arena.alloc(Expr::Constant {
                     value: match source_type {
-                        ValueType::Bool | ValueType::I32 | ValueType::I64 | ValueType::I128 => {
-                            unreachable!()
-                        }
+                        ValueType::Bool
+                        | ValueType::I32
+                        | ValueType::I64
+                        | ValueType::I128
+                        | ValueType::Wide(_) => unreachable!(),
                         ValueType::U32 => Constant::U32(0),
                         ValueType::U64 => Constant::U64(0),
                         ValueType::U128 => Constant::U128(0),
@@ -757,7 +1651,43 @@ impl<'graph, 'context_arena, 'expr_arena> Compiler<'graph, 'context_arena, 'expr
         target_type: ValueType,
     ) -> &'expr_arena Expr<'expr_arena> {
         let shift = target_type.bit_width() - source_bit_width;
-        let expr = self.gen_shift_left(expr, shift);
-        self.gen_shift_right(expr, shift)
+        let expr = self.gen_shift_left(expr, shift, target_type);
+        self.gen_shift_right(expr, shift, target_type)
+    }
+
+    fn gen_wide_sign_extend(
+        &mut self,
+        expr: &'expr_arena Expr<'expr_arena>,
+        source_bit_width: u32,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        let source_bit_width = self.expr_arena.alloc(Expr::Constant {
+            value: Constant::U32(source_bit_width),
+        });
+        self.gen_wide_call("sign_extend", vec![expr, source_bit_width])
+    }
+
+    fn gen_wide_call(
+        &mut self,
+        name: &str,
+        args: Vec<&'expr_arena Expr<'expr_arena>>,
+    ) -> &'expr_arena Expr<'expr_arena> {
+        self.expr_arena.alloc(Expr::FunctionCall {
+            name: format!("kaze::runtime::wide::{}", name),
+            args,
+        })
     }
 }
+
+// Returns the name of the member holding the unknown bit mask for the member named `name` in
+//  four-state simulators.
+pub(super) fn unknown_name(name: &str) -> String {
+    format!("__x_{}", name)
+}
+
+// Returns a `bit_width`-bit constant with all bits set.
+pub(super) fn all_ones(bit_width: u32) -> graph::Constant {
+    let num_words = bit_width.div_ceil(64);
+    let mut words = vec![u64::MAX; num_words as usize];
+    *words.last_mut().unwrap() >>= num_words * 64 - bit_width;
+    graph::Constant::Wide(words)
+}
@@ -0,0 +1,597 @@
+use super::compiler::*;
+use super::ir::*;
+use super::lanes::*;
+use super::snapshot::*;
+use super::state_elements::*;
+use super::{from_u128_str, PropRoot};
+
+use crate::code_writer;
+use crate::graph;
+
+use typed_arena::Arena;
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Result, Write};
+
+// With four-state simulation, each value has a corresponding member holding its unknown bits,
+//  which are computed by `prop` alongside it (except for property conditions, whose unknown bits
+//  are ignored)
+pub(super) fn unknown_roots<'graph, 'arena>(
+    prop_roots: &[PropRoot<'graph, 'arena>],
+) -> Vec<PropRoot<'graph, 'arena>> {
+    prop_roots
+        .iter()
+        .map(|root| PropRoot {
+            target_name: unknown_name(&root.target_name),
+            value_type: root.value_type,
+            signal: root.signal,
+            context: root.context,
+            mask_granularity: root.mask_granularity,
+            is_unknown: true,
+        })
+        .collect()
+}
+
+// A member holding the unknown bits of a value, along with their initial value
+pub(super) struct UnknownMember {
+    name: String,
+    type_: ValueType,
+    initial_value: String,
+}
+
+pub(super) fn gather_unknown_members<'graph>(
+    m: &'graph graph::Module<'graph>,
+    state_elements: &StateElements<'graph, '_>,
+    prop_roots: &[PropRoot],
+) -> Vec<UnknownMember> {
+    let mut unknown_members = Vec::new();
+    let mut push = |name, type_: ValueType, initial_value| {
+        unknown_members.push(UnknownMember {
+            name,
+            type_,
+            initial_value,
+        })
+    };
+    for (name, input) in m.inputs.borrow().iter() {
+        let type_ = ValueType::from_bit_width(input.bit_width());
+        push(unknown_name(name), type_, type_.zero_str());
+    }
+    // Registers without default values start out unknown
+    for (_, reg) in state_elements.regs.iter() {
+        let type_ = ValueType::from_bit_width(reg.data.bit_width);
+        let initial_value = if reg.data.initial_value.borrow().is_some() {
+            type_.zero_str()
+        } else {
+            all_ones_str(reg.data.bit_width)
+        };
+        push(unknown_name(&reg.value_name), type_, initial_value);
+    }
+    for (_, mem) in state_elements.mems.iter() {
+        for (_, read_signal_names) in mem.read_signal_names.iter() {
+            let type_ = ValueType::from_bit_width(mem.mem.element_bit_width);
+            push(
+                unknown_name(&read_signal_names.value_name),
+                type_,
+                type_.zero_str(),
+            );
+        }
+    }
+    for root in prop_roots.iter().filter(|root| root.is_unknown) {
+        push(
+            root.target_name.clone(),
+            root.value_type,
+            root.value_type.zero_str(),
+        );
+    }
+    unknown_members
+}
+
+// Generates the assignment that makes `reg`'s value known when it's reset to its default value.
+pub(super) fn gen_reg_reset_assignment<'expr_arena>(
+    reg: &Register,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+    a: &mut AssignmentContext<'expr_arena>,
+) {
+    a.push(Assignment {
+        target: expr_arena.alloc(Expr::Ref {
+            name: unknown_name(&reg.value_name),
+            scope: Scope::Member,
+        }),
+        expr: Expr::from_constant(&graph::Constant::U32(0), reg.data.bit_width, expr_arena),
+    });
+}
+
+// Generates the assignment that updates `reg`'s unknown bits on a clock edge.
+pub(super) fn gen_reg_posedge_assignment<'expr_arena>(
+    reg: &Register,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+    a: &mut AssignmentContext<'expr_arena>,
+) {
+    a.push(Assignment {
+        target: expr_arena.alloc(Expr::Ref {
+            name: unknown_name(&reg.value_name),
+            scope: Scope::Member,
+        }),
+        expr: expr_arena.alloc(Expr::Ref {
+            name: unknown_name(&reg.next_name),
+            scope: Scope::Member,
+        }),
+    });
+}
+
+// Generates the assignment that writes the unknown bits of the addressed element for a mem write
+//  port, and returns the port's `enable` and `bit_mask` with writes with unknown enables or
+//  addresses ignored, as well as unknown mask bits.
+pub(super) fn gen_write_port_assignment<'graph, 'context_arena, 'expr_arena>(
+    c: &mut Compiler<'graph, 'context_arena, 'expr_arena>,
+    mem: &Mem,
+    write_signal_names: &WriteSignalNames,
+    enable: &'expr_arena Expr<'expr_arena>,
+    bit_mask: Option<&'expr_arena Expr<'expr_arena>>,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+    a: &mut AssignmentContext<'expr_arena>,
+) -> (
+    &'expr_arena Expr<'expr_arena>,
+    Option<&'expr_arena Expr<'expr_arena>>,
+) {
+    let element_type = ValueType::from_bit_width(mem.mem.element_bit_width);
+    let address = expr_arena.alloc(Expr::Ref {
+        name: write_signal_names.address_name.clone(),
+        scope: Scope::Member,
+    });
+    let enable_unknown = expr_arena.alloc(Expr::Ref {
+        name: unknown_name(&write_signal_names.enable_name),
+        scope: Scope::Member,
+    });
+    let address_unknown = expr_arena.alloc(Expr::Ref {
+        name: unknown_name(&write_signal_names.address_name),
+        scope: Scope::Member,
+    });
+    let unknown = expr_arena.alloc(Expr::InfixBinOp {
+        lhs: enable_unknown,
+        rhs: c.gen_any_unknown(address_unknown, mem.mem.address_bit_width),
+        op: InfixBinOp::BitOr,
+    });
+    let enable = &*expr_arena.alloc(Expr::InfixBinOp {
+        lhs: enable,
+        rhs: c.gen_not(unknown, ValueType::Bool),
+        op: InfixBinOp::BitAnd,
+    });
+    let bit_mask = bit_mask.map(|bit_mask| {
+        let mask_unknown = expr_arena.alloc(Expr::Ref {
+            name: unknown_name(&write_signal_names.mask_name),
+            scope: Scope::Member,
+        });
+        let mask_known = c.gen_not(mask_unknown, element_type);
+        c.gen_bitwise_op(bit_mask, mask_known, InfixBinOp::BitAnd, element_type)
+    });
+
+    let element_unknown = expr_arena.alloc(Expr::ArrayIndex {
+        target: expr_arena.alloc(Expr::Ref {
+            name: unknown_name(&mem.mem_name),
+            scope: Scope::Member,
+        }),
+        index: address,
+    });
+    let value_unknown = expr_arena.alloc(Expr::Ref {
+        name: unknown_name(&write_signal_names.value_name),
+        scope: Scope::Member,
+    });
+    let value_unknown = match bit_mask {
+        Some(bit_mask) => c.gen_masked_merge(
+            element_unknown,
+            value_unknown,
+            bit_mask,
+            mem.mem.element_bit_width,
+        ),
+        _ => value_unknown,
+    };
+    a.push(Assignment {
+        target: element_unknown,
+        expr: expr_arena.alloc(Expr::Ternary {
+            cond: enable,
+            when_true: value_unknown,
+            when_false: element_unknown,
+        }),
+    });
+
+    (enable, bit_mask)
+}
+
+// Generates the assignment that reads the unknown bits of the element at `address` for a mem read
+//  port, and returns the port's `enable` with reads with unknown enables ignored. Reads from
+//  unknown addresses are entirely unknown.
+pub(super) fn gen_read_port_assignment<'graph, 'context_arena, 'expr_arena>(
+    c: &mut Compiler<'graph, 'context_arena, 'expr_arena>,
+    mem: &Mem,
+    read_signal_names: &ReadSignalNames,
+    address: &'expr_arena Expr<'expr_arena>,
+    enable: &'expr_arena Expr<'expr_arena>,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+    a: &mut AssignmentContext<'expr_arena>,
+) -> &'expr_arena Expr<'expr_arena> {
+    let enable_unknown = expr_arena.alloc(Expr::Ref {
+        name: unknown_name(&read_signal_names.enable_name),
+        scope: Scope::Member,
+    });
+    let enable = &*expr_arena.alloc(Expr::InfixBinOp {
+        lhs: enable,
+        rhs: c.gen_not(enable_unknown, ValueType::Bool),
+        op: InfixBinOp::BitAnd,
+    });
+    let address_unknown = expr_arena.alloc(Expr::Ref {
+        name: unknown_name(&read_signal_names.address_name),
+        scope: Scope::Member,
+    });
+    let any = c.gen_any_unknown(address_unknown, mem.mem.address_bit_width);
+    let value_unknown = expr_arena.alloc(Expr::Ref {
+        name: unknown_name(&read_signal_names.value_name),
+        scope: Scope::Member,
+    });
+    a.push(Assignment {
+        target: value_unknown,
+        expr: expr_arena.alloc(Expr::Ternary {
+            cond: enable,
+            when_true: expr_arena.alloc(Expr::Ternary {
+                cond: any,
+                when_true: c.gen_unknown_fill(any, mem.mem.element_bit_width),
+                when_false: expr_arena.alloc(Expr::ArrayIndex {
+                    target: expr_arena.alloc(Expr::Ref {
+                        name: unknown_name(&mem.mem_name),
+                        scope: Scope::Member,
+                    }),
+                    index: address,
+                }),
+            }),
+            when_false: value_unknown,
+        }),
+    });
+
+    enable
+}
+
+// Generates the assignment that makes the value read by a don't-care mem read port entirely
+//  unknown if any write port writes the element it reads (with a known enable and address, as
+//  other writes are ignored), like the `x`s read in Verilog. `enable` is the read port's enable
+//  as returned by `gen_read_port_assignment`.
+pub(super) fn gen_dont_care_read_assignment<'graph, 'context_arena, 'expr_arena>(
+    c: &mut Compiler<'graph, 'context_arena, 'expr_arena>,
+    mem: &Mem,
+    read_signal_names: &ReadSignalNames,
+    address: &'expr_arena Expr<'expr_arena>,
+    enable: &'expr_arena Expr<'expr_arena>,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+    a: &mut AssignmentContext<'expr_arena>,
+) {
+    let value_unknown = expr_arena.alloc(Expr::Ref {
+        name: unknown_name(&read_signal_names.value_name),
+        scope: Scope::Member,
+    });
+    for write_signal_names in mem.write_signal_names.iter() {
+        let write_enable = expr_arena.alloc(Expr::Ref {
+            name: write_signal_names.enable_name.clone(),
+            scope: Scope::Member,
+        });
+        let write_enable_unknown = expr_arena.alloc(Expr::Ref {
+            name: unknown_name(&write_signal_names.enable_name),
+            scope: Scope::Member,
+        });
+        let write_address = expr_arena.alloc(Expr::Ref {
+            name: write_signal_names.address_name.clone(),
+            scope: Scope::Member,
+        });
+        let write_address_unknown = expr_arena.alloc(Expr::Ref {
+            name: unknown_name(&write_signal_names.address_name),
+            scope: Scope::Member,
+        });
+        let write_address_any = c.gen_any_unknown(write_address_unknown, mem.mem.address_bit_width);
+        let write_known = expr_arena.alloc(Expr::InfixBinOp {
+            lhs: c.gen_not(write_enable_unknown, ValueType::Bool),
+            rhs: c.gen_not(write_address_any, ValueType::Bool),
+            op: InfixBinOp::BitAnd,
+        });
+        let collision = expr_arena.alloc(Expr::InfixBinOp {
+            lhs: expr_arena.alloc(Expr::InfixBinOp {
+                lhs: enable,
+                rhs: write_enable,
+                op: InfixBinOp::BitAnd,
+            }),
+            rhs: expr_arena.alloc(Expr::InfixBinOp {
+                lhs: write_known,
+                rhs: expr_arena.alloc(Expr::InfixBinOp {
+                    lhs: write_address,
+                    rhs: address,
+                    op: InfixBinOp::Equal,
+                }),
+                op: InfixBinOp::BitAnd,
+            }),
+            op: InfixBinOp::BitAnd,
+        });
+        a.push(Assignment {
+            target: value_unknown,
+            expr: expr_arena.alloc(Expr::Ternary {
+                cond: collision,
+                when_true: Expr::from_constant(
+                    &all_ones(mem.mem.element_bit_width),
+                    mem.mem.element_bit_width,
+                    expr_arena,
+                ),
+                when_false: value_unknown,
+            }),
+        });
+    }
+}
+
+pub(super) fn write_struct_attributes<W: Write>(w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+    // Unknown-bit members are named after the members they track, including internal ones that
+    //  start with `__`
+    w.append_line("#[allow(non_snake_case)]")?;
+
+    Ok(())
+}
+
+pub(super) fn write_members<W: Write>(
+    unknown_members: &[UnknownMember],
+    state_elements: &StateElements,
+    lanes: Lanes,
+    state_members: &mut Vec<StateMember>,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_newline()?;
+    w.append_line("// Unknown bits")?;
+    for member in unknown_members.iter() {
+        let type_name = lanes.array(member.type_.name());
+        state_members.push(StateMember::new(member.name.clone(), type_name.clone()));
+        w.append_line(&format!("{}: {},", member.name, type_name))?;
+    }
+    for (_, mem) in state_elements.mems.iter() {
+        let name = unknown_name(&mem.mem_name);
+        let element_type_name = ValueType::from_bit_width(mem.mem.element_bit_width).name();
+        let contents_type_name = lanes.vec(format!("Box<[{}]>", element_type_name));
+        state_members.push(StateMember {
+            len: Some(1usize << mem.mem.address_bit_width),
+            ..StateMember::new(name.clone(), contents_type_name.clone())
+        });
+        w.append_line(&format!("{}: {},", name, contents_type_name))?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn write_initializers<W: Write>(
+    unknown_members: &[UnknownMember],
+    state_elements: &StateElements,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_newline()?;
+    w.append_line("// Unknown bits")?;
+    for member in unknown_members.iter() {
+        w.append_line(&format!(
+            "{}: {},",
+            member.name,
+            lanes.array(member.initial_value.clone())
+        ))?;
+    }
+    // Mems without initial contents start out unknown
+    for (_, mem) in state_elements.mems.iter() {
+        let element_type = ValueType::from_bit_width(mem.mem.element_bit_width);
+        let initial_value = if mem.mem.initial_contents.borrow().is_some() {
+            element_type.zero_str()
+        } else {
+            all_ones_str(mem.mem.element_bit_width)
+        };
+        w.append_line(&format!(
+            "{}: {}vec![{}; {}].into_boxed_slice(){},",
+            unknown_name(&mem.mem_name),
+            lanes.vec_prefix(),
+            initial_value,
+            1 << mem.mem.address_bit_width,
+            lanes.vec_suffix()
+        ))?;
+    }
+
+    Ok(())
+}
+
+// Writes the end of `prop` when `assert_known_outputs` is set, which panics if any of `outputs`
+//  have unknown bits.
+pub(super) fn write_known_outputs_check<W: Write>(
+    outputs: &BTreeMap<String, &graph::Signal>,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    lanes.write_loop_begin(w)?;
+    for (name, output) in outputs.iter() {
+        let unknown = format!("self.{}{}", unknown_name(name), lanes.loop_index());
+        let type_ = ValueType::from_bit_width(output.bit_width());
+        if type_ == ValueType::Bool {
+            w.append_line(&format!("if {} {{", unknown))?;
+        } else {
+            w.append_line(&format!("if {} != {} {{", unknown, type_.zero_str()))?;
+        }
+        w.indent();
+        if lanes.is_some() {
+            w.append_line(&format!(
+                "panic!(\"Output {{:?}} has unknown bits {{:x?}} (lane {{}})\", {:?}, {}, __lane);",
+                name, unknown
+            ))?;
+        } else {
+            w.append_line(&format!(
+                "panic!(\"Output {{:?}} has unknown bits {{:x?}}\", {:?}, {});",
+                name, unknown
+            ))?;
+        }
+        w.unindent();
+        w.append_line("}")?;
+    }
+    lanes.write_loop_end(w)?;
+
+    Ok(())
+}
+
+// Writes `signal_unknown` and `set_signal_unknown`, which look up the same paths as `signal` and
+//  `set_signal` (`path_lookups` and `reg_paths`, respectively), along with the paths of inputs.
+pub(super) fn write_signal_unknown_accessors<W: Write>(
+    inputs: &BTreeMap<String, &graph::Signal>,
+    path_lookups: &[(String, String, u32)],
+    reg_paths: &HashSet<String>,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    // Inputs' unknown bits are looked up by their names, after the other paths
+    let mut unknown_path_lookups = path_lookups.to_vec();
+    for (name, input) in inputs.iter() {
+        let bit_width = input.bit_width();
+        if !ValueType::from_bit_width(bit_width).is_wide()
+            && !path_lookups.iter().any(|(path, _, _)| path == name)
+        {
+            unknown_path_lookups.push((name.clone(), name.clone(), bit_width));
+        }
+    }
+
+    w.append_newline()?;
+    w.append_line(&format!(
+        "pub fn signal_unknown(&self{}, path: &str) -> Option<u128> {{",
+        lanes.param()
+    ))?;
+    w.indent();
+    w.append_line("match path {")?;
+    w.indent();
+    for (path, value_name, _) in unknown_path_lookups.iter() {
+        w.append_line(&format!(
+            "\"{}\" => Some(self.{}{} as u128),",
+            path,
+            unknown_name(value_name),
+            lanes.index()
+        ))?;
+    }
+    w.append_line("_ => None,")?;
+    w.unindent();
+    w.append_line("}")?;
+    w.unindent();
+    w.append_line("}")?;
+
+    let set_signal_unknown_lookups = unknown_path_lookups
+        .iter()
+        .filter(|(path, _, _)| reg_paths.contains(path) || inputs.contains_key(path))
+        .collect::<Vec<_>>();
+    w.append_newline()?;
+    if set_signal_unknown_lookups.is_empty() {
+        w.append_line("#[allow(unused_variables)]")?;
+    }
+    w.append_line(&format!(
+        "pub fn set_signal_unknown(&mut self{}, path: &str, mask: u128) -> bool {{",
+        lanes.param()
+    ))?;
+    w.indent();
+    w.append_line("match path {")?;
+    w.indent();
+    for (path, value_name, bit_width) in set_signal_unknown_lookups {
+        w.append_line(&format!("\"{}\" => {{", path))?;
+        w.indent();
+        w.append_line(&format!(
+            "self.{}{} = {};",
+            unknown_name(value_name),
+            lanes.index(),
+            from_u128_str("mask", *bit_width)
+        ))?;
+        w.append_line("true")?;
+        w.unindent();
+        w.append_line("}")?;
+    }
+    w.append_line("_ => false,")?;
+    w.unindent();
+    w.append_line("}")?;
+    w.unindent();
+    w.append_line("}")?;
+
+    Ok(())
+}
+
+// Writes the statement that makes the `bit_width`-bit member `value_name` known, for accessors
+//  that write it.
+pub(super) fn write_accessor_write<W: Write>(
+    value_name: &str,
+    bit_width: u32,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_line(&format!(
+        "self.{}{} = {};",
+        unknown_name(value_name),
+        lanes.index(),
+        ValueType::from_bit_width(bit_width).zero_str()
+    ))
+}
+
+// Writes the statement that makes the element of `mem` at `address` known, for `poke_mem_*`.
+pub(super) fn write_poke_mem_unknown<W: Write>(
+    mem: &Mem,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_line(&format!(
+        "self.{}{}[address as usize] = {};",
+        unknown_name(&mem.mem_name),
+        lanes.index(),
+        ValueType::from_bit_width(mem.mem.element_bit_width).zero_str()
+    ))
+}
+
+// Writes the statement that makes the elements of `mem` replaced by `load_mem_*` known.
+pub(super) fn write_load_mem_unknown<W: Write>(
+    mem: &Mem,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_line(&format!(
+        "self.{}{}[address..address + contents.len()].fill({});",
+        unknown_name(&mem.mem_name),
+        lanes.index(),
+        ValueType::from_bit_width(mem.mem.element_bit_width).zero_str()
+    ))
+}
+
+pub(super) fn write_peek_mem_unknown<W: Write>(
+    mem: &Mem,
+    lanes: Lanes,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_newline()?;
+    w.append_line(&format!(
+        "pub fn peek_mem_unknown_{}(&self{}, address: {}) -> {} {{",
+        mem.accessor_name,
+        lanes.param(),
+        ValueType::from_bit_width(mem.mem.address_bit_width).name(),
+        ValueType::from_bit_width(mem.mem.element_bit_width).name()
+    ))?;
+    w.indent();
+    w.append_line(&format!(
+        "self.{}{}[address as usize]",
+        unknown_name(&mem.mem_name),
+        lanes.index()
+    ))?;
+    w.unindent();
+    w.append_line("}")?;
+
+    Ok(())
+}
+
+// Returns a Rust literal for a `bit_width`-bit value with all bits set.
+fn all_ones_str(bit_width: u32) -> String {
+    let value = all_ones(bit_width);
+    match ValueType::from_bit_width(bit_width) {
+        ValueType::Bool => "true".into(),
+        ValueType::Wide(num_words) => format!(
+            "[{}]",
+            value
+                .words(num_words as _)
+                .iter()
+                .map(|word| format!("0x{:x}", word))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => format!("0x{:x}", value.numeric_value()),
+    }
+}
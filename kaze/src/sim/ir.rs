@@ -3,12 +3,26 @@ use crate::graph;
 
 use typed_arena::Arena;
 
+use std::collections::{HashMap, HashSet};
 use std::io::{Result, Write};
 
 pub struct AssignmentContext<'arena> {
     arena: &'arena Arena<Expr<'arena>>,
     assignments: Vec<Assignment<'arena>>,
-    local_count: u32,
+    local_types: HashMap<String, ValueType>,
+}
+
+// A contiguous run of a context's assignments, to be written as a separate function or block. Temps
+//  are locals, so those that are read by a later chunk are passed on through members of the same name.
+pub struct AssignmentChunk<'context, 'arena> {
+    pub assignments: &'context [Assignment<'arena>],
+    // Temps defined by earlier chunks that are read by this chunk
+    pub imported_temps: Vec<&'context str>,
+    // Temps defined by this chunk that are read by later chunks
+    pub exported_temps: Vec<&'context str>,
+    // Members read by this chunk (directly, or through the temps it reads) that aren't assigned by
+    //  the context, i.e. the values this chunk's results depend on
+    pub read_members: Vec<&'context str>,
 }
 
 impl<'arena> AssignmentContext<'arena> {
@@ -16,17 +30,21 @@ impl<'arena> AssignmentContext<'arena> {
         AssignmentContext {
             arena,
             assignments: Vec::new(),
-            local_count: 0,
+            local_types: HashMap::new(),
         }
     }
 
-    pub fn gen_temp(&mut self, expr: &'arena Expr<'arena>) -> &'arena Expr<'arena> {
+    pub fn gen_temp(
+        &mut self,
+        expr: &'arena Expr<'arena>,
+        value_type: ValueType,
+    ) -> &'arena Expr<'arena> {
         match expr {
             // We don't need to generate a temp for Constants or Refs
             Expr::Constant { .. } | Expr::Ref { .. } => expr,
             _ => {
-                let name = format!("__temp_{}", self.local_count);
-                self.local_count += 1;
+                let name = format!("__temp_{}", self.local_types.len());
+                self.local_types.insert(name.clone(), value_type);
 
                 self.assignments.push(Assignment {
                     target: self.arena.alloc(Expr::Ref {
@@ -52,13 +70,126 @@ impl<'arena> AssignmentContext<'arena> {
         self.assignments.push(assignment);
     }
 
-    pub fn write<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+    pub fn write<W: Write>(
+        &self,
+        w: &mut code_writer::CodeWriter<W>,
+        lane: Option<&str>,
+    ) -> Result<()> {
         for assignment in self.assignments.iter() {
-            assignment.write(w)?;
+            assignment.write(w, lane)?;
         }
 
         Ok(())
     }
+
+    pub fn assignments(&self) -> &[Assignment<'arena>] {
+        &self.assignments
+    }
+
+    pub fn temp_type(&self, name: &str) -> ValueType {
+        self.local_types[name]
+    }
+
+    // Splits the context's assignments into chunks of at most `chunk_size` assignments each, if
+    //  specified. If `split_after_member_assignments` is `true`, chunks also end after each
+    //  assignment to a member, so that each chunk computes at most one member.
+    pub fn chunks(
+        &self,
+        chunk_size: Option<usize>,
+        split_after_member_assignments: bool,
+    ) -> Vec<AssignmentChunk<'_, 'arena>> {
+        let mut chunks = Vec::new();
+        let mut chunk_start = 0;
+        for (index, assignment) in self.assignments.iter().enumerate() {
+            let chunk_len = index + 1 - chunk_start;
+            let is_member_assignment = matches!(
+                *assignment.target,
+                Expr::Ref {
+                    scope: Scope::Member,
+                    ..
+                }
+            );
+            if chunk_size.is_some_and(|chunk_size| chunk_len >= chunk_size)
+                || (split_after_member_assignments && is_member_assignment)
+                || index + 1 == self.assignments.len()
+            {
+                chunks.push(AssignmentChunk {
+                    assignments: &self.assignments[chunk_start..index + 1],
+                    imported_temps: Vec::new(),
+                    exported_temps: Vec::new(),
+                    read_members: Vec::new(),
+                });
+                chunk_start = index + 1;
+            }
+        }
+
+        let assigned_members = self
+            .assignments
+            .iter()
+            .filter_map(|assignment| match *assignment.target {
+                Expr::Ref {
+                    ref name,
+                    scope: Scope::Member,
+                } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        let mut temp_chunk_indices = HashMap::new();
+        // Members read by each temp, directly or through other temps
+        let mut temp_read_members: HashMap<&str, Vec<&str>> = HashMap::new();
+        for chunk_index in 0..chunks.len() {
+            let assignments = chunks[chunk_index].assignments;
+            let mut imported_temps = Vec::new();
+            let mut read_members = Vec::new();
+            let mut read_members_set = HashSet::new();
+            for assignment in assignments.iter() {
+                let mut temps = Vec::new();
+                let mut members = Vec::new();
+                assignment.expr.gather_refs(&mut temps, &mut members);
+                let mut assignment_read_members = Vec::new();
+                let mut assignment_read_members_set = HashSet::new();
+                for member in members.into_iter().chain(
+                    temps
+                        .iter()
+                        .flat_map(|temp| temp_read_members[temp].iter().copied()),
+                ) {
+                    if !assigned_members.contains(member)
+                        && assignment_read_members_set.insert(member)
+                    {
+                        assignment_read_members.push(member);
+                    }
+                }
+                for temp in temps {
+                    let defining_chunk_index: usize = temp_chunk_indices[temp];
+                    if defining_chunk_index != chunk_index && !imported_temps.contains(&temp) {
+                        imported_temps.push(temp);
+                        let exported_temps = &mut chunks[defining_chunk_index].exported_temps;
+                        if !exported_temps.contains(&temp) {
+                            exported_temps.push(temp);
+                        }
+                    }
+                }
+                for &member in assignment_read_members.iter() {
+                    if read_members_set.insert(member) {
+                        read_members.push(member);
+                    }
+                }
+                if let Expr::Ref {
+                    ref name,
+                    scope: Scope::Local,
+                } = *assignment.target
+                {
+                    temp_chunk_indices.insert(name.as_str(), chunk_index);
+                    temp_read_members.insert(name.as_str(), assignment_read_members);
+                }
+            }
+            chunks[chunk_index].imported_temps = imported_temps;
+            chunks[chunk_index].read_members = read_members;
+        }
+
+        chunks
+    }
 }
 
 pub struct Assignment<'arena> {
@@ -67,7 +198,57 @@ pub struct Assignment<'arena> {
 }
 
 impl<'arena> Assignment<'arena> {
-    pub fn write<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+    pub fn write<W: Write>(
+        &self,
+        w: &mut code_writer::CodeWriter<W>,
+        lane: Option<&str>,
+    ) -> Result<()> {
+        self.write_with_member_access(w, lane, MemberAccess::Field)
+    }
+
+    // Appends the names of the members this assignment reads and writes to `read_members` and
+    //  `written_members`, respectively.
+    pub fn gather_member_accesses<'a>(
+        &'a self,
+        read_members: &mut Vec<&'a str>,
+        written_members: &mut Vec<&'a str>,
+    ) {
+        let mut local_refs = Vec::new();
+        self.expr.gather_refs(&mut local_refs, read_members);
+        match *self.target {
+            Expr::Ref {
+                ref name,
+                scope: Scope::Member,
+            } => {
+                written_members.push(name);
+            }
+            Expr::ArrayIndex {
+                target:
+                    Expr::Ref {
+                        ref name,
+                        scope: Scope::Member,
+                    },
+                index,
+            } => {
+                written_members.push(name);
+                index.gather_refs(&mut local_refs, read_members);
+            }
+            _ => (),
+        }
+    }
+
+    // Writes this assignment where each member is accessed through a local reference of the same
+    //  name, e.g. in closures that only borrow some of the simulator's members.
+    pub fn write_borrowed<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        self.write_with_member_access(w, None, MemberAccess::Borrowed)
+    }
+
+    fn write_with_member_access<W: Write>(
+        &self,
+        w: &mut code_writer::CodeWriter<W>,
+        lane: Option<&str>,
+        member_access: MemberAccess,
+    ) -> Result<()> {
         w.append_indent()?;
         // TODO: I hate these kind of conditionals...
         if let Expr::Ref { ref scope, .. } = self.target {
@@ -78,9 +259,9 @@ impl<'arena> Assignment<'arena> {
                 Scope::Member => (),
             }
         }
-        self.target.write(w)?;
+        self.target.write(w, lane, member_access)?;
         w.append(" = ")?;
-        self.expr.write(w)?;
+        self.expr.write(w, lane, member_access)?;
         w.append(";")?;
         w.append_newline()?;
 
@@ -105,6 +286,10 @@ pub enum Expr<'arena> {
     Constant {
         value: Constant,
     },
+    FunctionCall {
+        name: String,
+        args: Vec<&'arena Expr<'arena>>,
+    },
     InfixBinOp {
         lhs: &'arena Expr<'arena>,
         rhs: &'arena Expr<'arena>,
@@ -131,18 +316,80 @@ pub enum Expr<'arena> {
 }
 
 impl<'arena> Expr<'arena> {
+    // Appends the names of all locals and members referred to by this expression to `local_refs`
+    //  and `member_refs`, respectively.
+    pub fn gather_refs<'a>(
+        &'a self,
+        local_refs: &mut Vec<&'a str>,
+        member_refs: &mut Vec<&'a str>,
+    ) {
+        let mut exprs = vec![self];
+        while let Some(expr) = exprs.pop() {
+            match *expr {
+                Expr::ArrayIndex { target, index } => {
+                    exprs.push(target);
+                    exprs.push(index);
+                }
+                Expr::BinaryFunctionCall { lhs, rhs, .. } | Expr::InfixBinOp { lhs, rhs, .. } => {
+                    exprs.push(lhs);
+                    exprs.push(rhs);
+                }
+                Expr::Cast { source, .. } | Expr::UnOp { source, .. } => {
+                    exprs.push(source);
+                }
+                Expr::Constant { .. } => (),
+                Expr::FunctionCall { ref args, .. } => {
+                    exprs.extend(args.iter());
+                }
+                Expr::Ref {
+                    ref name,
+                    scope: Scope::Local,
+                } => {
+                    local_refs.push(name);
+                }
+                Expr::Ref {
+                    ref name,
+                    scope: Scope::Member,
+                } => {
+                    member_refs.push(name);
+                }
+                Expr::Ternary {
+                    cond,
+                    when_true,
+                    when_false,
+                } => {
+                    exprs.push(cond);
+                    exprs.push(when_true);
+                    exprs.push(when_false);
+                }
+                Expr::UnaryMemberCall { target, arg, .. } => {
+                    exprs.push(target);
+                    exprs.push(arg);
+                }
+            }
+        }
+    }
+
     pub fn from_constant(
         value: &graph::Constant,
         bit_width: u32,
         arena: &'arena Arena<Expr<'arena>>,
     ) -> &'arena Expr<'arena> {
+        let target_type = ValueType::from_bit_width(bit_width);
+        if let ValueType::Wide(num_words) = target_type {
+            return arena.alloc(Expr::Constant {
+                value: Constant::Wide(value.words(num_words as _)),
+            });
+        }
+
         let value = value.numeric_value();
 
-        let target_type = ValueType::from_bit_width(bit_width);
         arena.alloc(Expr::Constant {
             value: match target_type {
                 ValueType::Bool => Constant::Bool(value != 0),
-                ValueType::I32 | ValueType::I64 | ValueType::I128 => unreachable!(),
+                ValueType::I32 | ValueType::I64 | ValueType::I128 | ValueType::Wide(_) => {
+                    unreachable!()
+                }
                 ValueType::U32 => Constant::U32(value as _),
                 ValueType::U64 => Constant::U64(value as _),
                 ValueType::U128 => Constant::U128(value),
@@ -150,10 +397,18 @@ impl<'arena> Expr<'arena> {
         })
     }
 
-    pub fn write<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+    // If `lane` is specified, each member is indexed by it, as members of simulators with multiple
+    //  lanes hold a value for each lane.
+    pub fn write<W: Write>(
+        &self,
+        w: &mut code_writer::CodeWriter<W>,
+        lane: Option<&str>,
+        member_access: MemberAccess,
+    ) -> Result<()> {
         enum Command<'arena> {
             Expr { expr: &'arena Expr<'arena> },
             Str { s: &'arena str },
+            String { s: String },
         }
 
         let mut commands = Vec::new();
@@ -187,8 +442,8 @@ impl<'arena> Expr<'arena> {
                         target_type,
                     } => {
                         commands.push(Command::Str { s: ")" });
-                        commands.push(Command::Str {
-                            s: &target_type.name(),
+                        commands.push(Command::String {
+                            s: target_type.name(),
                         });
                         commands.push(Command::Str { s: " as " });
                         commands.push(Command::Expr { expr: source });
@@ -200,8 +455,26 @@ impl<'arena> Expr<'arena> {
                             Constant::U32(value) => format!("0x{:x}u32", value),
                             Constant::U64(value) => format!("0x{:x}u64", value),
                             Constant::U128(value) => format!("0x{:x}u128", value),
+                            Constant::Wide(ref words) => format!(
+                                "[{}]",
+                                words
+                                    .iter()
+                                    .map(|word| format!("0x{:x}u64", word))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
                         })?;
                     }
+                    Expr::FunctionCall { ref name, ref args } => {
+                        commands.push(Command::Str { s: ")" });
+                        for (i, arg) in args.iter().enumerate().rev() {
+                            commands.push(Command::Expr { expr: arg });
+                            if i > 0 {
+                                commands.push(Command::Str { s: ", " });
+                            }
+                        }
+                        w.append(&format!("{}(", name))?;
+                    }
                     Expr::InfixBinOp {
                         ref lhs,
                         ref rhs,
@@ -231,10 +504,18 @@ impl<'arena> Expr<'arena> {
                         w.append("(")?;
                     }
                     Expr::Ref { ref name, scope } => {
-                        if let Scope::Member = scope {
-                            w.append("self.")?;
+                        match (scope, member_access) {
+                            (Scope::Local, _) => w.append(name)?,
+                            (Scope::Member, MemberAccess::Field) => {
+                                w.append(&format!("self.{}", name))?
+                            }
+                            (Scope::Member, MemberAccess::Borrowed) => {
+                                w.append(&format!("(*{})", name))?
+                            }
+                        }
+                        if let (Scope::Member, Some(lane)) = (scope, lane) {
+                            w.append(&format!("[{}]", lane))?;
                         }
-                        w.append(name)?;
                     }
                     Expr::Ternary {
                         ref cond,
@@ -271,6 +552,9 @@ impl<'arena> Expr<'arena> {
                 Command::Str { s } => {
                     w.append(s)?;
                 }
+                Command::String { s } => {
+                    w.append(&s)?;
+                }
             }
         }
 
@@ -283,6 +567,7 @@ pub enum Constant {
     U32(u32),
     U64(u64),
     U128(u128),
+    Wide(Vec<u64>),
 }
 
 #[derive(Clone, Copy)]
@@ -307,6 +592,15 @@ pub enum Scope {
     Member,
 }
 
+// How members are accessed in generated code
+#[derive(Clone, Copy)]
+pub enum MemberAccess {
+    // Through `self`, e.g. `self.x`
+    Field,
+    // Through a local reference of the same name, e.g. `(*x)`
+    Borrowed,
+}
+
 #[derive(Clone, Copy)]
 pub enum UnOp {
     Not,
@@ -321,6 +615,8 @@ pub enum ValueType {
     U32,
     U64,
     U128,
+    /// An array of the given number of 64-bit words, least significant word first
+    Wide(u32),
 }
 
 impl ValueType {
@@ -334,44 +630,55 @@ impl ValueType {
         } else if bit_width <= 128 {
             ValueType::U128
         } else {
-            unreachable!()
+            ValueType::Wide(bit_width.div_ceil(64))
         }
     }
 
     pub fn to_signed(&self) -> ValueType {
         match self {
-            ValueType::Bool | ValueType::I32 | ValueType::I64 | ValueType::I128 => unreachable!(),
+            ValueType::Bool
+            | ValueType::I32
+            | ValueType::I64
+            | ValueType::I128
+            | ValueType::Wide(_) => unreachable!(),
             ValueType::U32 => ValueType::I32,
             ValueType::U64 => ValueType::I64,
             ValueType::U128 => ValueType::I128,
         }
     }
 
-    pub fn name(&self) -> &'static str {
+    pub fn name(&self) -> String {
         match self {
-            ValueType::Bool => "bool",
-            ValueType::I32 => "i32",
-            ValueType::I64 => "i64",
-            ValueType::I128 => "i128",
-            ValueType::U32 => "u32",
-            ValueType::U64 => "u64",
-            ValueType::U128 => "u128",
+            ValueType::Bool => "bool".into(),
+            ValueType::I32 => "i32".into(),
+            ValueType::I64 => "i64".into(),
+            ValueType::I128 => "i128".into(),
+            ValueType::U32 => "u32".into(),
+            ValueType::U64 => "u64".into(),
+            ValueType::U128 => "u128".into(),
+            ValueType::Wide(num_words) => format!("[u64; {}]", num_words),
         }
     }
 
+    pub fn is_wide(&self) -> bool {
+        matches!(self, ValueType::Wide(_))
+    }
+
     pub fn bit_width(&self) -> u32 {
         match self {
             ValueType::Bool => 1,
             ValueType::I32 | ValueType::U32 => 32,
             ValueType::I64 | ValueType::U64 => 64,
             ValueType::I128 | ValueType::U128 => 128,
+            ValueType::Wide(num_words) => num_words * 64,
         }
     }
 
-    pub fn zero_str(&self) -> &'static str {
+    pub fn zero_str(&self) -> String {
         match self {
-            ValueType::Bool => "false",
-            _ => "0",
+            ValueType::Bool => "false".into(),
+            ValueType::Wide(num_words) => format!("[0; {}]", num_words),
+            _ => "0".into(),
         }
     }
 }
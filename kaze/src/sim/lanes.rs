@@ -0,0 +1,98 @@
+use crate::code_writer;
+
+use std::io::{Result, Write};
+
+// With lanes, each member is an array (or for mems, a `Vec`) with one element per lane, logic is
+//  evaluated within loops over the lanes, and accessors take the lane they apply to as an
+//  additional parameter
+#[derive(Clone, Copy)]
+pub(super) struct Lanes {
+    pub count: Option<usize>,
+}
+
+impl Lanes {
+    pub fn new(count: Option<usize>) -> Lanes {
+        Lanes { count }
+    }
+
+    pub fn is_some(&self) -> bool {
+        self.count.is_some()
+    }
+
+    // Returns the type (or value) of a member holding one `s` per lane.
+    pub fn array(&self, s: String) -> String {
+        match self.count {
+            Some(count) => format!("[{}; {}]", s, count),
+            _ => s,
+        }
+    }
+
+    // Returns the type of a mem contents member holding one `s` per lane.
+    pub fn vec(&self, s: String) -> String {
+        match self.count {
+            Some(_) => format!("Vec<{}>", s),
+            _ => s,
+        }
+    }
+
+    // Returns the text written before and after the initial value of a mem contents member to
+    //  hold one copy of it per lane.
+    pub fn vec_prefix(&self) -> String {
+        match self.count {
+            Some(_) => "vec![".into(),
+            _ => String::new(),
+        }
+    }
+
+    pub fn vec_suffix(&self) -> String {
+        match self.count {
+            Some(count) => format!("; {}]", count),
+            _ => String::new(),
+        }
+    }
+
+    // The parameter that accessors take to select a lane, and the index they apply to members.
+    pub fn param(&self) -> &'static str {
+        match self.count {
+            Some(_) => ", lane: usize",
+            _ => "",
+        }
+    }
+
+    pub fn index(&self) -> &'static str {
+        match self.count {
+            Some(_) => "[lane]",
+            _ => "",
+        }
+    }
+
+    // The variable that lane loops iterate over, and the index they apply to members.
+    pub fn loop_lane(&self) -> Option<&'static str> {
+        self.count.map(|_| "__lane")
+    }
+
+    pub fn loop_index(&self) -> &'static str {
+        match self.count {
+            Some(_) => "[__lane]",
+            _ => "",
+        }
+    }
+
+    pub fn write_loop_begin<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        if let Some(count) = self.count {
+            w.append_line(&format!("for __lane in 0..{} {{", count))?;
+            w.indent();
+        }
+
+        Ok(())
+    }
+
+    pub fn write_loop_end<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        if self.count.is_some() {
+            w.unindent();
+            w.append_line("}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,603 @@
+use super::compiler::*;
+use super::ir::*;
+use super::{compile_prop_root, PropRoot};
+
+use crate::code_writer;
+use crate::graph;
+use crate::module_context::*;
+
+use typed_arena::Arena;
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Result, Write};
+
+// With partitioning, `prop` and each clock domain's `posedge_*` evaluate each partition in its own
+//  job, and the simulator's worker threads run these jobs in parallel. Jobs only borrow the
+//  members they access, mutably if they write them, and each value is computed by exactly one
+//  partition: signals that are reached through instance ports but computed by another partition
+//  are passed on by that partition rather than being computed again. `prop` therefore evaluates
+//  partitions in stages, separated by barriers, where each stage only reads the values other
+//  partitions computed in earlier stages. These values are passed through slots, which are members
+//  of the simulator, so that they're only allocated once.
+pub(super) struct PropPartitions<'graph, 'arena, 'expr_arena> {
+    partitioning: Partitioning<'graph, 'arena>,
+    partitions: Vec<PropPartition<'expr_arena>>,
+    num_stages: usize,
+}
+
+struct PropPartition<'expr_arena> {
+    context: AssignmentContext<'expr_arena>,
+    // The index of each stage's first assignment in `context`
+    stage_starts: Vec<usize>,
+}
+
+// Assigns partitions to contexts and tracks the values that are passed between partitions
+pub(super) struct Partitioning<'graph, 'arena> {
+    depth: u32,
+    // The index of each partition's context. Partition 0 holds everything outside of the
+    //  instances at `depth`, and doesn't have a context.
+    indices: HashMap<*const ModuleContext<'graph, 'arena>, usize>,
+    // The index of each value that's passed between partitions, by the context and signal it's
+    //  computed for, and whether it's that signal's unknown mask
+    cross_value_indices: HashMap<
+        (
+            *const ModuleContext<'graph, 'arena>,
+            *const graph::Signal<'graph>,
+            bool,
+        ),
+        usize,
+    >,
+    cross_values: Vec<CrossValue>,
+    // The partition that's being compiled
+    partition_index: usize,
+}
+
+struct CrossValue {
+    value_type: ValueType,
+    partition_index: usize,
+    stage: usize,
+    reader_indices: Vec<usize>,
+}
+
+impl<'graph, 'arena> Partitioning<'graph, 'arena> {
+    fn new(depth: u32) -> Partitioning<'graph, 'arena> {
+        Partitioning {
+            depth,
+            indices: HashMap::new(),
+            cross_value_indices: HashMap::new(),
+            cross_values: Vec::new(),
+            partition_index: 0,
+        }
+    }
+
+    fn num_partitions(&self) -> usize {
+        self.indices.len() + 1
+    }
+
+    fn partition_index(&self, context: &'arena ModuleContext<'graph, 'arena>) -> usize {
+        partition_context(context, self.depth)
+            .map_or(0, |context| self.indices[&(context as *const _)])
+    }
+
+    fn add_partition(&mut self, context: &'arena ModuleContext<'graph, 'arena>) -> usize {
+        match partition_context(context, self.depth) {
+            Some(context) => {
+                let index = self.num_partitions();
+                *self.indices.entry(context).or_insert(index)
+            }
+            _ => 0,
+        }
+    }
+
+    // Returns the name of the local holding the value (or unknown mask) of `signal` in `context`,
+    //  which is reached through an instance port, if it's computed by another partition than the
+    //  one being compiled.
+    pub fn cross_value_ref(
+        &mut self,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        is_unknown: bool,
+    ) -> Option<String> {
+        let index = *self.cross_value_indices.get(&(
+            context as *const _,
+            signal as *const _,
+            is_unknown,
+        ))?;
+        let cross_value = &mut self.cross_values[index];
+        if cross_value.partition_index == self.partition_index {
+            return None;
+        }
+        if !cross_value.reader_indices.contains(&self.partition_index) {
+            cross_value.reader_indices.push(self.partition_index);
+        }
+        Some(cross_value_name(index))
+    }
+
+    // Determines the stage of every signal in `prop_roots`' cones, which is the number of times
+    //  values are passed between partitions along the longest path leading to it, and gathers the
+    //  values that are passed between partitions, along with the roots that compute them.
+    fn gather_stages<'expr_arena>(
+        &mut self,
+        c: &mut Compiler<'graph, 'arena, 'expr_arena>,
+        prop_roots: &[PropRoot<'graph, 'arena>],
+        four_state: bool,
+        cross_roots: &mut Vec<PropRoot<'graph, 'arena>>,
+    ) -> HashMap<
+        (
+            *const ModuleContext<'graph, 'arena>,
+            *const graph::Signal<'graph>,
+        ),
+        usize,
+    > {
+        enum Frame<'graph, 'arena> {
+            Enter {
+                signal: &'graph graph::Signal<'graph>,
+                context: &'arena ModuleContext<'graph, 'arena>,
+            },
+            Leave {
+                signal: &'graph graph::Signal<'graph>,
+                context: &'arena ModuleContext<'graph, 'arena>,
+            },
+        }
+
+        let mut stages = HashMap::new();
+        for root in prop_roots.iter() {
+            let mut frames = vec![Frame::Enter {
+                signal: c.simplify(root.signal),
+                context: root.context,
+            }];
+            while let Some(frame) = frames.pop() {
+                match frame {
+                    Frame::Enter { signal, context } => {
+                        if stages.contains_key(&(context as *const _, signal as *const _)) {
+                            continue;
+                        }
+                        frames.push(Frame::Leave { signal, context });
+                        for (signal, context) in operands(c, signal, context) {
+                            frames.push(Frame::Enter { signal, context });
+                        }
+                    }
+                    Frame::Leave { signal, context } => {
+                        let partition_index = self.add_partition(context);
+                        let mut stage = 0;
+                        for (operand, operand_context) in operands(c, signal, context) {
+                            let mut operand_stage =
+                                stages[&(operand_context as *const _, operand as *const _)];
+                            if is_computed(operand)
+                                && self.add_partition(operand_context) != partition_index
+                            {
+                                self.add_cross_value(
+                                    operand,
+                                    operand_context,
+                                    operand_stage,
+                                    four_state,
+                                    cross_roots,
+                                );
+                                operand_stage += 1;
+                            }
+                            stage = stage.max(operand_stage);
+                        }
+                        stages.insert((context as *const _, signal as *const _), stage);
+                    }
+                }
+            }
+        }
+        stages
+    }
+
+    fn add_cross_value(
+        &mut self,
+        signal: &'graph graph::Signal<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        stage: usize,
+        four_state: bool,
+        cross_roots: &mut Vec<PropRoot<'graph, 'arena>>,
+    ) {
+        for &is_unknown in [false, true].iter().take(if four_state { 2 } else { 1 }) {
+            let key = (context as *const _, signal as *const _, is_unknown);
+            if self.cross_value_indices.contains_key(&key) {
+                continue;
+            }
+            let index = self.cross_values.len();
+            self.cross_value_indices.insert(key, index);
+            let value_type = ValueType::from_bit_width(signal.bit_width());
+            self.cross_values.push(CrossValue {
+                value_type,
+                partition_index: self.partition_index(context),
+                stage,
+                reader_indices: Vec::new(),
+            });
+            cross_roots.push(PropRoot {
+                target_name: cross_value_name(index),
+                value_type,
+                signal,
+                context,
+                mask_granularity: None,
+                is_unknown,
+            });
+        }
+    }
+}
+
+// Splits `prop_roots` into partitions at the instances `depth` levels below the root module, with
+//  one more partition for everything outside of these instances, and compiles each partition.
+//  Returns `None` if there's only one partition.
+pub(super) fn partition_prop_roots<'graph, 'arena, 'expr_arena>(
+    c: &mut Compiler<'graph, 'arena, 'expr_arena>,
+    prop_roots: &[PropRoot<'graph, 'arena>],
+    depth: u32,
+    four_state: bool,
+    expr_arena: &'expr_arena Arena<Expr<'expr_arena>>,
+) -> Option<PropPartitions<'graph, 'arena, 'expr_arena>> {
+    enum Root<'a, 'graph, 'arena> {
+        Prop(&'a PropRoot<'graph, 'arena>),
+        Cross(usize),
+    }
+
+    let mut partitioning = Partitioning::new(depth);
+    for root in prop_roots.iter() {
+        partitioning.add_partition(root.context);
+    }
+    let mut cross_roots = Vec::new();
+    let stages = partitioning.gather_stages(c, prop_roots, four_state, &mut cross_roots);
+    if partitioning.num_partitions() == 1 {
+        return None;
+    }
+
+    let mut partition_roots = (0..partitioning.num_partitions())
+        .map(|_| Vec::new())
+        .collect::<Vec<_>>();
+    for root in prop_roots.iter() {
+        let stage = stages[&(
+            root.context as *const _,
+            c.simplify(root.signal) as *const _,
+        )];
+        partition_roots[partitioning.partition_index(root.context)].push((stage, Root::Prop(root)));
+    }
+    for (index, cross_value) in partitioning.cross_values.iter().enumerate() {
+        partition_roots[cross_value.partition_index].push((cross_value.stage, Root::Cross(index)));
+    }
+    let num_stages = partition_roots
+        .iter()
+        .flat_map(|roots| roots.iter().map(|&(stage, _)| stage + 1))
+        .max()
+        .unwrap_or(1);
+
+    let mut partitions = Vec::new();
+    for (partition_index, mut roots) in partition_roots.into_iter().enumerate() {
+        // Each partition's roots are compiled in the order of their stages, so that each stage's
+        //  assignments are contiguous, and only temps from earlier stages are shared with later ones
+        roots.sort_by_key(|&(stage, _)| stage);
+        partitioning.partition_index = partition_index;
+        c.set_partitioning(partitioning);
+        c.clear_signal_exprs();
+        let mut context = AssignmentContext::new(expr_arena);
+        let mut stage_starts = Vec::new();
+        for (stage, root) in roots {
+            while stage_starts.len() <= stage {
+                stage_starts.push(context.assignments().len());
+            }
+            match root {
+                Root::Prop(root) => {
+                    compile_prop_root(c, root, Scope::Member, expr_arena, &mut context)
+                }
+                Root::Cross(index) => compile_prop_root(
+                    c,
+                    &cross_roots[index],
+                    Scope::Local,
+                    expr_arena,
+                    &mut context,
+                ),
+            }
+        }
+        while stage_starts.len() < num_stages {
+            stage_starts.push(context.assignments().len());
+        }
+        partitioning = c.take_partitioning().unwrap();
+        partitions.push(PropPartition {
+            context,
+            stage_starts,
+        });
+    }
+
+    Some(PropPartitions {
+        partitioning,
+        partitions,
+        num_stages,
+    })
+}
+
+impl<'graph, 'arena, 'expr_arena> PropPartitions<'graph, 'arena, 'expr_arena> {
+    pub fn num_partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    // Returns the index of the partition that `context` belongs to.
+    pub fn partition_index(&self, context: &'arena ModuleContext<'graph, 'arena>) -> usize {
+        self.partitioning.partition_index(context)
+    }
+
+    pub fn write_members<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        w.append_newline()?;
+        w.append_line("// Partitions")?;
+        w.append_line("__partition_workers: kaze::runtime::workers::Workers,")?;
+        for (index, cross_value) in self.partitioning.cross_values.iter().enumerate() {
+            w.append_line(&format!(
+                "{}: kaze::runtime::workers::Slot<{}>,",
+                cross_value_slot_name(index),
+                cross_value.value_type.name()
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_initializers<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        w.append_newline()?;
+        w.append_line("// Partitions")?;
+        w.append_line(&format!(
+            "__partition_workers: kaze::runtime::workers::Workers::new({}),",
+            self.partitions.len() - 1
+        ))?;
+        for index in 0..self.partitioning.cross_values.len() {
+            w.append_line(&format!(
+                "{}: kaze::runtime::workers::Slot::new(),",
+                cross_value_slot_name(index)
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    // Writes `prop`'s body, which evaluates each partition in its own job, stage by stage.
+    pub fn write_prop_body<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        let cross_values = &self.partitioning.cross_values;
+        write_member_borrows(
+            self.partitions.iter().map(|partition| &partition.context),
+            (0..cross_values.len()).map(cross_value_slot_name),
+            w,
+        )?;
+
+        for (partition_index, partition) in self.partitions.iter().enumerate() {
+            write_job_begin(partition_index, self.num_stages > 1, w)?;
+
+            let assignments = partition.context.assignments();
+            for stage in 0..self.num_stages {
+                if stage > 0 {
+                    w.append_line("if !__barrier.wait() {")?;
+                    w.indent();
+                    w.append_line("return;")?;
+                    w.unindent();
+                    w.append_line("}")?;
+                    for (index, cross_value) in cross_values.iter().enumerate() {
+                        if cross_value.stage == stage - 1
+                            && cross_value.reader_indices.contains(&partition_index)
+                        {
+                            w.append_line(&format!(
+                                "let {} = {}.get();",
+                                cross_value_name(index),
+                                cross_value_slot_name(index)
+                            ))?;
+                        }
+                    }
+                }
+
+                let end = partition
+                    .stage_starts
+                    .get(stage + 1)
+                    .copied()
+                    .unwrap_or(assignments.len());
+                for assignment in assignments[partition.stage_starts[stage]..end].iter() {
+                    assignment.write_borrowed(w)?;
+                }
+
+                for (index, cross_value) in cross_values.iter().enumerate() {
+                    if cross_value.stage == stage && cross_value.partition_index == partition_index
+                    {
+                        w.append_line(&format!(
+                            "{}.set({});",
+                            cross_value_slot_name(index),
+                            cross_value_name(index)
+                        ))?;
+                    }
+                }
+            }
+
+            w.unindent();
+            w.append_line("};")?;
+        }
+
+        write_run(0..self.partitions.len(), w)
+    }
+}
+
+// Writes the part of a clock domain's `posedge_*` method that applies the clock edge to each
+//  partition (with its state elements' assignments in `contexts`) in its own job.
+pub(super) fn write_posedge_body<W: Write>(
+    contexts: &[AssignmentContext],
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    let partition_indices = (0..contexts.len())
+        .filter(|&index| !contexts[index].is_empty())
+        .collect::<Vec<_>>();
+    write_member_borrows(
+        partition_indices.iter().map(|&index| &contexts[index]),
+        std::iter::empty(),
+        w,
+    )?;
+    for &index in partition_indices.iter() {
+        write_job_begin(index, false, w)?;
+        for assignment in contexts[index].assignments() {
+            assignment.write_borrowed(w)?;
+        }
+        w.unindent();
+        w.append_line("};")?;
+    }
+
+    write_run(partition_indices.into_iter(), w)
+}
+
+// Writes a pattern that borrows each member that `contexts` access from `self` (mutably if it's
+//  written), along with the workers and `slot_names`, so that each context's assignments can be
+//  evaluated by a separate job.
+fn write_member_borrows<'a, 'arena: 'a, W: Write>(
+    contexts: impl Iterator<Item = &'a AssignmentContext<'arena>>,
+    slot_names: impl Iterator<Item = String>,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    let mut read_members = Vec::new();
+    let mut written_members = Vec::new();
+    for context in contexts {
+        for assignment in context.assignments() {
+            assignment.gather_member_accesses(&mut read_members, &mut written_members);
+        }
+    }
+    let written_members = written_members.into_iter().collect::<HashSet<_>>();
+    let mut members = read_members
+        .into_iter()
+        .chain(written_members.iter().copied())
+        .collect::<Vec<_>>();
+    members.sort_unstable();
+    members.dedup();
+
+    w.append_line("let Self {")?;
+    w.indent();
+    w.append_line("ref mut __partition_workers,")?;
+    for slot_name in slot_names {
+        w.append_line(&format!("ref {},", slot_name))?;
+    }
+    for member in members {
+        if written_members.contains(member) {
+            w.append_line(&format!("ref mut {},", member))?;
+        } else {
+            w.append_line(&format!("ref {},", member))?;
+        }
+    }
+    w.append_line("..")?;
+    w.unindent();
+    w.append_line("} = *self;")?;
+
+    Ok(())
+}
+
+// Writes the start of the closure for the job that evaluates partition `partition_index`, which
+//  takes the barrier that the jobs wait on between stages.
+fn write_job_begin<W: Write>(
+    partition_index: usize,
+    uses_barrier: bool,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_line(&format!(
+        "let mut {} = |{}: &kaze::runtime::workers::Barrier| {{",
+        job_name(partition_index),
+        if uses_barrier { "__barrier" } else { "_" }
+    ))?;
+    w.indent();
+
+    Ok(())
+}
+
+fn write_run<W: Write>(
+    partition_indices: impl Iterator<Item = usize>,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_line(&format!(
+        "__partition_workers.run(&mut [{}]);",
+        partition_indices
+            .map(|index| format!("&mut {}", job_name(index)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+fn job_name(partition_index: usize) -> String {
+    format!("__partition_{}", partition_index)
+}
+
+fn cross_value_name(index: usize) -> String {
+    format!("__cross_value_{}", index)
+}
+
+fn cross_value_slot_name(index: usize) -> String {
+    format!("__cross_{}", index)
+}
+
+// Returns the signals (and their contexts) that `signal`'s value is computed from in `prop`, where
+//  instance ports are followed to the signals that drive them.
+fn operands<'graph, 'arena>(
+    c: &mut Compiler<'graph, 'arena, '_>,
+    signal: &'graph graph::Signal<'graph>,
+    context: &'arena ModuleContext<'graph, 'arena>,
+) -> Vec<(
+    &'graph graph::Signal<'graph>,
+    &'arena ModuleContext<'graph, 'arena>,
+)> {
+    let operands = match signal.data {
+        graph::SignalData::Lit { .. }
+        | graph::SignalData::Reg { .. }
+        | graph::SignalData::MemReadPortOutput { .. } => Vec::new(),
+
+        graph::SignalData::Input { .. } => {
+            if context.instance_and_parent.is_none() {
+                return Vec::new();
+            }
+            return vec![c.resolve_ports(signal, context)];
+        }
+        graph::SignalData::InstanceOutput { .. } => {
+            return vec![c.resolve_ports(signal, context)];
+        }
+
+        graph::SignalData::UnOp { source, .. }
+        | graph::SignalData::Bits { source, .. }
+        | graph::SignalData::Repeat { source, .. } => vec![source],
+        graph::SignalData::SimpleBinOp { lhs, rhs, .. }
+        | graph::SignalData::AdditiveBinOp { lhs, rhs, .. }
+        | graph::SignalData::ComparisonBinOp { lhs, rhs, .. }
+        | graph::SignalData::ShiftBinOp { lhs, rhs, .. }
+        | graph::SignalData::Mul { lhs, rhs, .. }
+        | graph::SignalData::MulSigned { lhs, rhs, .. }
+        | graph::SignalData::Concat { lhs, rhs, .. } => vec![lhs, rhs],
+        graph::SignalData::Mux {
+            cond,
+            when_true,
+            when_false,
+            ..
+        } => vec![cond, when_true, when_false],
+        graph::SignalData::MemAsyncReadPortOutput { address, .. } => vec![address],
+    };
+    operands
+        .into_iter()
+        .map(|operand| (c.simplify(operand), context))
+        .collect()
+}
+
+// Returns `true` if `signal`'s value is computed by `prop`, rather than being read from a member
+//  or a constant, which every partition can do on its own.
+fn is_computed(signal: &graph::Signal) -> bool {
+    !matches!(
+        signal.data,
+        graph::SignalData::Lit { .. }
+            | graph::SignalData::Input { .. }
+            | graph::SignalData::Reg { .. }
+            | graph::SignalData::MemReadPortOutput { .. }
+    )
+}
+
+// Returns the context of the instance `depth` levels below the root module that `context` is
+//  within (or is), if any.
+fn partition_context<'graph, 'arena>(
+    context: &'arena ModuleContext<'graph, 'arena>,
+    depth: u32,
+) -> Option<&'arena ModuleContext<'graph, 'arena>> {
+    let mut ancestors = Vec::new();
+    let mut context = context;
+    while let Some((_, parent)) = context.instance_and_parent {
+        ancestors.push(context);
+        context = parent;
+    }
+    let depth = depth as usize;
+    if depth == 0 || ancestors.len() < depth {
+        return None;
+    }
+    Some(ancestors[ancestors.len() - depth])
+}
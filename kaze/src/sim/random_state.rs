@@ -0,0 +1,86 @@
+use super::ir::*;
+use super::lanes::*;
+use super::GenerationOptions;
+
+use crate::code_writer;
+use crate::graph;
+
+use std::io::{Result, Write};
+
+// With random initial state, `new` uses a fixed seed, and the constructor that takes the seed is
+//  called `new_with_seed` instead
+pub(super) fn write_new<W: Write>(
+    m: &graph::Module,
+    options: &GenerationOptions,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    if options.tracing {
+        w.append_line(&format!(
+            "pub fn new(instance_name: &'static str, trace: T) -> std::io::Result<{}<T>> {{",
+            m.name
+        ))?;
+        w.indent();
+        w.append_line("Self::new_with_seed(instance_name, trace, 0)")?;
+    } else {
+        w.append_line(&format!("pub fn new() -> {} {{", m.name))?;
+        w.indent();
+        w.append_line("Self::new_with_seed(0)")?;
+    }
+    w.unindent();
+    w.append_line("}")?;
+    w.append_newline()?;
+
+    Ok(())
+}
+
+pub(super) fn write_rng<W: Write>(w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+    w.append_line("let mut __rng = kaze::runtime::random::Rng::new(seed);")?;
+    w.append_newline()?;
+
+    Ok(())
+}
+
+// Returns the initial value of a `bit_width`-bit member that's initialized with random values when
+//  `random` is `true`, and zero otherwise. Each lane gets its own values.
+pub(super) fn initial_value_str(bit_width: u32, random: bool, lanes: Lanes) -> String {
+    let value_type = ValueType::from_bit_width(bit_width);
+    if !random {
+        return lanes.array(value_type.zero_str());
+    }
+    let value = random_str(bit_width);
+    match lanes.count {
+        Some(_) => format!("std::array::from_fn(|_| {})", value),
+        _ => value,
+    }
+}
+
+// Returns the initial value of the contents member of `mem`, with every element initialized with a
+//  random value. Each lane gets its own values.
+pub(super) fn mem_contents_str(mem: &graph::Mem, lanes: Lanes) -> String {
+    let contents = format!(
+        "(0..{}).map(|_| {}).collect::<Vec<_>>().into_boxed_slice()",
+        1 << mem.address_bit_width,
+        random_str(mem.element_bit_width)
+    );
+    match lanes.count {
+        Some(count) => format!("(0..{}).map(|_| {}).collect()", count, contents),
+        _ => contents,
+    }
+}
+
+// Returns a Rust expression for a pseudorandom `bit_width`-bit value drawn from `__rng`.
+fn random_str(bit_width: u32) -> String {
+    let value_type = ValueType::from_bit_width(bit_width);
+    let value = match value_type {
+        ValueType::Bool => return "__rng.next_bool()".into(),
+        ValueType::Wide(num_words) => format!("__rng.next_wide::<{}>()", num_words),
+        _ => format!("__rng.next_{}()", value_type.name()),
+    };
+    if bit_width == value_type.bit_width() {
+        return value;
+    }
+    match value_type {
+        ValueType::Wide(_) => format!("kaze::runtime::wide::mask({}, {})", value, bit_width),
+        _ => format!("({} & 0x{:x})", value, (1u128 << bit_width) - 1),
+    }
+}
@@ -0,0 +1,192 @@
+use super::lanes::*;
+
+use crate::code_writer;
+
+use std::io::{Result, Write};
+
+// A member that's part of the simulator's state, which is captured by `snapshot` and serialized by
+//  the state type
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct StateMember {
+    pub name: String,
+    pub type_name: String,
+    // For members that are boxed slices (i.e. mem contents), the number of elements in each slice
+    pub len: Option<usize>,
+}
+
+impl StateMember {
+    pub fn new(name: String, type_name: String) -> StateMember {
+        StateMember {
+            name,
+            type_name,
+            len: None,
+        }
+    }
+}
+
+// Writes the state type called `state_name`, along with its `to_bytes` and `from_bytes` methods.
+//  With `four_state`, the state includes unknown-bit members, which are named like the simulator's.
+pub(super) fn write_state_type<W: Write>(
+    state_name: &str,
+    state_members: &mut [StateMember],
+    lanes: Lanes,
+    four_state: bool,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    // Members are sorted so that the serialization format doesn't depend on hash map iteration
+    //  order, and the format's fingerprint covers their names and types, so snapshots can only be
+    //  deserialized by simulators with the same state
+    state_members.sort();
+    let state_fingerprint = fingerprint(
+        state_members
+            .iter()
+            .map(|member| format!("{}: {} {:?};", member.name, member.type_name, member.len)),
+    );
+    if four_state {
+        w.append_line("#[allow(dead_code, non_snake_case)]")?;
+    } else {
+        w.append_line("#[allow(dead_code)]")?;
+    }
+    w.append_line("#[derive(Clone)]")?;
+    w.append_line(&format!("pub struct {} {{", state_name))?;
+    w.indent();
+    for member in state_members.iter() {
+        w.append_line(&format!("{}: {},", member.name, member.type_name))?;
+    }
+    w.unindent();
+    w.append_line("}")?;
+    w.append_newline()?;
+
+    // Not every simulator serializes its snapshots
+    w.append_line("#[allow(dead_code)]")?;
+    w.append_line("#[automatically_derived]")?;
+    w.append_line(&format!("impl {} {{", state_name))?;
+    w.indent();
+
+    w.append_line("pub fn to_bytes(&self) -> Vec<u8> {")?;
+    w.indent();
+    w.append_line(&format!(
+        "let mut bytes = kaze::runtime::snapshot::begin_write(0x{:016x});",
+        state_fingerprint
+    ))?;
+    for member in state_members.iter() {
+        w.append_line(&format!(
+            "kaze::runtime::snapshot::StateValue::write_bytes(&self.{}, &mut bytes);",
+            member.name
+        ))?;
+    }
+    w.append_line("bytes")?;
+    w.unindent();
+    w.append_line("}")?;
+
+    w.append_newline()?;
+    w.append_line(&format!(
+        "pub fn from_bytes(bytes: &[u8]) -> std::io::Result<{}> {{",
+        state_name
+    ))?;
+    w.indent();
+    w.append_line(&format!(
+        "let mut bytes = kaze::runtime::snapshot::begin_read(bytes, 0x{:016x})?;",
+        state_fingerprint
+    ))?;
+    w.append_line(&format!("let state = {} {{", state_name))?;
+    w.indent();
+    for member in state_members.iter() {
+        w.append_line(&format!(
+            "{}: kaze::runtime::snapshot::read(&mut bytes)?,",
+            member.name
+        ))?;
+    }
+    w.unindent();
+    w.append_line("};")?;
+    // Mem contents must have the right number of elements to be restored
+    for member in state_members.iter() {
+        if let Some(len) = member.len {
+            let name = &member.name;
+            let lengths = match lanes.count {
+                Some(count) => format!(
+                    "state.{}.len() == {} && state.{}.iter().all(|lane| lane.len() == {})",
+                    name, count, name, len
+                ),
+                _ => format!("state.{}.len() == {}", name, len),
+            };
+            w.append_line(&format!("kaze::runtime::snapshot::check({})?;", lengths))?;
+        }
+    }
+    w.append_line("kaze::runtime::snapshot::end_read(bytes)?;")?;
+    w.append_line("Ok(state)")?;
+    w.unindent();
+    w.append_line("}")?;
+
+    w.unindent();
+    w.append_line("}")?;
+    w.append_newline()?;
+
+    Ok(())
+}
+
+// Writes the simulator's `snapshot` and `restore` methods. With activity tracking, restoring the
+//  state makes the next call to `prop` evaluate everything.
+pub(super) fn write_methods<W: Write>(
+    state_name: &str,
+    state_members: &[StateMember],
+    lanes: Lanes,
+    activity_tracking: bool,
+    w: &mut code_writer::CodeWriter<W>,
+) -> Result<()> {
+    w.append_newline()?;
+    w.append_line(&format!("pub fn snapshot(&self) -> {} {{", state_name))?;
+    w.indent();
+    w.append_line(&format!("{} {{", state_name))?;
+    w.indent();
+    for member in state_members.iter() {
+        if member.len.is_some() {
+            w.append_line(&format!("{}: self.{}.clone(),", member.name, member.name))?;
+        } else {
+            w.append_line(&format!("{}: self.{},", member.name, member.name))?;
+        }
+    }
+    w.unindent();
+    w.append_line("}")?;
+    w.unindent();
+    w.append_line("}")?;
+
+    w.append_newline()?;
+    w.append_line(&format!(
+        "pub fn restore(&mut self, state: &{}) {{",
+        state_name
+    ))?;
+    w.indent();
+    for member in state_members.iter() {
+        let name = &member.name;
+        if member.len.is_some() && lanes.is_some() {
+            w.append_line(&format!(
+                "self.{}.iter_mut().zip(state.{}.iter()).for_each(|(lane, state)| lane.copy_from_slice(state));",
+                name, name
+            ))?;
+        } else if member.len.is_some() {
+            w.append_line(&format!("self.{}.copy_from_slice(&state.{});", name, name))?;
+        } else {
+            w.append_line(&format!("self.{} = state.{};", name, name))?;
+        }
+    }
+    if activity_tracking {
+        w.append_line("self.__propagated = false;")?;
+    }
+    w.unindent();
+    w.append_line("}")?;
+
+    Ok(())
+}
+
+// Returns the 64-bit FNV-1a hash of `strings`, concatenated.
+fn fingerprint<I: IntoIterator<Item = String>>(strings: I) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for string in strings {
+        for byte in string.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
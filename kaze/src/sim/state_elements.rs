@@ -1,23 +1,38 @@
 use crate::graph;
 use crate::module_context::*;
+use crate::simplify::*;
 
 use typed_arena::Arena;
 
 use std::collections::HashMap;
 
+// The number of references to each signal in each context, keyed by address (like
+//  `ModuleContext`'s children), as both contain `RefCell`s.
+pub(super) type SignalReferenceCounts<'graph, 'arena> = HashMap<
+    (
+        *const ModuleContext<'graph, 'arena>,
+        *const graph::Signal<'graph>,
+    ),
+    u32,
+>;
+
 pub(super) struct Register<'a> {
     pub data: &'a graph::RegisterData<'a>,
     pub value_name: String,
     pub next_name: String,
+    // Stable name derived from the register's hierarchical path, used for generated accessors
+    pub accessor_name: String,
+    pub path: String,
 }
 
 pub(super) struct Mem<'a> {
     pub mem: &'a graph::Mem<'a>,
     pub mem_name: String,
-    pub read_signal_names: HashMap<(&'a graph::Signal<'a>, &'a graph::Signal<'a>), ReadSignalNames>,
-    pub write_address_name: String,
-    pub write_value_name: String,
-    pub write_enable_name: String,
+    // Stable name derived from the mem's hierarchical path, used for generated accessors
+    pub accessor_name: String,
+    pub path: String,
+    pub read_signal_names: HashMap<graph::ReadPort<'a>, ReadSignalNames>,
+    pub write_signal_names: Vec<WriteSignalNames>,
 }
 
 pub struct ReadSignalNames {
@@ -26,6 +41,32 @@ pub struct ReadSignalNames {
     pub value_name: String,
 }
 
+// The output of a (non-root) instance, which is stored so that it can be accessed from generated code
+pub(super) struct InstanceOutput<'graph, 'arena> {
+    pub signal: &'graph graph::Signal<'graph>,
+    pub context: &'arena ModuleContext<'graph, 'arena>,
+    pub value_name: String,
+    pub accessor_name: String,
+    pub path: String,
+}
+
+// A property's condition, which is stored so that it can be checked when its clock domain's clock
+//  ticks (along with the number of times it held, for covers)
+pub(super) struct Property<'graph, 'arena> {
+    pub property: &'graph graph::Property<'graph>,
+    pub context: &'arena ModuleContext<'graph, 'arena>,
+    pub value_name: String,
+    pub count_name: String,
+    pub path: String,
+}
+
+pub struct WriteSignalNames {
+    pub address_name: String,
+    pub value_name: String,
+    pub enable_name: String,
+    pub mask_name: String,
+}
+
 pub(super) struct StateElements<'graph, 'arena> {
     pub mems: HashMap<
         (
@@ -41,6 +82,8 @@ pub(super) struct StateElements<'graph, 'arena> {
         ),
         Register<'graph>,
     >,
+    pub instance_outputs: Vec<InstanceOutput<'graph, 'arena>>,
+    pub properties: Vec<Property<'graph, 'arena>>,
 }
 
 impl<'graph, 'arena> StateElements<'graph, 'arena> {
@@ -48,6 +91,8 @@ impl<'graph, 'arena> StateElements<'graph, 'arena> {
         StateElements {
             mems: HashMap::new(),
             regs: HashMap::new(),
+            instance_outputs: Vec::new(),
+            properties: Vec::new(),
         }
     }
 
@@ -57,13 +102,8 @@ impl<'graph, 'arena> StateElements<'graph, 'arena> {
         signal: &'graph graph::Signal<'graph>,
         context: &'arena ModuleContext<'graph, 'arena>,
         context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
-        signal_reference_counts: &mut HashMap<
-            (
-                &'arena ModuleContext<'graph, 'arena>,
-                &'graph graph::Signal<'graph>,
-            ),
-            u32,
-        >,
+        signal_reference_counts: &mut SignalReferenceCounts<'graph, 'arena>,
+        simplifier: &mut Simplifier<'graph>,
     ) {
         struct Frame<'graph, 'arena> {
             signal: &'graph graph::Signal<'graph>,
@@ -74,11 +114,11 @@ impl<'graph, 'arena> StateElements<'graph, 'arena> {
         frames.push(Frame { signal, context });
 
         while let Some(frame) = frames.pop() {
-            let signal = frame.signal;
+            let signal = simplifier.simplify(frame.signal);
             let context = frame.context;
 
             let reference_count = signal_reference_counts
-                .entry((context, signal))
+                .entry((context as *const _, signal as *const _))
                 .or_insert(0);
             *reference_count += 1;
 
@@ -108,6 +148,8 @@ impl<'graph, 'arena> StateElements<'graph, 'arena> {
                             data,
                             value_name,
                             next_name,
+                            accessor_name: context.qualified_name(&data.name),
+                            path: context.relative_path(&data.name),
                         },
                     );
                     frames.push(Frame {
@@ -240,65 +282,171 @@ impl<'graph, 'arena> StateElements<'graph, 'arena> {
                 }
 
                 graph::SignalData::MemReadPortOutput { mem, .. } => {
-                    let key = (context, mem);
-                    let mem_name = format!("{}_{}", mem.name, self.mems.len());
-                    // TODO: It might actually be too conservative to trace all read ports,
-                    //  as we only know that the write port and _this_ read port are reachable
-                    //  at this point, but we have to keep some extra state to know whether or
-                    //  not we've hit each read port otherwise.
-                    let mut read_signal_names = HashMap::new();
-                    for (index, (address, enable)) in mem.read_ports.borrow().iter().enumerate() {
-                        let name_prefix = format!("{}_read_port_{}_", mem_name, index);
-                        read_signal_names.insert(
-                            (*address, *enable),
-                            ReadSignalNames {
-                                address_name: format!("{}address", name_prefix),
-                                enable_name: format!("{}enable", name_prefix),
-                                value_name: format!("{}value", name_prefix),
-                            },
-                        );
-                    }
-                    let name_prefix = format!("{}_write_port_", mem_name);
-                    let write_address_name = format!("{}address", name_prefix);
-                    let write_value_name = format!("{}value", name_prefix);
-                    let write_enable_name = format!("{}enable", name_prefix);
-                    self.mems.insert(
-                        key,
-                        Mem {
-                            mem,
-                            mem_name,
-                            write_address_name,
-                            write_value_name,
-                            write_enable_name,
-                            read_signal_names,
-                        },
-                    );
-                    for (address, enable) in mem.read_ports.borrow().iter() {
-                        frames.push(Frame {
-                            signal: address,
-                            context,
-                        });
-                        frames.push(Frame {
-                            signal: enable,
-                            context,
-                        });
+                    if self.add_mem(mem, context) {
+                        for signal in mem_port_signals(mem) {
+                            frames.push(Frame { signal, context });
+                        }
                     }
-                    if let Some((address, value, enable)) = *mem.write_port.borrow() {
-                        frames.push(Frame {
-                            signal: address,
-                            context,
-                        });
-                        frames.push(Frame {
-                            signal: value,
-                            context,
-                        });
-                        frames.push(Frame {
-                            signal: enable,
-                            context,
-                        });
+                }
+                // Unlike synchronous read ports, asynchronous read ports depend on their address
+                //  combinationally
+                graph::SignalData::MemAsyncReadPortOutput { mem, address } => {
+                    frames.push(Frame {
+                        signal: address,
+                        context,
+                    });
+                    if self.add_mem(mem, context) {
+                        for signal in mem_port_signals(mem) {
+                            frames.push(Frame { signal, context });
+                        }
                     }
                 }
             }
         }
     }
+
+    // Adds every property in `m`'s hierarchy, and if `full_hierarchy_access` is `true`, every
+    //  register, mem, and instance output as well, including those that don't affect any outputs,
+    //  so that they're still accessible in generated code. Returns the signals that must be
+    //  gathered as a result.
+    pub fn gather_hierarchy(
+        &mut self,
+        m: &'graph graph::Module<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
+        full_hierarchy_access: bool,
+    ) -> Vec<(
+        &'graph graph::Signal<'graph>,
+        &'arena ModuleContext<'graph, 'arena>,
+    )> {
+        let mut ret = Vec::new();
+        if full_hierarchy_access {
+            for &register in m.registers.borrow().iter() {
+                ret.push((register, context));
+            }
+            for mem in m.mems.borrow().iter() {
+                if self.add_mem(mem, context) {
+                    ret.extend(
+                        mem_port_signals(mem)
+                            .into_iter()
+                            .map(|signal| (signal, context)),
+                    );
+                }
+            }
+        }
+        if full_hierarchy_access && context.instance_and_parent.is_some() {
+            for (name, &output) in m.outputs.borrow().iter() {
+                self.instance_outputs.push(InstanceOutput {
+                    signal: output,
+                    context,
+                    value_name: format!(
+                        "__instance_output_{}_{}",
+                        name,
+                        self.instance_outputs.len()
+                    ),
+                    accessor_name: context.qualified_name(name),
+                    path: context.relative_path(name),
+                });
+                ret.push((output, context));
+            }
+        }
+        for &property in m.properties.borrow().iter() {
+            let value_name = format!("__property_{}_{}", property.name, self.properties.len());
+            self.properties.push(Property {
+                property,
+                context,
+                count_name: format!("{}_count", value_name),
+                value_name,
+                path: context.relative_path(&property.name),
+            });
+            ret.push((property.condition, context));
+        }
+        for instance in m.instances.borrow().iter() {
+            ret.extend(self.gather_hierarchy(
+                instance.instantiated_module,
+                context.get_child(instance, context_arena),
+                context_arena,
+                full_hierarchy_access,
+            ));
+        }
+        ret
+    }
+
+    // Returns whether or not `mem` was added, i.e. it wasn't already gathered in this context.
+    fn add_mem(
+        &mut self,
+        mem: &'graph graph::Mem<'graph>,
+        context: &'arena ModuleContext<'graph, 'arena>,
+    ) -> bool {
+        let key = (context, mem);
+        if self.mems.contains_key(&key) {
+            return false;
+        }
+        let mem_name = format!("{}_{}", mem.name, self.mems.len());
+        // Read ports are hashed and compared by the addresses of their signals, so the signals'
+        //  interior mutability can't affect the map
+        #[allow(clippy::mutable_key_type)]
+        let mut read_signal_names = HashMap::new();
+        for (index, read_port) in mem.read_ports.borrow().iter().enumerate() {
+            let name_prefix = format!("{}_read_port_{}_", mem_name, index);
+            read_signal_names.insert(
+                *read_port,
+                ReadSignalNames {
+                    address_name: format!("{}address", name_prefix),
+                    enable_name: format!("{}enable", name_prefix),
+                    value_name: format!("{}value", name_prefix),
+                },
+            );
+        }
+        let num_write_ports = mem.write_ports.borrow().len();
+        let write_signal_names = (0..num_write_ports)
+            .map(|index| {
+                // Memories with a single write port keep the unindexed names
+                let name_prefix = if num_write_ports == 1 {
+                    format!("{}_write_port_", mem_name)
+                } else {
+                    format!("{}_write_port_{}_", mem_name, index)
+                };
+                WriteSignalNames {
+                    address_name: format!("{}address", name_prefix),
+                    value_name: format!("{}value", name_prefix),
+                    enable_name: format!("{}enable", name_prefix),
+                    mask_name: format!("{}mask", name_prefix),
+                }
+            })
+            .collect();
+        self.mems.insert(
+            key,
+            Mem {
+                mem,
+                mem_name,
+                accessor_name: context.qualified_name(&mem.name),
+                path: context.relative_path(&mem.name),
+                write_signal_names,
+                read_signal_names,
+            },
+        );
+        true
+    }
+}
+
+// Returns the signals driving `mem`'s (synchronous) read ports and write ports.
+// TODO: It might actually be too conservative to trace all read ports, as we only know that the
+//  write port and _one_ read port are reachable when a mem is reached, but we have to keep some
+//  extra state to know whether or not we've hit each read port otherwise.
+fn mem_port_signals<'graph>(mem: &'graph graph::Mem<'graph>) -> Vec<&'graph graph::Signal<'graph>> {
+    let mut signals = Vec::new();
+    for read_port in mem.read_ports.borrow().iter() {
+        signals.push(read_port.address);
+        signals.push(read_port.enable);
+    }
+    for write_port in mem.write_ports.borrow().iter() {
+        signals.push(write_port.address);
+        signals.push(write_port.value);
+        signals.push(write_port.enable);
+        if let Some(mask) = write_port.mask {
+            signals.push(mask.mask);
+        }
+    }
+    signals
 }
@@ -0,0 +1,332 @@
+use super::ir::*;
+use super::lanes::*;
+use super::state_elements::*;
+use super::TraceFilter;
+
+use crate::code_writer;
+use crate::graph;
+use crate::module_context::*;
+use crate::runtime::tracing::*;
+
+use std::collections::HashMap;
+use std::io::{Result, Write};
+
+// The signals that are traced in each scope (keyed by the address of the scope's context), along
+//  with every scope in the trace, each of which has a flag that's used to enable and disable
+//  tracing its signals at runtime
+pub(super) struct TraceSignals<'graph, 'arena, 'options> {
+    enabled: bool,
+    filter: &'options TraceFilter,
+    signals: HashMap<*const ModuleContext<'graph, 'arena>, Vec<TraceSignal>>,
+    num_signals: usize,
+    scopes: Vec<&'arena ModuleContext<'graph, 'arena>>,
+}
+
+struct TraceSignal {
+    name: String,
+    member_name: String,
+    value_name: String,
+    bit_width: u32,
+    type_: TraceValueType,
+}
+
+impl<'graph, 'arena, 'options> TraceSignals<'graph, 'arena, 'options> {
+    pub fn new(
+        enabled: bool,
+        filter: &'options TraceFilter,
+    ) -> TraceSignals<'graph, 'arena, 'options> {
+        TraceSignals {
+            enabled,
+            filter,
+            signals: HashMap::new(),
+            num_signals: 0,
+            scopes: Vec::new(),
+        }
+    }
+
+    // Traces the member `value_name` as a signal called `name` in `context`'s scope, if tracing is
+    //  enabled and the signal isn't filtered out.
+    fn add(
+        &mut self,
+        context: &'arena ModuleContext<'graph, 'arena>,
+        name: String,
+        value_name: String,
+        bit_width: u32,
+    ) {
+        if self.enabled && self.filter.includes_signal(context, &name) {
+            let member_name = format!("__trace_signal_id_{}_{}", name, self.num_signals);
+            self.signals
+                .entry(context as *const _)
+                .or_default()
+                .push(TraceSignal {
+                    name,
+                    member_name,
+                    value_name,
+                    bit_width,
+                    type_: TraceValueType::from_bit_width(bit_width),
+                });
+            self.num_signals += 1;
+        }
+    }
+
+    // Traces `m`'s inputs and outputs (in `root_context`), along with the registers in
+    //  `state_elements` and the values driving its mems' ports.
+    pub fn gather(
+        &mut self,
+        m: &'graph graph::Module<'graph>,
+        state_elements: &StateElements<'graph, 'arena>,
+        root_context: &'arena ModuleContext<'graph, 'arena>,
+    ) {
+        for (name, input) in m.inputs.borrow().iter() {
+            self.add(root_context, name.clone(), name.clone(), input.bit_width());
+        }
+        for (name, output) in m.outputs.borrow().iter() {
+            self.add(root_context, name.clone(), name.clone(), output.bit_width());
+        }
+        for ((context, _), mem) in state_elements.mems.iter() {
+            for (_, read_signal_names) in mem.read_signal_names.iter() {
+                self.add(
+                    context,
+                    read_signal_names.address_name.clone(),
+                    read_signal_names.address_name.clone(),
+                    mem.mem.address_bit_width,
+                );
+                self.add(
+                    context,
+                    read_signal_names.enable_name.clone(),
+                    read_signal_names.enable_name.clone(),
+                    1,
+                );
+            }
+            for (write_port, write_signal_names) in mem
+                .mem
+                .write_ports
+                .borrow()
+                .iter()
+                .zip(mem.write_signal_names.iter())
+            {
+                self.add(
+                    context,
+                    write_signal_names.address_name.clone(),
+                    write_signal_names.address_name.clone(),
+                    mem.mem.address_bit_width,
+                );
+                self.add(
+                    context,
+                    write_signal_names.value_name.clone(),
+                    write_signal_names.value_name.clone(),
+                    mem.mem.element_bit_width,
+                );
+                self.add(
+                    context,
+                    write_signal_names.enable_name.clone(),
+                    write_signal_names.enable_name.clone(),
+                    1,
+                );
+                if write_port.mask.is_some() {
+                    self.add(
+                        context,
+                        write_signal_names.mask_name.clone(),
+                        write_signal_names.mask_name.clone(),
+                        mem.mem.element_bit_width,
+                    );
+                }
+            }
+        }
+        for ((context, _), reg) in state_elements.regs.iter() {
+            self.add(
+                context,
+                reg.data.name.clone(),
+                reg.value_name.clone(),
+                reg.data.bit_width,
+            );
+        }
+    }
+
+    // Gathers the scopes in the trace, which must be done once every context in the hierarchy
+    //  has been created.
+    pub fn gather_scopes(&mut self, root_context: &'arena ModuleContext<'graph, 'arena>) {
+        fn gather<'graph, 'arena>(
+            context: &'arena ModuleContext<'graph, 'arena>,
+            filter: &TraceFilter,
+            scopes: &mut Vec<&'arena ModuleContext<'graph, 'arena>>,
+        ) {
+            scopes.push(context);
+            for child in context.children().values() {
+                if filter.includes_scope(child) {
+                    gather(child, filter, scopes);
+                }
+            }
+        }
+        if self.enabled {
+            gather(root_context, self.filter, &mut self.scopes);
+        }
+    }
+
+    pub fn write_members<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        w.append_newline()?;
+        w.append_line("__trace: T,")?;
+        for context_trace_signals in self.signals.values() {
+            for trace_signal in context_trace_signals.iter() {
+                w.append_line(&format!("{}: T::SignalId,", trace_signal.member_name))?;
+            }
+        }
+        w.append_line("__trace_window_start: u64,")?;
+        w.append_line("__trace_window_end: Option<u64>,")?;
+        for index in 0..self.scopes.len() {
+            w.append_line(&format!(
+                "{}: {},",
+                scope_enabled_name(index),
+                ValueType::Bool.name()
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    // Writes the part of the constructor that adds every traced signal to `trace`.
+    pub fn write_signal_registration<W: Write>(
+        &self,
+        root_context: &'arena ModuleContext<'graph, 'arena>,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> Result<()> {
+        fn visit_context<'graph, 'arena, W: Write>(
+            context: &'arena ModuleContext<'graph, 'arena>,
+            signals: &HashMap<*const ModuleContext<'graph, 'arena>, Vec<TraceSignal>>,
+            filter: &TraceFilter,
+            w: &mut code_writer::CodeWriter<W>,
+        ) -> Result<()> {
+            let module_name = if let Some((instance, _)) = context.instance_and_parent {
+                format!("\"{}\"", instance.name)
+            } else {
+                "instance_name".into()
+            };
+            w.append_line(&format!("trace.push_module({})?;", module_name))?;
+
+            if let Some(module_trace_signals) = signals.get(&(context as *const _)) {
+                for trace_signal in module_trace_signals.iter() {
+                    w.append_line(&format!("let {} = trace.add_signal(\"{}\", {}, kaze::runtime::tracing::TraceValueType::{})?;", trace_signal.member_name, trace_signal.name, trace_signal.bit_width, type_name(&trace_signal.type_)))?;
+                }
+            }
+
+            for child in context.children().values() {
+                if filter.includes_scope(child) {
+                    visit_context(child, signals, filter, w)?;
+                }
+            }
+
+            w.append_line("trace.pop_module()?;")?;
+
+            Ok(())
+        }
+        visit_context(root_context, &self.signals, self.filter, w)?;
+        w.append_newline()?;
+
+        Ok(())
+    }
+
+    pub fn write_initializers<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
+        w.append_newline()?;
+        w.append_line("__trace: trace,")?;
+        for context_trace_signals in self.signals.values() {
+            for trace_signal in context_trace_signals.iter() {
+                w.append_line(&format!("{},", trace_signal.member_name))?;
+            }
+        }
+        w.append_line("__trace_window_start: 0,")?;
+        w.append_line("__trace_window_end: None,")?;
+        for index in 0..self.scopes.len() {
+            w.append_line(&format!("{}: true,", scope_enabled_name(index)))?;
+        }
+
+        Ok(())
+    }
+
+    // Writes `update_trace`, `set_trace_scope_enabled`, and `set_trace_window`. With lanes, only
+    //  lane 0 is traced.
+    pub fn write_methods<W: Write>(
+        &self,
+        lanes: Lanes,
+        w: &mut code_writer::CodeWriter<W>,
+    ) -> Result<()> {
+        w.append_newline()?;
+        w.append_line("pub fn update_trace(&mut self, time_stamp: u64) -> std::io::Result<()> {")?;
+        w.indent();
+
+        w.append_line("if time_stamp < self.__trace_window_start || matches!(self.__trace_window_end, Some(end) if time_stamp >= end) {")?;
+        w.indent();
+        w.append_line("return Ok(());")?;
+        w.unindent();
+        w.append_line("}")?;
+        w.append_newline()?;
+
+        w.append_line("self.__trace.update_time_stamp(time_stamp)?;")?;
+        w.append_newline()?;
+
+        for (index, context) in self.scopes.iter().enumerate() {
+            let context_trace_signals = match self.signals.get(&(*context as *const _)) {
+                Some(context_trace_signals) => context_trace_signals,
+                _ => continue,
+            };
+            w.append_line(&format!("if self.{} {{", scope_enabled_name(index)))?;
+            w.indent();
+            for trace_signal in context_trace_signals.iter() {
+                w.append_line(&format!("self.__trace.update_signal(&self.{}, kaze::runtime::tracing::TraceValue::{}({}self.{}{}))?;", trace_signal.member_name, type_name(&trace_signal.type_), match trace_signal.type_ {
+                    TraceValueType::Wide => "&",
+                    _ => "",
+                }, trace_signal.value_name, if lanes.is_some() { "[0]" } else { "" }))?;
+            }
+            w.unindent();
+            w.append_line("}")?;
+        }
+        w.append_newline()?;
+
+        w.append_line("Ok(())")?;
+
+        w.unindent();
+        w.append_line("}")?;
+
+        w.append_newline()?;
+        w.append_line(
+            "pub fn set_trace_scope_enabled(&mut self, scope: &str, enabled: bool) -> bool {",
+        )?;
+        w.indent();
+        w.append_line("let contains = |path: &str| scope.is_empty() || matches!(path.strip_prefix(scope), Some(rest) if rest.is_empty() || rest.starts_with('.'));")?;
+        w.append_line("let mut found = false;")?;
+        for (index, context) in self.scopes.iter().enumerate() {
+            w.append_line(&format!("if contains({:?}) {{", context.instance_path()))?;
+            w.indent();
+            w.append_line(&format!("self.{} = enabled;", scope_enabled_name(index)))?;
+            w.append_line("found = true;")?;
+            w.unindent();
+            w.append_line("}")?;
+        }
+        w.append_line("found")?;
+        w.unindent();
+        w.append_line("}")?;
+
+        w.append_newline()?;
+        w.append_line("pub fn set_trace_window(&mut self, start: u64, end: Option<u64>) {")?;
+        w.indent();
+        w.append_line("self.__trace_window_start = start;")?;
+        w.append_line("self.__trace_window_end = end;")?;
+        w.unindent();
+        w.append_line("}")?;
+
+        Ok(())
+    }
+}
+
+fn scope_enabled_name(index: usize) -> String {
+    format!("__trace_scope_enabled_{}", index)
+}
+
+fn type_name(type_: &TraceValueType) -> &'static str {
+    match type_ {
+        TraceValueType::Bool => "Bool",
+        TraceValueType::U32 => "U32",
+        TraceValueType::U64 => "U64",
+        TraceValueType::U128 => "U128",
+        TraceValueType::Wide => "Wide",
+    }
+}
@@ -0,0 +1,1644 @@
+use crate::graph;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem;
+
+// Rewrites signals into simpler, equivalent signals before code generation by folding constants,
+//  eliminating identity and absorbing elements, removing redundant `bits` and `repeat` operations,
+//  and merging common subexpressions. Code generators pass each signal they visit through
+//  `simplify`, which also covers the operands of rewritten signals, as these are simplified too.
+//
+// State elements, inputs, and instance outputs are never rewritten, as generators refer to them
+//  (and to the signals stored in them, such as memory port addresses) by identity. New signals
+//  are allocated in an arena of the `Context` that's reserved for them, so they live as long as
+//  the signals they replace, but aren't part of any module. The simplified signals and common
+//  subexpressions found so far are kept in the `Context` too, between each simplifier's creation
+//  and drop, so that generating code repeatedly reuses the signals allocated by earlier
+//  generators rather than growing the `Context`. A disabled simplifier returns every signal as-is.
+//
+// When simplifying logic for four-state simulation, rewrites that would make values with unknown
+//  (X) bits more known than Verilog's X-propagation rules allow (e.g. `x ^ x` or `x * 0` becoming
+//  0, or `x + 0` becoming `x`, which keeps its known bits) are skipped.
+pub struct Simplifier<'a> {
+    context: &'a graph::Context<'a>,
+    enabled: bool,
+    preserve_unknowns: bool,
+    cache: SimplifierCache<'a>,
+}
+
+// The results of earlier simplifiers with the same `preserve_unknowns` setting, which are stored in
+//  their `Context`
+#[derive(Default)]
+pub(crate) struct SimplifierCache<'a> {
+    simplified_signals: HashMap<*const graph::Signal<'a>, &'a graph::Signal<'a>>,
+    common_subexpressions: HashMap<Expr<'a>, &'a graph::Signal<'a>>,
+}
+
+// The structure of an operation, used to identify common subexpressions. Operands of commutative
+//  operations are ordered by address, so that e.g. `a & b` and `b & a` match.
+#[derive(Eq, Hash, PartialEq)]
+enum Expr<'a> {
+    UnOp(*const graph::Signal<'a>, graph::UnOp),
+    SimpleBinOp(
+        *const graph::Signal<'a>,
+        *const graph::Signal<'a>,
+        graph::SimpleBinOp,
+    ),
+    AdditiveBinOp(
+        *const graph::Signal<'a>,
+        *const graph::Signal<'a>,
+        graph::AdditiveBinOp,
+    ),
+    ComparisonBinOp(
+        *const graph::Signal<'a>,
+        *const graph::Signal<'a>,
+        graph::ComparisonBinOp,
+    ),
+    ShiftBinOp(
+        *const graph::Signal<'a>,
+        *const graph::Signal<'a>,
+        graph::ShiftBinOp,
+    ),
+    Mul(*const graph::Signal<'a>, *const graph::Signal<'a>),
+    MulSigned(*const graph::Signal<'a>, *const graph::Signal<'a>),
+    Bits(*const graph::Signal<'a>, u32, u32),
+    Repeat(*const graph::Signal<'a>, u32),
+    Concat(*const graph::Signal<'a>, *const graph::Signal<'a>),
+    Mux(
+        *const graph::Signal<'a>,
+        *const graph::Signal<'a>,
+        *const graph::Signal<'a>,
+    ),
+}
+
+impl<'a> Expr<'a> {
+    fn from_signal_data(data: &graph::SignalData<'a>) -> Expr<'a> {
+        match *data {
+            graph::SignalData::UnOp { source, op, .. } => Expr::UnOp(source, op),
+            graph::SignalData::SimpleBinOp { lhs, rhs, op, .. } => {
+                let (lhs, rhs) = commutative_operands(lhs, rhs);
+                Expr::SimpleBinOp(lhs, rhs, op)
+            }
+            graph::SignalData::AdditiveBinOp { lhs, rhs, op, .. } => match op {
+                graph::AdditiveBinOp::Add => {
+                    let (lhs, rhs) = commutative_operands(lhs, rhs);
+                    Expr::AdditiveBinOp(lhs, rhs, op)
+                }
+                graph::AdditiveBinOp::Sub => Expr::AdditiveBinOp(lhs, rhs, op),
+            },
+            graph::SignalData::ComparisonBinOp { lhs, rhs, op } => match op {
+                graph::ComparisonBinOp::Equal | graph::ComparisonBinOp::NotEqual => {
+                    let (lhs, rhs) = commutative_operands(lhs, rhs);
+                    Expr::ComparisonBinOp(lhs, rhs, op)
+                }
+                _ => Expr::ComparisonBinOp(lhs, rhs, op),
+            },
+            graph::SignalData::ShiftBinOp { lhs, rhs, op, .. } => Expr::ShiftBinOp(lhs, rhs, op),
+            graph::SignalData::Mul { lhs, rhs, .. } => {
+                let (lhs, rhs) = commutative_operands(lhs, rhs);
+                Expr::Mul(lhs, rhs)
+            }
+            graph::SignalData::MulSigned { lhs, rhs, .. } => {
+                let (lhs, rhs) = commutative_operands(lhs, rhs);
+                Expr::MulSigned(lhs, rhs)
+            }
+            graph::SignalData::Bits {
+                source,
+                range_high,
+                range_low,
+            } => Expr::Bits(source, range_high, range_low),
+            graph::SignalData::Repeat { source, count, .. } => Expr::Repeat(source, count),
+            graph::SignalData::Concat { lhs, rhs, .. } => Expr::Concat(lhs, rhs),
+            graph::SignalData::Mux {
+                cond,
+                when_true,
+                when_false,
+                ..
+            } => Expr::Mux(cond, when_true, when_false),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Simplifier<'a> {
+    pub fn new(
+        context: &'a graph::Context<'a>,
+        enabled: bool,
+        preserve_unknowns: bool,
+    ) -> Simplifier<'a> {
+        let cache = if enabled {
+            mem::take(&mut *context.simplifier_cache(preserve_unknowns).borrow_mut())
+        } else {
+            SimplifierCache::default()
+        };
+        Simplifier {
+            context,
+            enabled,
+            preserve_unknowns,
+            cache,
+        }
+    }
+
+    pub fn simplify(&mut self, signal: &'a graph::Signal<'a>) -> &'a graph::Signal<'a> {
+        if !self.enabled {
+            return signal;
+        }
+
+        enum Frame<'a> {
+            Enter(&'a graph::Signal<'a>),
+            Leave(&'a graph::Signal<'a>),
+        }
+
+        let mut frames = Vec::new();
+        frames.push(Frame::Enter(signal));
+
+        let mut results = Vec::new();
+
+        while let Some(frame) = frames.pop() {
+            if let Some((signal, simplified)) = match frame {
+                Frame::Enter(signal) => {
+                    if let Some(&simplified) =
+                        self.cache.simplified_signals.get(&(signal as *const _))
+                    {
+                        results.push(simplified);
+                        continue;
+                    }
+
+                    match signal.data {
+                        graph::SignalData::Lit { .. }
+                        | graph::SignalData::Input { .. }
+                        | graph::SignalData::Reg { .. }
+                        | graph::SignalData::InstanceOutput { .. }
+                        | graph::SignalData::MemReadPortOutput { .. }
+                        | graph::SignalData::MemAsyncReadPortOutput { .. } => {
+                            Some((signal, signal))
+                        }
+
+                        graph::SignalData::UnOp { source, .. }
+                        | graph::SignalData::Bits { source, .. }
+                        | graph::SignalData::Repeat { source, .. } => {
+                            frames.push(Frame::Leave(signal));
+                            frames.push(Frame::Enter(source));
+                            None
+                        }
+                        graph::SignalData::SimpleBinOp { lhs, rhs, .. }
+                        | graph::SignalData::AdditiveBinOp { lhs, rhs, .. }
+                        | graph::SignalData::ComparisonBinOp { lhs, rhs, .. }
+                        | graph::SignalData::ShiftBinOp { lhs, rhs, .. }
+                        | graph::SignalData::Mul { lhs, rhs, .. }
+                        | graph::SignalData::MulSigned { lhs, rhs, .. }
+                        | graph::SignalData::Concat { lhs, rhs, .. } => {
+                            frames.push(Frame::Leave(signal));
+                            frames.push(Frame::Enter(lhs));
+                            frames.push(Frame::Enter(rhs));
+                            None
+                        }
+
+                        graph::SignalData::Mux {
+                            cond,
+                            when_true,
+                            when_false,
+                            ..
+                        } => {
+                            frames.push(Frame::Leave(signal));
+                            frames.push(Frame::Enter(cond));
+                            frames.push(Frame::Enter(when_true));
+                            frames.push(Frame::Enter(when_false));
+                            None
+                        }
+                    }
+                }
+                Frame::Leave(signal) => {
+                    let simplified = match signal.data {
+                        graph::SignalData::UnOp { op, bit_width, .. } => {
+                            let source = results.pop().unwrap();
+                            self.simplify_un_op(signal, source, op, bit_width)
+                        }
+                        graph::SignalData::SimpleBinOp { op, bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            self.simplify_simple_bin_op(signal, lhs, rhs, op, bit_width)
+                        }
+                        graph::SignalData::AdditiveBinOp { op, bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            self.simplify_additive_bin_op(signal, lhs, rhs, op, bit_width)
+                        }
+                        graph::SignalData::ComparisonBinOp { op, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            self.simplify_comparison_bin_op(signal, lhs, rhs, op)
+                        }
+                        graph::SignalData::ShiftBinOp { op, bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            self.simplify_shift_bin_op(signal, lhs, rhs, op, bit_width)
+                        }
+                        graph::SignalData::Mul { bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            self.simplify_mul(signal, lhs, rhs, false, bit_width)
+                        }
+                        graph::SignalData::MulSigned { bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            self.simplify_mul(signal, lhs, rhs, true, bit_width)
+                        }
+                        graph::SignalData::Bits {
+                            range_high,
+                            range_low,
+                            ..
+                        } => {
+                            let source = results.pop().unwrap();
+                            self.simplify_bits(signal, source, range_high, range_low)
+                        }
+                        graph::SignalData::Repeat {
+                            count, bit_width, ..
+                        } => {
+                            let source = results.pop().unwrap();
+                            self.simplify_repeat(signal, source, count, bit_width)
+                        }
+                        graph::SignalData::Concat { bit_width, .. } => {
+                            let lhs = results.pop().unwrap();
+                            let rhs = results.pop().unwrap();
+                            self.simplify_concat(signal, lhs, rhs, bit_width)
+                        }
+                        graph::SignalData::Mux { bit_width, .. } => {
+                            let cond = results.pop().unwrap();
+                            let when_true = results.pop().unwrap();
+                            let when_false = results.pop().unwrap();
+                            self.simplify_mux(signal, cond, when_true, when_false, bit_width)
+                        }
+
+                        _ => unreachable!(),
+                    };
+                    Some((signal, simplified))
+                }
+            } {
+                self.cache.simplified_signals.insert(signal, simplified);
+                // Simplified signals are already as simple as they get
+                self.cache.simplified_signals.insert(simplified, simplified);
+                results.push(simplified);
+            }
+        }
+
+        results.pop().unwrap()
+    }
+
+    // Returns the value of `signal` as little-endian 64-bit words if it folds to a constant.
+    pub fn constant_value(&mut self, signal: &'a graph::Signal<'a>) -> Option<Vec<u64>> {
+        lit_value(self.simplify(signal))
+    }
+
+    fn simplify_un_op(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        source: &'a graph::Signal<'a>,
+        op: graph::UnOp,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        match op {
+            graph::UnOp::Not => {
+                if let Some(value) = lit_value(source) {
+                    return self.lit(signal, not(value, bit_width), bit_width);
+                }
+                // !!x == x
+                if let graph::SignalData::UnOp {
+                    source,
+                    op: graph::UnOp::Not,
+                    ..
+                } = source.data
+                {
+                    return source;
+                }
+            }
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::UnOp {
+                source,
+                op,
+                bit_width,
+            },
+        )
+    }
+
+    fn simplify_simple_bin_op(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        lhs: &'a graph::Signal<'a>,
+        rhs: &'a graph::Signal<'a>,
+        op: graph::SimpleBinOp,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        match (lit_value(lhs), lit_value(rhs)) {
+            (Some(lhs), Some(rhs)) => {
+                let value = lhs
+                    .iter()
+                    .zip(rhs.iter())
+                    .map(|(lhs, rhs)| match op {
+                        graph::SimpleBinOp::BitAnd => lhs & rhs,
+                        graph::SimpleBinOp::BitOr => lhs | rhs,
+                        graph::SimpleBinOp::BitXor => lhs ^ rhs,
+                    })
+                    .collect();
+                return self.lit(signal, value, bit_width);
+            }
+            (Some(value), _) | (_, Some(value)) => {
+                let (constant, other) = if lit_value(lhs).is_some() {
+                    (lhs, rhs)
+                } else {
+                    (rhs, lhs)
+                };
+                let value_is_zero = is_zero(&value);
+                let value_is_ones = value == ones(bit_width);
+                match op {
+                    graph::SimpleBinOp::BitAnd if value_is_zero => return constant,
+                    graph::SimpleBinOp::BitAnd if value_is_ones => return other,
+                    graph::SimpleBinOp::BitOr if value_is_zero => return other,
+                    graph::SimpleBinOp::BitOr if value_is_ones => return constant,
+                    graph::SimpleBinOp::BitXor if value_is_zero => return other,
+                    _ => (),
+                }
+            }
+            _ => {
+                if std::ptr::eq(lhs, rhs) {
+                    match op {
+                        graph::SimpleBinOp::BitAnd | graph::SimpleBinOp::BitOr => return lhs,
+                        graph::SimpleBinOp::BitXor if !self.preserve_unknowns => {
+                            return self.lit(signal, zero(bit_width), bit_width);
+                        }
+                        graph::SimpleBinOp::BitXor => (),
+                    }
+                }
+            }
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::SimpleBinOp {
+                lhs,
+                rhs,
+                op,
+                bit_width,
+            },
+        )
+    }
+
+    fn simplify_additive_bin_op(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        lhs: &'a graph::Signal<'a>,
+        rhs: &'a graph::Signal<'a>,
+        op: graph::AdditiveBinOp,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        let lhs_value = lit_value(lhs);
+        let rhs_value = lit_value(rhs);
+        if let (Some(lhs_value), Some(rhs_value)) = (&lhs_value, &rhs_value) {
+            let value = match op {
+                graph::AdditiveBinOp::Add => add(lhs_value, rhs_value, bit_width),
+                graph::AdditiveBinOp::Sub => add(
+                    lhs_value,
+                    &add(
+                        &not(rhs_value.clone(), bit_width),
+                        &one(bit_width),
+                        bit_width,
+                    ),
+                    bit_width,
+                ),
+            };
+            return self.lit(signal, value, bit_width);
+        }
+        // Any unknown bits in an operand make an arithmetic op's entire result unknown
+        if !self.preserve_unknowns {
+            if rhs_value.as_deref().is_some_and(is_zero) {
+                return lhs;
+            }
+            if let graph::AdditiveBinOp::Add = op {
+                if lhs_value.as_deref().is_some_and(is_zero) {
+                    return rhs;
+                }
+            }
+            if let graph::AdditiveBinOp::Sub = op {
+                if std::ptr::eq(lhs, rhs) {
+                    return self.lit(signal, zero(bit_width), bit_width);
+                }
+            }
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::AdditiveBinOp {
+                lhs,
+                rhs,
+                op,
+                bit_width,
+            },
+        )
+    }
+
+    fn simplify_comparison_bin_op(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        lhs: &'a graph::Signal<'a>,
+        rhs: &'a graph::Signal<'a>,
+        op: graph::ComparisonBinOp,
+    ) -> &'a graph::Signal<'a> {
+        let ordering = match (lit_value(lhs), lit_value(rhs)) {
+            (Some(lhs_value), Some(rhs_value)) => {
+                let bit_width = lhs.bit_width();
+                Some(match op {
+                    graph::ComparisonBinOp::GreaterThanEqualSigned
+                    | graph::ComparisonBinOp::GreaterThanSigned
+                    | graph::ComparisonBinOp::LessThanEqualSigned
+                    | graph::ComparisonBinOp::LessThanSigned => compare(
+                        &flip_sign_bit(lhs_value, bit_width),
+                        &flip_sign_bit(rhs_value, bit_width),
+                    ),
+                    _ => compare(&lhs_value, &rhs_value),
+                })
+            }
+            // Any value is equal to itself, though comparisons of unknown values are unknown
+            _ if std::ptr::eq(lhs, rhs) && !self.preserve_unknowns => Some(Ordering::Equal),
+            _ => None,
+        };
+        if let Some(ordering) = ordering {
+            let value = match op {
+                graph::ComparisonBinOp::Equal => ordering == Ordering::Equal,
+                graph::ComparisonBinOp::NotEqual => ordering != Ordering::Equal,
+                graph::ComparisonBinOp::LessThan | graph::ComparisonBinOp::LessThanSigned => {
+                    ordering == Ordering::Less
+                }
+                graph::ComparisonBinOp::LessThanEqual
+                | graph::ComparisonBinOp::LessThanEqualSigned => ordering != Ordering::Greater,
+                graph::ComparisonBinOp::GreaterThan | graph::ComparisonBinOp::GreaterThanSigned => {
+                    ordering == Ordering::Greater
+                }
+                graph::ComparisonBinOp::GreaterThanEqual
+                | graph::ComparisonBinOp::GreaterThanEqualSigned => ordering != Ordering::Less,
+            };
+            return self.lit(signal, vec![value as u64], 1);
+        }
+
+        self.intern(signal, graph::SignalData::ComparisonBinOp { lhs, rhs, op })
+    }
+
+    fn simplify_shift_bin_op(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        lhs: &'a graph::Signal<'a>,
+        rhs: &'a graph::Signal<'a>,
+        op: graph::ShiftBinOp,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        let lhs_value = lit_value(lhs);
+        let rhs_value = lit_value(rhs);
+        if let (Some(lhs_value), Some(rhs_value)) = (&lhs_value, &rhs_value) {
+            let amount = shift_amount(rhs_value);
+            let value = match op {
+                graph::ShiftBinOp::Shl => shl(lhs_value, amount, bit_width),
+                graph::ShiftBinOp::Shr => shr(lhs_value, amount, bit_width),
+                graph::ShiftBinOp::ShrArithmetic => {
+                    if is_negative(lhs_value, bit_width) {
+                        let inverted = not(lhs_value.clone(), bit_width);
+                        not(shr(&inverted, amount, bit_width), bit_width)
+                    } else {
+                        shr(lhs_value, amount, bit_width)
+                    }
+                }
+            };
+            return self.lit(signal, value, bit_width);
+        }
+        // Shifting by zero has no effect, and shifting zero produces zero (unless the shift amount
+        //  may be unknown, which makes the entire result unknown)
+        if rhs_value.as_deref().is_some_and(is_zero)
+            || (lhs_value.as_deref().is_some_and(is_zero) && !self.preserve_unknowns)
+        {
+            return lhs;
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::ShiftBinOp {
+                lhs,
+                rhs,
+                op,
+                bit_width,
+            },
+        )
+    }
+
+    fn simplify_mul(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        lhs: &'a graph::Signal<'a>,
+        rhs: &'a graph::Signal<'a>,
+        signed: bool,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        let lhs_value = lit_value(lhs);
+        let rhs_value = lit_value(rhs);
+        if let (Some(lhs_value), Some(rhs_value)) = (&lhs_value, &rhs_value) {
+            let extend = |value: &[u64], source_bit_width| {
+                if signed {
+                    sign_extend(value, source_bit_width, bit_width)
+                } else {
+                    resize(value, bit_width)
+                }
+            };
+            let lhs_value = extend(lhs_value, lhs.bit_width());
+            let rhs_value = extend(rhs_value, rhs.bit_width());
+            return self.lit(signal, mul(&lhs_value, &rhs_value, bit_width), bit_width);
+        }
+        if (lhs_value.as_deref().is_some_and(is_zero) || rhs_value.as_deref().is_some_and(is_zero))
+            && !self.preserve_unknowns
+        {
+            return self.lit(signal, zero(bit_width), bit_width);
+        }
+
+        if signed {
+            self.intern(
+                signal,
+                graph::SignalData::MulSigned {
+                    lhs,
+                    rhs,
+                    bit_width,
+                },
+            )
+        } else {
+            self.intern(
+                signal,
+                graph::SignalData::Mul {
+                    lhs,
+                    rhs,
+                    bit_width,
+                },
+            )
+        }
+    }
+
+    fn simplify_bits(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        source: &'a graph::Signal<'a>,
+        range_high: u32,
+        range_low: u32,
+    ) -> &'a graph::Signal<'a> {
+        let mut source = source;
+        let mut range_high = range_high;
+        let mut range_low = range_low;
+        loop {
+            let bit_width = range_high - range_low + 1;
+            if range_low == 0 && bit_width == source.bit_width() {
+                return source;
+            }
+            if let Some(value) = lit_value(source) {
+                let value = resize(&shr(&value, range_low, source.bit_width()), bit_width);
+                return self.lit(signal, mask(value, bit_width), bit_width);
+            }
+            match source.data {
+                // Take the bits from the original source
+                graph::SignalData::Bits {
+                    source: inner_source,
+                    range_low: inner_range_low,
+                    ..
+                } => {
+                    source = inner_source;
+                    range_high += inner_range_low;
+                    range_low += inner_range_low;
+                }
+                // Take the bits from one side of a concatenation, if they're all on that side
+                graph::SignalData::Concat { lhs, rhs, .. } => {
+                    let rhs_bit_width = rhs.bit_width();
+                    if range_high < rhs_bit_width {
+                        source = rhs;
+                    } else if range_low >= rhs_bit_width {
+                        source = lhs;
+                        range_high -= rhs_bit_width;
+                        range_low -= rhs_bit_width;
+                    } else {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::Bits {
+                source,
+                range_high,
+                range_low,
+            },
+        )
+    }
+
+    fn simplify_repeat(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        source: &'a graph::Signal<'a>,
+        count: u32,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        if count == 1 {
+            return source;
+        }
+        if let Some(value) = lit_value(source) {
+            let source_bit_width = source.bit_width();
+            let mut ret = zero(bit_width);
+            for _ in 0..count {
+                ret = shl(&ret, source_bit_width, bit_width);
+                ret = or(&ret, &resize(&value, bit_width));
+            }
+            return self.lit(signal, ret, bit_width);
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::Repeat {
+                source,
+                count,
+                bit_width,
+            },
+        )
+    }
+
+    fn simplify_concat(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        lhs: &'a graph::Signal<'a>,
+        rhs: &'a graph::Signal<'a>,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        if let (Some(lhs_value), Some(rhs_value)) = (lit_value(lhs), lit_value(rhs)) {
+            let value = or(
+                &shl(&resize(&lhs_value, bit_width), rhs.bit_width(), bit_width),
+                &resize(&rhs_value, bit_width),
+            );
+            return self.lit(signal, value, bit_width);
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::Concat {
+                lhs,
+                rhs,
+                bit_width,
+            },
+        )
+    }
+
+    fn simplify_mux(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        cond: &'a graph::Signal<'a>,
+        when_true: &'a graph::Signal<'a>,
+        when_false: &'a graph::Signal<'a>,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        if let Some(value) = lit_value(cond) {
+            return if is_zero(&value) {
+                when_false
+            } else {
+                when_true
+            };
+        }
+        if std::ptr::eq(when_true, when_false) {
+            return when_true;
+        }
+        // A mux selecting between 1-bit `1` and `0` is its condition
+        if bit_width == 1
+            && lit_value(when_true) == Some(vec![1])
+            && lit_value(when_false) == Some(vec![0])
+        {
+            return cond;
+        }
+
+        self.intern(
+            signal,
+            graph::SignalData::Mux {
+                cond,
+                when_true,
+                when_false,
+                bit_width,
+            },
+        )
+    }
+
+    fn lit(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        value: Vec<u64>,
+        bit_width: u32,
+    ) -> &'a graph::Signal<'a> {
+        let value = if bit_width <= 128 {
+            graph::Constant::U128(graph::Constant::Wide(value).numeric_value())
+        } else {
+            graph::Constant::Wide(value)
+        };
+        self.alloc(signal.sibling(graph::SignalData::Lit { value, bit_width }))
+    }
+
+    fn alloc(&mut self, signal: graph::Signal<'a>) -> &'a graph::Signal<'a> {
+        self.context.rewritten_signal_arena.alloc(signal)
+    }
+
+    // Returns the existing signal equivalent to `data`, if any. Otherwise, `signal` (or a new signal
+    //  with `data`, if `signal`'s operands were simplified) becomes the signal for `data`.
+    fn intern(
+        &mut self,
+        signal: &'a graph::Signal<'a>,
+        data: graph::SignalData<'a>,
+    ) -> &'a graph::Signal<'a> {
+        let expr = Expr::from_signal_data(&data);
+        if let Some(&existing) = self.cache.common_subexpressions.get(&expr) {
+            return existing;
+        }
+        let ret = if Expr::from_signal_data(&signal.data) == expr {
+            signal
+        } else {
+            self.alloc(signal.sibling(data))
+        };
+        self.cache.common_subexpressions.insert(expr, ret);
+        ret
+    }
+}
+
+impl<'a> Drop for Simplifier<'a> {
+    fn drop(&mut self) {
+        if self.enabled {
+            *self
+                .context
+                .simplifier_cache(self.preserve_unknowns)
+                .borrow_mut() = mem::take(&mut self.cache);
+        }
+    }
+}
+
+fn commutative_operands<'a>(
+    lhs: &'a graph::Signal<'a>,
+    rhs: &'a graph::Signal<'a>,
+) -> (&'a graph::Signal<'a>, &'a graph::Signal<'a>) {
+    if (lhs as *const graph::Signal) <= (rhs as *const graph::Signal) {
+        (lhs, rhs)
+    } else {
+        (rhs, lhs)
+    }
+}
+
+// Constant values are represented as little-endian 64-bit words (as many as required for their
+//  bit width), with any bits above their bit width cleared.
+
+fn lit_value(signal: &graph::Signal) -> Option<Vec<u64>> {
+    match signal.data {
+        graph::SignalData::Lit {
+            ref value,
+            bit_width,
+        } => Some(mask(value.words(num_words(bit_width)), bit_width)),
+        _ => None,
+    }
+}
+
+fn num_words(bit_width: u32) -> usize {
+    (bit_width as usize).div_ceil(64)
+}
+
+fn mask(mut value: Vec<u64>, bit_width: u32) -> Vec<u64> {
+    let num_words = num_words(bit_width);
+    value.resize(num_words, 0);
+    let top_word_bit_width = bit_width % 64;
+    if top_word_bit_width > 0 {
+        value[num_words - 1] &= (1 << top_word_bit_width) - 1;
+    }
+    value
+}
+
+fn resize(value: &[u64], bit_width: u32) -> Vec<u64> {
+    mask(value.to_vec(), bit_width)
+}
+
+fn zero(bit_width: u32) -> Vec<u64> {
+    vec![0; num_words(bit_width)]
+}
+
+fn one(bit_width: u32) -> Vec<u64> {
+    let mut ret = zero(bit_width);
+    ret[0] = 1;
+    ret
+}
+
+fn ones(bit_width: u32) -> Vec<u64> {
+    mask(vec![!0; num_words(bit_width)], bit_width)
+}
+
+fn is_zero(value: &[u64]) -> bool {
+    value.iter().all(|&word| word == 0)
+}
+
+fn is_negative(value: &[u64], bit_width: u32) -> bool {
+    let sign_bit = bit_width - 1;
+    (value[(sign_bit / 64) as usize] >> (sign_bit % 64)) & 1 != 0
+}
+
+fn not(value: Vec<u64>, bit_width: u32) -> Vec<u64> {
+    mask(value.into_iter().map(|word| !word).collect(), bit_width)
+}
+
+fn or(lhs: &[u64], rhs: &[u64]) -> Vec<u64> {
+    lhs.iter()
+        .zip(rhs.iter())
+        .map(|(lhs, rhs)| lhs | rhs)
+        .collect()
+}
+
+fn add(lhs: &[u64], rhs: &[u64], bit_width: u32) -> Vec<u64> {
+    let mut carry = false;
+    let ret = lhs
+        .iter()
+        .zip(rhs.iter())
+        .map(|(&lhs, &rhs)| {
+            let (sum, carry1) = lhs.overflowing_add(rhs);
+            let (sum, carry2) = sum.overflowing_add(carry as u64);
+            carry = carry1 || carry2;
+            sum
+        })
+        .collect();
+    mask(ret, bit_width)
+}
+
+fn mul(lhs: &[u64], rhs: &[u64], bit_width: u32) -> Vec<u64> {
+    let num_words = num_words(bit_width);
+    let mut ret = vec![0u64; num_words];
+    for (i, &lhs_word) in lhs.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, &rhs_word) in rhs.iter().enumerate().take(num_words - i) {
+            let product = (lhs_word as u128) * (rhs_word as u128) + ret[i + j] as u128 + carry;
+            ret[i + j] = product as u64;
+            carry = product >> 64;
+        }
+    }
+    mask(ret, bit_width)
+}
+
+fn sign_extend(value: &[u64], source_bit_width: u32, bit_width: u32) -> Vec<u64> {
+    let ret = resize(value, bit_width);
+    if is_negative(value, source_bit_width) {
+        or(&ret, &shl(&ones(bit_width), source_bit_width, bit_width))
+    } else {
+        ret
+    }
+}
+
+// Saturates to `u32::MAX`, which shifts out all bits of any value.
+fn shift_amount(value: &[u64]) -> u32 {
+    if value.iter().skip(1).any(|&word| word != 0) || value[0] > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        value[0] as u32
+    }
+}
+
+fn shl(value: &[u64], amount: u32, bit_width: u32) -> Vec<u64> {
+    if amount >= bit_width {
+        return zero(bit_width);
+    }
+    let word_shift = (amount / 64) as usize;
+    let bit_shift = amount % 64;
+    let ret = (0..num_words(bit_width))
+        .map(|i| {
+            if i < word_shift {
+                return 0;
+            }
+            let word = value[i - word_shift] << bit_shift;
+            if bit_shift != 0 && i > word_shift {
+                word | (value[i - word_shift - 1] >> (64 - bit_shift))
+            } else {
+                word
+            }
+        })
+        .collect();
+    mask(ret, bit_width)
+}
+
+fn shr(value: &[u64], amount: u32, bit_width: u32) -> Vec<u64> {
+    if amount >= bit_width {
+        return zero(bit_width);
+    }
+    let word_shift = (amount / 64) as usize;
+    let bit_shift = amount % 64;
+    let word = |i: usize| value.get(i).copied().unwrap_or(0);
+    (0..num_words(bit_width))
+        .map(|i| {
+            let ret = word(i + word_shift) >> bit_shift;
+            if bit_shift != 0 {
+                ret | (word(i + word_shift + 1) << (64 - bit_shift))
+            } else {
+                ret
+            }
+        })
+        .collect()
+}
+
+fn flip_sign_bit(mut value: Vec<u64>, bit_width: u32) -> Vec<u64> {
+    let sign_bit = bit_width - 1;
+    value[(sign_bit / 64) as usize] ^= 1 << (sign_bit % 64);
+    value
+}
+
+fn compare(lhs: &[u64], rhs: &[u64]) -> Ordering {
+    lhs.iter().rev().cmp(rhs.iter().rev())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::*;
+
+    use std::ptr;
+
+    fn folded<'a>(s: &mut Simplifier<'a>, signal: &'a Signal<'a>) -> u128 {
+        let simplified = s.simplify(signal);
+        graph::Constant::Wide(lit_value(simplified).expect("signal was not folded")).numeric_value()
+    }
+
+    #[test]
+    fn constant_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let a = m.lit(0xa5u32, 8);
+        let b = m.lit(0x3cu32, 8);
+        assert_eq!(folded(&mut s, !a), 0x5a);
+        assert_eq!(folded(&mut s, a & b), 0x24);
+        assert_eq!(folded(&mut s, a | b), 0xbd);
+        assert_eq!(folded(&mut s, a ^ b), 0x99);
+        assert_eq!(folded(&mut s, a + b), 0xe1);
+        assert_eq!(folded(&mut s, a + a), 0x4a);
+        assert_eq!(folded(&mut s, b - a), 0x97);
+        assert_eq!(folded(&mut s, a * b), 0xa5 * 0x3c);
+        // -91 * 60
+        assert_eq!(folded(&mut s, a.mul_signed(b)), (-5460i32 as u16) as u128);
+        assert_eq!(folded(&mut s, a.lt(b)), 0);
+        assert_eq!(folded(&mut s, a.lt_signed(b)), 1);
+        assert_eq!(folded(&mut s, a.ge(b)), 1);
+        assert_eq!(folded(&mut s, a.eq(a + m.lit(0u32, 8))), 1);
+        assert_eq!(folded(&mut s, a << m.lit(3u32, 4)), 0x28);
+        assert_eq!(folded(&mut s, a >> m.lit(3u32, 4)), 0x14);
+        assert_eq!(folded(&mut s, a.shr_arithmetic(m.lit(3u32, 4))), 0xf4);
+        assert_eq!(folded(&mut s, a.shr_arithmetic(m.lit(8u32, 4))), 0xff);
+        assert_eq!(folded(&mut s, a << m.lit(1u32 << 20, 32)), 0);
+        assert_eq!(folded(&mut s, a.bits(6, 2)), 0x09);
+        assert_eq!(folded(&mut s, a.bits(3, 0).repeat(3)), 0x555);
+        assert_eq!(folded(&mut s, a.concat(b)), 0xa53c);
+        assert_eq!(folded(&mut s, m.mux(a.bit(0), a, b)), 0xa5);
+
+        // Wide values
+        let w = m.lit(vec![!0u64, !0u64, 1], 130);
+        assert_eq!(
+            lit_value(s.simplify(w + m.lit(1u32, 130))).unwrap(),
+            vec![0, 0, 2]
+        );
+        assert_eq!(
+            lit_value(s.simplify(w << m.lit(65u32, 8))).unwrap(),
+            vec![0, !1u64, 3]
+        );
+        assert_eq!(
+            lit_value(
+                s.simplify(
+                    m.lit(vec![0u64, 0, 2], 130)
+                        .shr_arithmetic(m.lit(128u32, 8))
+                )
+            )
+            .unwrap(),
+            vec![!1u64, !0u64, 3]
+        );
+        assert_eq!(
+            lit_value(s.simplify(w * m.lit(2u32, 2))).unwrap(),
+            vec![!1u64, !0u64, 3]
+        );
+        assert_eq!(folded(&mut s, w.bits(129, 64)), (1 << 64) | (!0u64 as u128));
+    }
+
+    #[test]
+    fn identities() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 8);
+        let zero = m.lit(0u32, 8);
+        let ones = m.lit(0xffu32, 8);
+
+        assert!(ptr::eq(s.simplify(x | zero), x));
+        assert!(ptr::eq(s.simplify(zero ^ x), x));
+        assert!(ptr::eq(s.simplify(x & ones), x));
+        assert_eq!(folded(&mut s, x & zero), 0);
+        assert_eq!(folded(&mut s, ones | x), 0xff);
+        assert!(ptr::eq(s.simplify(x & x), x));
+        assert_eq!(folded(&mut s, x ^ x), 0);
+        assert!(ptr::eq(s.simplify(x + zero), x));
+        assert!(ptr::eq(s.simplify(x - zero), x));
+        assert_eq!(folded(&mut s, x - x), 0);
+        assert!(ptr::eq(s.simplify(x << zero), x));
+        assert_eq!(folded(&mut s, zero >> x), 0);
+        assert_eq!(folded(&mut s, x * zero), 0);
+        assert_eq!(folded(&mut s, x.eq(x)), 1);
+        assert_eq!(folded(&mut s, x.lt_signed(x)), 0);
+        assert!(ptr::eq(s.simplify(!!x), x));
+        assert!(ptr::eq(s.simplify(m.mux(m.high(), x, y)), x));
+        assert!(ptr::eq(s.simplify(m.mux(m.low(), x, y)), y));
+        assert!(ptr::eq(s.simplify(m.mux(y.bit(0), x, x)), x));
+        let cond = x.bit(0);
+        assert!(ptr::eq(
+            s.simplify(m.mux(cond, m.high(), m.low())),
+            s.simplify(cond)
+        ));
+        assert!(ptr::eq(s.simplify(x.bits(7, 0)), x));
+        assert!(ptr::eq(s.simplify(x.repeat(1)), x));
+        assert!(ptr::eq(s.simplify(x.concat(y).bits(7, 0)), y));
+        assert!(ptr::eq(s.simplify(x.concat(y).bits(15, 8)), x));
+
+        // Nested bits are taken from the original source
+        match s.simplify(x.bits(6, 1).bits(4, 2)).data {
+            SignalData::Bits {
+                source,
+                range_high,
+                range_low,
+            } => {
+                assert!(ptr::eq(source, x));
+                assert_eq!((range_high, range_low), (5, 3));
+            }
+            _ => panic!("expected bits"),
+        }
+    }
+
+    #[test]
+    fn common_subexpressions() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 8);
+
+        let a = x + y;
+        let b = y + x;
+        assert!(!ptr::eq(a, b));
+        assert!(ptr::eq(s.simplify(a), s.simplify(b)));
+
+        // Subtraction isn't commutative
+        assert!(!ptr::eq(s.simplify(x - y), s.simplify(y - x)));
+
+        // Operands are merged before their users
+        let sum = s.simplify(a.bits(3, 0).concat(b.bits(3, 0)));
+        match sum.data {
+            SignalData::Concat { lhs, rhs, .. } => assert!(ptr::eq(lhs, rhs)),
+            _ => panic!("expected concat"),
+        }
+
+        // Unchanged signals are kept as-is
+        let unchanged = x & y;
+        assert!(ptr::eq(s.simplify(unchanged), unchanged));
+    }
+
+    #[test]
+    fn reused_between_simplifiers() {
+        let c = Context::new();
+        let m = c.module("A");
+
+        let x = m.input("x", 8);
+        let folded = m.lit(0xa5u32, 8) & m.lit(0x3cu32, 8);
+        let merged = (x & m.lit(0xffu32, 8)) ^ x;
+        let (first_folded, first_merged) = {
+            let mut s = Simplifier::new(&c, true, false);
+            (s.simplify(folded), s.simplify(merged))
+        };
+
+        // Later simplifiers return the same signals, rather than allocating new ones
+        let mut s = Simplifier::new(&c, true, false);
+        assert!(ptr::eq(s.simplify(folded), first_folded));
+        assert!(ptr::eq(s.simplify(merged), first_merged));
+
+        // Simplifiers that preserve unknowns don't share results with those that don't
+        let mut s = Simplifier::new(&c, true, true);
+        assert!(!ptr::eq(s.simplify(folded), first_folded));
+    }
+
+    #[test]
+    fn disabled() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, false, false);
+
+        let x = m.input("x", 8);
+        let a = m.lit(0xa5u32, 8) & m.lit(0x3cu32, 8);
+        assert!(ptr::eq(s.simplify(a), a));
+        let b = x | m.lit(0u32, 8);
+        assert!(ptr::eq(s.simplify(b), b));
+        assert!(s.constant_value(a).is_none());
+        let (c0, c1) = (x + x, x + x);
+        assert!(!ptr::eq(s.simplify(c0), s.simplify(c1)));
+    }
+
+    #[test]
+    fn not_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        assert_eq!(folded(&mut s, !m.lit(0x0fu32, 8)), 0xf0);
+        assert_eq!(folded(&mut s, !m.lit(0u32, 1)), 1);
+        assert_eq!(
+            lit_value(s.simplify(!m.lit(0u32, 70))).unwrap(),
+            vec![!0u64, 0x3f]
+        );
+    }
+
+    #[test]
+    fn double_not() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        // A single not is kept
+        let not_x = !x;
+        assert!(ptr::eq(s.simplify(not_x), not_x));
+        assert!(ptr::eq(s.simplify(!!x), x));
+        assert!(ptr::eq(s.simplify(!!!x), not_x));
+        assert!(ptr::eq(s.simplify(!!!!x), x));
+    }
+
+    #[test]
+    fn bitwise_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let a = m.lit(0b1100u32, 4);
+        let b = m.lit(0b1010u32, 4);
+        assert_eq!(folded(&mut s, a & b), 0b1000);
+        assert_eq!(folded(&mut s, a | b), 0b1110);
+        assert_eq!(folded(&mut s, a ^ b), 0b0110);
+    }
+
+    #[test]
+    fn bitwise_identity_elements() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let zero = m.lit(0u32, 8);
+        let ones = m.lit(0xffu32, 8);
+        assert!(ptr::eq(s.simplify(x & ones), x));
+        assert!(ptr::eq(s.simplify(ones & x), x));
+        assert!(ptr::eq(s.simplify(x | zero), x));
+        assert!(ptr::eq(s.simplify(zero | x), x));
+        assert!(ptr::eq(s.simplify(x ^ zero), x));
+        assert!(ptr::eq(s.simplify(zero ^ x), x));
+        // Xor with all ones isn't an identity
+        let inverted = x ^ ones;
+        assert!(ptr::eq(s.simplify(inverted), inverted));
+    }
+
+    #[test]
+    fn bitwise_absorbing_elements() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let zero = m.lit(0u32, 8);
+        let ones = m.lit(0xffu32, 8);
+        assert!(ptr::eq(s.simplify(x & zero), zero));
+        assert!(ptr::eq(s.simplify(zero & x), zero));
+        assert!(ptr::eq(s.simplify(x | ones), ones));
+        assert!(ptr::eq(s.simplify(ones | x), ones));
+    }
+
+    #[test]
+    fn bitwise_same_operands() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        assert!(ptr::eq(s.simplify(x & x), x));
+        assert!(ptr::eq(s.simplify(x | x), x));
+        assert_eq!(folded(&mut s, x ^ x), 0);
+        // Operands are compared after they're simplified
+        assert!(ptr::eq(s.simplify((x + x) & (x + x)), s.simplify(x + x)));
+    }
+
+    #[test]
+    fn additive_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let a = m.lit(0xf0u32, 8);
+        let b = m.lit(0x20u32, 8);
+        // Results wrap around
+        assert_eq!(folded(&mut s, a + b), 0x10);
+        assert_eq!(folded(&mut s, b - a), 0x30);
+        assert_eq!(folded(&mut s, a - b), 0xd0);
+    }
+
+    #[test]
+    fn additive_identity_elements() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let zero = m.lit(0u32, 8);
+        assert!(ptr::eq(s.simplify(x + zero), x));
+        assert!(ptr::eq(s.simplify(zero + x), x));
+        assert!(ptr::eq(s.simplify(x - zero), x));
+        // Subtraction from zero is negation
+        let negated = zero - x;
+        assert!(ptr::eq(s.simplify(negated), negated));
+    }
+
+    #[test]
+    fn sub_same_operands() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 8);
+        assert_eq!(folded(&mut s, x - x), 0);
+        let difference = x - y;
+        assert!(ptr::eq(s.simplify(difference), difference));
+    }
+
+    #[test]
+    fn comparison_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        // -1 and 1 when signed
+        let a = m.lit(0xffu32, 8);
+        let b = m.lit(0x01u32, 8);
+        assert_eq!(folded(&mut s, a.eq(b)), 0);
+        assert_eq!(folded(&mut s, a.ne(b)), 1);
+        assert_eq!(folded(&mut s, a.lt(b)), 0);
+        assert_eq!(folded(&mut s, a.le(b)), 0);
+        assert_eq!(folded(&mut s, a.gt(b)), 1);
+        assert_eq!(folded(&mut s, a.ge(b)), 1);
+        assert_eq!(folded(&mut s, a.lt_signed(b)), 1);
+        assert_eq!(folded(&mut s, a.le_signed(b)), 1);
+        assert_eq!(folded(&mut s, a.gt_signed(b)), 0);
+        assert_eq!(folded(&mut s, a.ge_signed(b)), 0);
+    }
+
+    #[test]
+    fn comparison_same_operands() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        assert_eq!(folded(&mut s, x.eq(x)), 1);
+        assert_eq!(folded(&mut s, x.ne(x)), 0);
+        assert_eq!(folded(&mut s, x.lt(x)), 0);
+        assert_eq!(folded(&mut s, x.le(x)), 1);
+        assert_eq!(folded(&mut s, x.gt(x)), 0);
+        assert_eq!(folded(&mut s, x.ge(x)), 1);
+        assert_eq!(folded(&mut s, x.lt_signed(x)), 0);
+        assert_eq!(folded(&mut s, x.le_signed(x)), 1);
+        assert_eq!(folded(&mut s, x.gt_signed(x)), 0);
+        assert_eq!(folded(&mut s, x.ge_signed(x)), 1);
+    }
+
+    #[test]
+    fn shift_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let a = m.lit(0x81u32, 8);
+        assert_eq!(folded(&mut s, a << m.lit(1u32, 3)), 0x02);
+        assert_eq!(folded(&mut s, a >> m.lit(1u32, 3)), 0x40);
+        assert_eq!(folded(&mut s, a.shr_arithmetic(m.lit(1u32, 3))), 0xc0);
+        // Shift amounts at or above the bit width shift out every bit
+        assert_eq!(folded(&mut s, a << m.lit(8u32, 4)), 0);
+        assert_eq!(folded(&mut s, a >> m.lit(9u32, 4)), 0);
+        assert_eq!(folded(&mut s, a.shr_arithmetic(m.lit(15u32, 4))), 0xff);
+    }
+
+    #[test]
+    fn shift_by_zero() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let zero = m.lit(0u32, 3);
+        assert!(ptr::eq(s.simplify(x << zero), x));
+        assert!(ptr::eq(s.simplify(x >> zero), x));
+        assert!(ptr::eq(s.simplify(x.shr_arithmetic(zero)), x));
+    }
+
+    #[test]
+    fn shift_of_zero() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 3);
+        let zero = m.lit(0u32, 8);
+        assert!(ptr::eq(s.simplify(zero << x), zero));
+        assert!(ptr::eq(s.simplify(zero >> x), zero));
+        assert!(ptr::eq(s.simplify(zero.shr_arithmetic(x)), zero));
+    }
+
+    #[test]
+    fn mul_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        // 15 * 3, and -1 * 3 when signed
+        let a = m.lit(0xfu32, 4);
+        let b = m.lit(0x3u32, 4);
+        assert_eq!(folded(&mut s, a * b), 45);
+        assert_eq!(folded(&mut s, a.mul_signed(b)), 0xfd);
+    }
+
+    #[test]
+    fn mul_by_zero() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let zero = m.lit(0u32, 4);
+        assert_eq!(folded(&mut s, x * zero), 0);
+        assert_eq!(folded(&mut s, zero * x), 0);
+        assert_eq!(folded(&mut s, x.mul_signed(zero)), 0);
+        assert_eq!(s.simplify(x * zero).bit_width(), 12);
+    }
+
+    #[test]
+    fn bits_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let a = m.lit(0b1011_0110u32, 8);
+        assert_eq!(folded(&mut s, a.bits(5, 2)), 0b1101);
+        assert_eq!(folded(&mut s, a.bit(7)), 1);
+    }
+
+    #[test]
+    fn bits_full_range() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        assert!(ptr::eq(s.simplify(x.bits(7, 0)), x));
+        let partial = x.bits(6, 0);
+        assert!(ptr::eq(s.simplify(partial), partial));
+    }
+
+    #[test]
+    fn bits_of_bits() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        match s.simplify(x.bits(7, 2).bits(3, 1)).data {
+            SignalData::Bits {
+                source,
+                range_high,
+                range_low,
+            } => {
+                assert!(ptr::eq(source, x));
+                assert_eq!((range_high, range_low), (5, 3));
+            }
+            _ => panic!("expected bits"),
+        }
+        // Nested bits that cover their source entirely disappear
+        assert!(ptr::eq(s.simplify(x.bits(7, 0).bits(7, 0)), x));
+    }
+
+    #[test]
+    fn bits_of_concat() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 4);
+        let xy = x.concat(y);
+        assert!(ptr::eq(s.simplify(xy.bits(3, 0)), y));
+        assert!(ptr::eq(s.simplify(xy.bits(11, 4)), x));
+        match s.simplify(xy.bits(9, 6)).data {
+            SignalData::Bits {
+                source,
+                range_high,
+                range_low,
+            } => {
+                assert!(ptr::eq(source, x));
+                assert_eq!((range_high, range_low), (5, 2));
+            }
+            _ => panic!("expected bits"),
+        }
+        // Ranges spanning both sides are kept
+        let spanning = xy.bits(5, 2);
+        assert!(ptr::eq(s.simplify(spanning), spanning));
+    }
+
+    #[test]
+    fn repeat_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        assert_eq!(folded(&mut s, m.lit(0b10u32, 2).repeat(3)), 0b101010);
+        assert_eq!(
+            lit_value(s.simplify(m.lit(1u32, 1).repeat(65))).unwrap(),
+            vec![!0u64, 1]
+        );
+    }
+
+    #[test]
+    fn repeat_once() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        assert!(ptr::eq(s.simplify(x.repeat(1)), x));
+        let repeated = x.repeat(2);
+        assert!(ptr::eq(s.simplify(repeated), repeated));
+    }
+
+    #[test]
+    fn concat_folding() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        assert_eq!(
+            folded(&mut s, m.lit(0b101u32, 3).concat(m.lit(0b01u32, 2))),
+            0b10101
+        );
+        let x = m.input("x", 8);
+        let partial = x.concat(m.lit(0u32, 2));
+        assert!(ptr::eq(s.simplify(partial), partial));
+    }
+
+    #[test]
+    fn mux_constant_condition() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 8);
+        assert!(ptr::eq(s.simplify(m.mux(m.high(), x, y)), x));
+        assert!(ptr::eq(s.simplify(m.mux(m.low(), x, y)), y));
+        // Conditions that fold to constants count too
+        assert!(ptr::eq(s.simplify(m.mux(x.eq(x), x, y)), x));
+    }
+
+    #[test]
+    fn mux_same_arms() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let cond = m.input("cond", 1);
+        assert!(ptr::eq(s.simplify(m.mux(cond, x, x)), x));
+        assert!(ptr::eq(
+            s.simplify(m.mux(cond, x + x, x + x)),
+            s.simplify(x + x)
+        ));
+    }
+
+    #[test]
+    fn mux_of_bools() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let cond = m.input("cond", 1);
+        assert!(ptr::eq(s.simplify(m.mux(cond, m.high(), m.low())), cond));
+        // The inverse mux isn't rewritten
+        let inverted = m.mux(cond, m.low(), m.high());
+        assert!(ptr::eq(s.simplify(inverted), inverted));
+    }
+
+    #[test]
+    fn commutative_common_subexpressions() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 8);
+        assert!(ptr::eq(s.simplify(x & y), s.simplify(y & x)));
+        assert!(ptr::eq(s.simplify(x | y), s.simplify(y | x)));
+        assert!(ptr::eq(s.simplify(x ^ y), s.simplify(y ^ x)));
+        assert!(ptr::eq(s.simplify(x + y), s.simplify(y + x)));
+        assert!(ptr::eq(s.simplify(x.eq(y)), s.simplify(y.eq(x))));
+        assert!(ptr::eq(s.simplify(x.ne(y)), s.simplify(y.ne(x))));
+        assert!(ptr::eq(s.simplify(x * y), s.simplify(y * x)));
+        assert!(ptr::eq(
+            s.simplify(x.mul_signed(y)),
+            s.simplify(y.mul_signed(x))
+        ));
+    }
+
+    #[test]
+    fn non_commutative_common_subexpressions() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, false);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 8);
+        assert!(!ptr::eq(s.simplify(x.lt(y)), s.simplify(y.lt(x))));
+        assert!(!ptr::eq(
+            s.simplify(x << y.bits(2, 0)),
+            s.simplify(y << x.bits(2, 0))
+        ));
+        assert!(!ptr::eq(s.simplify(x.concat(y)), s.simplify(y.concat(x))));
+        // Identical expressions are merged regardless
+        assert!(ptr::eq(s.simplify(x.lt(y)), s.simplify(x.lt(y))));
+        assert!(ptr::eq(
+            s.simplify(x.bits(3, 0).repeat(2)),
+            s.simplify(x.bits(3, 0).repeat(2))
+        ));
+        let cond = m.input("cond", 1);
+        assert!(ptr::eq(
+            s.simplify(m.mux(cond, x, y)),
+            s.simplify(m.mux(cond, x, y))
+        ));
+        assert!(!ptr::eq(
+            s.simplify(m.mux(cond, x, y)),
+            s.simplify(m.mux(cond, y, x))
+        ));
+    }
+
+    #[test]
+    fn preserve_unknowns() {
+        let c = Context::new();
+        let m = c.module("A");
+        let mut s = Simplifier::new(&c, true, true);
+
+        let x = m.input("x", 8);
+        let y = m.input("y", 3);
+        let zero = m.lit(0u32, 8);
+        let ones = m.lit(0xffu32, 8);
+
+        // Rewrites that would make unknown bits known are skipped
+        for signal in [
+            x ^ x,
+            x - x,
+            x + zero,
+            zero + x,
+            x - zero,
+            x * zero,
+            zero * x,
+            x.mul_signed(zero),
+            x.eq(x),
+            x.ne(x),
+            x.lt(x),
+            x.ge_signed(x),
+            zero << y,
+            zero >> y,
+            zero.shr_arithmetic(y),
+        ]
+        .iter()
+        {
+            let simplified = s.simplify(signal);
+            assert!(lit_value(simplified).is_none());
+            assert!(!ptr::eq(simplified, x));
+        }
+
+        // Rewrites that follow Verilog's X-propagation rules still apply
+        assert!(ptr::eq(s.simplify(x & zero), zero));
+        assert!(ptr::eq(s.simplify(x | ones), ones));
+        assert!(ptr::eq(s.simplify(x & x), x));
+        assert!(ptr::eq(s.simplify(x ^ zero), x));
+        assert!(ptr::eq(s.simplify(x << m.lit(0u32, 3)), x));
+        assert!(ptr::eq(s.simplify(m.mux(y.bit(0), x, x)), x));
+        assert_eq!(folded(&mut s, m.lit(3u32, 8) * m.lit(5u32, 8)), 15);
+    }
+}
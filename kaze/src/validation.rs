@@ -1,8 +1,12 @@
+use std::collections::HashSet;
 use std::ptr;
 
 use super::module_context::*;
 
+use crate::diagnostic::*;
+use crate::error::*;
 use crate::graph;
+use crate::reset::*;
 
 use typed_arena::Arena;
 
@@ -14,52 +18,165 @@ struct ModuleStackFrame<'graph, 'frame> {
     module: &'graph graph::Module<'graph>,
 }
 
-pub fn validate_module_hierarchy<'graph>(m: &'graph graph::Module<'graph>) {
-    detect_recursive_definitions(
-        m,
-        &ModuleStackFrame {
-            parent: None,
-            module: m,
-        },
-        m,
-    );
-    detect_undriven_registers(
-        m,
-        &ModuleStackFrame {
-            parent: None,
-            module: m,
-        },
-        m,
-    );
-    detect_mem_errors(
-        m,
-        &ModuleStackFrame {
-            parent: None,
-            module: m,
-        },
-        m,
-    );
+impl<'graph, 'frame> ModuleStackFrame<'graph, 'frame> {
+    fn path(&self, root: &graph::Module<'graph>, name: &str) -> String {
+        let mut instance_names = Vec::new();
+        let mut frame = self;
+        while let Some((instance, parent)) = frame.parent {
+            instance_names.push(instance.name.as_str());
+            frame = parent;
+        }
+        let mut path = root.name.clone();
+        for instance_name in instance_names.into_iter().rev() {
+            path.push('.');
+            path.push_str(instance_name);
+        }
+        path.push('.');
+        path.push_str(name);
+        path
+    }
+}
+
+struct Diagnostics {
+    errors: Vec<(String, Error)>,
+    // When set, checks stop as soon as the first error is found
+    first_error_only: bool,
+}
+
+impl Diagnostics {
+    fn error(&mut self, path: String, error: Error) {
+        self.errors.push((path, error));
+    }
+
+    fn done(&self) -> bool {
+        self.first_error_only && !self.errors.is_empty()
+    }
+}
+
+pub fn validate_module_hierarchy<'graph>(m: &'graph graph::Module<'graph>) -> Result<(), Error> {
+    match detect_errors(m, true).into_iter().next() {
+        Some((_, error)) => Err(error),
+        _ => Ok(()),
+    }
+}
+
+pub fn collect_diagnostics<'graph>(m: &'graph graph::Module<'graph>) -> Vec<Diagnostic> {
+    detect_errors(m, false)
+        .into_iter()
+        .map(|(path, error)| Diagnostic {
+            severity: Severity::Error,
+            path,
+            kind: DiagnosticKind::Error(error),
+        })
+        .collect()
+}
+
+fn detect_errors<'graph>(
+    m: &'graph graph::Module<'graph>,
+    first_error_only: bool,
+) -> Vec<(String, Error)> {
+    let mut diagnostics = Diagnostics {
+        errors: Vec::new(),
+        first_error_only,
+    };
+
+    let root_frame = ModuleStackFrame {
+        parent: None,
+        module: m,
+    };
+    // The remaining checks walk the entire hierarchy, which isn't possible if it's infinite
+    if detect_recursive_definitions(m, &root_frame, m, &mut diagnostics) || diagnostics.done() {
+        return diagnostics.errors;
+    }
+    detect_undriven_clock_domains(m, &root_frame, m, &mut diagnostics);
+    if diagnostics.done() {
+        return diagnostics.errors;
+    }
+    detect_undriven_registers(m, &root_frame, m, &mut diagnostics);
+    if diagnostics.done() {
+        return diagnostics.errors;
+    }
+    detect_property_name_errors(m, &root_frame, m, &mut diagnostics);
+    if diagnostics.done() {
+        return diagnostics.errors;
+    }
+    detect_mem_errors(m, &root_frame, m, &mut diagnostics);
+    if diagnostics.done() {
+        return diagnostics.errors;
+    }
     let context_arena = Arena::new();
     let root_context = context_arena.alloc(ModuleContext::new());
-    detect_combinational_loops(m, root_context, &context_arena, m);
+    detect_combinational_loops(m, root_context, &context_arena, m, &mut diagnostics);
+
+    diagnostics.errors
+}
+
+// Checks that no module in `m`'s hierarchy has an input or output with the same name as one of
+//  the clock or reset ports that are generated for its clock domains in Verilog code. This
+//  assumes that `m`'s hierarchy has already been validated.
+pub fn validate_clock_domain_port_names<'graph>(
+    m: &'graph graph::Module<'graph>,
+    reset_polarity: ResetPolarity,
+) -> Result<(), Error> {
+    fn visit<'graph>(
+        m: &'graph graph::Module<'graph>,
+        root: &graph::Module<'graph>,
+        reset_polarity: ResetPolarity,
+        visited: &mut HashSet<*const graph::Module<'graph>>,
+    ) -> Result<(), Error> {
+        if !visited.insert(m) {
+            return Ok(());
+        }
+
+        let mut port_names = HashSet::new();
+        for clock_domain in std::iter::once(m.default_clock_domain())
+            .chain(m.clock_domains.borrow().iter().copied())
+        {
+            port_names.insert(clock_domain.clock_name());
+            port_names.insert(clock_domain.reset_name(reset_polarity));
+        }
+        for name in m.inputs.borrow().keys().chain(m.outputs.borrow().keys()) {
+            if port_names.contains(name) {
+                return Err(Error::ClockDomainPortConflict {
+                    root: root.name.clone(),
+                    module: m.name.clone(),
+                    port: name.clone(),
+                });
+            }
+        }
+
+        for instance in m.instances.borrow().iter() {
+            visit(instance.instantiated_module, root, reset_polarity, visited)?;
+        }
+
+        Ok(())
+    }
+
+    visit(m, m, reset_polarity, &mut HashSet::new())
 }
 
+// Returns `true` if any recursive definitions were found.
 fn detect_recursive_definitions<'graph, 'frame>(
-    m: &graph::Module<'graph>,
+    m: &'graph graph::Module<'graph>,
     module_stack_frame: &ModuleStackFrame<'graph, 'frame>,
     root: &graph::Module<'graph>,
-) {
-    for instance in m.instances.borrow().iter() {
-        let instantiated_module = instance.instantiated_module;
+    diagnostics: &mut Diagnostics,
+) -> bool {
+    let mut found_recursive_definition = false;
 
-        if ptr::eq(instantiated_module, m) {
-            panic!("Cannot generate code for module \"{}\" because it has a recursive definition formed by an instance of itself called \"{}\".", m.name, instance.name);
+    for instance in m.instances.borrow().iter() {
+        if diagnostics.done() {
+            break;
         }
 
+        let instantiated_module = instance.instantiated_module;
+
+        let mut is_recursive = false;
         let mut frame = module_stack_frame;
         loop {
             if ptr::eq(instantiated_module, frame.module) {
-                panic!("Cannot generate code for module \"{}\" because it has a recursive definition formed by an instance of itself called \"{}\" in module \"{}\".", root.name, instance.name, m.name);
+                is_recursive = true;
+                break;
             }
 
             if let Some((_, parent)) = frame.parent {
@@ -68,20 +185,90 @@ fn detect_recursive_definitions<'graph, 'frame>(
                 break;
             }
         }
+        if is_recursive {
+            diagnostics.error(
+                module_stack_frame.path(root, &instance.name),
+                Error::RecursiveDefinition {
+                    root: root.name.clone(),
+                    module: m.name.clone(),
+                    instance: instance.name.clone(),
+                },
+            );
+            found_recursive_definition = true;
+            continue;
+        }
 
         for input_name in instantiated_module.inputs.borrow().keys() {
             if !instance.driven_inputs.borrow().contains_key(input_name) {
-                panic!("Cannot generate code for module \"{}\" because module \"{}\" contains an instance of module \"{}\" called \"{}\" whose input \"{}\" is not driven.", root.name, m.name, instantiated_module.name, instance.name, input_name);
+                diagnostics.error(
+                    module_stack_frame.path(root, &format!("{}.{}", instance.name, input_name)),
+                    Error::UndrivenInstanceInput {
+                        root: root.name.clone(),
+                        module: m.name.clone(),
+                        instance: instance.name.clone(),
+                        instantiated_module: instantiated_module.name.clone(),
+                        input: input_name.clone(),
+                    },
+                );
             }
         }
 
-        detect_recursive_definitions(
+        found_recursive_definition |= detect_recursive_definitions(
             instantiated_module,
             &ModuleStackFrame {
                 parent: Some((instance, module_stack_frame)),
                 module: instantiated_module,
             },
             root,
+            diagnostics,
+        );
+    }
+
+    found_recursive_definition
+}
+
+fn detect_undriven_clock_domains<'graph, 'frame>(
+    m: &graph::Module<'graph>,
+    module_stack_frame: &ModuleStackFrame<'graph, 'frame>,
+    root: &graph::Module<'graph>,
+    diagnostics: &mut Diagnostics,
+) {
+    for instance in m.instances.borrow().iter() {
+        if diagnostics.done() {
+            return;
+        }
+
+        let instantiated_module = instance.instantiated_module;
+
+        for clock_domain in instantiated_module.clock_domains.borrow().iter() {
+            let clock_domain_name = clock_domain.name.as_ref().unwrap();
+            if !instance
+                .driven_clock_domains
+                .borrow()
+                .contains_key(clock_domain_name)
+            {
+                diagnostics.error(
+                    module_stack_frame
+                        .path(root, &format!("{}.{}", instance.name, clock_domain_name)),
+                    Error::UndrivenInstanceClockDomain {
+                        root: root.name.clone(),
+                        module: m.name.clone(),
+                        instance: instance.name.clone(),
+                        instantiated_module: instantiated_module.name.clone(),
+                        clock_domain: clock_domain_name.clone(),
+                    },
+                );
+            }
+        }
+
+        detect_undriven_clock_domains(
+            instantiated_module,
+            &ModuleStackFrame {
+                parent: Some((instance, module_stack_frame)),
+                module: instantiated_module,
+            },
+            root,
+            diagnostics,
         );
     }
 }
@@ -90,12 +277,24 @@ fn detect_undriven_registers<'graph, 'frame>(
     m: &graph::Module<'graph>,
     module_stack_frame: &ModuleStackFrame<'graph, 'frame>,
     root: &graph::Module<'graph>,
+    diagnostics: &mut Diagnostics,
 ) {
     for register in m.registers.borrow().iter() {
+        if diagnostics.done() {
+            return;
+        }
+
         match register.data {
             graph::SignalData::Reg { ref data } => {
                 if data.next.borrow().is_none() {
-                    panic!("Cannot generate code for module \"{}\" because module \"{}\" contains a register called \"{}\" which is not driven.", root.name, m.name, data.name);
+                    diagnostics.error(
+                        module_stack_frame.path(root, &data.name),
+                        Error::UndrivenRegister {
+                            root: root.name.clone(),
+                            module: m.name.clone(),
+                            register: data.name.clone(),
+                        },
+                    );
                 }
             }
             _ => unreachable!(),
@@ -105,6 +304,10 @@ fn detect_undriven_registers<'graph, 'frame>(
     for instance in m.instances.borrow().iter() {
         let instantiated_module = instance.instantiated_module;
 
+        if diagnostics.done() {
+            return;
+        }
+
         detect_undriven_registers(
             instantiated_module,
             &ModuleStackFrame {
@@ -112,28 +315,118 @@ fn detect_undriven_registers<'graph, 'frame>(
                 module: instantiated_module,
             },
             root,
+            diagnostics,
         );
     }
 }
 
+// Property names are used as labels in generated Verilog code and as parts of symbols in BTOR2
+//  models, so they must be valid identifiers, and unique within their module.
+fn detect_property_name_errors<'graph, 'frame>(
+    m: &graph::Module<'graph>,
+    module_stack_frame: &ModuleStackFrame<'graph, 'frame>,
+    root: &graph::Module<'graph>,
+    diagnostics: &mut Diagnostics,
+) {
+    let mut names = HashSet::new();
+    for property in m.properties.borrow().iter() {
+        if diagnostics.done() {
+            return;
+        }
+
+        if !is_valid_identifier(&property.name) {
+            diagnostics.error(
+                module_stack_frame.path(root, &property.name),
+                Error::InvalidPropertyName {
+                    root: root.name.clone(),
+                    module: m.name.clone(),
+                    property: property.name.clone(),
+                },
+            );
+        } else if !names.insert(property.name.as_str()) {
+            diagnostics.error(
+                module_stack_frame.path(root, &property.name),
+                Error::DuplicatePropertyName {
+                    root: root.name.clone(),
+                    module: m.name.clone(),
+                    property: property.name.clone(),
+                },
+            );
+        }
+    }
+
+    for instance in m.instances.borrow().iter() {
+        let instantiated_module = instance.instantiated_module;
+
+        if diagnostics.done() {
+            return;
+        }
+
+        detect_property_name_errors(
+            instantiated_module,
+            &ModuleStackFrame {
+                parent: Some((instance, module_stack_frame)),
+                module: instantiated_module,
+            },
+            root,
+            diagnostics,
+        );
+    }
+}
+
+// Returns whether `name` is a non-empty sequence of ASCII letters, digits, and `_` that doesn't
+//  start with a digit.
+fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
 fn detect_mem_errors<'graph, 'frame>(
     m: &graph::Module<'graph>,
     module_stack_frame: &ModuleStackFrame<'graph, 'frame>,
     root: &graph::Module<'graph>,
+    diagnostics: &mut Diagnostics,
 ) {
     for mem in m.mems.borrow().iter() {
-        if mem.read_ports.borrow().is_empty() {
-            panic!("Cannot generate code for module \"{}\" because module \"{}\" contains a memory called \"{}\" which doesn't have any read ports.", root.name, m.name, mem.name);
+        if diagnostics.done() {
+            return;
+        }
+
+        if mem.read_ports.borrow().is_empty() && mem.async_read_ports.borrow().is_empty() {
+            diagnostics.error(
+                module_stack_frame.path(root, &mem.name),
+                Error::MemWithoutReadPorts {
+                    root: root.name.clone(),
+                    module: m.name.clone(),
+                    mem: mem.name.clone(),
+                },
+            );
         }
 
-        if mem.initial_contents.borrow().is_none() && mem.write_port.borrow().is_none() {
-            panic!("Cannot generate code for module \"{}\" because module \"{}\" contains a memory called \"{}\" which doesn't have initial contents or a write port specified. At least one of the two is required.", root.name, m.name, mem.name);
+        if mem.initial_contents.borrow().is_none() && mem.write_ports.borrow().is_empty() {
+            diagnostics.error(
+                module_stack_frame.path(root, &mem.name),
+                Error::MemWithoutInitialContentsOrWritePort {
+                    root: root.name.clone(),
+                    module: m.name.clone(),
+                    mem: mem.name.clone(),
+                },
+            );
         }
     }
 
     for instance in m.instances.borrow().iter() {
         let instantiated_module = instance.instantiated_module;
 
+        if diagnostics.done() {
+            return;
+        }
+
         detect_mem_errors(
             instantiated_module,
             &ModuleStackFrame {
@@ -141,6 +434,7 @@ fn detect_mem_errors<'graph, 'frame>(
                 module: instantiated_module,
             },
             root,
+            diagnostics,
         );
     }
 }
@@ -150,6 +444,7 @@ fn detect_combinational_loops<'graph, 'arena>(
     context: &'arena ModuleContext<'graph, 'arena>,
     context_arena: &'arena Arena<ModuleContext<'graph, 'arena>>,
     root: &graph::Module<'graph>,
+    diagnostics: &mut Diagnostics,
 ) {
     for instance in m.instances.borrow().iter() {
         let instantiated_module = instance.instantiated_module;
@@ -157,10 +452,27 @@ fn detect_combinational_loops<'graph, 'arena>(
         let context = context.get_child(instance, context_arena);
 
         for (_, output) in instantiated_module.outputs.borrow().iter() {
-            trace_signal(output, context, context_arena, (context, output), root);
+            if diagnostics.done() {
+                return;
+            }
+
+            trace_signal(
+                output,
+                context,
+                context_arena,
+                (context, output),
+                root,
+                diagnostics,
+            );
         }
 
-        detect_combinational_loops(instantiated_module, context, context_arena, root);
+        detect_combinational_loops(
+            instantiated_module,
+            context,
+            context_arena,
+            root,
+            diagnostics,
+        );
     }
 }
 
@@ -173,12 +485,14 @@ fn trace_signal<'graph, 'arena>(
         &'graph graph::Signal<'graph>,
     ),
     root: &graph::Module<'graph>,
+    diagnostics: &mut Diagnostics,
 ) {
     struct Frame<'graph, 'arena> {
         signal: &'graph graph::Signal<'graph>,
         context: &'arena ModuleContext<'graph, 'arena>,
     }
 
+    let mut visited: HashSet<(*const ModuleContext, *const graph::Signal)> = HashSet::new();
     let mut frames = Vec::new();
     frames.push(Frame { signal, context });
 
@@ -186,15 +500,22 @@ fn trace_signal<'graph, 'arena>(
         let signal = frame.signal;
         let context = frame.context;
 
+        if !visited.insert((context as *const _, signal as *const _)) {
+            continue;
+        }
+
         match signal.data {
             graph::SignalData::Lit { .. } => (),
 
             graph::SignalData::Input { ref name, .. } => {
                 if let Some((instance, parent)) = context.instance_and_parent {
-                    frames.push(Frame {
-                        signal: instance.driven_inputs.borrow()[name],
-                        context: parent,
-                    });
+                    // Undriven inputs are reported separately
+                    if let Some(driver) = instance.driven_inputs.borrow().get(name) {
+                        frames.push(Frame {
+                            signal: driver,
+                            context: parent,
+                        });
+                    }
                 }
             }
 
@@ -333,7 +654,15 @@ fn trace_signal<'graph, 'arena>(
                 let output = instantiated_module.outputs.borrow()[name];
                 let context = context.get_child(instance, context_arena);
                 if context == source_output.0 && output == source_output.1 {
-                    panic!("Cannot generate code for module \"{}\" because module \"{}\" contains an output called \"{}\" which forms a combinational loop with itself.", root.name, instantiated_module.name, name);
+                    diagnostics.error(
+                        format!("{}.{}", context.path(root), name),
+                        Error::CombinationalLoop {
+                            root: root.name.clone(),
+                            module: instantiated_module.name.clone(),
+                            output: name.clone(),
+                        },
+                    );
+                    return;
                 }
                 frames.push(Frame {
                     signal: output,
@@ -342,6 +671,12 @@ fn trace_signal<'graph, 'arena>(
             }
 
             graph::SignalData::MemReadPortOutput { .. } => (),
+            graph::SignalData::MemAsyncReadPortOutput { address, .. } => {
+                frames.push(Frame {
+                    signal: address,
+                    context,
+                });
+            }
         }
     }
 }
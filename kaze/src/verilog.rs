@@ -9,16 +9,120 @@ use ir::*;
 use module_decls::*;
 
 use crate::code_writer;
+use crate::error::*;
 use crate::graph;
+use crate::reset::*;
 use crate::validation::*;
 
-use std::collections::HashMap;
-use std::io::{Result, Write};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::ptr;
+
+pub struct GenerationOptions {
+    pub reset_type: ResetType,
+    pub reset_polarity: ResetPolarity,
+    /// The name of the macro that guards generated [`Property`](crate::Property) statements (`assert property`, `assume property`, and `cover property`), which are only included when the macro is defined, as they require SystemVerilog support. Defaults to `"FORMAL"`.
+    pub property_guard_macro: String,
+    /// If `true`, logic isn't simplified (by folding constants, eliminating identity and absorbing elements, and merging common subexpressions) before code generation, so the generated code mirrors the module graph exactly. This is mostly useful for comparing against simplified output when debugging.
+    pub disable_simplification: bool,
+}
+
+impl Default for GenerationOptions {
+    fn default() -> GenerationOptions {
+        GenerationOptions {
+            reset_type: ResetType::default(),
+            reset_polarity: ResetPolarity::default(),
+            property_guard_macro: "FORMAL".into(),
+            disable_simplification: false,
+        }
+    }
+}
 
 // TODO: Note that mutable writer reference can be passed, see https://rust-lang.github.io/api-guidelines/interoperability.html#c-rw-value
-pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
-    validate_module_hierarchy(m);
+pub fn generate<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: GenerationOptions,
+    w: W,
+) -> Result<(), Error> {
+    validate_module_hierarchy(m)?;
+    validate_clock_domain_port_names(m, options.reset_polarity)?;
+
+    Ok(generate_module(m, &options, w)?)
+}
+
+/// Generates Verilog code for `m` and every [`Module`](crate::Module) instantiated (directly or indirectly) within it, writing each module exactly once.
+///
+/// Modules are written in dependency order, so every module is written before any module that instantiates it, and `m` is written last.
+pub fn generate_hierarchy<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: GenerationOptions,
+    mut w: W,
+) -> Result<(), Error> {
+    validate_module_hierarchy(m)?;
+    validate_clock_domain_port_names(m, options.reset_polarity)?;
+
+    for module in modules_in_dependency_order(m) {
+        generate_module(module, &options, &mut w)?;
+    }
+
+    Ok(())
+}
+
+/// Like [`generate_hierarchy`], but writes each module to its own `<module name>.v` file in `dir`.
+///
+/// A filelist called `<top module name>.f` is also written to `dir`, which lists the path of each generated file (`dir` joined with the file name) on its own line in dependency order. This can be passed to most synthesis and simulation tools (e.g. with `-f`).
+pub fn generate_hierarchy_files<'a, P: AsRef<Path>>(
+    m: &'a graph::Module<'a>,
+    options: GenerationOptions,
+    dir: P,
+) -> Result<(), Error> {
+    validate_module_hierarchy(m)?;
+    validate_clock_domain_port_names(m, options.reset_polarity)?;
+
+    let dir = dir.as_ref();
+    let mut filelist = BufWriter::new(File::create(dir.join(format!("{}.f", m.name)))?);
+    for module in modules_in_dependency_order(m) {
+        let path = dir.join(format!("{}.v", module.name));
+        let mut file = BufWriter::new(File::create(&path)?);
+        generate_module(module, &options, &mut file)?;
+        file.flush()?;
+        writeln!(filelist, "{}", path.display())?;
+    }
+    filelist.flush()?;
+
+    Ok(())
+}
+
+fn modules_in_dependency_order<'a>(m: &'a graph::Module<'a>) -> Vec<&'a graph::Module<'a>> {
+    fn visit<'a>(
+        m: &'a graph::Module<'a>,
+        visited: &mut HashSet<*const graph::Module<'a>>,
+        modules: &mut Vec<&'a graph::Module<'a>>,
+    ) {
+        if !visited.insert(m) {
+            return;
+        }
+
+        for instance in m.instances.borrow().iter() {
+            visit(instance.instantiated_module, visited, modules);
+        }
 
+        modules.push(m);
+    }
+
+    let mut visited = HashSet::new();
+    let mut modules = Vec::new();
+    visit(m, &mut visited, &mut modules);
+    modules
+}
+
+fn generate_module<'a, W: Write>(
+    m: &'a graph::Module<'a>,
+    options: &GenerationOptions,
+    w: W,
+) -> io::Result<()> {
     let mut instances = HashMap::new();
     for instance in m.instances.borrow().iter() {
         let mut input_names = HashMap::new();
@@ -44,10 +148,10 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
     for mem in m.mems.borrow().iter() {
         let mem_name = format!("__mem_{}", mem.name);
         let mut read_signal_names = HashMap::new();
-        for (index, (address, enable)) in mem.read_ports.borrow().iter().enumerate() {
+        for (index, read_port) in mem.read_ports.borrow().iter().enumerate() {
             let name_prefix = format!("{}_read_port_{}_", mem_name, index);
             read_signal_names.insert(
-                (*address, *enable),
+                *read_port,
                 ReadSignalNames {
                     address_name: format!("{}address", name_prefix),
                     enable_name: format!("{}enable", name_prefix),
@@ -55,17 +159,28 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
                 },
             );
         }
-        let name_prefix = format!("{}_write_port_", mem_name);
-        let write_address_name = format!("{}address", name_prefix);
-        let write_value_name = format!("{}value", name_prefix);
-        let write_enable_name = format!("{}enable", name_prefix);
+        let num_write_ports = mem.write_ports.borrow().len();
+        let write_signal_names = (0..num_write_ports)
+            .map(|index| {
+                // Memories with a single write port keep the unindexed names
+                let name_prefix = if num_write_ports == 1 {
+                    format!("{}_write_port_", mem_name)
+                } else {
+                    format!("{}_write_port_{}_", mem_name, index)
+                };
+                WriteSignalNames {
+                    address_name: format!("{}address", name_prefix),
+                    value_name: format!("{}value", name_prefix),
+                    enable_name: format!("{}enable", name_prefix),
+                    mask_name: format!("{}mask", name_prefix),
+                }
+            })
+            .collect();
         mems.insert(
             *mem,
             MemDecls {
                 read_signal_names,
-                write_address_name,
-                write_value_name,
-                write_enable_name,
+                write_signal_names,
             },
         );
     }
@@ -95,7 +210,7 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
         regs,
     };
 
-    let mut c = Compiler::new();
+    let mut c = Compiler::new(m, !options.disable_simplification);
 
     let mut assignments = AssignmentContext::new();
     for (name, output) in m.outputs.borrow().iter() {
@@ -137,7 +252,9 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
     }
 
     for (mem, mem_decls) in module_decls.mems.iter() {
-        for ((address, enable), read_signal_names) in mem_decls.read_signal_names.iter() {
+        for (read_port, read_signal_names) in mem_decls.read_signal_names.iter() {
+            let address = read_port.address;
+            let enable = read_port.enable;
             let expr = c.compile_signal(address, &module_decls, &mut assignments);
             node_decls.push(NodeDecl {
                 net_type: NetType::Wire,
@@ -164,37 +281,57 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
                 bit_width: mem.element_bit_width,
             });
         }
-        if let Some((address, value, enable)) = *mem.write_port.borrow() {
+        for (write_port, write_signal_names) in mem
+            .write_ports
+            .borrow()
+            .iter()
+            .zip(mem_decls.write_signal_names.iter())
+        {
+            let address = write_port.address;
+            let value = write_port.value;
+            let enable = write_port.enable;
             let expr = c.compile_signal(address, &module_decls, &mut assignments);
             node_decls.push(NodeDecl {
                 net_type: NetType::Wire,
-                name: mem_decls.write_address_name.clone(),
+                name: write_signal_names.address_name.clone(),
                 bit_width: address.bit_width(),
             });
             assignments.push(Assignment {
-                target_name: mem_decls.write_address_name.clone(),
+                target_name: write_signal_names.address_name.clone(),
                 expr,
             });
             let expr = c.compile_signal(value, &module_decls, &mut assignments);
             node_decls.push(NodeDecl {
                 net_type: NetType::Wire,
-                name: mem_decls.write_value_name.clone(),
+                name: write_signal_names.value_name.clone(),
                 bit_width: value.bit_width(),
             });
             assignments.push(Assignment {
-                target_name: mem_decls.write_value_name.clone(),
+                target_name: write_signal_names.value_name.clone(),
                 expr,
             });
             let expr = c.compile_signal(enable, &module_decls, &mut assignments);
             node_decls.push(NodeDecl {
                 net_type: NetType::Wire,
-                name: mem_decls.write_enable_name.clone(),
+                name: write_signal_names.enable_name.clone(),
                 bit_width: enable.bit_width(),
             });
             assignments.push(Assignment {
-                target_name: mem_decls.write_enable_name.clone(),
+                target_name: write_signal_names.enable_name.clone(),
                 expr,
             });
+            if let Some(mask) = write_port.mask {
+                let expr = c.compile_signal(mask.mask, &module_decls, &mut assignments);
+                node_decls.push(NodeDecl {
+                    net_type: NetType::Wire,
+                    name: write_signal_names.mask_name.clone(),
+                    bit_width: mask.mask.bit_width(),
+                });
+                assignments.push(Assignment {
+                    target_name: write_signal_names.mask_name.clone(),
+                    expr,
+                });
+            }
         }
     }
 
@@ -221,47 +358,68 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
         });
     }
 
+    let mut property_names = Vec::new();
+    for (index, property) in m.properties.borrow().iter().enumerate() {
+        let name = format!("__property_{}_{}", property.name, index);
+        node_decls.push(NodeDecl {
+            net_type: NetType::Wire,
+            name: name.clone(),
+            bit_width: 1,
+        });
+        let expr = c.compile_signal(property.condition, &module_decls, &mut assignments);
+        assignments.push(Assignment {
+            target_name: name.clone(),
+            expr,
+        });
+        property_names.push(name);
+    }
+
     let mut w = code_writer::CodeWriter::new(w);
 
     w.append_line(&format!("module {}(", m.name))?;
     w.indent();
 
+    let mut clock_domains = vec![m.default_clock_domain()];
+    clock_domains.extend(m.clock_domains.borrow().iter());
+
     // TODO: Make conditional based on the presence of (resetable) state elements
-    w.append_line("input wire reset_n,")?;
-    w.append_indent()?;
-    w.append("input wire clk")?;
-    if !m.inputs.borrow().is_empty() || !m.outputs.borrow().is_empty() {
-        w.append(",")?;
-        w.append_newline()?;
+    let mut port_decls = Vec::new();
+    for clock_domain in clock_domains.iter() {
+        port_decls.push(format!(
+            "input wire {}",
+            clock_domain.reset_name(options.reset_polarity)
+        ));
+        port_decls.push(format!("input wire {}", clock_domain.clock_name()));
     }
-    w.append_newline()?;
-    let inputs = m.inputs.borrow();
-    let num_inputs = inputs.len();
-    for (i, (name, source)) in inputs.iter().enumerate() {
-        w.append_indent()?;
-        w.append("input wire ")?;
+    let num_clock_port_decls = port_decls.len();
+    for (name, source) in m.inputs.borrow().iter() {
+        let mut port_decl = "input wire ".to_string();
         if source.bit_width() > 1 {
-            w.append(&format!("[{}:{}] ", source.bit_width() - 1, 0))?;
-        }
-        w.append(name)?;
-        if !m.outputs.borrow().is_empty() || i < num_inputs - 1 {
-            w.append(",")?;
+            port_decl.push_str(&format!("[{}:{}] ", source.bit_width() - 1, 0));
         }
-        w.append_newline()?;
+        port_decl.push_str(name);
+        port_decls.push(port_decl);
     }
-    let outputs = m.outputs.borrow();
-    let num_outputs = outputs.len();
-    for (i, (name, output)) in outputs.iter().enumerate() {
-        w.append_indent()?;
-        w.append("output wire ")?;
+    for (name, output) in m.outputs.borrow().iter() {
+        let mut port_decl = "output wire ".to_string();
         if output.bit_width() > 1 {
-            w.append(&format!("[{}:{}] ", output.bit_width() - 1, 0))?;
+            port_decl.push_str(&format!("[{}:{}] ", output.bit_width() - 1, 0));
         }
-        w.append(name)?;
-        if i < num_outputs - 1 {
+        port_decl.push_str(name);
+        port_decls.push(port_decl);
+    }
+    let num_port_decls = port_decls.len();
+    for (i, port_decl) in port_decls.into_iter().enumerate() {
+        w.append_indent()?;
+        w.append(&port_decl)?;
+        if i < num_port_decls - 1 {
             w.append(",")?;
         }
         w.append_newline()?;
+        // Clock and reset ports are separated from inputs and outputs by an empty line
+        if i == num_clock_port_decls - 1 && i < num_port_decls - 1 {
+            w.append_newline()?;
+        }
     }
     w.append_line(");")?;
     w.append_newline()?;
@@ -280,24 +438,35 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
         ))?;
         w.indent();
         // TODO: Make conditional based on the presence of (resetable) state elements
-        w.append_line(".reset_n(reset_n),")?;
-        w.append_indent()?;
-        w.append(".clk(clk)")?;
-        if !instance_decls.input_names.is_empty() {
-            for (name, decl_name) in instance_decls.input_names.iter() {
-                w.append(",")?;
-                w.append_newline()?;
-                w.append_indent()?;
-                w.append(&format!(".{}({})", name, decl_name))?;
-            }
+        let mut instance_clock_domains = vec![instance.instantiated_module.default_clock_domain()];
+        instance_clock_domains.extend(instance.instantiated_module.clock_domains.borrow().iter());
+        let mut connections = Vec::new();
+        for clock_domain in instance_clock_domains {
+            let driving_clock_domain = instance.driving_clock_domain(clock_domain);
+            connections.push(format!(
+                ".{}({})",
+                clock_domain.reset_name(options.reset_polarity),
+                driving_clock_domain.reset_name(options.reset_polarity)
+            ));
+            connections.push(format!(
+                ".{}({})",
+                clock_domain.clock_name(),
+                driving_clock_domain.clock_name()
+            ));
+        }
+        for (name, decl_name) in instance_decls.input_names.iter() {
+            connections.push(format!(".{}({})", name, decl_name));
+        }
+        for (name, decl_name) in instance_decls.output_names.iter() {
+            connections.push(format!(".{}({})", name, decl_name));
         }
-        if !instance_decls.output_names.is_empty() {
-            for (name, decl_name) in instance_decls.output_names.iter() {
+        for (i, connection) in connections.into_iter().enumerate() {
+            if i > 0 {
                 w.append(",")?;
                 w.append_newline()?;
-                w.append_indent()?;
-                w.append(&format!(".{}({})", name, decl_name))?;
             }
+            w.append_indent()?;
+            w.append(&connection)?;
         }
         w.unindent();
         w.append(");")?;
@@ -305,7 +474,12 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
         w.append_newline()?;
     }
 
-    for (mem, mem_decls) in module_decls.mems.iter() {
+    // Modules without explicit clock domains get an `always` block for each memory and each
+    //  register, while the state elements of modules with explicit clock domains are grouped into
+    //  `always` blocks per clock domain
+    let has_explicit_clock_domains = !m.clock_domains.borrow().is_empty();
+
+    for mem in m.mems.borrow().iter() {
         w.append_indent()?;
         w.append("reg ")?;
         if mem.element_bit_width > 1 {
@@ -319,80 +493,115 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
         ))?;
         w.append_newline()?;
         w.append_newline()?;
-        if let Some(ref initial_contents) = *mem.initial_contents.borrow() {
+        if let Some((ref path, format)) = *mem.initial_contents_file.borrow() {
+            w.append_line("initial begin")?;
+            w.indent();
+            w.append_line(&format!(
+                "{}(\"{}\", {});",
+                format.readmem_task_name(),
+                path.display()
+                    .to_string()
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\""),
+                mem.name
+            ))?;
+            w.unindent();
+            w.append_line("end")?;
+            w.append_newline()?;
+        } else if let Some(ref initial_contents) = *mem.initial_contents.borrow() {
             w.append_line("initial begin")?;
             w.indent();
             for (i, element) in initial_contents.iter().enumerate() {
                 w.append_line(&format!(
-                    "{}[{}] = {}'h{:x};",
+                    "{}[{}] = {}'h{};",
                     mem.name,
                     i,
                     mem.element_bit_width,
-                    element.numeric_value()
+                    element.hex_string()
                 ))?;
             }
             w.unindent();
             w.append_line("end")?;
             w.append_newline()?;
         }
-        if !mem_decls.read_signal_names.is_empty() || mem.write_port.borrow().is_some() {
-            w.append_line("always @(posedge clk) begin")?;
-            w.indent();
-        }
-        for (_, read_signal_names) in mem_decls.read_signal_names.iter() {
-            w.append_line(&format!("if ({}) begin", read_signal_names.enable_name))?;
-            w.indent();
+
+        let mem_decls = &module_decls.mems[mem];
+        if !has_explicit_clock_domains && has_mem_ports(mem_decls) {
             w.append_line(&format!(
-                "{} <= {}[{}];",
-                read_signal_names.value_name, mem.name, read_signal_names.address_name
+                "always @(posedge {}) begin",
+                mem.resolved_clock_domain().clock_name()
             ))?;
-            w.unindent();
-            w.append_line("end")?;
-        }
-        if mem.write_port.borrow().is_some() {
-            w.append_line(&format!("if ({}) begin", mem_decls.write_enable_name))?;
             w.indent();
-            w.append_line(&format!(
-                "{}[{}] <= {};",
-                mem.name, mem_decls.write_address_name, mem_decls.write_value_name
-            ))?;
-            w.unindent();
-            w.append_line("end")?;
-        }
-        if !mem_decls.read_signal_names.is_empty() || mem.write_port.borrow().is_some() {
+            write_mem_ports(&mut w, mem, mem_decls)?;
             w.unindent();
             w.append_line("end")?;
             w.append_newline()?;
         }
     }
 
-    for reg in module_decls.regs.values() {
-        w.append_indent()?;
-        w.append("always @(posedge clk")?;
-        if reg.data.initial_value.borrow().is_some() {
-            w.append(", negedge reset_n")?;
+    if !has_explicit_clock_domains {
+        for reg in m.registers.borrow().iter() {
+            let reg = &module_decls.regs[reg];
+            let clock_domain = reg.data.resolved_clock_domain();
+            if reg.data.initial_value.borrow().is_some() {
+                write_reset_regs(&mut w, clock_domain, &[reg], options)?;
+            } else {
+                w.append_line(&format!(
+                    "always @(posedge {}) begin",
+                    clock_domain.clock_name()
+                ))?;
+                w.indent();
+                w.append_line(&format!("{} <= {};", reg.value_name, reg.next_name))?;
+                w.unindent();
+                w.append_line("end")?;
+                w.append_newline()?;
+            }
         }
-        w.append(") begin")?;
-        w.append_newline()?;
+    }
+
+    for clock_domain in clock_domains.iter().filter(|_| has_explicit_clock_domains) {
+        let clock_domain_regs = m
+            .registers
+            .borrow()
+            .iter()
+            .map(|reg| &module_decls.regs[reg])
+            .filter(|reg| reg.data.resolved_clock_domain() == *clock_domain)
+            .collect::<Vec<_>>();
+        let clock_domain_mems = m
+            .mems
+            .borrow()
+            .iter()
+            .filter(|mem| mem.resolved_clock_domain() == *clock_domain)
+            .map(|mem| (*mem, &module_decls.mems[mem]))
+            .collect::<Vec<_>>();
+
+        // Registers with default values are sensitive to reset, so they get their own always block
+        let (reset_regs, regs): (Vec<_>, Vec<_>) = clock_domain_regs
+            .into_iter()
+            .partition(|reg| reg.data.initial_value.borrow().is_some());
+
+        if !reset_regs.is_empty() {
+            write_reset_regs(&mut w, clock_domain, &reset_regs, options)?;
+        }
+
+        if regs.is_empty()
+            && !clock_domain_mems
+                .iter()
+                .any(|(_, mem_decls)| has_mem_ports(mem_decls))
+        {
+            continue;
+        }
+
+        w.append_line(&format!(
+            "always @(posedge {}) begin",
+            clock_domain.clock_name()
+        ))?;
         w.indent();
-        if let Some(ref initial_value) = *reg.data.initial_value.borrow() {
-            w.append_line("if (~reset_n) begin")?;
-            w.indent();
-            w.append_line(&format!(
-                "{} <= {}'h{:x};",
-                reg.value_name,
-                reg.data.bit_width,
-                initial_value.numeric_value()
-            ))?;
-            w.unindent();
-            w.append_line("end")?;
-            w.append_line("else begin")?;
-            w.indent();
+        for reg in regs.iter() {
+            w.append_line(&format!("{} <= {};", reg.value_name, reg.next_name))?;
         }
-        w.append_line(&format!("{} <= {};", reg.value_name, reg.next_name))?;
-        if reg.data.initial_value.borrow().is_some() {
-            w.unindent();
-            w.append_line("end")?;
+        for (mem, mem_decls) in clock_domain_mems.iter() {
+            write_mem_ports(&mut w, mem, mem_decls)?;
         }
         w.unindent();
         w.append_line("end")?;
@@ -404,6 +613,35 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
         w.append_newline()?;
     }
 
+    if !property_names.is_empty() {
+        w.append(&format!("`ifdef {}", options.property_guard_macro))?;
+        w.append_newline()?;
+        for (property, name) in m.properties.borrow().iter().zip(property_names.iter()) {
+            let clock_domain = property.resolved_clock_domain();
+            let reset_name = clock_domain.reset_name(options.reset_polarity);
+            let reset_cond = match options.reset_polarity {
+                ResetPolarity::ActiveLow => format!("~{}", reset_name),
+                ResetPolarity::ActiveHigh => reset_name,
+            };
+            let statement = match property.kind {
+                graph::PropertyKind::Assert => "assert",
+                graph::PropertyKind::Assume => "assume",
+                graph::PropertyKind::Cover => "cover",
+            };
+            w.append_line(&format!(
+                "{}: {} property (@(posedge {}) disable iff ({}) {});",
+                property.name,
+                statement,
+                clock_domain.clock_name(),
+                reset_cond,
+                name
+            ))?;
+        }
+        w.append("`endif")?;
+        w.append_newline()?;
+        w.append_newline()?;
+    }
+
     w.unindent();
     w.append_line("endmodule")?;
     w.append_newline()?;
@@ -411,6 +649,268 @@ pub fn generate<'a, W: Write>(m: &'a graph::Module<'a>, w: W) -> Result<()> {
     Ok(())
 }
 
+// Returns `true` if the memory described by `mem_decls` has any (synchronous) ports that are updated on clock edges.
+fn has_mem_ports(mem_decls: &MemDecls) -> bool {
+    !mem_decls.read_signal_names.is_empty() || !mem_decls.write_signal_names.is_empty()
+}
+
+// Writes an `always` block that resets `regs` (which must all have default values) in `clock_domain`, and updates them otherwise.
+fn write_reset_regs<W: Write>(
+    w: &mut code_writer::CodeWriter<W>,
+    clock_domain: &graph::ClockDomain,
+    regs: &[&RegisterDecls],
+    options: &GenerationOptions,
+) -> io::Result<()> {
+    let reset_name = clock_domain.reset_name(options.reset_polarity);
+    let (reset_edge, reset_cond) = match options.reset_polarity {
+        ResetPolarity::ActiveLow => ("negedge", format!("~{}", reset_name)),
+        ResetPolarity::ActiveHigh => ("posedge", reset_name.clone()),
+    };
+    w.append_indent()?;
+    w.append(&format!("always @(posedge {}", clock_domain.clock_name()))?;
+    if options.reset_type == ResetType::Asynchronous {
+        w.append(&format!(", {} {}", reset_edge, reset_name))?;
+    }
+    w.append(") begin")?;
+    w.append_newline()?;
+    w.indent();
+    w.append_line(&format!("if ({}) begin", reset_cond))?;
+    w.indent();
+    for reg in regs.iter() {
+        let initial_value = reg.data.initial_value.borrow();
+        w.append_line(&format!(
+            "{} <= {}'h{};",
+            reg.value_name,
+            reg.data.bit_width,
+            initial_value.as_ref().unwrap().hex_string()
+        ))?;
+    }
+    w.unindent();
+    w.append_line("end")?;
+    w.append_line("else begin")?;
+    w.indent();
+    for reg in regs.iter() {
+        w.append_line(&format!("{} <= {};", reg.value_name, reg.next_name))?;
+    }
+    w.unindent();
+    w.append_line("end")?;
+    w.unindent();
+    w.append_line("end")?;
+    w.append_newline()?;
+
+    Ok(())
+}
+
+// Writes the updates of `mem`'s read and write ports for the body of an `always` block.
+fn write_mem_ports<'a, W: Write>(
+    w: &mut code_writer::CodeWriter<W>,
+    mem: &graph::Mem<'a>,
+    mem_decls: &MemDecls<'a>,
+) -> io::Result<()> {
+    let write_ports = mem.write_ports.borrow();
+    for (read_port, read_signal_names) in mem_decls.read_signal_names.iter() {
+        w.append_line(&format!("if ({}) begin", read_signal_names.enable_name))?;
+        w.indent();
+        if read_port.read_during_write == graph::ReadDuringWrite::WriteFirst
+            && write_ports.len() == 1
+        {
+            write_mem_write_first_read(
+                w,
+                mem,
+                read_port,
+                read_signal_names,
+                &write_ports[0],
+                &mem_decls.write_signal_names[0],
+            )?;
+            w.unindent();
+            w.append_line("end")?;
+            continue;
+        }
+        w.append_line(&format!(
+            "{} <= {}[{}];",
+            read_signal_names.value_name, mem.name, read_signal_names.address_name
+        ))?;
+        if read_port.read_during_write != graph::ReadDuringWrite::ReadFirst {
+            // Bypass (or invalidate) the value read when a write port writes the same
+            //  location, in the order the write ports take priority. There's no canonical
+            //  write-first template for memories with several write ports, so this is also
+            //  used for write-first reads in that case.
+            for (write_port, write_signal_names) in
+                write_ports.iter().zip(mem_decls.write_signal_names.iter())
+            {
+                w.append_line(&format!(
+                    "if ({} && {} == {}) begin",
+                    write_signal_names.enable_name,
+                    write_signal_names.address_name,
+                    read_signal_names.address_name
+                ))?;
+                w.indent();
+                match read_port.read_during_write {
+                    graph::ReadDuringWrite::WriteFirst => write_mem_write_port_value(
+                        w,
+                        write_port,
+                        write_signal_names,
+                        &read_signal_names.value_name,
+                    )?,
+                    _ => w.append_line(&format!(
+                        "{} <= {}'bx;",
+                        read_signal_names.value_name, mem.element_bit_width
+                    ))?,
+                }
+                w.unindent();
+                w.append_line("end")?;
+            }
+        }
+        w.unindent();
+        w.append_line("end")?;
+    }
+    for (write_port, write_signal_names) in
+        write_ports.iter().zip(mem_decls.write_signal_names.iter())
+    {
+        w.append_line(&format!("if ({}) begin", write_signal_names.enable_name))?;
+        w.indent();
+        write_mem_write_port_value(
+            w,
+            write_port,
+            write_signal_names,
+            &format!("{}[{}]", mem.name, write_signal_names.address_name),
+        )?;
+        w.unindent();
+        w.append_line("end")?;
+    }
+
+    Ok(())
+}
+
+// Emits the canonical write-first block RAM template for a read port of a memory with a single
+//  write port: the value read is the value being written when the write port writes the location
+//  being read, and the stored value otherwise (per lane, for masked write ports).
+fn write_mem_write_first_read<'a, W: Write>(
+    w: &mut code_writer::CodeWriter<W>,
+    mem: &graph::Mem,
+    read_port: &graph::ReadPort<'a>,
+    read_signal_names: &ReadSignalNames,
+    write_port: &graph::WritePort<'a>,
+    write_signal_names: &WriteSignalNames,
+) -> io::Result<()> {
+    // The address comparison is redundant for single-port memories
+    if ptr::eq(read_port.address, write_port.address) {
+        w.append_line(&format!("if ({}) begin", write_signal_names.enable_name))?;
+    } else {
+        w.append_line(&format!(
+            "if ({} && {} == {}) begin",
+            write_signal_names.enable_name,
+            write_signal_names.address_name,
+            read_signal_names.address_name
+        ))?;
+    }
+    w.indent();
+    match write_port.mask {
+        Some(mask) => {
+            let lanes = if mask.mask.bit_width() > 1 {
+                (0..mask.mask.bit_width())
+                    .map(|lane| {
+                        let range = format!(
+                            "[{}:{}]",
+                            (lane + 1) * mask.granularity - 1,
+                            lane * mask.granularity
+                        );
+                        (format!("{}[{}]", write_signal_names.mask_name, lane), range)
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                vec![(write_signal_names.mask_name.clone(), String::new())]
+            };
+            for (lane_enable, range) in lanes {
+                w.append_line(&format!("if ({}) begin", lane_enable))?;
+                w.indent();
+                w.append_line(&format!(
+                    "{}{} <= {}{};",
+                    read_signal_names.value_name, range, write_signal_names.value_name, range
+                ))?;
+                w.unindent();
+                w.append_line("end")?;
+                w.append_line("else begin")?;
+                w.indent();
+                w.append_line(&format!(
+                    "{}{} <= {}[{}]{};",
+                    read_signal_names.value_name,
+                    range,
+                    mem.name,
+                    read_signal_names.address_name,
+                    range
+                ))?;
+                w.unindent();
+                w.append_line("end")?;
+            }
+        }
+        None => {
+            w.append_line(&format!(
+                "{} <= {};",
+                read_signal_names.value_name, write_signal_names.value_name
+            ))?;
+        }
+    }
+    w.unindent();
+    w.append_line("end")?;
+    w.append_line("else begin")?;
+    w.indent();
+    w.append_line(&format!(
+        "{} <= {}[{}];",
+        read_signal_names.value_name, mem.name, read_signal_names.address_name
+    ))?;
+    w.unindent();
+    w.append_line("end")?;
+
+    Ok(())
+}
+
+// Assigns `target` the value written by `write_port`, taking its mask into account (if any).
+fn write_mem_write_port_value<W: Write>(
+    w: &mut code_writer::CodeWriter<W>,
+    write_port: &graph::WritePort,
+    write_signal_names: &WriteSignalNames,
+    target: &str,
+) -> io::Result<()> {
+    match write_port.mask {
+        // Per-lane writes, so that synthesis tools can infer byte/lane enables
+        Some(mask) if mask.mask.bit_width() > 1 => {
+            for lane in 0..mask.mask.bit_width() {
+                let range_high = (lane + 1) * mask.granularity - 1;
+                let range_low = lane * mask.granularity;
+                w.append_line(&format!(
+                    "if ({}[{}]) begin",
+                    write_signal_names.mask_name, lane
+                ))?;
+                w.indent();
+                w.append_line(&format!(
+                    "{}[{}:{}] <= {}[{}:{}];",
+                    target,
+                    range_high,
+                    range_low,
+                    write_signal_names.value_name,
+                    range_high,
+                    range_low
+                ))?;
+                w.unindent();
+                w.append_line("end")?;
+            }
+        }
+        Some(_) => {
+            w.append_line(&format!("if ({}) begin", write_signal_names.mask_name))?;
+            w.indent();
+            w.append_line(&format!("{} <= {};", target, write_signal_names.value_name))?;
+            w.unindent();
+            w.append_line("end")?;
+        }
+        None => {
+            w.append_line(&format!("{} <= {};", target, write_signal_names.value_name))?;
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -418,9 +918,6 @@ mod tests {
     use crate::*;
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\"."
-    )]
     fn recursive_module_definition_error1() {
         let c = Context::new();
 
@@ -428,14 +925,15 @@ mod tests {
 
         let _ = a.instance("a", "A");
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\"."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\" in module \"B\"."
-    )]
     fn recursive_module_definition_error2() {
         let c = Context::new();
 
@@ -445,14 +943,15 @@ mod tests {
         let _ = a.instance("b", "B");
         let _ = b.instance("a", "A");
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because it has a recursive definition formed by an instance of itself called \"a\" in module \"B\"."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains an instance of module \"B\" called \"b\" whose input \"i\" is not driven."
-    )]
     fn undriven_instance_input_error() {
         let c = Context::new();
 
@@ -462,28 +961,48 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains an instance of module \"B\" called \"b\" whose input \"i\" is not driven."
+        );
+    }
+
+    #[test]
+    fn undriven_instance_clock_domain_error() {
+        let c = Context::new();
+
+        let a = c.module("A");
+        let b = c.module("B");
+        let _ = b.clock_domain("d");
+
+        let _ = a.instance("b", "B");
+
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains an instance of module \"B\" called \"b\" whose clock domain \"d\" is not driven."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains a register called \"r\" which is not driven."
-    )]
     fn undriven_register_error1() {
         let c = Context::new();
 
         let a = c.module("A");
         let _ = a.reg("r", 1);
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains a register called \"r\" which is not driven."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"B\" contains a register called \"r\" which is not driven."
-    )]
     fn undriven_register_error2() {
         let c = Context::new();
 
@@ -493,28 +1012,30 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"B\" contains a register called \"r\" which is not driven."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have any read ports."
-    )]
     fn mem_without_read_ports_error1() {
         let c = Context::new();
 
         let a = c.module("A");
         let _ = a.mem("m", 1, 1);
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have any read ports."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have any read ports."
-    )]
     fn mem_without_read_ports_error2() {
         let c = Context::new();
 
@@ -524,14 +1045,15 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have any read ports."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
-    )]
     fn mem_without_initial_contents_or_write_port_error1() {
         let c = Context::new();
 
@@ -539,14 +1061,15 @@ mod tests {
         let m = a.mem("m", 1, 1);
         let _ = m.read_port(a.low(), a.low());
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"A\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
-    )]
     fn mem_without_initial_contents_or_write_port_error2() {
         let c = Context::new();
 
@@ -557,14 +1080,15 @@ mod tests {
 
         let _ = a.instance("b", "B");
 
-        // Panic
-        generate(a, Vec::new()).unwrap();
+        // Error
+        let e = generate(a, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"A\" because module \"B\" contains a memory called \"m\" which doesn't have initial contents or a write port specified. At least one of the two is required."
+        );
     }
 
     #[test]
-    #[should_panic(
-        expected = "Cannot generate code for module \"b\" because module \"a\" contains an output called \"o\" which forms a combinational loop with itself."
-    )]
     fn combinational_loop_error() {
         let c = Context::new();
 
@@ -576,7 +1100,476 @@ mod tests {
         let a_inst_o = a_inst.output("o");
         a_inst.drive_input("i", a_inst_o);
 
-        // Panic
-        generate(b, Vec::new()).unwrap();
+        // Error
+        let e = generate(b, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"b\" because module \"a\" contains an output called \"o\" which forms a combinational loop with itself."
+        );
+    }
+
+    #[test]
+    fn async_read_port_combinational_loop_error() {
+        let c = Context::new();
+
+        let a = c.module("a");
+        a.output("o", a.input("i", 1));
+
+        let b = c.module("b");
+        let a_inst = b.instance("a_inst", "a");
+        let m = b.mem("m", 1, 1);
+        m.initial_contents(&[false, true]);
+        a_inst.drive_input("i", m.async_read_port(a_inst.output("o")));
+        b.output("o", a_inst.output("o"));
+
+        // Error
+        let e = generate(b, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"b\" because module \"a\" contains an output called \"o\" which forms a combinational loop with itself."
+        );
+    }
+
+    #[test]
+    fn generate_hierarchy_dependency_order() {
+        let c = Context::new();
+
+        let leaf = c.module("Leaf");
+        leaf.output("o", leaf.input("i", 1));
+
+        let mid = c.module("Mid");
+        let leaf_inst = mid.instance("leaf", "Leaf");
+        leaf_inst.drive_input("i", mid.input("i", 1));
+        mid.output("o", leaf_inst.output("o"));
+
+        let top = c.module("Top");
+        let mid_inst = top.instance("mid", "Mid");
+        mid_inst.drive_input("i", top.input("i", 1));
+        let leaf_inst = top.instance("leaf", "Leaf");
+        leaf_inst.drive_input("i", mid_inst.output("o"));
+        top.output("o", leaf_inst.output("o"));
+
+        let mut w = Vec::new();
+        generate_hierarchy(top, GenerationOptions::default(), &mut w).unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        let module_names = code
+            .lines()
+            .filter_map(|line| line.strip_prefix("module "))
+            .collect::<Vec<_>>();
+        assert_eq!(module_names, ["Leaf(", "Mid(", "Top("]);
+    }
+
+    #[test]
+    fn properties() {
+        let c = Context::new();
+
+        let m = c.module("Top");
+        let slow = m.clock_domain("slow");
+        let push = m.input("push", 1);
+        let full = m.input("full", 1);
+        m.output("o", push);
+        m.assert("no_overflow", !(push & full));
+        m.cover("push_when_full", push & full).clock_domain(slow);
+
+        let mut w = Vec::new();
+        generate(
+            m,
+            GenerationOptions {
+                reset_polarity: ResetPolarity::ActiveHigh,
+                property_guard_macro: "ASSERTIONS".into(),
+                ..GenerationOptions::default()
+            },
+            &mut w,
+        )
+        .unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        let property_lines = code
+            .lines()
+            .skip_while(|line| *line != "`ifdef ASSERTIONS")
+            .take_while(|line| *line != "`endif")
+            .map(|line| line.trim())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            property_lines,
+            [
+                "`ifdef ASSERTIONS",
+                "no_overflow: assert property (@(posedge clk) disable iff (reset) __property_no_overflow_0);",
+                "push_when_full: cover property (@(posedge slow_clk) disable iff (slow_reset) __property_push_when_full_1);",
+            ]
+        );
+    }
+
+    #[test]
+    fn clock_domain_port_conflict_error() {
+        let c = Context::new();
+
+        let inner = c.module("Inner");
+        let _ = inner.clock_domain("mem");
+        inner.output("mem_clk", inner.input("i", 1));
+
+        let m = c.module("Top");
+        let inner_inst = m.instance("inner", "Inner");
+        inner_inst.drive_clock_domain("mem", m.default_clock_domain());
+        inner_inst.drive_input("i", m.input("i", 1));
+        m.output("o", inner_inst.output("mem_clk"));
+
+        // Error
+        let e = generate(m, GenerationOptions::default(), Vec::new()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"Top\" because module \"Inner\" contains an input or output called \"mem_clk\" which has the same name as one of its clock or reset ports."
+        );
+
+        let m = c.module("Top2");
+        m.output("reset", m.input("i", 1));
+
+        // Reset port names depend on the reset polarity
+        generate(m, GenerationOptions::default(), Vec::new()).unwrap();
+        let e = generate(
+            m,
+            GenerationOptions {
+                reset_polarity: ResetPolarity::ActiveHigh,
+                ..GenerationOptions::default()
+            },
+            Vec::new(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Cannot generate code for module \"Top2\" because module \"Top2\" contains an input or output called \"reset\" which has the same name as one of its clock or reset ports."
+        );
+    }
+
+    #[test]
+    fn single_clock_domain_always_blocks() {
+        let c = Context::new();
+
+        let m = c.module("Top");
+        let i = m.input("i", 4);
+        let r1 = m.reg("r1", 4);
+        r1.default_value(0xau32);
+        r1.drive_next(i);
+        let r2 = m.reg("r2", 4);
+        r2.drive_next(r1.value);
+        let rom = m.mem("rom", 1, 4);
+        rom.initial_contents(&[5u32, 6]);
+        m.output("o", r2.value ^ rom.read_port(i.bit(0), m.high()));
+
+        let mut w = Vec::new();
+        generate(m, GenerationOptions::default(), &mut w).unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        let port_lines = code.lines().take(8).collect::<Vec<_>>();
+        assert_eq!(
+            port_lines,
+            [
+                "module Top(",
+                "    input wire reset_n,",
+                "    input wire clk,",
+                "",
+                "    input wire [3:0] i,",
+                "    output wire [3:0] o",
+                "    );",
+                "",
+            ]
+        );
+
+        // Each memory and register gets its own always block
+        let state_element_lines = code
+            .lines()
+            .skip_while(|line| *line != "    reg [3:0] rom[0:1];")
+            .take_while(|line| !line.starts_with("    wire"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            state_element_lines,
+            [
+                "    reg [3:0] rom[0:1];",
+                "",
+                "    initial begin",
+                "        rom[0] = 4'h5;",
+                "        rom[1] = 4'h6;",
+                "    end",
+                "",
+                "    always @(posedge clk) begin",
+                "        if (__mem_rom_read_port_0_enable) begin",
+                "            __mem_rom_read_port_0_value <= rom[__mem_rom_read_port_0_address];",
+                "        end",
+                "    end",
+                "",
+                "    always @(posedge clk, negedge reset_n) begin",
+                "        if (~reset_n) begin",
+                "            __reg_r1_0 <= 4'ha;",
+                "        end",
+                "        else begin",
+                "            __reg_r1_0 <= __reg_r1_0_next;",
+                "        end",
+                "    end",
+                "",
+                "    always @(posedge clk) begin",
+                "        __reg_r2_1 <= __reg_r2_1_next;",
+                "    end",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_clock_domain_always_blocks() {
+        let c = Context::new();
+
+        let m = c.module("Top");
+        let slow = m.clock_domain("slow");
+        let i = m.input("i", 4);
+        let r1 = m.reg("r1", 4);
+        r1.default_value(0xau32);
+        r1.drive_next(i);
+        let r2 = m.reg("r2", 4);
+        r2.drive_next(r1.value);
+        let r3 = m.reg("r3", 4);
+        r3.clock_domain(slow);
+        r3.drive_next(r2.value);
+        m.output("o", r3.value);
+
+        let mut w = Vec::new();
+        generate(m, GenerationOptions::default(), &mut w).unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        let port_lines = code.lines().take(10).collect::<Vec<_>>();
+        assert_eq!(
+            port_lines,
+            [
+                "module Top(",
+                "    input wire reset_n,",
+                "    input wire clk,",
+                "    input wire slow_reset_n,",
+                "    input wire slow_clk,",
+                "",
+                "    input wire [3:0] i,",
+                "    output wire [3:0] o",
+                "    );",
+                "",
+            ]
+        );
+
+        // The registers in each clock domain are grouped into always blocks
+        let always_block_lines = code
+            .lines()
+            .skip_while(|line| !line.starts_with("    always"))
+            .take_while(|line| !line.starts_with("    assign"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            always_block_lines,
+            [
+                "    always @(posedge clk, negedge reset_n) begin",
+                "        if (~reset_n) begin",
+                "            __reg_r1_0 <= 4'ha;",
+                "        end",
+                "        else begin",
+                "            __reg_r1_0 <= __reg_r1_0_next;",
+                "        end",
+                "    end",
+                "",
+                "    always @(posedge clk) begin",
+                "        __reg_r2_1 <= __reg_r2_1_next;",
+                "    end",
+                "",
+                "    always @(posedge slow_clk) begin",
+                "        __reg_r3_2 <= __reg_r3_2_next;",
+                "    end",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn initial_contents_file_readmem() {
+        let path = std::env::temp_dir().join(format!(
+            "kaze_initial_contents_file_readmem_{}.bin",
+            std::process::id()
+        ));
+        std::fs::write(&path, "0101\n1010\n").unwrap();
+
+        let c = Context::new();
+
+        let m = c.module("Top");
+        let rom = m.mem("rom", 1, 4);
+        rom.initial_contents_file(&path, MemFileFormat::Binary);
+        m.output("o", rom.read_port(m.input("addr", 1), m.high()));
+
+        let mut w = Vec::new();
+        generate(m, GenerationOptions::default(), &mut w).unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        // The file is loaded instead of initializing each element separately
+        let initial_lines = code
+            .lines()
+            .skip_while(|line| *line != "    reg [3:0] rom[0:1];")
+            .take(5)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            initial_lines,
+            [
+                "    reg [3:0] rom[0:1];",
+                "",
+                "    initial begin",
+                &format!("        $readmemb(\"{}\", rom);", path.display()),
+                "    end",
+            ]
+        );
+    }
+
+    #[test]
+    fn initial_contents_file_readmem_path_escaping() {
+        let dir =
+            std::env::temp_dir().join(format!("kaze_readmem_\"escaped\"_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rom.hex");
+        std::fs::write(&path, "5 6").unwrap();
+
+        let c = Context::new();
+
+        let m = c.module("Top");
+        let rom = m.mem("rom", 1, 4);
+        rom.initial_contents_file(&path, MemFileFormat::Hex);
+        m.output("o", rom.read_port(m.input("addr", 1), m.high()));
+
+        let mut w = Vec::new();
+        generate(m, GenerationOptions::default(), &mut w).unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(code.contains(&format!(
+            "        $readmemh(\"{}\", rom);\n",
+            path.display().to_string().replace('"', "\\\"")
+        )));
+    }
+
+    fn mem_always_block_lines(code: &str, mem_decl: &str) -> Vec<String> {
+        code.lines()
+            .skip_while(|line| *line != mem_decl)
+            .skip_while(|line| !line.starts_with("    always"))
+            .take_while(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn write_first_read_port_template() {
+        let c = Context::new();
+
+        let m = c.module("Top");
+        let addr = m.input("addr", 2);
+        let mem = m.mem("mem", 2, 8);
+        mem.write_port(addr, m.input("value", 8), m.input("we", 1));
+        m.output(
+            "o",
+            mem.read_port_with_read_during_write(
+                addr,
+                m.input("re", 1),
+                ReadDuringWrite::WriteFirst,
+            ),
+        );
+
+        let mut w = Vec::new();
+        generate(m, GenerationOptions::default(), &mut w).unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        assert_eq!(
+            mem_always_block_lines(&code, "    reg [7:0] mem[0:3];"),
+            [
+                "    always @(posedge clk) begin",
+                "        if (__mem_mem_read_port_0_enable) begin",
+                "            if (__mem_mem_write_port_enable) begin",
+                "                __mem_mem_read_port_0_value <= __mem_mem_write_port_value;",
+                "            end",
+                "            else begin",
+                "                __mem_mem_read_port_0_value <= mem[__mem_mem_read_port_0_address];",
+                "            end",
+                "        end",
+                "        if (__mem_mem_write_port_enable) begin",
+                "            mem[__mem_mem_write_port_address] <= __mem_mem_write_port_value;",
+                "        end",
+                "    end",
+            ]
+        );
+    }
+
+    #[test]
+    fn write_first_read_port_template_masked() {
+        let c = Context::new();
+
+        let m = c.module("Top");
+        let mem = m.mem("mem", 2, 8);
+        mem.masked_write_port(
+            m.input("write_addr", 2),
+            m.input("value", 8),
+            m.input("we", 1),
+            m.input("mask", 2),
+            4,
+        );
+        m.output(
+            "o",
+            mem.read_port_with_read_during_write(
+                m.input("read_addr", 2),
+                m.input("re", 1),
+                ReadDuringWrite::WriteFirst,
+            ),
+        );
+
+        let mut w = Vec::new();
+        generate(m, GenerationOptions::default(), &mut w).unwrap();
+        let code = String::from_utf8(w).unwrap();
+
+        assert_eq!(
+            mem_always_block_lines(&code, "    reg [7:0] mem[0:3];")[..18],
+            [
+                "    always @(posedge clk) begin",
+                "        if (__mem_mem_read_port_0_enable) begin",
+                "            if (__mem_mem_write_port_enable && __mem_mem_write_port_address == __mem_mem_read_port_0_address) begin",
+                "                if (__mem_mem_write_port_mask[0]) begin",
+                "                    __mem_mem_read_port_0_value[3:0] <= __mem_mem_write_port_value[3:0];",
+                "                end",
+                "                else begin",
+                "                    __mem_mem_read_port_0_value[3:0] <= mem[__mem_mem_read_port_0_address][3:0];",
+                "                end",
+                "                if (__mem_mem_write_port_mask[1]) begin",
+                "                    __mem_mem_read_port_0_value[7:4] <= __mem_mem_write_port_value[7:4];",
+                "                end",
+                "                else begin",
+                "                    __mem_mem_read_port_0_value[7:4] <= mem[__mem_mem_read_port_0_address][7:4];",
+                "                end",
+                "            end",
+                "            else begin",
+                "                __mem_mem_read_port_0_value <= mem[__mem_mem_read_port_0_address];",
+            ]
+        );
+    }
+
+    #[test]
+    fn disable_simplification() {
+        let c = Context::new();
+
+        let m = c.module("A");
+        let i = m.input("i", 8);
+        m.output("o", i & m.lit(0xffu32, 8));
+
+        let generate_string = |options| {
+            let mut w = Vec::new();
+            generate(m, options, &mut w).unwrap();
+            String::from_utf8(w).unwrap()
+        };
+        let simplified = generate_string(GenerationOptions::default());
+        assert!(simplified.contains("    assign o = i;\n"));
+        let unsimplified = generate_string(GenerationOptions {
+            disable_simplification: true,
+            ..GenerationOptions::default()
+        });
+        assert!(unsimplified.contains("    assign __temp_0 = i & 8'hff;\n"));
+        assert!(unsimplified.contains("    assign o = __temp_0;\n"));
     }
 }
@@ -2,16 +2,19 @@ use super::ir::*;
 use super::module_decls::*;
 
 use crate::graph;
+use crate::simplify::*;
 
 use std::collections::HashMap;
 
 pub struct Compiler<'graph> {
+    simplifier: Simplifier<'graph>,
     signal_exprs: HashMap<&'graph graph::Signal<'graph>, Expr>,
 }
 
 impl<'graph> Compiler<'graph> {
-    pub fn new() -> Compiler<'graph> {
+    pub fn new(m: &'graph graph::Module<'graph>, simplify: bool) -> Compiler<'graph> {
         Compiler {
+            simplifier: Simplifier::new(m.context(), simplify, false),
             signal_exprs: HashMap::new(),
         }
     }
@@ -35,6 +38,7 @@ impl<'graph> Compiler<'graph> {
         while let Some(frame) = frames.pop() {
             if let Some(expr) = match frame {
                 Frame::Enter(signal) => {
+                    let signal = self.simplifier.simplify(signal);
                     if let Some(expr) = self.signal_exprs.get(&signal) {
                         results.push(expr.clone());
                         continue;
@@ -141,13 +145,24 @@ impl<'graph> Compiler<'graph> {
                             mem,
                             address,
                             enable,
+                            read_during_write,
                         } => {
                             let mem = &module_decls.mems[&mem];
-                            let read_signal_names = &mem.read_signal_names[&(address, enable)];
+                            let read_signal_names = &mem.read_signal_names[&graph::ReadPort {
+                                address,
+                                enable,
+                                read_during_write,
+                            }];
                             Some(Expr::Ref {
                                 name: read_signal_names.value_name.clone(),
                             })
                         }
+
+                        graph::SignalData::MemAsyncReadPortOutput { address, .. } => {
+                            frames.push(Frame::Leave(signal));
+                            frames.push(Frame::Enter(address));
+                            None
+                        }
                     }
                 }
                 Frame::Leave(signal) => {
@@ -357,6 +372,19 @@ impl<'graph> Compiler<'graph> {
                         graph::SignalData::InstanceOutput { .. } => unreachable!(),
 
                         graph::SignalData::MemReadPortOutput { .. } => unreachable!(),
+
+                        graph::SignalData::MemAsyncReadPortOutput { mem, .. } => {
+                            let address = results.pop().unwrap();
+                            Some(a.gen_temp(
+                                Expr::ArrayIndex {
+                                    target: Box::new(Expr::Ref {
+                                        name: mem.name.clone(),
+                                    }),
+                                    index: Box::new(address),
+                                },
+                                mem.element_bit_width,
+                            ))
+                        }
                     }
                 }
             } {
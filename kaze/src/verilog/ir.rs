@@ -112,6 +112,10 @@ impl Assignment {
 
 #[derive(Clone)]
 pub enum Expr {
+    ArrayIndex {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
     BinOp {
         lhs: Box<Expr>,
         rhs: Box<Expr>,
@@ -128,7 +132,7 @@ pub enum Expr {
     },
     Constant {
         bit_width: u32,
-        value: u128,
+        value: String,
     },
     Ref {
         name: String,
@@ -155,12 +159,18 @@ impl Expr {
     pub fn from_constant(value: &graph::Constant, bit_width: u32) -> Expr {
         Expr::Constant {
             bit_width,
-            value: value.numeric_value(),
+            value: value.hex_string(),
         }
     }
 
     pub fn write<W: Write>(&self, w: &mut code_writer::CodeWriter<W>) -> Result<()> {
         match self {
+            Expr::ArrayIndex { target, index } => {
+                target.write(w)?;
+                w.append("[")?;
+                index.write(w)?;
+                w.append("]")?;
+            }
             Expr::BinOp { lhs, rhs, op } => {
                 lhs.write(w)?;
                 w.append(&format!(
@@ -205,7 +215,7 @@ impl Expr {
                 w.append("}")?;
             }
             Expr::Constant { bit_width, value } => {
-                w.append(&format!("{}'h{:x}", bit_width, value))?;
+                w.append(&format!("{}'h{}", bit_width, value))?;
             }
             Expr::Ref { name } => {
                 w.append(name)?;
@@ -8,10 +8,8 @@ pub struct InstanceDecls {
 }
 
 pub struct MemDecls<'a> {
-    pub read_signal_names: HashMap<(&'a graph::Signal<'a>, &'a graph::Signal<'a>), ReadSignalNames>,
-    pub write_address_name: String,
-    pub write_value_name: String,
-    pub write_enable_name: String,
+    pub read_signal_names: HashMap<graph::ReadPort<'a>, ReadSignalNames>,
+    pub write_signal_names: Vec<WriteSignalNames>,
 }
 
 pub struct ReadSignalNames {
@@ -20,6 +18,13 @@ pub struct ReadSignalNames {
     pub value_name: String,
 }
 
+pub struct WriteSignalNames {
+    pub address_name: String,
+    pub value_name: String,
+    pub enable_name: String,
+    pub mask_name: String,
+}
+
 pub struct RegisterDecls<'a> {
     pub(super) data: &'a graph::RegisterData<'a>,
     pub value_name: String,
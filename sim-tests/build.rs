@@ -1,7 +1,7 @@
 use kaze::*;
 
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Result;
 use std::path::Path;
 
@@ -244,6 +244,251 @@ fn main() -> Result<()> {
         },
         &mut file,
     )?;
+    sim::generate(
+        trace_test_module_4(&c),
+        sim::GenerationOptions {
+            tracing: true,
+            ..sim::GenerationOptions::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        wide_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        wide_reg_mem_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        clock_domain_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        sync_reset_test_module(&c),
+        sim::GenerationOptions {
+            reset_type: ResetType::Synchronous,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        masked_mem_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        multi_write_port_mem_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        async_read_mem_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        read_during_write_mem_test_module(&c, "ReadDuringWriteMemTestModule"),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        mem_file_test_module(&c, Path::new(&out_dir))?,
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        mem_access_test_module(&c),
+        sim::GenerationOptions {
+            full_hierarchy_access: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        signal_access_test_module(&c),
+        sim::GenerationOptions {
+            full_hierarchy_access: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        property_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        property_test_module_sync_reset(&c),
+        sim::GenerationOptions {
+            reset_type: ResetType::Synchronous,
+            ..sim::GenerationOptions::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        simplification_test_module(&c),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        prop_chunk_test_module(&c, "PropChunkTestModule"),
+        sim::GenerationOptions {
+            prop_chunk_size: Some(2),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate_files(
+        prop_chunk_test_module(&c, "PropChunkFilesTestModule"),
+        sim::GenerationOptions {
+            prop_chunk_size: Some(2),
+            ..Default::default()
+        },
+        &out_dir,
+    )?;
+    sim::generate(
+        activity_test_module(&c, "ActivityTestModuleReference"),
+        sim::GenerationOptions::default(),
+        &mut file,
+    )?;
+    sim::generate(
+        activity_test_module(&c, "ActivityTestModule"),
+        sim::GenerationOptions {
+            activity_tracking: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        activity_test_module(&c, "ActivityChunkTestModule"),
+        sim::GenerationOptions {
+            activity_tracking: true,
+            prop_chunk_size: Some(3),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        prop_chunk_test_module(&c, "PartitionTestModule"),
+        sim::GenerationOptions {
+            partition_depth: Some(1),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        activity_test_module(&c, "PartitionActivityTestModule"),
+        sim::GenerationOptions {
+            partition_depth: Some(1),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        activity_test_module(&c, "LanesTestModule"),
+        sim::GenerationOptions {
+            lanes: Some(4),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        activity_test_module(&c, "LanesChunkTestModule"),
+        sim::GenerationOptions {
+            prop_chunk_size: Some(3),
+            lanes: Some(4),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        four_state_test_module(&c, "FourStateTestModule"),
+        sim::GenerationOptions {
+            four_state: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        four_state_test_module(&c, "FourStateChunkTestModule"),
+        sim::GenerationOptions {
+            four_state: true,
+            prop_chunk_size: Some(3),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        read_during_write_mem_test_module(&c, "FourStateReadDuringWriteMemTestModule"),
+        sim::GenerationOptions {
+            four_state: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        four_state_assert_test_module(&c),
+        sim::GenerationOptions {
+            four_state: true,
+            assert_known_outputs: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        four_state_simplification_test_module(&c),
+        sim::GenerationOptions {
+            four_state: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        random_initial_state_test_module(&c, "RandomInitialStateTestModule"),
+        sim::GenerationOptions {
+            random_initial_state: true,
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        random_initial_state_test_module(&c, "RandomInitialStateLanesTestModule"),
+        sim::GenerationOptions {
+            random_initial_state: true,
+            lanes: Some(2),
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        trace_filter_test_module(&c, "TraceFilterTestModule"),
+        sim::GenerationOptions {
+            tracing: true,
+            trace_filter: sim::TraceFilter {
+                include: vec!["o".into(), "a.*".into(), "b.r".into()],
+                exclude: vec!["a.leaf.*".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        &mut file,
+    )?;
+    sim::generate(
+        trace_filter_test_module(&c, "TraceDepthTestModule"),
+        sim::GenerationOptions {
+            tracing: true,
+            trace_filter: sim::TraceFilter {
+                max_depth: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        &mut file,
+    )?;
     sim::generate(
         deep_graph_test_module(&c),
         sim::GenerationOptions::default(),
@@ -1041,6 +1286,607 @@ fn trace_test_module_3<'a>(c: &'a Context<'a>) -> &Module<'a> {
     m
 }
 
+fn trace_test_module_4<'a>(c: &'a Context<'a>) -> &'a Module<'a> {
+    let m = c.module("TraceTestModule4");
+
+    m.output("o", m.input("i", 200));
+
+    m
+}
+
+fn wide_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("WideTestModule");
+
+    let i1 = m.input("i1", 200);
+    let i2 = m.input("i2", 200);
+    let shift = m.input("shift", 9);
+    let narrow = m.input("narrow", 32);
+
+    m.output("add", i1 + i2);
+    m.output("sub", i1 - i2);
+    m.output("bit_and", i1 & i2);
+    m.output("bit_or", i1 | i2);
+    m.output("bit_xor", i1 ^ i2);
+    m.output("not", !i1);
+    m.output("eq", i1.eq(i2));
+    m.output("lt", i1.lt(i2));
+    m.output("lt_signed", i1.lt_signed(i2));
+    m.output("ge_signed", i1.ge_signed(i2));
+    m.output("shl", i1 << shift);
+    m.output("shr", i1 >> shift);
+    m.output("shr_arithmetic", i1.shr_arithmetic(shift));
+    m.output("narrow_shl", narrow << i2);
+    m.output("bits", i1.bits(163, 36));
+    m.output("concat", i1.bits(99, 0).concat(i2.bits(99, 0)));
+    m.output("repeat", i1.bits(69, 0).repeat(3));
+    m.output("mul", i1.bits(129, 0) * i2.bits(129, 0));
+    m.output("mul_signed", i1.bits(129, 0).mul_signed(i2.bits(129, 0)));
+
+    m
+}
+
+fn wide_reg_mem_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("WideRegMemTestModule");
+
+    m.output(
+        "lit",
+        m.lit(vec![0xfadebabedeadbeefu64, 0xabad1deabadc0de5, 0x1], 129),
+    );
+
+    let r = m.reg("r", 146);
+    r.default_value(vec![0x1u64, 0x2, 0x3]);
+    r.drive_next(m.input("reg_next", 146));
+    m.output("reg", r.value);
+
+    let mem = m.mem("mem", 1, 160);
+    mem.initial_contents(&[vec![0u64, 0, 0], vec![0, 0, 0x10000000]]);
+    mem.write_port(
+        m.input("write_addr", 1),
+        m.input("write_value", 160),
+        m.input("write_enable", 1),
+    );
+    m.output(
+        "read_data",
+        mem.read_port(m.input("read_addr", 1), m.input("read_enable", 1)),
+    );
+
+    m
+}
+
+fn clock_domain_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let inner = c.module("ClockDomainTestModuleInner");
+    let fast = inner.clock_domain("fast");
+
+    let i = inner.input("i", 8);
+
+    let r0 = inner.reg("r0", 8);
+    r0.default_value(0u32);
+    r0.drive_next(i);
+    inner.output("o0", r0.value);
+
+    let r1 = inner.reg("r1", 8);
+    r1.clock_domain(fast);
+    r1.default_value(0u32);
+    r1.drive_next(i);
+    inner.output("o1", r1.value);
+
+    let m = c.module("ClockDomainTestModule");
+    let a = m.clock_domain("a");
+    let b = m.clock_domain("b");
+
+    let i = m.input("i", 8);
+
+    let inner_inst = m.instance("inner_inst", "ClockDomainTestModuleInner");
+    inner_inst.drive_default_clock_domain(a);
+    inner_inst.drive_clock_domain("fast", b);
+    inner_inst.drive_input("i", i);
+    m.output("o0", inner_inst.output("o0"));
+    m.output("o1", inner_inst.output("o1"));
+
+    let r2 = m.reg("r2", 8);
+    r2.default_value(0u32);
+    r2.drive_next(i);
+    m.output("o2", r2.value);
+
+    let mem = m.mem("mem", 1, 8);
+    mem.clock_domain(b);
+    mem.initial_contents(&[0u8, 0u8]);
+    mem.write_port(m.low(), i, m.high());
+    m.output("o3", mem.read_port(m.low(), m.high()));
+
+    m
+}
+
+fn sync_reset_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("SyncResetTestModule");
+    let a = m.clock_domain("a");
+
+    let i = m.input("i", 8);
+
+    let r0 = m.reg("r0", 8);
+    r0.default_value(0xabu32);
+    r0.drive_next(i);
+    m.output("o0", r0.value);
+
+    let r1 = m.reg("r1", 8);
+    r1.clock_domain(a);
+    r1.default_value(0xcdu32);
+    r1.drive_next(i);
+    m.output("o1", r1.value);
+
+    m
+}
+
+fn masked_mem_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("MaskedMemTestModule");
+
+    let mem = m.mem("mem", 1, 32);
+    mem.initial_contents(&[0x01234567u32, 0x89abcdefu32]);
+    mem.masked_write_port(
+        m.input("write_addr", 1),
+        m.input("write_value", 32),
+        m.input("write_enable", 1),
+        m.input("write_mask", 4),
+        8,
+    );
+    m.output(
+        "read_data",
+        mem.read_port(m.input("read_addr", 1), m.input("read_enable", 1)),
+    );
+
+    let wide_mem = m.mem("wide_mem", 1, 160);
+    wide_mem.initial_contents(&[vec![0u64, 0, 0], vec![0, 0, 0]]);
+    wide_mem.masked_write_port(
+        m.input("wide_write_addr", 1),
+        m.input("wide_write_value", 160),
+        m.input("wide_write_enable", 1),
+        m.input("wide_write_mask", 5),
+        32,
+    );
+    m.output(
+        "wide_read_data",
+        wide_mem.read_port(m.input("wide_read_addr", 1), m.input("wide_read_enable", 1)),
+    );
+
+    m
+}
+
+fn multi_write_port_mem_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("MultiWritePortMemTestModule");
+
+    let mem = m.mem("mem", 1, 8);
+    mem.initial_contents(&[0u8, 0u8]);
+    mem.write_port(
+        m.input("write_addr_0", 1),
+        m.input("write_value_0", 8),
+        m.input("write_enable_0", 1),
+    );
+    mem.masked_write_port(
+        m.input("write_addr_1", 1),
+        m.input("write_value_1", 8),
+        m.input("write_enable_1", 1),
+        m.input("write_mask_1", 2),
+        4,
+    );
+    m.output("read_data_0", mem.read_port(m.low(), m.high()));
+    m.output("read_data_1", mem.read_port(m.high(), m.high()));
+
+    m
+}
+
+fn async_read_mem_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("AsyncReadMemTestModule");
+
+    let mem = m.mem("mem", 2, 32);
+    mem.initial_contents(&[0x00000000u32, 0x11111111u32, 0x22222222u32, 0x33333333u32]);
+    mem.write_port(
+        m.input("write_addr", 2),
+        m.input("write_value", 32),
+        m.input("write_enable", 1),
+    );
+    let read_addr_0 = m.input("read_addr_0", 2);
+    m.output("async_read_data_0", mem.async_read_port(read_addr_0));
+    m.output(
+        "async_read_data_1",
+        mem.async_read_port(m.input("read_addr_1", 2) + m.lit(1u32, 2)),
+    );
+    m.output("sync_read_data", mem.read_port(read_addr_0, m.high()));
+
+    let async_only_mem = m.mem("async_only_mem", 1, 1);
+    async_only_mem.initial_contents(&[false, true]);
+    m.output(
+        "async_only_read_data",
+        async_only_mem.async_read_port(m.input("async_only_read_addr", 1)),
+    );
+
+    m
+}
+
+fn read_during_write_mem_test_module<'a>(c: &'a Context<'a>, name: &str) -> &'a Module<'a> {
+    let m = c.module(name);
+
+    let mem = m.mem("mem", 1, 16);
+    mem.initial_contents(&[0x0000u32, 0x1111u32]);
+    mem.write_port(
+        m.input("write_addr_0", 1),
+        m.input("write_value_0", 16),
+        m.input("write_enable_0", 1),
+    );
+    mem.masked_write_port(
+        m.input("write_addr_1", 1),
+        m.input("write_value_1", 16),
+        m.input("write_enable_1", 1),
+        m.input("write_mask_1", 2),
+        8,
+    );
+    let read_addr = m.input("read_addr", 1);
+    m.output(
+        "read_first_data",
+        mem.read_port_with_read_during_write(read_addr, m.high(), ReadDuringWrite::ReadFirst),
+    );
+    m.output(
+        "write_first_data",
+        mem.read_port_with_read_during_write(read_addr, m.high(), ReadDuringWrite::WriteFirst),
+    );
+    m.output(
+        "dont_care_data",
+        mem.read_port_with_read_during_write(read_addr, m.high(), ReadDuringWrite::DontCare),
+    );
+
+    m
+}
+
+fn mem_file_test_module<'a>(c: &'a Context<'a>, out_dir: &Path) -> Result<&'a Module<'a>> {
+    let m = c.module("MemFileTestModule");
+
+    let hex_path = out_dir.join("mem_file_test_module.hex");
+    fs::write(
+        &hex_path,
+        "// Out of order, with separators\n@2\ncafe_f00d 12345678\n@0 /* first */ 0\ndeadbeef\n",
+    )?;
+    let hex_mem = m.mem("hex_mem", 2, 32);
+    hex_mem.initial_contents_file(&hex_path, MemFileFormat::Hex);
+    m.output(
+        "hex_read_data",
+        hex_mem.read_port(m.input("hex_read_addr", 2), m.high()),
+    );
+
+    // Round trip through a binary file written from another memory's contents
+    let source = c.module("MemFileTestModuleSource");
+    let source_mem = source.mem("source_mem", 1, 70);
+    source_mem.initial_contents(&[vec![0x5u64, 0x20u64], vec![0xffffffff_ffffffffu64, 0x3fu64]]);
+    let bin_path = out_dir.join("mem_file_test_module.bin");
+    source_mem.write_initial_contents(MemFileFormat::Binary, File::create(&bin_path)?)?;
+    let bin_mem = m.mem("bin_mem", 1, 70);
+    bin_mem.initial_contents_file(&bin_path, MemFileFormat::Binary);
+    m.output(
+        "bin_read_data",
+        bin_mem.read_port(m.input("bin_read_addr", 1), m.high()),
+    );
+
+    Ok(m)
+}
+
+fn mem_access_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let inner = c.module("MemAccessTestModuleInner");
+    let ram = inner.mem("ram", 4, 8);
+    ram.write_port(
+        inner.input("write_addr", 4),
+        inner.input("write_value", 8),
+        inner.input("write_enable", 1),
+    );
+    inner.output(
+        "read_data",
+        ram.async_read_port(inner.input("read_addr", 4)),
+    );
+    // Doesn't affect any outputs, but should still be accessible
+    let scratch = inner.mem("scratch", 1, 100);
+    scratch.initial_contents(&[vec![1u64, 2u64], vec![3u64, 4u64]]);
+    let _ = scratch.read_port(inner.low(), inner.low());
+
+    let m = c.module("MemAccessTestModule");
+    let rom = m.mem("rom", 2, 1);
+    rom.initial_contents(&[true, false, true, false]);
+    m.output("rom_data", rom.read_port(m.input("rom_addr", 2), m.high()));
+    for name in ["i1", "i2"].iter() {
+        let i = m.instance(*name, "MemAccessTestModuleInner");
+        i.drive_input("write_addr", m.input(format!("{}_write_addr", name), 4));
+        i.drive_input("write_value", m.input(format!("{}_write_value", name), 8));
+        i.drive_input("write_enable", m.input(format!("{}_write_enable", name), 1));
+        i.drive_input("read_addr", m.input(format!("{}_read_addr", name), 4));
+        m.output(format!("{}_read_data", name), i.output("read_data"));
+    }
+
+    m
+}
+
+fn signal_access_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let counter = c.module("SignalAccessTestModuleCounter");
+    let count = counter.reg("count", 4);
+    count.default_value(0u32);
+    count.drive_next(count.value + counter.lit(1u32, 4));
+    // Shares its path with the register above
+    counter.output("count", count.value);
+    counter.output("next_count", count.value + counter.lit(1u32, 4));
+    // Doesn't affect any outputs, but should still be accessible
+    let wide = counter.reg("wide", 130);
+    wide.default_value(vec![0u64, 0u64, 0u64]);
+    wide.drive_next(wide.value);
+
+    let core = c.module("SignalAccessTestModuleCore");
+    let counter = core.instance("counter", "SignalAccessTestModuleCounter");
+    let flag = core.reg("flag", 1);
+    flag.default_value(false);
+    flag.drive_next(!flag.value);
+    core.output("count_is_odd", counter.output("count").bit(0) ^ flag.value);
+
+    let m = c.module("SignalAccessTestModule");
+    let core = m.instance("core", "SignalAccessTestModuleCore");
+    let r = m.reg("r", 8);
+    r.default_value(0u32);
+    r.drive_next(m.input("r_next", 8));
+    m.output("out", core.output("count_is_odd") | r.value.bit(0));
+
+    m
+}
+
+fn property_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let inner = c.module("PropertyTestModuleCounter");
+    let push = inner.input("push", 1);
+    let count = inner.reg("count", 3);
+    count.default_value(0u32);
+    count.drive_next(inner.mux(push, count.value + inner.lit(1u32, 3), count.value));
+    inner.output("count", count.value);
+    let full = count.value.eq(inner.lit(7u32, 3));
+    inner.assert("no_overflow", !(push & full));
+    inner.cover("full", full);
+
+    let m = c.module("PropertyTestModule");
+    let slow = m.clock_domain("slow");
+    let push = m.input("push", 1);
+    let counter = m.instance("counter", "PropertyTestModuleCounter");
+    counter.drive_input("push", push);
+    m.output("count", counter.output("count"));
+    m.assume("push_valid", !(push & m.input("push_disabled", 1)));
+    m.cover("push", push).clock_domain(slow);
+
+    m
+}
+
+fn property_test_module_sync_reset<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("PropertyTestModuleSyncReset");
+    let r = m.reg("r", 1);
+    r.default_value(true);
+    r.drive_next(m.input("i", 1));
+    m.output("o", r.value);
+    m.assert("r_high", r.value);
+
+    m
+}
+
+// Builds graph operators on identical operands on purpose, to check that they're folded
+#[allow(clippy::eq_op)]
+fn simplification_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
+    let m = c.module("SimplificationTestModule");
+    let x = m.input("x", 8);
+    let y = m.input("y", 8);
+    let w = m.input("w", 130);
+
+    // Constant operands
+    let k = (m.lit(0x12u32, 8) + m.lit(0xf0u32, 8)).concat(m.lit(0xau32, 4).repeat(2));
+    m.output("k", k);
+    m.output("k_masked", x & k.bits(15, 8));
+
+    // Identity and absorbing elements
+    m.output("or_zero", m.lit(0u32, 8) | x);
+    m.output("and_zero", x & m.lit(0u32, 8));
+    m.output("mux_high", m.mux(m.high(), x, y));
+    m.output("full_bits", x.bits(7, 0));
+    m.output("shift_zero", x << m.lit(0u32, 3));
+    m.output("sub_self", x - x);
+    m.output("eq_self", x.eq(x));
+    m.output("concat_bits", x.concat(y).bits(11, 4));
+    m.output("wide_add_zero", w + m.lit(0u32, 130));
+
+    // Common subexpressions
+    m.output("sums", (x + y).concat(y + x));
+
+    m
+}
+
+fn prop_chunk_test_module<'a>(c: &'a Context<'a>, name: &str) -> &'a Module<'a> {
+    let inner = c.module(format!("{}Inner", name));
+    let a = inner.input("a", 8);
+    let b = inner.input("b", 8);
+    let s = a + b;
+    let r = inner.reg("r", 8);
+    r.default_value(0u32);
+    r.drive_next(r.value + s);
+    inner.output("o", s ^ r.value);
+    inner.output("p", s.concat(s));
+    inner.output("q", s.repeat(20));
+
+    let m = c.module(name);
+    let x = m.instance("x", &format!("{}Inner", name));
+    x.drive_input("a", m.input("a", 8));
+    x.drive_input("b", m.input("b", 8));
+    let y = m.instance("y", &format!("{}Inner", name));
+    y.drive_input("a", x.output("o"));
+    y.drive_input("b", x.output("p").bits(7, 0));
+    m.output("o", y.output("o"));
+    m.output("sum", x.output("p"));
+    m.output("wide", y.output("q"));
+
+    m
+}
+
+fn activity_test_module<'a>(c: &'a Context<'a>, name: &str) -> &'a Module<'a> {
+    let inner = c.module(format!("{}Inner", name));
+    let i = inner.input("i", 8);
+    let acc = inner.reg("acc", 8);
+    acc.default_value(0u32);
+    acc.drive_next(acc.value + i);
+    inner.output("acc", acc.value);
+
+    let m = c.module(name);
+    let en = m.input("en", 1);
+    let data = m.input("data", 8);
+    let addr = m.input("addr", 2);
+    let w = m.input("w", 130);
+
+    let count = m.reg("count", 8);
+    count.default_value(0u32);
+    count.drive_next(m.mux(en, count.value + m.lit(1u32, 8), count.value));
+
+    let mem = m.mem("mem", 2, 8);
+    mem.initial_contents(&[1u32, 2, 3, 4]);
+    mem.write_port(addr, data, en);
+    let async_data = mem.async_read_port(addr);
+    m.output("async_data", async_data);
+    m.output("sync_data", mem.read_port(count.value.bits(1, 0), m.high()));
+
+    let sum = count.value + data;
+    m.output("sum", sum);
+    m.output("sum_xor", sum ^ data);
+    m.output("w_plus", w + m.lit(3u32, 130));
+
+    let acc = m.instance("acc", &format!("{}Inner", name));
+    acc.drive_input("i", async_data);
+    m.output("acc", acc.output("acc"));
+
+    m
+}
+
+fn four_state_test_module<'a>(c: &'a Context<'a>, name: &str) -> &'a Module<'a> {
+    let m = c.module(name);
+    let a = m.input("a", 8);
+    let sel = m.input("sel", 1);
+    let en = m.input("en", 1);
+    let addr = m.input("addr", 2);
+    let data = m.input("data", 8);
+    let w = m.input("w", 130);
+
+    let r = m.reg("r", 8);
+    r.drive_next(a);
+    let d = m.reg("d", 8);
+    d.default_value(0u32);
+    d.drive_next(a);
+
+    let mem = m.mem("mem", 2, 8);
+    mem.write_port(addr, data, en);
+
+    m.output("r_out", r.value);
+    m.output("d_out", d.value);
+    m.output("r_and", r.value & a);
+    m.output("r_or", r.value | a);
+    m.output("r_add", r.value + a);
+    m.output("r_eq", r.value.bits(3, 0).concat(m.lit(0u32, 4)).eq(a));
+    m.output("r_shl", a << r.value.bits(2, 0));
+    m.output("r_mux", m.mux(r.value.bit(0), a, data));
+    m.output("sel_mux", m.mux(sel, a, d.value));
+    m.output("mem_out", mem.async_read_port(addr));
+    m.output("mem_sync_out", mem.read_port(addr, en));
+    m.output("wide_bits", w.concat(r.value).bits(11, 4));
+
+    m
+}
+
+fn four_state_assert_test_module<'a>(c: &'a Context<'a>) -> &'a Module<'a> {
+    let m = c.module("FourStateAssertTestModule");
+    let i = m.input("i", 8);
+
+    let r = m.reg("r", 8);
+    r.drive_next(i);
+    m.output("o", r.value);
+
+    m
+}
+
+// Builds graph operators on identical operands on purpose, to check that they aren't folded
+#[allow(clippy::eq_op)]
+fn four_state_simplification_test_module<'a>(c: &'a Context<'a>) -> &'a Module<'a> {
+    let m = c.module("FourStateSimplificationTestModule");
+    let a = m.input("a", 8);
+
+    let r = m.reg("r", 8);
+    r.drive_next(a);
+    // Only the high half is unknown until `r` is written
+    let p = r.value.bits(3, 0).concat(a.bits(3, 0));
+    let zero = m.lit(0u32, 8);
+
+    m.output("xor_self", r.value ^ r.value);
+    m.output("sub_self", r.value - r.value);
+    m.output("add_zero", p + zero);
+    m.output("sub_zero", p - zero);
+    m.output("mul_zero", r.value * zero);
+    m.output("mul_signed_zero", zero.mul_signed(r.value));
+    m.output("eq_self", r.value.eq(r.value));
+    m.output("ne_self", r.value.ne(r.value));
+    m.output("lt_self", r.value.lt(r.value));
+    m.output("ge_signed_self", r.value.ge_signed(r.value));
+    m.output("shl_zero", zero << r.value.bits(2, 0));
+    m.output("and_zero", r.value & zero);
+
+    m
+}
+
+fn random_initial_state_test_module<'a>(c: &'a Context<'a>, name: &str) -> &'a Module<'a> {
+    let m = c.module(name);
+    let addr = m.input("addr", 4);
+
+    let r = m.reg("r", 7);
+    r.drive_next(r.value);
+    let b = m.reg("b", 1);
+    b.drive_next(b.value);
+    let w = m.reg("w", 130);
+    w.drive_next(w.value);
+    let d = m.reg("d", 8);
+    d.default_value(5u32);
+    d.drive_next(d.value);
+
+    let mem = m.mem("mem", 4, 12);
+    mem.write_port(addr, m.lit(0u32, 12), m.low());
+    let init_mem = m.mem("init_mem", 1, 8);
+    init_mem.initial_contents(&[1u32, 2]);
+
+    m.output("r", r.value);
+    m.output("b", b.value);
+    m.output("w", w.value);
+    m.output("d", d.value);
+    m.output("mem_data", mem.async_read_port(addr));
+    m.output("init_mem_data", init_mem.async_read_port(addr.bit(0)));
+
+    m
+}
+
+fn trace_filter_test_module<'a>(c: &'a Context<'a>, name: &str) -> &'a Module<'a> {
+    let leaf_name = format!("{}Leaf", name);
+    let m = c.module(leaf_name.clone());
+    let r = m.reg("r", 8);
+    r.default_value(0u8);
+    r.drive_next(m.input("i", 8));
+    m.output("o", r.value);
+
+    let inner_name = format!("{}Inner", name);
+    let m = c.module(inner_name.clone());
+    let leaf = m.instance("leaf", &leaf_name);
+    leaf.drive_input("i", m.input("i", 8));
+    let r = m.reg("r", 8);
+    r.default_value(0u8);
+    r.drive_next(leaf.output("o"));
+    m.output("o", r.value);
+
+    let m = c.module(name);
+    let i = m.input("i", 8);
+    let a = m.instance("a", &inner_name);
+    a.drive_input("i", i);
+    let b = m.instance("b", &inner_name);
+    b.drive_input("i", !i);
+    m.output("o", a.output("o") ^ b.output("o"));
+
+    m
+}
+
 fn deep_graph_test_module<'a>(c: &'a Context<'a>) -> &Module<'a> {
     let m = c.module("DeepGraphTestModule");
 
@@ -2,8 +2,12 @@
 mod tests {
     extern crate kaze;
 
+    // Generated code shouldn't cause warnings in the crates that include it, e.g. for accessors
+    //  with hierarchical names (like `peek_mem_i1__ram`) or methods that aren't called
+    #[deny(dead_code, non_snake_case)]
     mod modules {
         include!(concat!(env!("OUT_DIR"), "/modules.rs"));
+        include!(concat!(env!("OUT_DIR"), "/PropChunkFilesTestModule.rs"));
     }
 
     use modules::*;
@@ -125,7 +129,7 @@ mod tests {
     struct CaptureSignal {
         bit_width: u32,
         type_: TraceValueType,
-        values: RefCell<Vec<(u64, TraceValue)>>,
+        values: RefCell<Vec<(u64, TraceValue<'static>)>>,
     }
 
     struct CaptureTrace<'a> {
@@ -208,8 +212,16 @@ mod tests {
         fn update_signal(
             &mut self,
             signal_id: &Self::SignalId,
-            value: TraceValue,
+            value: TraceValue<'_>,
         ) -> io::Result<()> {
+            let value = match value {
+                TraceValue::Bool(value) => TraceValue::Bool(value),
+                TraceValue::U32(value) => TraceValue::U32(value),
+                TraceValue::U64(value) => TraceValue::U64(value),
+                TraceValue::U128(value) => TraceValue::U128(value),
+                // Wide values are borrowed from the simulator, so they're copied (and leaked, which is fine for tests) to be captured
+                TraceValue::Wide(words) => TraceValue::Wide(Box::leak(words.into())),
+            };
             signal_id.values.borrow_mut().push((self.time_stamp, value));
 
             Ok(())
@@ -2836,6 +2848,2106 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn trace_test_module_4() -> io::Result<()> {
+        let mut capture = Capture::new();
+        let trace = CaptureTrace::new(&mut capture);
+
+        let mut m = TraceTestModule4::new("m", trace)?;
+
+        m.i = [
+            0x0123456789abcdef,
+            0xfedcba9876543210,
+            0xdeadbeefcafed00d,
+            0xff,
+        ];
+        m.prop();
+        m.update_trace(0)?;
+        m.i = [0, 0, 0, 0x80];
+        m.prop();
+        m.update_trace(1)?;
+
+        let (_, root) = capture.root.as_ref().unwrap();
+        for name in ["i", "o"].iter() {
+            let signal = &root.signals[name];
+            assert_eq!(signal.bit_width, 200);
+            assert_eq!(signal.type_, TraceValueType::Wide);
+            assert_eq!(
+                *signal.values.borrow(),
+                vec![
+                    (
+                        0,
+                        TraceValue::Wide(&[
+                            0x0123456789abcdef,
+                            0xfedcba9876543210,
+                            0xdeadbeefcafed00d,
+                            0xff
+                        ])
+                    ),
+                    (1, TraceValue::Wide(&[0, 0, 0, 0x80])),
+                ]
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn wide_test_module() {
+        let mut m = WideTestModule::new();
+
+        m.i1 = [
+            0xdeadbeefcafebabe,
+            0x123456789abcdef,
+            0xfedcba9876543210,
+            0x89,
+        ];
+        m.i2 = [
+            0x89abcdef01234567,
+            0xcafebabe01234567,
+            0xabad1deabadc0de5,
+            0xef,
+        ];
+        m.shift = 0x46;
+        m.narrow = 0xdeadbeef;
+        m.prop();
+        assert_eq!(
+            m.add,
+            [
+                0x68598cdecc220025,
+                0xcc2200258acf1357,
+                0xaa89d88331303ff5,
+                0x79
+            ]
+        );
+        assert_eq!(
+            m.sub,
+            [
+                0x5501f100c9db7557,
+                0x36248aa988888888,
+                0x532f9cadbb78242a,
+                0x9a
+            ]
+        );
+        assert_eq!(
+            m.bit_and,
+            [
+                0x88a98cef00220026,
+                0x22002601234567,
+                0xaa8c188832540000,
+                0x89
+            ]
+        );
+        assert_eq!(
+            m.bit_or,
+            [
+                0xdfafffefcbffffff,
+                0xcbffffff89abcdef,
+                0xfffdbffafedc3ff5,
+                0xef
+            ]
+        );
+        assert_eq!(
+            m.bit_xor,
+            [
+                0x57067300cbddffd9,
+                0xcbddffd988888888,
+                0x5571a772cc883ff5,
+                0x66
+            ]
+        );
+        assert_eq!(
+            m.not,
+            [
+                0x2152411035014541,
+                0xfedcba9876543210,
+                0x123456789abcdef,
+                0x76
+            ]
+        );
+        assert_eq!(m.eq, false);
+        assert_eq!(m.lt, true);
+        assert_eq!(m.lt_signed, true);
+        assert_eq!(m.ge_signed, false);
+        assert_eq!(m.shl, [0x0, 0xab6fbbf2bfaeaf80, 0x48d159e26af37bf7, 0x0]);
+        assert_eq!(m.shr, [0x40048d159e26af37, 0x27fb72ea61d950c8, 0x2, 0x0]);
+        assert_eq!(
+            m.shr_arithmetic,
+            [
+                0x40048d159e26af37,
+                0x27fb72ea61d950c8,
+                0xfffffffffffffffe,
+                0xff
+            ]
+        );
+        assert_eq!(m.narrow_shl, 0x0);
+        assert_eq!(m.bits, 0x8765432100123456789abcdefdeadbee);
+        assert_eq!(
+            m.concat,
+            [
+                0x89abcdef01234567,
+                0xafebabee01234567,
+                0x9abcdefdeadbeefc,
+                0x78
+            ]
+        );
+        assert_eq!(
+            m.repeat,
+            [
+                0xdeadbeefcafebabe,
+                0xab6fbbf2bfaeafaf,
+                0xdbeefcafebabebf7,
+                0x2fdea
+            ]
+        );
+        assert_eq!(
+            m.mul,
+            [
+                0x6136b1219acc5872,
+                0x4c7d3d5149022bfc,
+                0x55b8de7ad2699b99,
+                0x20a3bfe91a3ff2b,
+                0x0
+            ]
+        );
+        assert_eq!(
+            m.mul_signed,
+            [
+                0x6136b1219acc5872,
+                0x4c7d3d5149022bfc,
+                0x55b8de7ad2699b99,
+                0x20a3bfe91a3ff2b,
+                0x0
+            ]
+        );
+
+        m.i1 = [
+            0x89abcdef01234567,
+            0xcafebabe01234567,
+            0xabad1deabadc0de5,
+            0xef,
+        ];
+        m.i2 = [
+            0xdeadbeefcafebabe,
+            0x123456789abcdef,
+            0xfedcba9876543210,
+            0x89,
+        ];
+        m.shift = 0xc7;
+        m.narrow = 0xdeadbeef;
+        m.prop();
+        assert_eq!(
+            m.add,
+            [
+                0x68598cdecc220025,
+                0xcc2200258acf1357,
+                0xaa89d88331303ff5,
+                0x79
+            ]
+        );
+        assert_eq!(
+            m.sub,
+            [
+                0xaafe0eff36248aa9,
+                0xc9db755677777777,
+                0xacd063524487dbd5,
+                0x65
+            ]
+        );
+        assert_eq!(
+            m.bit_and,
+            [
+                0x88a98cef00220026,
+                0x22002601234567,
+                0xaa8c188832540000,
+                0x89
+            ]
+        );
+        assert_eq!(
+            m.bit_or,
+            [
+                0xdfafffefcbffffff,
+                0xcbffffff89abcdef,
+                0xfffdbffafedc3ff5,
+                0xef
+            ]
+        );
+        assert_eq!(
+            m.bit_xor,
+            [
+                0x57067300cbddffd9,
+                0xcbddffd988888888,
+                0x5571a772cc883ff5,
+                0x66
+            ]
+        );
+        assert_eq!(
+            m.not,
+            [
+                0x76543210fedcba98,
+                0x35014541fedcba98,
+                0x5452e2154523f21a,
+                0x10
+            ]
+        );
+        assert_eq!(m.eq, false);
+        assert_eq!(m.lt, false);
+        assert_eq!(m.lt_signed, false);
+        assert_eq!(m.ge_signed, true);
+        assert_eq!(m.shl, [0x0, 0x0, 0x0, 0x80]);
+        assert_eq!(m.shr, [0x1, 0x0, 0x0, 0x0]);
+        assert_eq!(
+            m.shr_arithmetic,
+            [
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xff
+            ]
+        );
+        assert_eq!(m.narrow_shl, 0x0);
+        assert_eq!(m.bits, 0xabadc0de5cafebabe0123456789abcde);
+        assert_eq!(
+            m.concat,
+            [
+                0xdeadbeefcafebabe,
+                0x1234567789abcdef,
+                0x123456789abcdef0,
+                0xe0
+            ]
+        );
+        assert_eq!(
+            m.repeat,
+            [
+                0x89abcdef01234567,
+                0x6af37bc048d159e7,
+                0xbcdef012345679e2,
+                0x2789a
+            ]
+        );
+        assert_eq!(
+            m.mul,
+            [
+                0x6136b1219acc5872,
+                0x4c7d3d5149022bfc,
+                0x55b8de7ad2699b99,
+                0x20a3bfe91a3ff2b,
+                0x0
+            ]
+        );
+        assert_eq!(
+            m.mul_signed,
+            [
+                0x6136b1219acc5872,
+                0x4c7d3d5149022bfc,
+                0x55b8de7ad2699b99,
+                0x20a3bfe91a3ff2b,
+                0x0
+            ]
+        );
+
+        m.i1 = [
+            0xdeadbeefcafebabe,
+            0x123456789abcdef,
+            0xfedcba9876543210,
+            0x89,
+        ];
+        m.i2 = [
+            0xdeadbeefcafebabe,
+            0x123456789abcdef,
+            0xfedcba9876543210,
+            0x89,
+        ];
+        m.shift = 0x12c;
+        m.narrow = 0xdeadbeef;
+        m.prop();
+        assert_eq!(
+            m.add,
+            [
+                0xbd5b7ddf95fd757c,
+                0x2468acf13579bdf,
+                0xfdb97530eca86420,
+                0x13
+            ]
+        );
+        assert_eq!(m.sub, [0x0, 0x0, 0x0, 0x0]);
+        assert_eq!(
+            m.bit_and,
+            [
+                0xdeadbeefcafebabe,
+                0x123456789abcdef,
+                0xfedcba9876543210,
+                0x89
+            ]
+        );
+        assert_eq!(
+            m.bit_or,
+            [
+                0xdeadbeefcafebabe,
+                0x123456789abcdef,
+                0xfedcba9876543210,
+                0x89
+            ]
+        );
+        assert_eq!(m.bit_xor, [0x0, 0x0, 0x0, 0x0]);
+        assert_eq!(
+            m.not,
+            [
+                0x2152411035014541,
+                0xfedcba9876543210,
+                0x123456789abcdef,
+                0x76
+            ]
+        );
+        assert_eq!(m.eq, true);
+        assert_eq!(m.lt, false);
+        assert_eq!(m.lt_signed, false);
+        assert_eq!(m.ge_signed, true);
+        assert_eq!(m.shl, [0x0, 0x0, 0x0, 0x0]);
+        assert_eq!(m.shr, [0x0, 0x0, 0x0, 0x0]);
+        assert_eq!(
+            m.shr_arithmetic,
+            [
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xff
+            ]
+        );
+        assert_eq!(m.narrow_shl, 0x0);
+        assert_eq!(m.bits, 0x8765432100123456789abcdefdeadbee);
+        assert_eq!(
+            m.concat,
+            [
+                0xdeadbeefcafebabe,
+                0xafebabe789abcdef,
+                0x9abcdefdeadbeefc,
+                0x78
+            ]
+        );
+        assert_eq!(
+            m.repeat,
+            [
+                0xdeadbeefcafebabe,
+                0xab6fbbf2bfaeafaf,
+                0xdbeefcafebabebf7,
+                0x2fdea
+            ]
+        );
+        assert_eq!(
+            m.mul,
+            [
+                0xb295b2eef140a504,
+                0xbf1ee0fd571bf4dc,
+                0xdea099c66e57e55c,
+                0x14b66dc33f6ac,
+                0x0
+            ]
+        );
+        assert_eq!(
+            m.mul_signed,
+            [
+                0xb295b2eef140a504,
+                0xbf1ee0fd571bf4dc,
+                0xdea099c66e57e55c,
+                0x14b66dc33f6ac,
+                0x0
+            ]
+        );
+
+        m.i1 = [
+            0x89abcdef01234567,
+            0xcafebabe01234567,
+            0xabad1deabadc0de5,
+            0xef,
+        ];
+        m.i2 = [0x5, 0x0, 0x0, 0x0];
+        m.shift = 0x0;
+        m.narrow = 0xdeadbeef;
+        m.prop();
+        assert_eq!(
+            m.add,
+            [
+                0x89abcdef0123456c,
+                0xcafebabe01234567,
+                0xabad1deabadc0de5,
+                0xef
+            ]
+        );
+        assert_eq!(
+            m.sub,
+            [
+                0x89abcdef01234562,
+                0xcafebabe01234567,
+                0xabad1deabadc0de5,
+                0xef
+            ]
+        );
+        assert_eq!(m.bit_and, [0x5, 0x0, 0x0, 0x0]);
+        assert_eq!(
+            m.bit_or,
+            [
+                0x89abcdef01234567,
+                0xcafebabe01234567,
+                0xabad1deabadc0de5,
+                0xef
+            ]
+        );
+        assert_eq!(
+            m.bit_xor,
+            [
+                0x89abcdef01234562,
+                0xcafebabe01234567,
+                0xabad1deabadc0de5,
+                0xef
+            ]
+        );
+        assert_eq!(
+            m.not,
+            [
+                0x76543210fedcba98,
+                0x35014541fedcba98,
+                0x5452e2154523f21a,
+                0x10
+            ]
+        );
+        assert_eq!(m.eq, false);
+        assert_eq!(m.lt, false);
+        assert_eq!(m.lt_signed, true);
+        assert_eq!(m.ge_signed, false);
+        assert_eq!(
+            m.shl,
+            [
+                0x89abcdef01234567,
+                0xcafebabe01234567,
+                0xabad1deabadc0de5,
+                0xef
+            ]
+        );
+        assert_eq!(
+            m.shr,
+            [
+                0x89abcdef01234567,
+                0xcafebabe01234567,
+                0xabad1deabadc0de5,
+                0xef
+            ]
+        );
+        assert_eq!(
+            m.shr_arithmetic,
+            [
+                0x89abcdef01234567,
+                0xcafebabe01234567,
+                0xabad1deabadc0de5,
+                0xef
+            ]
+        );
+        assert_eq!(m.narrow_shl, 0xd5b7dde0);
+        assert_eq!(m.bits, 0xabadc0de5cafebabe0123456789abcde);
+        assert_eq!(
+            m.concat,
+            [0x5, 0x1234567000000000, 0x123456789abcdef0, 0xe0]
+        );
+        assert_eq!(
+            m.repeat,
+            [
+                0x89abcdef01234567,
+                0x6af37bc048d159e7,
+                0xbcdef012345679e2,
+                0x2789a
+            ]
+        );
+        assert_eq!(
+            m.mul,
+            [0xb05b05ab05b05b03, 0xf6f9a5b605b05b05, 0x8, 0x0, 0x0]
+        );
+        assert_eq!(
+            m.mul_signed,
+            [0xb05b05ab05b05b03, 0xf6f9a5b605b05b05, 0x8, 0x0, 0x0]
+        );
+
+        m.i1 = [
+            0xffffffffffffffff,
+            0xffffffffffffffff,
+            0xffffffffffffffff,
+            0xff,
+        ];
+        m.i2 = [0x3, 0x0, 0x0, 0x0];
+        m.shift = 0x1;
+        m.narrow = 0x1;
+        m.prop();
+        assert_eq!(m.add, [0x2, 0x0, 0x0, 0x0]);
+        assert_eq!(
+            m.sub,
+            [
+                0xfffffffffffffffc,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xff
+            ]
+        );
+        assert_eq!(m.bit_and, [0x3, 0x0, 0x0, 0x0]);
+        assert_eq!(
+            m.bit_or,
+            [
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xff
+            ]
+        );
+        assert_eq!(
+            m.bit_xor,
+            [
+                0xfffffffffffffffc,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xff
+            ]
+        );
+        assert_eq!(m.not, [0x0, 0x0, 0x0, 0x0]);
+        assert_eq!(m.eq, false);
+        assert_eq!(m.lt, false);
+        assert_eq!(m.lt_signed, true);
+        assert_eq!(m.ge_signed, false);
+        assert_eq!(
+            m.shl,
+            [
+                0xfffffffffffffffe,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xff
+            ]
+        );
+        assert_eq!(
+            m.shr,
+            [
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0x7f
+            ]
+        );
+        assert_eq!(
+            m.shr_arithmetic,
+            [
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xff
+            ]
+        );
+        assert_eq!(m.narrow_shl, 0x8);
+        assert_eq!(m.bits, 0xffffffffffffffffffffffffffffffff);
+        assert_eq!(
+            m.concat,
+            [0x3, 0xfffffff000000000, 0xffffffffffffffff, 0xff]
+        );
+        assert_eq!(
+            m.repeat,
+            [
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0x3ffff
+            ]
+        );
+        assert_eq!(
+            m.mul,
+            [0xfffffffffffffffd, 0xffffffffffffffff, 0xb, 0x0, 0x0]
+        );
+        assert_eq!(
+            m.mul_signed,
+            [
+                0xfffffffffffffffd,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xffffffffffffffff,
+                0xf
+            ]
+        );
+    }
+
+    #[test]
+    fn wide_reg_mem_test_module() {
+        let mut m = WideRegMemTestModule::new();
+
+        m.reset();
+
+        m.prop();
+        assert_eq!(m.lit, [0xfadebabedeadbeef, 0xabad1deabadc0de5, 0x1]);
+        assert_eq!(m.reg, [0x1, 0x2, 0x3]);
+        assert_eq!(m.read_data, [0x0, 0x0, 0x0]);
+
+        m.reg_next = [0xffffffffffffffff, 0xffffffffffffffff, 0xffffffffffffffff];
+        m.read_addr = true;
+        m.read_enable = true;
+        m.write_addr = false;
+        m.write_value = [0x5, 0x6, 0x7];
+        m.write_enable = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.reg, [0xffffffffffffffff, 0xffffffffffffffff, 0x3ffff]);
+        assert_eq!(m.read_data, [0x0, 0x0, 0x10000000]);
+
+        m.read_addr = false;
+        m.write_enable = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data, [0x5, 0x6, 0x7]);
+    }
+
+    #[test]
+    fn clock_domain_test_module() {
+        let mut m = ClockDomainTestModule::new();
+
+        m.reset();
+        m.reset_a();
+        m.reset_b();
+
+        m.i = 5;
+        m.prop();
+        assert_eq!(m.o0, 0);
+        assert_eq!(m.o1, 0);
+        assert_eq!(m.o2, 0);
+        assert_eq!(m.o3, 0);
+
+        m.posedge_a();
+        m.prop();
+        assert_eq!(m.o0, 5);
+        assert_eq!(m.o1, 0);
+        assert_eq!(m.o2, 0);
+        assert_eq!(m.o3, 0);
+
+        m.posedge_b();
+        m.prop();
+        assert_eq!(m.o0, 5);
+        assert_eq!(m.o1, 5);
+        assert_eq!(m.o2, 0);
+        assert_eq!(m.o3, 0);
+
+        m.posedge_b();
+        m.prop();
+        assert_eq!(m.o3, 5);
+
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.o2, 5);
+
+        m.i = 6;
+        m.reset_a();
+        m.prop();
+        assert_eq!(m.o0, 0);
+        assert_eq!(m.o1, 5);
+        assert_eq!(m.o2, 5);
+    }
+
+    #[test]
+    fn sync_reset_test_module() {
+        let mut m = SyncResetTestModule::new();
+
+        m.i = 5;
+        m.prop();
+        m.posedge_clk();
+        m.posedge_a();
+        m.prop();
+        assert_eq!(m.o0, 5);
+        assert_eq!(m.o1, 5);
+
+        // Reset doesn't take effect until the next clock edge
+        m.reset();
+        m.reset_a();
+        m.prop();
+        assert_eq!(m.o0, 5);
+        assert_eq!(m.o1, 5);
+
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.o0, 0xab);
+        assert_eq!(m.o1, 5);
+
+        m.posedge_a();
+        m.prop();
+        assert_eq!(m.o0, 0xab);
+        assert_eq!(m.o1, 0xcd);
+
+        // Reset is only asserted for a single cycle
+        m.posedge_clk();
+        m.posedge_a();
+        m.prop();
+        assert_eq!(m.o0, 5);
+        assert_eq!(m.o1, 5);
+    }
+
+    #[test]
+    fn masked_mem_test_module() {
+        let mut m = MaskedMemTestModule::new();
+
+        // Masked write to addr 1
+        m.write_addr = true;
+        m.write_value = 0xdeadbeef;
+        m.write_enable = true;
+        m.write_mask = 0b0101;
+        m.read_addr = true;
+        m.read_enable = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data, 0x89abcdef);
+
+        // Disabled write doesn't change contents, even with a full mask
+        m.write_enable = false;
+        m.write_mask = 0b1111;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data, 0x89adcdef);
+
+        // Empty mask doesn't change contents
+        m.write_enable = true;
+        m.write_mask = 0b0000;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data, 0x89adcdef);
+
+        // Addr 0 is unaffected
+        m.write_enable = false;
+        m.read_addr = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data, 0x01234567);
+
+        // Wide masked write to addr 0
+        m.wide_write_addr = false;
+        m.wide_write_value = [0xffffffffffffffff, 0xffffffffffffffff, 0xffffffff];
+        m.wide_write_enable = true;
+        m.wide_write_mask = 0b10110;
+        m.wide_read_addr = false;
+        m.wide_read_enable = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        m.wide_write_enable = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(
+            m.wide_read_data,
+            [0xffffffff00000000, 0x00000000ffffffff, 0xffffffff]
+        );
+    }
+
+    #[test]
+    fn multi_write_port_mem_test_module() {
+        let mut m = MultiWritePortMemTestModule::new();
+
+        // Writes to different addresses
+        m.write_addr_0 = false;
+        m.write_value_0 = 0x12;
+        m.write_enable_0 = true;
+        m.write_addr_1 = true;
+        m.write_value_1 = 0x34;
+        m.write_enable_1 = true;
+        m.write_mask_1 = 0b11;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        m.write_enable_0 = false;
+        m.write_enable_1 = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data_0, 0x12);
+        assert_eq!(m.read_data_1, 0x34);
+
+        // Writes to the same address; the last port takes priority
+        m.write_addr_0 = true;
+        m.write_value_0 = 0x56;
+        m.write_enable_0 = true;
+        m.write_addr_1 = true;
+        m.write_value_1 = 0x78;
+        m.write_enable_1 = true;
+        m.write_mask_1 = 0b11;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        m.write_enable_0 = false;
+        m.write_enable_1 = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data_1, 0x78);
+
+        // ...but only for the lanes it writes
+        m.write_addr_0 = true;
+        m.write_value_0 = 0x9a;
+        m.write_enable_0 = true;
+        m.write_addr_1 = true;
+        m.write_value_1 = 0xbc;
+        m.write_enable_1 = true;
+        m.write_mask_1 = 0b10;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        m.write_enable_0 = false;
+        m.write_enable_1 = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_data_0, 0x12);
+        assert_eq!(m.read_data_1, 0xba);
+    }
+
+    #[test]
+    fn async_read_mem_test_module() {
+        let mut m = AsyncReadMemTestModule::new();
+
+        // Async reads are visible in the same cycle
+        m.read_addr_0 = 2;
+        m.read_addr_1 = 0;
+        m.prop();
+        assert_eq!(m.async_read_data_0, 0x22222222);
+        assert_eq!(m.async_read_data_1, 0x11111111);
+
+        m.read_addr_0 = 3;
+        m.read_addr_1 = 3;
+        m.prop();
+        assert_eq!(m.async_read_data_0, 0x33333333);
+        assert_eq!(m.async_read_data_1, 0x00000000);
+
+        // Sync read port only updates after a clock edge
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.sync_read_data, 0x33333333);
+
+        // Writes are visible to async reads on the following cycle
+        m.write_addr = 3;
+        m.write_value = 0xdeadbeef;
+        m.write_enable = true;
+        m.prop();
+        assert_eq!(m.async_read_data_0, 0x33333333);
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.async_read_data_0, 0xdeadbeef);
+        assert_eq!(m.sync_read_data, 0x33333333);
+        m.write_enable = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.async_read_data_0, 0xdeadbeef);
+        assert_eq!(m.sync_read_data, 0xdeadbeef);
+
+        m.async_only_read_addr = false;
+        m.prop();
+        assert_eq!(m.async_only_read_data, false);
+        m.async_only_read_addr = true;
+        m.prop();
+        assert_eq!(m.async_only_read_data, true);
+    }
+
+    #[test]
+    fn read_during_write_mem_test_module() {
+        let mut m = ReadDuringWriteMemTestModule::new();
+
+        // No write
+        m.read_addr = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_first_data, 0x1111);
+        assert_eq!(m.write_first_data, 0x1111);
+        assert_eq!(m.dont_care_data, 0x1111);
+
+        // Write to the location being read (don't-care reads return the new value, whereas
+        //  generated Verilog reads `x`s)
+        m.write_addr_0 = true;
+        m.write_value_0 = 0x2222;
+        m.write_enable_0 = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_first_data, 0x1111);
+        assert_eq!(m.write_first_data, 0x2222);
+        assert_eq!(m.dont_care_data, 0x2222);
+        m.write_enable_0 = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_first_data, 0x2222);
+        assert_eq!(m.write_first_data, 0x2222);
+
+        // Write to another location
+        m.write_addr_0 = false;
+        m.write_value_0 = 0x3333;
+        m.write_enable_0 = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_first_data, 0x2222);
+        assert_eq!(m.write_first_data, 0x2222);
+        m.write_enable_0 = false;
+
+        // Write-first reads follow write port priority and masks
+        m.write_addr_0 = true;
+        m.write_value_0 = 0x4444;
+        m.write_enable_0 = true;
+        m.write_addr_1 = true;
+        m.write_value_1 = 0x5555;
+        m.write_enable_1 = true;
+        m.write_mask_1 = 0b01;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_first_data, 0x2222);
+        assert_eq!(m.write_first_data, 0x4455);
+        m.write_enable_0 = false;
+        m.write_enable_1 = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_first_data, 0x4455);
+        assert_eq!(m.write_first_data, 0x4455);
+    }
+
+    #[test]
+    fn four_state_read_during_write_mem_test_module() {
+        let mut m = FourStateReadDuringWriteMemTestModule::new();
+
+        // No write
+        m.read_addr = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.dont_care_data, 0x1111);
+        assert_eq!(m.signal_unknown("dont_care_data"), Some(0));
+
+        // Write to another location
+        m.write_value_0 = 0x2222;
+        m.write_enable_0 = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.dont_care_data, 0x1111);
+        assert_eq!(m.signal_unknown("dont_care_data"), Some(0));
+
+        // Write to the location being read, like in Verilog
+        m.write_addr_0 = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.read_first_data, 0x1111);
+        assert_eq!(m.signal_unknown("read_first_data"), Some(0));
+        assert_eq!(m.write_first_data, 0x2222);
+        assert_eq!(m.signal_unknown("write_first_data"), Some(0));
+        assert_eq!(m.signal_unknown("dont_care_data"), Some(0xffff));
+
+        // Writes with unknown enables are ignored, so they don't affect reads either
+        m.write_value_0 = 0x3333;
+        assert!(m.set_signal_unknown("write_enable_0", 1));
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.dont_care_data, 0x2222);
+        assert_eq!(m.signal_unknown("dont_care_data"), Some(0));
+    }
+
+    #[test]
+    fn mem_file_test_module() {
+        let mut m = MemFileTestModule::new();
+
+        for (addr, expected) in [0, 0xdeadbeef, 0xcafef00d, 0x12345678].iter().enumerate() {
+            m.hex_read_addr = addr as _;
+            m.prop();
+            m.posedge_clk();
+            m.prop();
+            assert_eq!(m.hex_read_data, *expected);
+        }
+
+        m.bin_read_addr = false;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.bin_read_data, 0x20_0000000000000005);
+        m.bin_read_addr = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.bin_read_data, 0x3f_ffffffffffffffff);
+    }
+
+    #[test]
+    fn mem_access_test_module() {
+        let mut m = MemAccessTestModule::new();
+
+        assert_eq!(m.dump_mem_rom(), &[true, false, true, false]);
+        assert_eq!(m.peek_mem_rom(2), true);
+        m.poke_mem_rom(1, true);
+        m.rom_addr = 1;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.rom_data, true);
+
+        // Instanced memories are accessed separately
+        m.load_mem_i1__ram(2, &[0x12, 0x34, 0x56]);
+        m.poke_mem_i2__ram(3, 0x78);
+        m.i1_read_addr = 3;
+        m.i2_read_addr = 3;
+        m.prop();
+        assert_eq!(m.i1_read_data, 0x34);
+        assert_eq!(m.i2_read_data, 0x78);
+        assert_eq!(&m.dump_mem_i1__ram()[..5], &[0, 0, 0x12, 0x34, 0x56]);
+        assert_eq!(m.peek_mem_i2__ram(2), 0);
+
+        // Writes made by the design are visible
+        m.i2_write_addr = 15;
+        m.i2_write_value = 0x9a;
+        m.i2_write_enable = true;
+        m.prop();
+        m.posedge_clk();
+        assert_eq!(m.peek_mem_i2__ram(15), 0x9a);
+        assert_eq!(m.peek_mem_i1__ram(15), 0);
+
+        assert_eq!(m.peek_mem_i1__scratch(true), (4 << 64) | 3);
+        assert_eq!(m.dump_mem_i2__scratch(), &[(2 << 64) | 1, (4 << 64) | 3]);
+    }
+
+    #[test]
+    fn signal_access_test_module() {
+        let mut m = SignalAccessTestModule::new();
+
+        m.reset();
+        m.r_next = 0x42;
+        m.prop();
+        assert_eq!(m.peek_reg_core__counter__count(), 0);
+        assert_eq!(m.peek_output_core__counter__next_count(), 1);
+        assert_eq!(m.signal("core.counter.next_count"), Some(1));
+        assert_eq!(m.signal("out"), Some(0));
+
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.peek_reg_r(), 0x42);
+        assert_eq!(m.peek_reg_core__flag(), true);
+        assert_eq!(m.peek_reg_core__counter__count(), 1);
+        assert_eq!(m.peek_output_core__count_is_odd(), false);
+        assert_eq!(m.signal("r"), Some(0x42));
+        assert_eq!(m.signal("core.flag"), Some(1));
+        assert_eq!(m.signal("core.counter.count"), Some(1));
+        assert_eq!(m.signal("core.count_is_odd"), Some(0));
+
+        // Pokes take effect on the next prop
+        m.poke_reg_core__counter__count(6);
+        assert!(m.set_signal("core.flag", 0));
+        m.prop();
+        assert_eq!(m.peek_output_core__counter__count(), 6);
+        assert_eq!(m.peek_output_core__count_is_odd(), false);
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.signal("core.counter.count"), Some(7));
+
+        // Values are truncated to the register's bit width
+        assert!(m.set_signal("core.counter.count", 0x1f));
+        assert_eq!(m.signal("core.counter.count"), Some(0xf));
+
+        // Registers that don't affect any outputs are still accessible, although wide values can
+        //  only be accessed through their typed accessors
+        assert_eq!(m.peek_reg_core__counter__wide(), [0, 0, 0]);
+        m.poke_reg_core__counter__wide([1, 2, 3]);
+        m.prop();
+        m.posedge_clk();
+        assert_eq!(m.peek_reg_core__counter__wide(), [1, 2, 3]);
+        assert_eq!(m.signal("core.counter.wide"), None);
+
+        // Outputs can't be set, and unknown paths are rejected
+        assert!(!m.set_signal("out", 1));
+        assert!(!m.set_signal("core.counter.next_count", 1));
+        assert!(!m.set_signal("core.nope", 1));
+        assert_eq!(m.signal("core.nope"), None);
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let mut m = SignalAccessTestModule::new();
+        m.reset();
+        m.r_next = 0x12;
+        for _ in 0..3 {
+            m.prop();
+            m.posedge_clk();
+        }
+        m.prop();
+        let snapshot = m.snapshot();
+
+        // Restoring into a fresh simulator resumes from the same point
+        let mut forked = SignalAccessTestModule::new();
+        forked.restore(&snapshot);
+        assert_eq!(forked.r_next, 0x12);
+        assert_eq!(forked.peek_reg_r(), 0x12);
+        assert_eq!(forked.peek_reg_core__counter__count(), 3);
+        assert_eq!(forked.peek_reg_core__flag(), true);
+        assert_eq!(forked.out, m.out);
+        forked.posedge_clk();
+        forked.prop();
+        assert_eq!(forked.peek_reg_core__counter__count(), 4);
+
+        // Diverging and restoring again
+        m.r_next = 0x34;
+        m.poke_reg_core__counter__wide([1, 2, 3]);
+        m.prop();
+        m.posedge_clk();
+        m.restore(&snapshot);
+        assert_eq!(m.r_next, 0x12);
+        assert_eq!(m.peek_reg_r(), 0x12);
+        assert_eq!(m.peek_reg_core__counter__count(), 3);
+        assert_eq!(m.peek_reg_core__counter__wide(), [0, 0, 0]);
+
+        // Mem contents and read port state are captured as well
+        let mut m = MemAccessTestModule::new();
+        m.poke_mem_i1__ram(1, 0x11);
+        m.rom_addr = 2;
+        m.prop();
+        m.posedge_clk();
+        let snapshot = m.snapshot();
+        m.poke_mem_i1__ram(1, 0x22);
+        m.poke_mem_i2__scratch(false, 5);
+        m.rom_addr = 1;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.rom_data, false);
+        m.restore(&snapshot);
+        m.prop();
+        assert_eq!(m.rom_data, true);
+        assert_eq!(m.peek_mem_i1__ram(1), 0x11);
+        assert_eq!(m.peek_mem_i2__scratch(false), (2 << 64) | 1);
+        let cloned = snapshot.clone();
+        m.poke_mem_i1__ram(1, 0x33);
+        m.restore(&cloned);
+        assert_eq!(m.peek_mem_i1__ram(1), 0x11);
+    }
+
+    #[test]
+    fn snapshot_serialization() {
+        let mut m = SignalAccessTestModule::new();
+        m.reset();
+        m.r_next = 0x12;
+        m.poke_reg_core__counter__wide([1, 2, 3]);
+        for _ in 0..3 {
+            m.prop();
+            m.posedge_clk();
+        }
+        m.prop();
+        let bytes = m.snapshot().to_bytes();
+
+        let mut restored = SignalAccessTestModule::new();
+        restored.restore(&SignalAccessTestModuleState::from_bytes(&bytes).unwrap());
+        assert_eq!(restored.r_next, 0x12);
+        assert_eq!(restored.peek_reg_r(), 0x12);
+        assert_eq!(restored.peek_reg_core__counter__count(), 3);
+        assert_eq!(restored.peek_reg_core__counter__wide(), [1, 2, 3]);
+        assert_eq!(restored.out, m.out);
+        assert_eq!(restored.snapshot().to_bytes(), bytes);
+
+        // Snapshots can't be deserialized by other simulators, or when they're incomplete
+        assert!(MemAccessTestModuleState::from_bytes(&bytes).is_err());
+        assert!(SignalAccessTestModuleState::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(SignalAccessTestModuleState::from_bytes(&extended).is_err());
+
+        // Mem contents and lanes round-trip as well
+        let mut m = MemAccessTestModule::new();
+        m.poke_mem_i1__ram(1, 0x11);
+        m.poke_mem_i2__scratch(true, 5);
+        let mut restored = MemAccessTestModule::new();
+        restored.restore(&MemAccessTestModuleState::from_bytes(&m.snapshot().to_bytes()).unwrap());
+        assert_eq!(restored.peek_mem_i1__ram(1), 0x11);
+        assert_eq!(restored.peek_mem_i2__scratch(true), 5);
+
+        let mut m = LanesTestModule::new();
+        m.poke_mem_mem(1, 2, 0x42);
+        m.poke_reg_count(3, 7);
+        let mut restored = LanesTestModule::new();
+        restored.restore(&LanesTestModuleState::from_bytes(&m.snapshot().to_bytes()).unwrap());
+        assert_eq!(restored.dump_mem_mem(1), m.dump_mem_mem(1));
+        assert_eq!(restored.peek_reg_count(3), 7);
+    }
+
+    #[test]
+    fn property_test_module() {
+        let mut m = PropertyTestModule::new();
+        m.reset();
+        m.reset_slow();
+
+        // Covers are counted in their own clock domains
+        m.push = true;
+        for _ in 0..7 {
+            m.prop();
+            m.posedge_clk();
+        }
+        m.prop();
+        m.posedge_slow();
+        assert_eq!(m.count, 7);
+        assert_eq!(m.cover_count("counter.full"), Some(0));
+        assert_eq!(m.cover_count("push"), Some(1));
+        assert_eq!(m.cover_count("counter.no_overflow"), None);
+        assert_eq!(m.cover_count("nope"), None);
+
+        m.push = false;
+        m.prop();
+        m.posedge_clk();
+        m.posedge_clk();
+        assert_eq!(m.cover_count("counter.full"), Some(2));
+        assert_eq!(m.cover_count("push"), Some(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Assertion \"counter.no_overflow\" failed in cycle 7")]
+    fn property_test_module_assertion_failure() {
+        let mut m = PropertyTestModule::new();
+        m.reset();
+
+        m.push = true;
+        for _ in 0..8 {
+            m.prop();
+            m.posedge_clk();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Assumption \"push_valid\" violated in cycle 2")]
+    fn property_test_module_assumption_failure() {
+        let mut m = PropertyTestModule::new();
+        m.reset();
+
+        m.push_disabled = true;
+        for _ in 0..2 {
+            m.prop();
+            m.posedge_clk();
+        }
+        m.push = true;
+        m.prop();
+        m.posedge_clk();
+    }
+
+    #[test]
+    fn property_test_module_sync_reset() {
+        let mut m = PropertyTestModuleSyncReset::new();
+
+        // Properties aren't checked in the cycle reset is applied
+        m.reset();
+        m.prop();
+        m.posedge_clk();
+        m.i = true;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        m.posedge_clk();
+        assert_eq!(m.o, true);
+    }
+
+    #[test]
+    fn simplification_test_module() {
+        let mut m = SimplificationTestModule::new();
+
+        m.x = 0x5a;
+        m.y = 0xc3;
+        m.w = [0x1234, 0x5678, 0x3];
+        m.prop();
+
+        assert_eq!(m.k, 0x02aa);
+        assert_eq!(m.k_masked, 0x02);
+        assert_eq!(m.or_zero, 0x5a);
+        assert_eq!(m.and_zero, 0);
+        assert_eq!(m.mux_high, 0x5a);
+        assert_eq!(m.full_bits, 0x5a);
+        assert_eq!(m.shift_zero, 0x5a);
+        assert_eq!(m.sub_self, 0);
+        assert_eq!(m.eq_self, true);
+        assert_eq!(m.concat_bits, 0xac);
+        assert_eq!(m.wide_add_zero, [0x1234, 0x5678, 0x3]);
+        assert_eq!(m.sums, 0x1d1d);
+    }
+
+    #[test]
+    fn prop_chunk_test_module() {
+        let mut m = PropChunkTestModule::new();
+
+        m.reset();
+        m.a = 3;
+        m.b = 4;
+        m.prop();
+        assert_eq!(m.o, 0x0e);
+        assert_eq!(m.sum, 0x0707);
+        assert_eq!(m.wide, [0x0e0e0e0e0e0e0e0e, 0x0e0e0e0e0e0e0e0e, 0x0e0e0e0e]);
+
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.o, 0x09);
+        assert_eq!(m.sum, 0x0707);
+        assert_eq!(m.wide, [0x0707070707070707, 0x0707070707070707, 0x07070707]);
+    }
+
+    #[test]
+    fn prop_chunk_files_test_module() {
+        let mut m = PropChunkFilesTestModule::new();
+
+        m.reset();
+        m.a = 3;
+        m.b = 4;
+        m.prop();
+        assert_eq!(m.o, 0x0e);
+        assert_eq!(m.sum, 0x0707);
+        assert_eq!(m.wide, [0x0e0e0e0e0e0e0e0e, 0x0e0e0e0e0e0e0e0e, 0x0e0e0e0e]);
+
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.o, 0x09);
+        assert_eq!(m.sum, 0x0707);
+        assert_eq!(m.wide, [0x0707070707070707, 0x0707070707070707, 0x07070707]);
+    }
+
+    #[test]
+    fn activity_test_module() {
+        let mut r = ActivityTestModuleReference::new();
+        let mut a = ActivityTestModule::new();
+        let mut b = ActivityChunkTestModule::new();
+
+        r.reset();
+        a.reset();
+        b.reset();
+
+        let mut seed = 0x1234u32;
+        let mut snapshots = None;
+        for cycle in 0..100 {
+            // Only change inputs in some cycles, so that most of the design is idle in others
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            if cycle % 3 == 0 {
+                let en = (seed >> 16) & 1 != 0;
+                let data = (seed >> 17) & 0xff;
+                let addr = (seed >> 25) & 0x3;
+                r.en = en;
+                a.en = en;
+                b.en = en;
+                r.data = data;
+                a.data = data;
+                b.data = data;
+                r.addr = addr;
+                a.addr = addr;
+                b.addr = addr;
+            }
+            if cycle % 7 == 0 {
+                let w = [seed as u64, 0xffffffffffffffff, (cycle & 3) as u64];
+                r.w = w;
+                a.w = w;
+                b.w = w;
+            }
+            if cycle % 11 == 0 {
+                let value = seed >> 24;
+                r.poke_mem_mem(2, value);
+                a.poke_mem_mem(2, value);
+                b.poke_mem_mem(2, value);
+            }
+            if cycle % 13 == 0 {
+                r.poke_reg_count(cycle);
+                a.poke_reg_count(cycle);
+                b.poke_reg_count(cycle);
+            }
+            if cycle == 40 {
+                snapshots = Some((r.snapshot(), a.snapshot(), b.snapshot()));
+            }
+            if cycle == 60 {
+                let (rs, as_, bs) = snapshots.as_ref().unwrap();
+                r.restore(rs);
+                a.restore(as_);
+                b.restore(bs);
+            }
+
+            r.prop();
+            a.prop();
+            b.prop();
+
+            assert_eq!(a.async_data, r.async_data);
+            assert_eq!(a.sync_data, r.sync_data);
+            assert_eq!(a.sum, r.sum);
+            assert_eq!(a.sum_xor, r.sum_xor);
+            assert_eq!(a.w_plus, r.w_plus);
+            assert_eq!(a.acc, r.acc);
+            assert_eq!(b.async_data, r.async_data);
+            assert_eq!(b.sync_data, r.sync_data);
+            assert_eq!(b.sum, r.sum);
+            assert_eq!(b.sum_xor, r.sum_xor);
+            assert_eq!(b.w_plus, r.w_plus);
+            assert_eq!(b.acc, r.acc);
+
+            r.posedge_clk();
+            a.posedge_clk();
+            b.posedge_clk();
+        }
+    }
+
+    #[test]
+    fn partition_test_module() {
+        let mut m = PartitionTestModule::new();
+
+        m.reset();
+        m.a = 3;
+        m.b = 4;
+        m.prop();
+        assert_eq!(m.o, 0x0e);
+        assert_eq!(m.sum, 0x0707);
+        assert_eq!(m.wide, [0x0e0e0e0e0e0e0e0e, 0x0e0e0e0e0e0e0e0e, 0x0e0e0e0e]);
+
+        m.posedge_clk();
+        m.prop();
+        assert_eq!(m.o, 0x09);
+        assert_eq!(m.sum, 0x0707);
+        assert_eq!(m.wide, [0x0707070707070707, 0x0707070707070707, 0x07070707]);
+    }
+
+    #[test]
+    fn partition_activity_test_module() {
+        let mut r = ActivityTestModuleReference::new();
+        let mut p = PartitionActivityTestModule::new();
+
+        r.reset();
+        p.reset();
+
+        let mut seed = 0x1234u32;
+        let mut snapshots = None;
+        for cycle in 0..100 {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let en = (seed >> 16) & 1 != 0;
+            let data = (seed >> 17) & 0xff;
+            let addr = (seed >> 25) & 0x3;
+            let w = [seed as u64, 0xffffffffffffffff, (cycle & 3) as u64];
+            r.en = en;
+            p.en = en;
+            r.data = data;
+            p.data = data;
+            r.addr = addr;
+            p.addr = addr;
+            r.w = w;
+            p.w = w;
+            if cycle % 11 == 0 {
+                let value = seed >> 24;
+                r.poke_mem_mem(2, value);
+                p.poke_mem_mem(2, value);
+            }
+            if cycle == 40 {
+                snapshots = Some((r.snapshot(), p.snapshot()));
+            }
+            if cycle == 60 {
+                let (rs, ps) = snapshots.as_ref().unwrap();
+                r.restore(rs);
+                p.restore(ps);
+            }
+
+            r.prop();
+            p.prop();
+
+            assert_eq!(p.async_data, r.async_data);
+            assert_eq!(p.sync_data, r.sync_data);
+            assert_eq!(p.sum, r.sum);
+            assert_eq!(p.sum_xor, r.sum_xor);
+            assert_eq!(p.w_plus, r.w_plus);
+            assert_eq!(p.acc, r.acc);
+
+            r.posedge_clk();
+            p.posedge_clk();
+        }
+    }
+
+    #[test]
+    fn lanes_test_module() {
+        let mut r = [
+            ActivityTestModuleReference::new(),
+            ActivityTestModuleReference::new(),
+            ActivityTestModuleReference::new(),
+            ActivityTestModuleReference::new(),
+        ];
+        let mut l = LanesTestModule::new();
+        let mut c = LanesChunkTestModule::new();
+
+        for r in r.iter_mut() {
+            r.reset();
+        }
+        l.reset();
+        c.reset();
+
+        // Each lane is driven with different stimuli
+        let mut seeds = [0x1234u32, 0x5678, 0x9abc, 0xdef0];
+        let mut snapshots = None;
+        for cycle in 0..100 {
+            for (lane, (r, seed)) in r.iter_mut().zip(seeds.iter_mut()).enumerate() {
+                *seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let en = (*seed >> 16) & 1 != 0;
+                let data = (*seed >> 17) & 0xff;
+                let addr = (*seed >> 25) & 0x3;
+                let w = [*seed as u64, 0xffffffffffffffff, (cycle & 3) as u64];
+                r.en = en;
+                l.en[lane] = en;
+                c.en[lane] = en;
+                r.data = data;
+                l.data[lane] = data;
+                c.data[lane] = data;
+                r.addr = addr;
+                l.addr[lane] = addr;
+                c.addr[lane] = addr;
+                r.w = w;
+                l.w[lane] = w;
+                c.w[lane] = w;
+                if cycle % 11 == lane as u32 {
+                    let value = *seed >> 24;
+                    r.poke_mem_mem(2, value);
+                    l.poke_mem_mem(lane, 2, value);
+                    c.poke_mem_mem(lane, 2, value);
+                }
+                if cycle % 13 == lane as u32 {
+                    r.poke_reg_count(cycle);
+                    l.poke_reg_count(lane, cycle);
+                    c.poke_reg_count(lane, cycle);
+                }
+            }
+            if cycle == 40 {
+                snapshots = Some((
+                    r.iter().map(|r| r.snapshot()).collect::<Vec<_>>(),
+                    l.snapshot(),
+                    c.snapshot(),
+                ));
+            }
+            if cycle == 60 {
+                let (rs, ls, cs) = snapshots.as_ref().unwrap();
+                for (r, rs) in r.iter_mut().zip(rs.iter()) {
+                    r.restore(rs);
+                }
+                l.restore(ls);
+                c.restore(cs);
+            }
+
+            for r in r.iter_mut() {
+                r.prop();
+            }
+            l.prop();
+            c.prop();
+
+            for (lane, r) in r.iter().enumerate() {
+                assert_eq!(l.async_data[lane], r.async_data);
+                assert_eq!(l.sync_data[lane], r.sync_data);
+                assert_eq!(l.sum[lane], r.sum);
+                assert_eq!(l.sum_xor[lane], r.sum_xor);
+                assert_eq!(l.w_plus[lane], r.w_plus);
+                assert_eq!(l.acc[lane], r.acc);
+                assert_eq!(l.peek_reg_count(lane), r.peek_reg_count());
+                assert_eq!(l.dump_mem_mem(lane), r.dump_mem_mem());
+                assert_eq!(l.signal(lane, "sum"), r.signal("sum"));
+                assert_eq!(c.async_data[lane], r.async_data);
+                assert_eq!(c.sync_data[lane], r.sync_data);
+                assert_eq!(c.sum[lane], r.sum);
+                assert_eq!(c.sum_xor[lane], r.sum_xor);
+                assert_eq!(c.w_plus[lane], r.w_plus);
+                assert_eq!(c.acc[lane], r.acc);
+                assert_eq!(c.peek_reg_count(lane), r.peek_reg_count());
+                assert_eq!(c.dump_mem_mem(lane), r.dump_mem_mem());
+                assert_eq!(c.signal(lane, "sum"), r.signal("sum"));
+            }
+
+            for r in r.iter_mut() {
+                r.posedge_clk();
+            }
+            l.posedge_clk();
+            c.posedge_clk();
+        }
+    }
+
+    #[test]
+    fn four_state_test_module() {
+        let mut m = FourStateTestModule::new();
+        let mut c = FourStateChunkTestModule::new();
+        let outputs = [
+            "r_out",
+            "d_out",
+            "r_and",
+            "r_or",
+            "r_add",
+            "r_eq",
+            "r_shl",
+            "r_mux",
+            "sel_mux",
+            "mem_out",
+            "mem_sync_out",
+            "wide_bits",
+        ];
+
+        m.a = 0x0f;
+        c.a = 0x0f;
+        m.sel = false;
+        c.sel = false;
+        m.en = false;
+        c.en = false;
+        m.addr = 1;
+        c.addr = 1;
+        m.data = 0x33;
+        c.data = 0x33;
+        m.w = [0x15, 0, 0];
+        c.w = [0x15, 0, 0];
+        m.prop();
+        c.prop();
+        // Registers without default values and mems without initial contents start out unknown
+        assert_eq!(m.signal_unknown("r_out"), Some(0xff));
+        assert_eq!(m.signal_unknown("r"), Some(0xff));
+        assert_eq!(m.signal_unknown("d_out"), Some(0));
+        assert_eq!(m.d_out, 0);
+        assert_eq!(m.signal_unknown("r_and"), Some(0x0f));
+        assert_eq!(m.signal_unknown("r_or"), Some(0xf0));
+        assert_eq!(m.signal_unknown("r_add"), Some(0xff));
+        assert_eq!(m.signal_unknown("r_eq"), Some(0));
+        assert_eq!(m.r_eq, false);
+        assert_eq!(m.signal_unknown("r_shl"), Some(0xff));
+        assert_eq!(m.signal_unknown("r_mux"), Some(0x3c));
+        assert_eq!(m.signal_unknown("sel_mux"), Some(0));
+        assert_eq!(m.sel_mux, 0);
+        assert_eq!(m.signal_unknown("mem_out"), Some(0xff));
+        assert_eq!(m.signal_unknown("mem_sync_out"), Some(0));
+        assert_eq!(m.signal_unknown("wide_bits"), Some(0x0f));
+        assert_eq!(m.wide_bits & 0xf0, 0x50);
+        assert_eq!(m.peek_mem_unknown_mem(1), 0xff);
+        assert_eq!(m.signal_unknown("a"), Some(0));
+        for output in outputs.iter() {
+            assert_eq!(c.signal_unknown(output), m.signal_unknown(output));
+        }
+
+        // Comparisons are known if known bits differ, and unknown otherwise
+        m.a = 0xf0;
+        c.a = 0xf0;
+        m.prop();
+        c.prop();
+        assert_eq!(m.signal_unknown("r_eq"), Some(1));
+        for output in outputs.iter() {
+            assert_eq!(c.signal_unknown(output), m.signal_unknown(output));
+        }
+
+        // Registers become known once they're written
+        m.a = 0x0f;
+        c.a = 0x0f;
+        m.prop();
+        c.prop();
+        m.reset();
+        c.reset();
+        m.posedge_clk();
+        c.posedge_clk();
+        m.prop();
+        c.prop();
+        assert_eq!(m.r_out, 0x0f);
+        assert_eq!(m.signal_unknown("r_out"), Some(0));
+        assert_eq!(m.r_add, 0x1e);
+        assert_eq!(m.signal_unknown("r_add"), Some(0));
+        assert_eq!(m.signal_unknown("r_mux"), Some(0));
+        assert_eq!(m.r_mux, 0x0f);
+        assert_eq!(m.signal_unknown("wide_bits"), Some(0));
+        assert_eq!(m.wide_bits, 0x50);
+        assert_eq!(m.signal_unknown("mem_out"), Some(0xff));
+        for output in outputs.iter() {
+            assert_eq!(c.signal(output), m.signal(output));
+            assert_eq!(c.signal_unknown(output), m.signal_unknown(output));
+        }
+
+        // Mem elements become known once they're written
+        m.en = true;
+        c.en = true;
+        m.prop();
+        c.prop();
+        m.posedge_clk();
+        c.posedge_clk();
+        m.prop();
+        c.prop();
+        assert_eq!(m.mem_out, 0x33);
+        assert_eq!(m.signal_unknown("mem_out"), Some(0));
+        // Sync read ports return the element's previous (unknown) value
+        assert_eq!(m.signal_unknown("mem_sync_out"), Some(0xff));
+        m.posedge_clk();
+        c.posedge_clk();
+        m.prop();
+        c.prop();
+        assert_eq!(m.mem_sync_out, 0x33);
+        assert_eq!(m.signal_unknown("mem_sync_out"), Some(0));
+        for output in outputs.iter() {
+            assert_eq!(c.signal(output), m.signal(output));
+            assert_eq!(c.signal_unknown(output), m.signal_unknown(output));
+        }
+
+        // Writes (and sync reads) with unknown enables are ignored
+        assert!(m.set_signal_unknown("en", 1));
+        assert!(c.set_signal_unknown("en", 1));
+        m.addr = 2;
+        c.addr = 2;
+        m.data = 0x44;
+        c.data = 0x44;
+        m.prop();
+        c.prop();
+        m.posedge_clk();
+        c.posedge_clk();
+        m.prop();
+        c.prop();
+        assert_eq!(m.peek_mem_unknown_mem(2), 0xff);
+        assert_eq!(m.signal_unknown("mem_out"), Some(0xff));
+        assert_eq!(m.mem_sync_out, 0x33);
+        assert_eq!(m.signal_unknown("mem_sync_out"), Some(0));
+        for output in outputs.iter() {
+            assert_eq!(c.signal_unknown(output), m.signal_unknown(output));
+        }
+
+        // Writes to unknown addresses are ignored, and reads from them are unknown
+        assert!(m.set_signal_unknown("en", 0));
+        assert!(c.set_signal_unknown("en", 0));
+        assert!(m.set_signal_unknown("addr", 1));
+        assert!(c.set_signal_unknown("addr", 1));
+        m.prop();
+        c.prop();
+        assert_eq!(m.signal_unknown("mem_out"), Some(0xff));
+        m.posedge_clk();
+        c.posedge_clk();
+        m.prop();
+        c.prop();
+        assert_eq!(m.peek_mem_unknown_mem(2), 0xff);
+        assert_eq!(m.peek_mem_unknown_mem(3), 0xff);
+        assert_eq!(m.signal_unknown("mem_sync_out"), Some(0xff));
+        for output in outputs.iter() {
+            assert_eq!(c.signal_unknown(output), m.signal_unknown(output));
+        }
+
+        // Writing values in other ways makes them known
+        m.poke_mem_mem(2, 0x55);
+        m.set_signal_unknown("addr", 0);
+        m.poke_reg_r(0x10);
+        m.prop();
+        assert_eq!(m.peek_mem_unknown_mem(2), 0);
+        assert_eq!(m.mem_out, 0x55);
+        assert_eq!(m.signal_unknown("mem_out"), Some(0));
+        m.set_signal_unknown("r", 0x03);
+        m.prop();
+        assert_eq!(m.signal_unknown("r_out"), Some(0x03));
+        assert!(m.set_signal("r", 0x10));
+        m.prop();
+        assert_eq!(m.signal_unknown("r_out"), Some(0));
+        assert!(!m.set_signal_unknown("r_out", 0x03));
+    }
+
+    #[test]
+    #[should_panic(expected = "Output \"o\" has unknown bits f0")]
+    fn four_state_assert_test_module() {
+        let mut m = FourStateAssertTestModule::new();
+
+        m.poke_reg_r(3);
+        m.prop();
+        assert_eq!(m.o, 3);
+
+        m.set_signal_unknown("i", 0xf0);
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+    }
+
+    #[test]
+    fn four_state_simplification_test_module() {
+        let mut m = FourStateSimplificationTestModule::new();
+
+        m.a = 0x35;
+        m.prop();
+        // Logic that's constant for known values is unknown for unknown values
+        assert_eq!(m.signal_unknown("xor_self"), Some(0xff));
+        assert_eq!(m.signal_unknown("sub_self"), Some(0xff));
+        assert_eq!(m.signal_unknown("add_zero"), Some(0xff));
+        assert_eq!(m.signal_unknown("sub_zero"), Some(0xff));
+        assert_eq!(m.signal_unknown("mul_zero"), Some(0xffff));
+        assert_eq!(m.signal_unknown("mul_signed_zero"), Some(0xffff));
+        assert_eq!(m.signal_unknown("eq_self"), Some(1));
+        assert_eq!(m.signal_unknown("ne_self"), Some(1));
+        assert_eq!(m.signal_unknown("lt_self"), Some(1));
+        assert_eq!(m.signal_unknown("ge_signed_self"), Some(1));
+        assert_eq!(m.signal_unknown("shl_zero"), Some(0xff));
+        // ANDing with 0 is known regardless
+        assert_eq!(m.signal_unknown("and_zero"), Some(0));
+        assert_eq!(m.and_zero, 0);
+
+        m.posedge_clk();
+        m.prop();
+        for output in [
+            "xor_self",
+            "sub_self",
+            "add_zero",
+            "sub_zero",
+            "mul_zero",
+            "mul_signed_zero",
+            "eq_self",
+            "ne_self",
+            "lt_self",
+            "ge_signed_self",
+            "shl_zero",
+            "and_zero",
+        ]
+        .iter()
+        {
+            assert_eq!(m.signal_unknown(output), Some(0));
+        }
+        assert_eq!(m.xor_self, 0);
+        assert_eq!(m.sub_self, 0);
+        assert_eq!(m.add_zero, 0x55);
+        assert_eq!(m.sub_zero, 0x55);
+        assert_eq!(m.mul_zero, 0);
+        assert_eq!(m.mul_signed_zero, 0);
+        assert!(m.eq_self);
+        assert!(!m.ne_self);
+        assert!(!m.lt_self);
+        assert!(m.ge_signed_self);
+        assert_eq!(m.shl_zero, 0);
+    }
+
+    #[test]
+    fn random_initial_state_test_module() {
+        let a = RandomInitialStateTestModule::new_with_seed(1);
+        let b = RandomInitialStateTestModule::new_with_seed(1);
+        let c = RandomInitialStateTestModule::new_with_seed(2);
+        let z = RandomInitialStateTestModule::new();
+        let z2 = RandomInitialStateTestModule::new_with_seed(0);
+
+        // The same seed always produces the same state
+        assert_eq!(a.peek_reg_r(), b.peek_reg_r());
+        assert_eq!(a.peek_reg_b(), b.peek_reg_b());
+        assert_eq!(a.peek_reg_w(), b.peek_reg_w());
+        assert_eq!(a.dump_mem_mem(), b.dump_mem_mem());
+        assert_eq!(z.peek_reg_r(), z2.peek_reg_r());
+        assert_eq!(z.peek_reg_w(), z2.peek_reg_w());
+        assert_eq!(z.dump_mem_mem(), z2.dump_mem_mem());
+
+        // Different seeds produce different state
+        assert_ne!(
+            (a.peek_reg_r(), a.peek_reg_w(), a.dump_mem_mem()),
+            (c.peek_reg_r(), c.peek_reg_w(), c.dump_mem_mem())
+        );
+
+        // Values are random, but still fit in their bit widths
+        for m in [&a, &c, &z].iter() {
+            assert!(m.peek_reg_r() < 0x80);
+            assert!(m.peek_reg_w()[2] < 0x4);
+            assert!(m.dump_mem_mem().iter().all(|element| *element < 0x1000));
+            assert_eq!(m.dump_mem_init_mem(), [1, 2]);
+            assert_eq!(m.peek_reg_d(), 0);
+        }
+        assert!(a.dump_mem_mem().iter().any(|element| *element != 0));
+        assert!(a
+            .dump_mem_mem()
+            .iter()
+            .any(|element| *element != a.dump_mem_mem()[0]));
+
+        let mut m = RandomInitialStateTestModule::new_with_seed(3);
+        m.reset();
+        m.addr = 1;
+        m.prop();
+        assert_eq!(m.d, 5);
+        assert_eq!(m.r, m.peek_reg_r());
+        assert_eq!(m.mem_data, m.peek_mem_mem(1));
+        assert_eq!(m.init_mem_data, 2);
+    }
+
+    #[test]
+    fn random_initial_state_lanes_test_module() {
+        let a = RandomInitialStateLanesTestModule::new_with_seed(1);
+        let b = RandomInitialStateLanesTestModule::new_with_seed(1);
+
+        // Each lane gets its own values
+        assert_ne!(
+            (a.peek_reg_r(0), a.peek_reg_w(0), a.dump_mem_mem(0)),
+            (a.peek_reg_r(1), a.peek_reg_w(1), a.dump_mem_mem(1))
+        );
+        for lane in 0..2 {
+            assert_eq!(a.peek_reg_r(lane), b.peek_reg_r(lane));
+            assert_eq!(a.peek_reg_w(lane), b.peek_reg_w(lane));
+            assert_eq!(a.dump_mem_mem(lane), b.dump_mem_mem(lane));
+            assert!(a.peek_reg_r(lane) < 0x80);
+            assert_eq!(a.dump_mem_init_mem(lane), [1, 2]);
+        }
+    }
+
+    #[test]
+    fn trace_filter_test_module() -> io::Result<()> {
+        let mut capture = Capture::new();
+        let trace = CaptureTrace::new(&mut capture);
+
+        let mut m = TraceFilterTestModule::new("m", trace)?;
+
+        m.reset();
+        m.i = 0x0f;
+        m.prop();
+        m.update_trace(0)?;
+        m.posedge_clk();
+        m.prop();
+        m.update_trace(1)?;
+        m.posedge_clk();
+        m.prop();
+        m.update_trace(2)?;
+        assert_eq!(m.o, 0xff);
+
+        let (name, root) = capture.root.as_ref().unwrap();
+        assert_eq!(*name, "m");
+        assert_eq!(root.signals.keys().collect::<Vec<_>>(), vec![&"o"]);
+        assert_eq!(
+            *root.signals["o"].values.borrow(),
+            vec![
+                (0, TraceValue::U32(0)),
+                (1, TraceValue::U32(0)),
+                (2, TraceValue::U32(0xff)),
+            ]
+        );
+        assert_eq!(root.children.keys().collect::<Vec<_>>(), vec![&"a", &"b"]);
+        for instance_name in ["a", "b"].iter() {
+            let instance = &root.children[instance_name];
+            assert_eq!(instance.signals.keys().collect::<Vec<_>>(), vec![&"r"]);
+            // Scopes whose signals are all filtered out are still present
+            assert_eq!(instance.children.keys().collect::<Vec<_>>(), vec![&"leaf"]);
+            assert!(instance.children["leaf"].signals.is_empty());
+            assert!(instance.children["leaf"].children.is_empty());
+        }
+        assert_eq!(
+            *root.children["a"].signals["r"].values.borrow(),
+            vec![
+                (0, TraceValue::U32(0)),
+                (1, TraceValue::U32(0)),
+                (2, TraceValue::U32(0x0f)),
+            ]
+        );
+        assert_eq!(
+            *root.children["b"].signals["r"].values.borrow(),
+            vec![
+                (0, TraceValue::U32(0)),
+                (1, TraceValue::U32(0)),
+                (2, TraceValue::U32(0xf0)),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn trace_depth_test_module() -> io::Result<()> {
+        let mut capture = Capture::new();
+        let trace = CaptureTrace::new(&mut capture);
+
+        let mut m = TraceDepthTestModule::new("m", trace)?;
+
+        m.reset();
+        m.i = 0x0f;
+        m.prop();
+        m.update_trace(0)?;
+
+        let (_, root) = capture.root.as_ref().unwrap();
+        assert_eq!(root.signals.keys().collect::<Vec<_>>(), vec![&"i", &"o"]);
+        assert_eq!(root.children.keys().collect::<Vec<_>>(), vec![&"a", &"b"]);
+        for instance_name in ["a", "b"].iter() {
+            let instance = &root.children[instance_name];
+            assert_eq!(instance.signals.keys().collect::<Vec<_>>(), vec![&"r"]);
+            assert!(instance.children.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn trace_scope_enabled() -> io::Result<()> {
+        let mut capture = Capture::new();
+        let trace = CaptureTrace::new(&mut capture);
+
+        let mut m = TraceTestModule2::new("m", trace)?;
+
+        assert!(m.set_trace_scope_enabled("inner1", false));
+        // Scopes are only matched by whole instance names
+        assert!(!m.set_trace_scope_enabled("inner", false));
+        assert!(!m.set_trace_scope_enabled("inner1.r", false));
+        assert!(!m.set_trace_scope_enabled("m", false));
+
+        m.reset();
+        m.prop();
+        m.update_trace(0)?;
+
+        // Disable everything except inner2
+        assert!(m.set_trace_scope_enabled("", false));
+        assert!(m.set_trace_scope_enabled("inner2", true));
+        m.i1 = 0xffffffff;
+        m.i2 = 0xffff0000;
+        m.i3 = 0x00ff0000;
+        m.i4 = 0x000f0000;
+        m.prop();
+        m.posedge_clk();
+        m.prop();
+        m.update_trace(1)?;
+
+        assert!(m.set_trace_scope_enabled("", true));
+        m.update_trace(2)?;
+
+        let (_, root) = capture.root.as_ref().unwrap();
+        assert_eq!(
+            *root.children["inner1"].signals["r"].values.borrow(),
+            vec![(2, TraceValue::U32(0xffff0000))]
+        );
+        assert_eq!(
+            *root.children["inner2"].signals["r"].values.borrow(),
+            vec![
+                (0, TraceValue::U32(0)),
+                (1, TraceValue::U32(0x000f0000)),
+                (2, TraceValue::U32(0x000f0000)),
+            ]
+        );
+        assert_eq!(
+            *root.children["inner3"].signals["r"].values.borrow(),
+            vec![(0, TraceValue::U32(0)), (2, TraceValue::U32(0))]
+        );
+        assert_eq!(
+            *root.signals["i1"].values.borrow(),
+            vec![(0, TraceValue::U32(0)), (2, TraceValue::U32(0xffffffff))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn trace_window() -> io::Result<()> {
+        let mut capture = Capture::new();
+        let trace = CaptureTrace::new(&mut capture);
+
+        let mut m = TraceTestModule2::new("m", trace)?;
+
+        m.set_trace_window(1, Some(3));
+
+        m.reset();
+        m.i1 = 0xffffffff;
+        m.i2 = 0xffff0000;
+        m.i3 = 0x00ff0000;
+        m.i4 = 0x000f0000;
+        for time_stamp in 0..4 {
+            m.prop();
+            m.update_trace(time_stamp)?;
+            m.posedge_clk();
+        }
+
+        let (_, root) = capture.root.as_ref().unwrap();
+        assert_eq!(
+            *root.children["inner1"].signals["r"].values.borrow(),
+            vec![
+                (1, TraceValue::U32(0xffff0000)),
+                (2, TraceValue::U32(0xffff0000)),
+            ]
+        );
+        assert_eq!(
+            *root.signals["o"].values.borrow(),
+            vec![(1, TraceValue::U32(0)), (2, TraceValue::U32(0x000f0000))]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn deep_graph_test_module() {
         let mut m = DeepGraphTestModule::new();